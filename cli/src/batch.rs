@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Result};
+use breez_sdk_ark::error::SdkError;
+use serde::Serialize;
+
+/// A payout read from a `destination,amount,label` CSV row
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct Payout {
+    /// Line of the file the payout was read from
+    pub(crate) line: usize,
+    pub(crate) destination: String,
+    pub(crate) amount_sat: u64,
+    pub(crate) label: Option<String>,
+}
+
+/// Whether a payout sent by `pay-batch` went out
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum PayoutStatus {
+    Sent,
+    /// The payout was rejected before anything was sent, it can be retried
+    Failed,
+    /// The send was interrupted and may have gone through, check the payments before retrying
+    Unknown,
+}
+
+impl PayoutStatus {
+    /// Status of a payout whose send returned an error
+    ///
+    /// Errors raised while checking the payout mean nothing was sent, while errors from the
+    /// connection or the Ark server may come after the server accepted the payment.
+    pub(crate) fn of_error(error: &SdkError) -> Self {
+        match error {
+            SdkError::InvalidInput(_)
+            | SdkError::InvalidNetwork
+            | SdkError::AddressParsingError(_)
+            | SdkError::AmountRequired(_)
+            | SdkError::AmountMismatch(_)
+            | SdkError::AmountOutOfRange { .. }
+            | SdkError::AmountTooSmall { .. }
+            | SdkError::AmountTooLarge { .. }
+            | SdkError::QuoteExpired(_)
+            | SdkError::NotInitialized
+            | SdkError::AlreadyStopped => PayoutStatus::Failed,
+            _ => PayoutStatus::Unknown,
+        }
+    }
+}
+
+/// Outcome of a payout sent by `pay-batch`
+#[derive(Debug, Serialize)]
+pub(crate) struct PayoutResult {
+    #[serde(flatten)]
+    pub(crate) payout: Payout,
+    pub(crate) status: PayoutStatus,
+    pub(crate) payment_id: Option<String>,
+    pub(crate) error: Option<String>,
+}
+
+/// Summary printed once `pay-batch` has gone through all payouts
+#[derive(Debug, Serialize)]
+pub(crate) struct BatchReport {
+    pub(crate) sent_count: usize,
+    pub(crate) failed_count: usize,
+    /// Payouts that may have been sent, see [PayoutStatus::Unknown]
+    pub(crate) unknown_count: usize,
+    pub(crate) sent_sat: u64,
+    pub(crate) fees_sat: u64,
+    pub(crate) payouts: Vec<PayoutResult>,
+}
+
+/// Parses a payout file with one `destination,amount,label` row per line
+///
+/// The amount is in satoshis and the label is optional, it may contain commas. Blank lines,
+/// lines starting with `#` and a `destination,amount,label` header are skipped. All rows are
+/// checked before returning, so every invalid row is reported at once.
+pub(crate) fn parse_payouts(contents: &str) -> Result<Vec<Payout>> {
+    let mut payouts = Vec::new();
+    let mut errors = Vec::new();
+    for (index, row) in contents.lines().enumerate() {
+        let line = index + 1;
+        let row = row.trim();
+        if row.is_empty() || row.starts_with('#') {
+            continue;
+        }
+        let mut fields = row.splitn(3, ',').map(str::trim);
+        let destination = fields.next().unwrap_or_default();
+        let amount = fields.next().unwrap_or_default();
+        let label = fields.next().filter(|label| !label.is_empty());
+        if payouts.is_empty()
+            && errors.is_empty()
+            && destination.eq_ignore_ascii_case("destination")
+        {
+            continue;
+        }
+
+        if destination.is_empty() {
+            errors.push(format!("line {line}: missing destination"));
+            continue;
+        }
+        match amount.parse::<u64>() {
+            Ok(amount_sat) if amount_sat > 0 => payouts.push(Payout {
+                line,
+                destination: destination.to_string(),
+                amount_sat,
+                label: label.map(str::to_string),
+            }),
+            _ => errors.push(format!("line {line}: invalid amount '{amount}'")),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(anyhow!("Invalid payout file:\n{}", errors.join("\n")));
+    }
+    if payouts.is_empty() {
+        return Err(anyhow!("The payout file has no payouts"));
+    }
+    Ok(payouts)
+}
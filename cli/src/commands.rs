@@ -1,8 +1,21 @@
+use crate::batch::{self, BatchReport, Payout, PayoutResult, PayoutStatus};
+use crate::denomination::Denomination;
+use crate::persist::CliPersistence;
+use crate::regtest;
+use crate::watch;
+use breez_sdk_ark::error::SdkError;
 use breez_sdk_ark::SendOnchainRequest;
 use breez_sdk_ark::{
-    models::PrepareSendOnchainRequest, BreezSdk, GetBalanceRequest, ListPaymentsRequest, PayAmount,
-    PaymentMethod, PrepareSendPaymentRequest, ReceiveOnchainRequest, ReceivePaymentRequest,
-    SendPaymentRequest, SyncWalletRequest,
+    models::PrepareSendOnchainRequest, BreezSdk, CancelPaymentRequest, ConsolidateRequest,
+    CreateNoteRequest, ExportPaymentsRequest, ExportRoundProofRequest, ExportStateRequest,
+    GetBalanceRequest, GetChainTipRequest, GetInfoRequest, GetLimitsRequest, GetRecentLogsRequest,
+    ImportStateRequest, ListNotesRequest, ListOnchainUtxosRequest, ListPaymentRequestsRequest,
+    ListPaymentsRequest, PayAmount, PaymentMethod, PaymentRequestStatus, PaymentRetention,
+    PrepareConsolidateRequest, PrepareSendPaymentRequest, PrepareSendPaymentResponse,
+    ReceiveArkRequest, ReceiveOnchainRequest, ReceivePaymentRequest, ReclaimNoteRequest,
+    RedeemNoteRequest, RegisterLightningAddressRequest, RetentionAction, RetryPaymentRequest,
+    RoutePaymentRequest, SendPaymentRequest, SortDirection, SyncMode, SyncWalletRequest,
+    UpdateConfigRequest, WaitForPaymentRequest,
 };
 use clap::arg;
 use rustyline::highlight::Highlighter;
@@ -13,6 +26,24 @@ use serde_json::to_string_pretty;
 use std::borrow::Cow;
 use std::borrow::Cow::Owned;
 use std::io::Write;
+use std::path::PathBuf;
+
+/// Shown instead of secrets in `--no-secrets` mode
+const HIDDEN: &str = "<hidden>";
+
+/// CLI state the commands may need besides the SDK
+pub(crate) struct CommandContext<'a> {
+    pub(crate) persistence: &'a CliPersistence,
+    /// Never print secrets, e.g. while sharing the screen
+    pub(crate) no_secrets: bool,
+}
+
+impl CommandContext<'_> {
+    /// Unit to show amounts in, as last set with `set-denomination`
+    fn denomination(&self) -> anyhow::Result<Denomination> {
+        Ok(self.persistence.load_config()?.denomination)
+    }
+}
 
 #[derive(Serialize, Deserialize)]
 struct WalletConfig {
@@ -23,7 +54,11 @@ struct WalletConfig {
 #[derive(Clone, clap::clap_derive::Parser)]
 pub(crate) enum Commands {
     /// Synchronize wallet with the Ark network
-    Sync {},
+    Sync {
+        /// Only refresh the balance, skipping boarding and payment history
+        #[arg(short, long)]
+        balance_only: bool,
+    },
 
     /// Generate a new on-chain deposit address
     ReceiveOnchain {},
@@ -42,6 +77,55 @@ pub(crate) enum Commands {
     /// Get your wallet balance
     GetBalance {},
 
+    /// Show amounts in sats or BTC, kept across runs
+    SetDenomination {
+        /// The unit to show amounts in
+        #[arg(value_enum)]
+        denomination: Denomination,
+    },
+
+    /// Print events as they happen, until interrupted with Ctrl-C
+    Watch {},
+
+    /// Get information about the SDK and its current state
+    GetInfo {},
+
+    /// Get the current limits of each payment method
+    GetLimits {},
+
+    /// Get the height and hash of the current chain tip
+    GetChainTip {},
+
+    /// Change settings of the running SDK, until the CLI exits
+    UpdateConfig {
+        /// The Esplora server URL
+        #[arg(long)]
+        esplora_url: Option<String>,
+
+        /// Seconds between background syncs
+        #[arg(long)]
+        sync_interval_secs: Option<u64>,
+
+        /// Base URL of the explorer payments link to
+        #[arg(long)]
+        explorer_url: Option<String>,
+
+        /// Move payments older than this many months out of the payment history
+        #[arg(long)]
+        retention_months: Option<u32>,
+
+        /// Delete the payments moved out of the payment history instead of archiving them
+        #[arg(long)]
+        prune: bool,
+    },
+
+    /// Merge small VTXOs into one in the next round, showing the cost and benefit first
+    Consolidate {
+        /// Consolidate when at least two VTXOs are below this amount in satoshis
+        #[arg(short, long)]
+        dust_threshold_sat: Option<u64>,
+    },
+
     /// List payments
     ListPayments {
         /// Number of payments to show
@@ -51,11 +135,30 @@ pub(crate) enum Commands {
         /// Number of payments to skip
         #[arg(short, long, default_value = "0")]
         offset: u32,
+
+        /// Continue after a previous page, with the cursor it printed
+        #[arg(short, long)]
+        cursor: Option<String>,
+
+        /// List the oldest payments first
+        #[arg(long)]
+        ascending: bool,
+    },
+
+    /// Compare the routes a payment can take, with their fees and timing
+    RoutePayment {
+        /// The destination to send to (Ark address, BOLT11 invoice, BIP21 URI, Bitcoin address)
+        #[arg(short, long)]
+        destination: String,
+
+        /// The amount to send in satoshis
+        #[arg(short, long)]
+        amount: u64,
     },
 
     /// Send payment to a destination (Ark address, BOLT11 invoice, etc.)
     SendPayment {
-        /// The destination to send to (Ark address, BOLT11 invoice, etc.)
+        /// The destination to send to (Ark address, BOLT11 invoice, lightning address, etc.)
         #[arg(short, long)]
         destination: String,
 
@@ -64,15 +167,174 @@ pub(crate) enum Commands {
         amount: u64,
     },
 
+    /// Send payments to every row of a `destination,amount,label` CSV file, e.g. payouts
+    PayBatch {
+        /// The CSV file with the payouts, amounts are in satoshis
+        #[arg(short, long)]
+        file: PathBuf,
+    },
+
+    /// Pay an LNURL-pay request or lightning address, with an optional comment. Not supported
+    /// until BOLT11 invoices can be paid
+    LnurlPay {
+        /// The LNURL or lightning address
+        lnurl: String,
+
+        /// The amount to send in satoshis
+        #[arg(short, long)]
+        amount: u64,
+
+        /// A comment for the receiver
+        #[arg(short, long)]
+        comment: Option<String>,
+    },
+
+    /// Retry a send that failed midway
+    RetryPayment {
+        /// The ID of the retryable payment
+        id: String,
+    },
+
+    /// Cancel a send that didn't reach the Ark server
+    CancelPayment {
+        /// The ID of the pending or retryable payment
+        id: String,
+    },
+
     /// Generate a payment destination (Ark address, Bitcoin address, etc.)
     ReceivePayment {
-        /// The payment method to use (ark, bitcoin, bolt11)
+        /// The payment method to use (ark, bitcoin, external, bolt11)
         #[arg(short, long)]
         method: String,
 
         /// Optional amount in satoshis
         #[arg(short, long)]
         amount: Option<u64>,
+
+        /// How long the request is tracked as outstanding, in seconds
+        #[arg(short, long)]
+        expiry_secs: Option<u64>,
+
+        /// External order ID the payment is for, copied to the received payment
+        #[arg(long)]
+        order_id: Option<String>,
+
+        /// Note about what the payment is for, shown with the received payment
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Wallet an external deposit is sent from, e.g. the hardware wallet model
+        #[arg(long, default_value = "Hardware wallet")]
+        source: String,
+    },
+
+    /// Wait until a generated destination is paid and the payment completed
+    WaitForPayment {
+        /// The destination returned by receive-payment
+        destination: String,
+
+        /// How long to wait, in seconds
+        #[arg(short, long, default_value = "600")]
+        timeout_secs: u64,
+    },
+
+    /// List the payment destinations generated so far and whether they were paid
+    ListPaymentRequests {
+        /// Only list the requests that weren't paid yet
+        #[arg(long)]
+        pending: bool,
+
+        /// Only list the requests for this external order ID
+        #[arg(long)]
+        order_id: Option<String>,
+    },
+
+    /// Export the proof that the round a payment settled in is confirmed onchain
+    ExportRoundProof {
+        /// The ID of the payment
+        payment_id: String,
+    },
+
+    /// Export the whole payment history, including archived payments
+    ExportPayments {},
+
+    /// Generate an Ark address along with its VTXO key and expiry parameters
+    ReceiveArk {},
+
+    /// Parse an input (Ark address, Ark note, etc.)
+    Parse {
+        /// The input to parse
+        input: String,
+    },
+
+    /// Redeem an Ark note into the wallet
+    RedeemNote {
+        /// The encoded Ark note
+        note: String,
+    },
+
+    /// Create a bearer note that anyone holding it can redeem
+    CreateNote {
+        /// The amount to lock into the note in satoshis
+        amount: u64,
+
+        /// How long the note can be redeemed for, in seconds
+        #[arg(short, long)]
+        expiry_secs: Option<u64>,
+    },
+
+    /// Reclaim the funds of a note that wasn't redeemed
+    ReclaimNote {
+        /// The note ID
+        id: String,
+    },
+
+    /// List the notes created by the wallet
+    ListNotes {},
+
+    /// List the unspent onchain outputs held by the wallet
+    ListOnchainUtxos {},
+
+    /// Export the wallet state as encrypted chunks, to move it to another device
+    ExportState {
+        /// Maximum number of payload characters per chunk
+        #[arg(short, long)]
+        max_chunk_size: Option<u32>,
+    },
+
+    /// Import a wallet state exported by another device
+    ImportState {
+        /// The exported chunks, in any order
+        chunks: Vec<String>,
+    },
+
+    /// Register a lightning address paying into the wallet
+    RegisterLightningAddress {
+        /// The username, i.e. the part before the '@'
+        username: String,
+    },
+
+    /// Show the wallet mnemonic after confirmation (not available with --no-secrets)
+    ShowMnemonic {},
+
+    /// Show the latest SDK log entries
+    RecentLogs {
+        /// Maximum number of entries to show
+        #[arg(default_value = "50")]
+        limit: u32,
+    },
+
+    /// Fund the boarding address from the bitcoind wallet (regtest only)
+    FaucetDeposit {
+        /// The amount to deposit in satoshis
+        amount: u64,
+    },
+
+    /// Mine blocks, e.g. to confirm a deposit (regtest only)
+    Mine {
+        /// Number of blocks to mine
+        #[arg(default_value = "1")]
+        blocks: u32,
     },
 
     /// Exit the interactive shell (interactive mode only)
@@ -123,10 +385,15 @@ macro_rules! wait_confirmation {
 pub(crate) async fn execute_command(
     command: Commands,
     sdk: &BreezSdk,
+    context: &CommandContext<'_>,
 ) -> Result<String, anyhow::Error> {
     Ok(match command {
-        Commands::Sync {} => {
-            sdk.sync_wallet(SyncWalletRequest {}).await?;
+        Commands::Sync { balance_only } => {
+            let mode = match balance_only {
+                true => SyncMode::BalanceOnly,
+                false => SyncMode::Full,
+            };
+            sdk.sync_wallet(SyncWalletRequest { mode }).await?;
             println!("Wallet synchronized successfully");
             command_result!("Wallet synchronized successfully")
         }
@@ -138,17 +405,19 @@ pub(crate) async fn execute_command(
             // First, prepare the transaction to get fee information
             let prepare_response = sdk
                 .prepare_send_onchain(PrepareSendOnchainRequest {
+                    onchain_address: address.clone(),
                     receiver_amount_sats: amount,
                 })
                 .await?;
 
+            let denomination = context.denomination()?;
             wait_confirmation!(
                 format!(
-                    "Preparing to send {} sats to {}\nFee: {} sats\nTotal amount (including fees): {} sats\nDo you want to proceed? (y/n): ",
-                    amount,
+                    "Preparing to send {} to {}\nFee: {}\nTotal amount (including fees): {}\nDo you want to proceed? (y/n): ",
+                    denomination.format(amount),
                     address,
-                    prepare_response.fee_sats,
-                    amount + prepare_response.fee_sats
+                    denomination.format(prepare_response.fee_sats),
+                    denomination.format(amount + prepare_response.fee_sats)
                 ),
                 "Aborting payment"
             );
@@ -156,7 +425,6 @@ pub(crate) async fn execute_command(
             let response = sdk
                 .send_onchain(SendOnchainRequest {
                     prepare_send_onchain_response: prepare_response,
-                    onchain_address: address,
                 })
                 .await?;
 
@@ -165,18 +433,126 @@ pub(crate) async fn execute_command(
 
         Commands::GetBalance {} => {
             let response = sdk.get_balance(GetBalanceRequest {}).await?;
+            let denomination = context.denomination()?;
+            // The summary is part of the result, so the output stays valid JSON
+            let mut result = serde_json::to_value(&response)?;
+            result["summary"] = format!(
+                "Balance: {} ({} spendable)",
+                denomination.format(response.balance.total_sats()),
+                denomination.format(response.spendable_sats)
+            )
+            .into();
+            command_result!(result)
+        }
+        Commands::SetDenomination { denomination } => {
+            let mut config = context.persistence.load_config()?;
+            config.denomination = denomination;
+            context.persistence.save_config(&config)?;
+            command_result!(format!(
+                "Amounts are now shown as {}",
+                denomination.format(100_000)
+            ))
+        }
+        Commands::Watch {} => {
+            let count = watch::watch_events(sdk).await?;
+            command_result!(format!("Stopped watching after {count} events"))
+        }
+        Commands::GetInfo {} => {
+            let response = sdk.get_info(GetInfoRequest {}).await?;
+            command_result!(response)
+        }
+        Commands::GetLimits {} => {
+            let response = sdk.get_limits(GetLimitsRequest {}).await?;
             command_result!(response)
         }
-        Commands::ListPayments { limit, offset } => {
-            let request = ListPaymentsRequest { offset, limit };
+        Commands::GetChainTip {} => {
+            let response = sdk.get_chain_tip(GetChainTipRequest {}).await?;
+            command_result!(response)
+        }
+        Commands::UpdateConfig {
+            esplora_url,
+            sync_interval_secs,
+            explorer_url,
+            retention_months,
+            prune,
+        } => {
+            let action = match prune {
+                true => RetentionAction::Prune,
+                false => RetentionAction::Archive,
+            };
+            let response = sdk
+                .update_config(UpdateConfigRequest {
+                    esplora_url,
+                    sync_interval_secs,
+                    explorer_url: explorer_url.map(Some),
+                    payment_retention: retention_months.map(|after_months| {
+                        Some(PaymentRetention {
+                            after_months,
+                            action,
+                        })
+                    }),
+                    ..Default::default()
+                })
+                .await?;
+            command_result!(response)
+        }
+        Commands::Consolidate { dust_threshold_sat } => {
+            let prepare_response = sdk
+                .prepare_consolidate(PrepareConsolidateRequest { dust_threshold_sat })
+                .await?;
+            if prepare_response.vtxo_count == 0 {
+                return Ok(command_result!("No small VTXOs to consolidate"));
+            }
+
+            let denomination = context.denomination()?;
+            println!(
+                "VTXOs to merge: {} holding {}, {} of them below the threshold",
+                prepare_response.vtxo_count,
+                denomination.format(prepare_response.amount_sat),
+                prepare_response.dust_vtxo_count
+            );
+            println!("Fee: {}", denomination.format(prepare_response.fee_sat));
+            println!(
+                "Exit savings: {}",
+                denomination.format(prepare_response.exit_savings_sat)
+            );
+            if !prepare_response.is_worth_it {
+                println!("The fees exceed the exit savings");
+            }
+
+            wait_confirmation!(
+                "Do you want to proceed with the consolidation? (y/n): ",
+                "Consolidation cancelled by user."
+            );
+
+            let response = sdk.consolidate(ConsolidateRequest {}).await?;
+            command_result!(response)
+        }
+        Commands::ListPayments {
+            limit,
+            offset,
+            cursor,
+            ascending,
+        } => {
+            let request = ListPaymentsRequest {
+                offset,
+                limit,
+                cursor,
+                sort: if ascending {
+                    SortDirection::Ascending
+                } else {
+                    SortDirection::Descending
+                },
+            };
             let response = sdk.list_payments(request).await?;
+            let denomination = context.denomination()?;
 
             println!("Recent payments:");
             println!(
-                "{:<40} {:<10} {:<10} {:<12} {:<8} Date",
-                "ID", "Type", "Status", "Amount (sats)", "Fee"
+                "{:<40} {:<10} {:<10} {:<16} {:<16} Date",
+                "ID", "Type", "Status", "Amount", "Fee"
             );
-            println!("{}", "-".repeat(100));
+            println!("{}", "-".repeat(116));
 
             for payment in response.payments {
                 let date = chrono::DateTime::from_timestamp(payment.timestamp.try_into()?, 0)
@@ -184,17 +560,41 @@ pub(crate) async fn execute_command(
                     .unwrap_or_else(|| "Unknown".to_string());
 
                 println!(
-                    "{:<40} {:<10} {:<10} {:<12} {:<8} {}",
+                    "{:<40} {:<10} {:<10} {:<16} {:<16} {}",
                     &payment.id,
                     payment.payment_type.to_string(),
                     payment.status.to_string(),
-                    payment.amount,
-                    payment.fees,
+                    denomination.format(payment.amount),
+                    denomination.format(payment.fees),
                     date
                 );
+                for onchain_tx in &payment.onchain_txs {
+                    let link = sdk
+                        .explorer_tx_url(&onchain_tx.txid)
+                        .unwrap_or_else(|| onchain_tx.txid.clone());
+                    println!("  {:?}: {}", onchain_tx.kind, link);
+                }
+                if let Some(reason) = &payment.failure_reason {
+                    println!("  {:?}: {}", reason.kind, reason.details);
+                }
+            }
+            if let Some(next_cursor) = response.next_cursor {
+                println!("Next page: --cursor {next_cursor}");
             }
             "".to_string()
         }
+        Commands::RoutePayment {
+            destination,
+            amount,
+        } => {
+            let response = sdk
+                .route_payment(RoutePaymentRequest {
+                    destination,
+                    amount_sat: amount,
+                })
+                .await?;
+            command_result!(response)
+        }
         Commands::SendPayment {
             destination,
             amount,
@@ -210,12 +610,16 @@ pub(crate) async fn execute_command(
                 .await?;
 
             // Show the payment details and fees to the user
+            let denomination = context.denomination()?;
             println!("Preparing payment to: {}", destination);
-            println!("Amount: {} sats", amount);
+            println!("Amount: {}", denomination.format(amount));
 
             if let Some(fees_sat) = prepare_response.fees_sat {
-                println!("Fee: {} sats", fees_sat);
-                println!("Total amount (including fees): {} sats", amount + fees_sat);
+                println!("Fee: {}", denomination.format(fees_sat));
+                println!(
+                    "Total amount (including fees): {}",
+                    denomination.format(amount + fees_sat)
+                );
             } else {
                 println!("Fee: 0");
             }
@@ -233,7 +637,137 @@ pub(crate) async fn execute_command(
 
             command_result!(response)
         }
-        Commands::ReceivePayment { method, amount } => {
+        Commands::PayBatch { file } => {
+            let contents = std::fs::read_to_string(&file)
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", file.display()))?;
+            let payouts = batch::parse_payouts(&contents)?;
+
+            // Prepare every payout before sending any, so invalid rows are reported before
+            // anything is sent. Payouts can still fail once sending started, the report lists them.
+            let mut prepared = Vec::new();
+            let mut errors = Vec::new();
+            for payout in &payouts {
+                match prepare_payout(sdk, payout).await {
+                    Ok(prepare_response) => prepared.push(prepare_response),
+                    Err(e) => errors.push(format!("line {}: {e}", payout.line)),
+                }
+            }
+            if !errors.is_empty() {
+                return Err(anyhow::anyhow!("Invalid payouts:\n{}", errors.join("\n")));
+            }
+
+            let denomination = context.denomination()?;
+            println!(
+                "{:<6} {:<16} {:<16} {:<20} Destination",
+                "Line", "Amount", "Fee", "Label"
+            );
+            println!("{}", "-".repeat(80));
+            for (payout, prepare_response) in payouts.iter().zip(&prepared) {
+                println!(
+                    "{:<6} {:<16} {:<16} {:<20} {}",
+                    payout.line,
+                    denomination.format(payout.amount_sat),
+                    denomination.format(prepare_response.fees_sat.unwrap_or_default()),
+                    payout.label.as_deref().unwrap_or("-"),
+                    payout.destination
+                );
+            }
+            let total_sat: u64 = payouts.iter().map(|payout| payout.amount_sat).sum();
+            let total_fees_sat: u64 = prepared.iter().filter_map(|p| p.fees_sat).sum();
+            let spendable_sats = sdk.get_balance(GetBalanceRequest {}).await?.spendable_sats;
+            if total_sat + total_fees_sat > spendable_sats {
+                return Err(anyhow::anyhow!(
+                    "The batch needs {} including fees, but only {} can be spent",
+                    denomination.format(total_sat + total_fees_sat),
+                    denomination.format(spendable_sats)
+                ));
+            }
+            println!("Payouts: {}", payouts.len());
+            println!("Total amount: {}", denomination.format(total_sat));
+            println!("Total fees: {}", denomination.format(total_fees_sat));
+            println!(
+                "Total amount (including fees): {}",
+                denomination.format(total_sat + total_fees_sat)
+            );
+            wait_confirmation!(
+                "Do you want to send these payments? (y/n): ",
+                "Batch cancelled by user."
+            );
+
+            // Failed payouts don't stop the batch, they're listed in the report. Payouts with an
+            // unknown status may have been sent, so they must be checked before being retried.
+            let mut report = BatchReport {
+                sent_count: 0,
+                failed_count: 0,
+                unknown_count: 0,
+                sent_sat: 0,
+                fees_sat: 0,
+                payouts: Vec::new(),
+            };
+            for (payout, prepare_response) in payouts.into_iter().zip(prepared) {
+                let previewed_fees_sat = prepare_response.fees_sat.unwrap_or_default();
+                let mut result = sdk
+                    .send_payment(SendPaymentRequest { prepare_response })
+                    .await
+                    .map(|response| (response, previewed_fees_sat));
+                // Onchain quotes expire after a few minutes, a long batch prepares them again as
+                // long as the fees didn't go up since the preview
+                if let Err(expired @ SdkError::QuoteExpired(_)) = result {
+                    result = match prepare_payout(sdk, &payout).await {
+                        Ok(prepare_response)
+                            if prepare_response.fees_sat.unwrap_or_default()
+                                <= previewed_fees_sat =>
+                        {
+                            let fees_sat = prepare_response.fees_sat.unwrap_or_default();
+                            sdk.send_payment(SendPaymentRequest { prepare_response })
+                                .await
+                                .map(|response| (response, fees_sat))
+                        }
+                        Ok(_) => Err(expired),
+                        Err(e) => Err(e),
+                    };
+                }
+
+                let (status, payment_id, error) = match result {
+                    Ok((response, fees_sat)) => {
+                        report.sent_count += 1;
+                        report.sent_sat += payout.amount_sat;
+                        report.fees_sat += fees_sat;
+                        (PayoutStatus::Sent, Some(response.payment.id), None)
+                    }
+                    Err(e) => {
+                        let status = PayoutStatus::of_error(&e);
+                        match status {
+                            PayoutStatus::Unknown => report.unknown_count += 1,
+                            _ => report.failed_count += 1,
+                        }
+                        (status, None, Some(e.to_string()))
+                    }
+                };
+                report.payouts.push(PayoutResult {
+                    payout,
+                    status,
+                    payment_id,
+                    error,
+                });
+            }
+            command_result!(report)
+        }
+        Commands::LnurlPay { lnurl, .. } => {
+            // Failed before the LNURL service is contacted, as the invoice it returns can't be
+            // paid until the SDK supports BOLT11 sends
+            return Err(anyhow::anyhow!(
+                "Can't pay {lnurl}: LNURL-pay needs BOLT11 payments, which aren't supported yet"
+            ));
+        }
+        Commands::ReceivePayment {
+            method,
+            amount,
+            expiry_secs,
+            order_id,
+            description,
+            source,
+        } => {
             // Parse the payment method from the user input
             let payment_method = match method.to_lowercase().as_str() {
                 "ark" => PaymentMethod::ArkAddress {
@@ -242,6 +776,10 @@ pub(crate) async fn execute_command(
                 "bitcoin" => PaymentMethod::BitcoinAddress {
                     receiver_amount_sat: amount,
                 },
+                "external" => PaymentMethod::ExternalDeposit {
+                    receiver_amount_sat: amount,
+                    source,
+                },
                 "bolt11" => {
                     if let Some(amt) = amount {
                         PaymentMethod::Bolt11Invoice {
@@ -256,7 +794,12 @@ pub(crate) async fn execute_command(
             };
 
             // Create the request
-            let request = ReceivePaymentRequest { payment_method };
+            let request = ReceivePaymentRequest {
+                payment_method,
+                expiry_secs,
+                order_id,
+                description,
+            };
 
             // Call the SDK to generate the payment destination
             let response = sdk.receive_payment(request).await?;
@@ -264,8 +807,151 @@ pub(crate) async fn execute_command(
             // Display the result to the user
             command_result!(response)
         }
+        Commands::WaitForPayment {
+            destination,
+            timeout_secs,
+        } => {
+            let response = sdk
+                .wait_for_payment(WaitForPaymentRequest {
+                    destination,
+                    timeout_secs,
+                })
+                .await?;
+            command_result!(response)
+        }
+        Commands::ListPaymentRequests { pending, order_id } => {
+            let response = sdk
+                .list_payment_requests(ListPaymentRequestsRequest {
+                    status: pending.then_some(PaymentRequestStatus::Pending),
+                    order_id,
+                })
+                .await?;
+            command_result!(response)
+        }
+        Commands::ExportRoundProof { payment_id } => {
+            let response = sdk
+                .export_round_proof(ExportRoundProofRequest { payment_id })
+                .await?;
+            command_result!(response)
+        }
+        Commands::ExportPayments {} => {
+            let response = sdk.export_payments(ExportPaymentsRequest {}).await?;
+            command_result!(response)
+        }
+        Commands::Parse { input } => {
+            let response = sdk.parse(&input).await?;
+            command_result!(response)
+        }
+        Commands::RedeemNote { note } => {
+            let response = sdk.redeem_note(RedeemNoteRequest { note }).await?;
+            command_result!(response)
+        }
+        Commands::CreateNote {
+            amount,
+            expiry_secs,
+        } => {
+            let mut response = sdk
+                .create_note(CreateNoteRequest {
+                    amount_sat: amount,
+                    expiry_secs,
+                })
+                .await?;
+            if context.no_secrets {
+                response.note.note = HIDDEN.to_string();
+            }
+            command_result!(response)
+        }
+        Commands::ReceiveArk {} => {
+            let response = sdk.receive_ark(ReceiveArkRequest {}).await?;
+            command_result!(response)
+        }
+        Commands::RetryPayment { id } => {
+            let response = sdk.retry_payment(RetryPaymentRequest { id }).await?;
+            command_result!(response)
+        }
+        Commands::CancelPayment { id } => {
+            let response = sdk.cancel_payment(CancelPaymentRequest { id }).await?;
+            command_result!(response)
+        }
+        Commands::ReclaimNote { id } => {
+            let response = sdk.reclaim_note(ReclaimNoteRequest { id }).await?;
+            command_result!(response)
+        }
+        Commands::ListNotes {} => {
+            let mut response = sdk.list_notes(ListNotesRequest {}).await?;
+            if context.no_secrets {
+                for note in &mut response.notes {
+                    note.note = HIDDEN.to_string();
+                }
+            }
+            command_result!(response)
+        }
+        Commands::ListOnchainUtxos {} => {
+            let response = sdk.list_onchain_utxos(ListOnchainUtxosRequest {}).await?;
+            command_result!(response)
+        }
+        Commands::ExportState { max_chunk_size } => {
+            let response = sdk
+                .export_state(ExportStateRequest { max_chunk_size })
+                .await?;
+            command_result!(response)
+        }
+        Commands::ImportState { chunks } => {
+            let response = sdk.import_state(ImportStateRequest { chunks }).await?;
+            command_result!(response)
+        }
+        Commands::RegisterLightningAddress { username } => {
+            let response = sdk
+                .register_lightning_address(RegisterLightningAddressRequest { username })
+                .await?;
+            command_result!(response)
+        }
+        Commands::ShowMnemonic {} => {
+            if context.no_secrets {
+                return Err(anyhow::anyhow!(
+                    "Secrets are hidden, restart without --no-secrets to show the mnemonic"
+                ));
+            }
+            wait_confirmation!(
+                "The mnemonic gives full access to the funds, make sure nobody else can see the screen. Show it? (y/n): ",
+                "Mnemonic not shown."
+            );
+            let mnemonic = context.persistence.get_or_create_mnemonic()?;
+            command_result!(mnemonic)
+        }
+        Commands::RecentLogs { limit } => {
+            let response = sdk.get_recent_logs(GetRecentLogsRequest { limit });
+            command_result!(response)
+        }
+        Commands::FaucetDeposit { amount } => {
+            regtest::ensure_regtest(sdk).await?;
+            let address = sdk
+                .receive_onchain(ReceiveOnchainRequest {})
+                .await?
+                .deposit_address;
+            let txid = regtest::send_to_address(&address, amount)?;
+            command_result!(serde_json::json!({ "address": address, "txid": txid }))
+        }
+        Commands::Mine { blocks } => {
+            regtest::ensure_regtest(sdk).await?;
+            let block_hashes = regtest::mine(blocks)?;
+            command_result!(block_hashes)
+        }
         Commands::Exit {} => {
             command_result!("Exiting...")
         }
     })
 }
+
+async fn prepare_payout(
+    sdk: &BreezSdk,
+    payout: &Payout,
+) -> Result<PrepareSendPaymentResponse, SdkError> {
+    sdk.prepare_send_payment(PrepareSendPaymentRequest {
+        destination: payout.destination.clone(),
+        amount: Some(PayAmount::Specific {
+            receiver_amount_sat: payout.amount_sat,
+        }),
+    })
+    .await
+}
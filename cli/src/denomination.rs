@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+const SATS_PER_BTC: u64 = 100_000_000;
+
+/// Unit amounts are shown in, set with the `set-denomination` command and kept across runs
+///
+/// Fiat currencies aren't offered, the SDK has no exchange rate source to convert with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Denomination {
+    #[default]
+    Sats,
+    Btc,
+}
+
+impl Denomination {
+    /// Formats an amount in satoshis, along with its unit
+    pub(crate) fn format(self, amount_sat: u64) -> String {
+        match self {
+            Denomination::Sats => format!("{amount_sat} sats"),
+            Denomination::Btc => format!(
+                "{}.{:08} BTC",
+                amount_sat / SATS_PER_BTC,
+                amount_sat % SATS_PER_BTC
+            ),
+        }
+    }
+}
@@ -1,21 +1,33 @@
+mod batch;
 mod commands;
+mod denomination;
 mod persist;
+mod profiles;
+mod progress;
+mod regtest;
+mod watch;
 
 use crate::commands::CliHelper;
-use crate::persist::CliPersistence;
+use crate::persist::{CliConfig, CliPersistence};
+use crate::profiles::ProfileCommand;
+use crate::progress::ProgressListener;
 use anyhow::anyhow;
 use anyhow::Result;
 use breez_sdk_ark::models::ConnectRequest;
-use breez_sdk_ark::models::{Config, Network};
+use breez_sdk_ark::models::{ArkOperator, Config, LoggingConfig, MnemonicSource, Network};
 use breez_sdk_ark::{connect, BreezSdk, EventListener, SdkEvent};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use commands::CommandResult;
-use commands::{execute_command, Commands};
-use log::{error, info};
+use commands::{execute_command, CommandContext, Commands};
+use log::{error, info, LevelFilter};
 use rustyline::error::ReadlineError;
 use rustyline::hint::HistoryHinter;
 use rustyline::Editor;
-use std::{fs, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
 #[derive(Parser)]
 #[command(version, about = "CLI client for Breez SDK with Ark", long_about = None)]
@@ -28,6 +40,46 @@ struct Cli {
     /// Network to use (bitcoin, testnet, signet, regtest)
     #[arg(long, default_value = "regtest")]
     network: String,
+
+    /// Run the commands in a file, one per line, instead of starting the interactive shell
+    #[arg(long, conflicts_with = "eval")]
+    script: Option<PathBuf>,
+
+    /// Run a command instead of starting the interactive shell. Can be repeated
+    #[arg(long)]
+    eval: Vec<String>,
+
+    /// Use the wallet of a profile, with its own data directory, mnemonic and history
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Write the HTTP and gRPC traffic traces to wire.log in the data directory
+    #[arg(long)]
+    wire_traces: bool,
+
+    /// Never print secrets such as the mnemonic or notes, e.g. while sharing the screen
+    #[arg(long)]
+    no_secrets: bool,
+
+    /// Ark server to use instead of the network's default. Kept for the next runs
+    #[arg(long)]
+    ark_server_url: Option<String>,
+
+    /// Esplora server to use instead of the network's default. Kept for the next runs
+    #[arg(long)]
+    esplora_url: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+enum CliCommand {
+    /// Manage wallet profiles
+    Profiles {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
 }
 
 fn expand_path(path: &str) -> PathBuf {
@@ -64,35 +116,122 @@ impl EventListener for CliEventListener {
     }
 }
 
-async fn run_interactive_mode(data_dir: PathBuf, network: Network) -> Result<()> {
-    // Create data directory if it doesn't exist
-    fs::create_dir_all(&data_dir)?;
-
-    // Initialize persistence
-    let persistence = CliPersistence {
-        data_dir: data_dir.clone(),
+/// Builds the SDK configuration of the network, using the configured server URLs if any
+///
+/// Networks without default servers can be used once both server URLs are configured.
+fn build_config(network: Network, data_dir: &Path, cli_config: CliConfig) -> Result<Config> {
+    let data_dir = data_dir.to_string_lossy().to_string();
+    let mut config = match Config::default_config(network.clone(), data_dir.clone()) {
+        Ok(config) => config,
+        Err(_) if cli_config.ark_server_url.is_some() && cli_config.esplora_url.is_some() => {
+            // Only the network differs from the preset, the URLs are replaced below
+            Config {
+                network,
+                ..Config::for_operator(ArkOperator::LocalRegtest, data_dir)
+            }
+        }
+        Err(e) => {
+            return Err(anyhow!(
+                "{e}: there are no default servers for {network}, set --ark-server-url and --esplora-url"
+            ))
+        }
     };
 
+    if let Some(ark_server_url) = cli_config.ark_server_url {
+        config.ark_server_url = ark_server_url;
+        // The key pinned by the preset belongs to its own server
+        config.expected_server_pubkey = None;
+    }
+    if let Some(esplora_url) = cli_config.esplora_url {
+        config.esplora_url = esplora_url;
+    }
+    Ok(config)
+}
+
+/// Connects to the SDK with the mnemonic stored in the data directory
+async fn connect_sdk(config: Config, persistence: &CliPersistence) -> Result<BreezSdk> {
     // Get or create mnemonic
     let mnemonic = persistence.get_or_create_mnemonic()?;
-    println!("Using mnemonic: {}", mnemonic);
-
-    // Create SDK configuration
-    let config = Config::default_config(network, data_dir.to_string_lossy().to_string())?;
-
-    // Initialize logging
-    BreezSdk::init_logging(&data_dir.to_string_lossy(), None)?;
 
     // Connect to the SDK
     let sdk = connect(ConnectRequest {
         config,
-        mnemonic: mnemonic.to_string(),
+        mnemonic: MnemonicSource::Existing {
+            mnemonic: mnemonic.clone(),
+        },
     })
     .await?;
 
     // Register event listener
     let _listener_id = sdk.add_event_listener(Box::new(CliEventListener {}));
 
+    Ok(sdk)
+}
+
+/// Runs commands non-interactively, printing the JSON result of each one
+///
+/// Empty lines and lines starting with `#` are skipped. Execution stops at the first command
+/// that fails, so scripts can be used as smoke tests.
+async fn run_commands(
+    data_dir: PathBuf,
+    config: Config,
+    no_secrets: bool,
+    commands: Vec<String>,
+) -> Result<()> {
+    fs::create_dir_all(&data_dir)?;
+    let persistence = CliPersistence {
+        data_dir: data_dir.clone(),
+        network: config.network.clone(),
+    };
+    let sdk = connect_sdk(config, &persistence).await?;
+    let context = CommandContext {
+        persistence: &persistence,
+        no_secrets,
+    };
+
+    let mut result = Ok(());
+    for line in commands.iter().map(|line| line.trim()) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let res = match parse_command(line) {
+            Ok(Commands::Exit {}) => break,
+            Ok(cmd) => execute_command(cmd, &sdk, &context).await,
+            Err(e) => Err(e),
+        };
+        let failed = res.is_err();
+        show_results(res)?;
+        if failed {
+            result = Err(anyhow!("Command failed: {line}"));
+            break;
+        }
+    }
+
+    sdk.disconnect_async().await?;
+    result
+}
+
+async fn run_interactive_mode(data_dir: PathBuf, config: Config, no_secrets: bool) -> Result<()> {
+    // Create data directory if it doesn't exist
+    fs::create_dir_all(&data_dir)?;
+
+    // Initialize persistence
+    let persistence = CliPersistence {
+        data_dir: data_dir.clone(),
+        network: config.network.clone(),
+    };
+
+    let sdk = connect_sdk(config, &persistence).await?;
+    let context = CommandContext {
+        persistence: &persistence,
+        no_secrets,
+    };
+
+    // Show progress of rounds and sends while commands run
+    let progress = ProgressListener::default();
+    let _progress_id = sdk.add_event_listener(Box::new(progress.clone()));
+
     // Initialize rustyline
     let helper = CliHelper {
         hinter: HistoryHinter {},
@@ -129,7 +268,8 @@ async fn run_interactive_mode(data_dir: PathBuf, network: Network) -> Result<()>
                             break;
                         }
 
-                        let res = execute_command(cmd, &sdk).await;
+                        let res = execute_command(cmd, &sdk, &context).await;
+                        progress.clear();
                         show_results(res)?;
                     }
                     Err(e) => println!("Error parsing command: {}", e),
@@ -156,7 +296,7 @@ async fn run_interactive_mode(data_dir: PathBuf, network: Network) -> Result<()>
     }
 
     // Stop the SDK
-    sdk.disconnect()?;
+    sdk.disconnect_async().await?;
 
     Ok(())
 }
@@ -182,6 +322,28 @@ async fn main() -> Result<(), anyhow::Error> {
     // Expand data directory path
     let data_dir = expand_path(&cli.data_dir);
 
+    // Manage profiles without connecting to any wallet
+    if let Some(CliCommand::Profiles { command }) = cli.command {
+        return profiles::execute_profile_command(&data_dir, command);
+    }
+
+    // Each profile keeps its wallet in its own directory
+    let data_dir = match &cli.profile {
+        Some(profile) => profiles::profile_dir(&data_dir, profile)?,
+        None => data_dir,
+    };
+
+    // Initialize logging, keeping the CLI's own lines next to the SDK's
+    BreezSdk::init_logging_with_config(
+        &data_dir.to_string_lossy(),
+        None,
+        LoggingConfig {
+            target_levels: HashMap::from([("breez_sdk_ark_cli".to_string(), LevelFilter::Debug)]),
+            wire_traces: cli.wire_traces,
+            ..LoggingConfig::default()
+        },
+    )?;
+
     // Parse network
     let network = match cli.network.to_lowercase().as_str() {
         "bitcoin" | "mainnet" => Network::Bitcoin,
@@ -191,6 +353,35 @@ async fn main() -> Result<(), anyhow::Error> {
         _ => return Err(anyhow!("Invalid network: {}", cli.network)),
     };
 
+    // Server URLs given on the command line are kept for the next runs
+    let persistence = CliPersistence {
+        data_dir: data_dir.clone(),
+        network: network.clone(),
+    };
+    let mut cli_config = persistence.load_config()?;
+    if cli.ark_server_url.is_some() || cli.esplora_url.is_some() {
+        cli_config.ark_server_url = cli.ark_server_url.or(cli_config.ark_server_url);
+        cli_config.esplora_url = cli.esplora_url.or(cli_config.esplora_url);
+        persistence.save_config(&cli_config)?;
+    }
+    let config = build_config(network, &data_dir, cli_config)?;
+
+    // Run scripted commands, if any, otherwise start the interactive shell
+    if let Some(script) = cli.script {
+        let commands = fs::read_to_string(&script)
+            .map_err(|e| anyhow!("Failed to read script {}: {e}", script.display()))?;
+        return run_commands(
+            data_dir,
+            config,
+            cli.no_secrets,
+            commands.lines().map(String::from).collect(),
+        )
+        .await;
+    }
+    if !cli.eval.is_empty() {
+        return run_commands(data_dir, config, cli.no_secrets, cli.eval).await;
+    }
+
     // Run in interactive mode
-    run_interactive_mode(data_dir, network).await
+    run_interactive_mode(data_dir, config, cli.no_secrets).await
 }
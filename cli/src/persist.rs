@@ -1,35 +1,81 @@
 use std::{
     fs, io,
     path::{Path, PathBuf},
-    str::FromStr,
 };
 
 use anyhow::{bail, Result};
-use bip39::{Language, Mnemonic};
+use breez_sdk_ark::models::Network;
+use breez_sdk_ark::{BreezSdk, GenerateMnemonicRequest, MnemonicWordCount};
+use serde::{Deserialize, Serialize};
+
+use crate::denomination::Denomination;
 
 const PHRASE_FILE_NAME: &str = "phrase";
 const HISTORY_FILE_NAME: &str = "history.txt";
+const CONFIG_FILE_PREFIX: &str = "cli_config";
+
+/// Server URLs overriding the defaults of the network and display settings, kept across runs
+///
+/// Each network has its own file, so servers set for one network aren't used on another.
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct CliConfig {
+    pub(crate) ark_server_url: Option<String>,
+    pub(crate) esplora_url: Option<String>,
+    #[serde(default)]
+    pub(crate) denomination: Denomination,
+}
 
 pub(crate) struct CliPersistence {
     pub(crate) data_dir: PathBuf,
+    pub(crate) network: Network,
 }
 
-impl CliPersistence {
-    pub(crate) fn get_or_create_mnemonic(&self) -> Result<Mnemonic> {
-        let filename = Path::new(&self.data_dir).join(PHRASE_FILE_NAME);
-
-        let mnemonic = match fs::read_to_string(filename.clone()) {
-            Ok(phrase) => Mnemonic::from_str(&phrase)?,
-            Err(e) => {
-                if e.kind() != io::ErrorKind::NotFound {
-                    bail!("Can't read from file: {}, err {e}", filename.display());
-                }
-                let mnemonic = Mnemonic::generate_in(Language::English, 12)?;
-                fs::write(filename, mnemonic.to_string())?;
-                mnemonic
+/// Reads the mnemonic stored in a data directory, generating one if there is none yet
+///
+/// All networks of a data directory share the mnemonic.
+pub(crate) fn get_or_create_mnemonic(data_dir: &Path) -> Result<String> {
+    let filename = data_dir.join(PHRASE_FILE_NAME);
+
+    let mnemonic = match fs::read_to_string(filename.clone()) {
+        Ok(phrase) => phrase.trim().to_string(),
+        Err(e) => {
+            if e.kind() != io::ErrorKind::NotFound {
+                bail!("Can't read from file: {}, err {e}", filename.display());
             }
-        };
-        Ok(mnemonic)
+            let mnemonic = BreezSdk::generate_mnemonic(GenerateMnemonicRequest {
+                word_count: MnemonicWordCount::Words12,
+                language: None,
+            })?
+            .mnemonic;
+            fs::write(filename, &mnemonic)?;
+            mnemonic
+        }
+    };
+    Ok(mnemonic)
+}
+
+impl CliPersistence {
+    pub(crate) fn get_or_create_mnemonic(&self) -> Result<String> {
+        get_or_create_mnemonic(&self.data_dir)
+    }
+
+    fn config_file(&self) -> PathBuf {
+        Path::new(&self.data_dir).join(format!("{CONFIG_FILE_PREFIX}_{}.json", self.network))
+    }
+
+    pub(crate) fn load_config(&self) -> Result<CliConfig> {
+        let filename = self.config_file();
+        match fs::read_to_string(&filename) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(CliConfig::default()),
+            Err(e) => bail!("Can't read from file: {}, err {e}", filename.display()),
+        }
+    }
+
+    pub(crate) fn save_config(&self, config: &CliConfig) -> Result<()> {
+        fs::create_dir_all(&self.data_dir)?;
+        fs::write(self.config_file(), serde_json::to_string_pretty(config)?)?;
+        Ok(())
     }
 
     pub(crate) fn history_file(&self) -> String {
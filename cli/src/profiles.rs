@@ -0,0 +1,94 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use clap::Subcommand;
+
+use crate::persist;
+
+const PROFILES_DIR_NAME: &str = "profiles";
+
+/// Manage wallet profiles, each with its own data directory, mnemonic and history
+#[derive(Clone, Subcommand)]
+pub(crate) enum ProfileCommand {
+    /// List the existing profiles
+    List {},
+
+    /// Create a profile with a new mnemonic
+    Create {
+        /// The profile name
+        name: String,
+    },
+
+    /// Delete a profile, including its mnemonic
+    Delete {
+        /// The profile name
+        name: String,
+
+        /// Delete without asking for confirmation
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Data directory of a profile, nested in the main data directory
+pub(crate) fn profile_dir(data_dir: &Path, name: &str) -> Result<PathBuf> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        bail!("Invalid profile name {name}, use letters, digits, '-' and '_' only");
+    }
+    Ok(data_dir.join(PROFILES_DIR_NAME).join(name))
+}
+
+pub(crate) fn execute_profile_command(data_dir: &Path, command: ProfileCommand) -> Result<()> {
+    match command {
+        ProfileCommand::List {} => {
+            let mut names: Vec<String> = match fs::read_dir(data_dir.join(PROFILES_DIR_NAME)) {
+                Ok(entries) => entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_dir())
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .collect(),
+                Err(_) => Vec::new(),
+            };
+            names.sort();
+            for name in names {
+                println!("{name}");
+            }
+        }
+        ProfileCommand::Create { name } => {
+            let dir = profile_dir(data_dir, &name)?;
+            if dir.exists() {
+                bail!("Profile {name} already exists");
+            }
+            fs::create_dir_all(&dir)?;
+            persist::get_or_create_mnemonic(&dir)?;
+            println!("Created profile {name}");
+        }
+        ProfileCommand::Delete { name, force } => {
+            let dir = profile_dir(data_dir, &name)?;
+            if !dir.exists() {
+                bail!("Profile {name} doesn't exist");
+            }
+            if !force {
+                print!("Delete profile {name}? Its mnemonic can't be recovered (y/n): ");
+                std::io::stdout().flush()?;
+                let mut buf = String::new();
+                std::io::stdin().read_line(&mut buf)?;
+                if !buf.trim().eq_ignore_ascii_case("y") {
+                    println!("Profile deletion cancelled.");
+                    return Ok(());
+                }
+            }
+            fs::remove_dir_all(&dir)?;
+            println!("Deleted profile {name}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use breez_sdk_ark::{EventListener, SdkEvent};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Shows a spinner while a round or a send is in progress, driven by SDK events
+///
+/// Clones share the same spinner, so one can be registered as event listener while another
+/// clears the spinner once the command returns.
+#[derive(Clone, Default)]
+pub(crate) struct ProgressListener {
+    spinner: Arc<Mutex<Option<ProgressBar>>>,
+}
+
+impl ProgressListener {
+    fn start(&self, message: &str) {
+        let mut spinner = self.spinner.lock().unwrap();
+        if let Some(spinner) = spinner.as_ref() {
+            spinner.set_message(message.to_string());
+            return;
+        }
+
+        let bar = ProgressBar::new_spinner();
+        if let Ok(style) = ProgressStyle::with_template("{spinner} {msg} ({elapsed})") {
+            bar.set_style(style);
+        }
+        bar.set_message(message.to_string());
+        bar.enable_steady_tick(TICK_INTERVAL);
+        *spinner = Some(bar);
+    }
+
+    fn finish(&self, message: &str) {
+        if let Some(spinner) = self.spinner.lock().unwrap().take() {
+            spinner.finish_with_message(message.to_string());
+        }
+    }
+
+    /// Removes the spinner, e.g. when the command failed before the operation was over
+    pub(crate) fn clear(&self) {
+        if let Some(spinner) = self.spinner.lock().unwrap().take() {
+            spinner.finish_and_clear();
+        }
+    }
+}
+
+impl EventListener for ProgressListener {
+    fn on_event(&self, event: &SdkEvent) {
+        match event {
+            SdkEvent::RoundStarted {} => self.start("Registered in round, waiting to sign..."),
+            SdkEvent::RoundFinished { success: true } => self.finish("Round completed"),
+            SdkEvent::RoundFinished { success: false } => self.finish("Round failed"),
+            SdkEvent::PaymentSending { .. } => self.start("Signing payment with the Ark server..."),
+            SdkEvent::PaymentPending { .. } => self.finish("Payment accepted by the Ark server"),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::process::Command;
+
+use anyhow::{anyhow, bail, Result};
+use bitcoin::Amount;
+use breez_sdk_ark::models::Network;
+use breez_sdk_ark::{BreezSdk, GetInfoRequest};
+
+/// Environment variable holding the command used to reach bitcoind, e.g. `bitcoin-cli -regtest`
+const BITCOIN_CLI_ENV: &str = "BREEZ_BITCOIN_CLI";
+/// Command used when none is configured, matching the usual nigiri based Ark regtest setup
+const DEFAULT_BITCOIN_CLI: &str = "nigiri rpc";
+
+/// Fails unless the SDK is connected to regtest, where funds and blocks are free
+pub(crate) async fn ensure_regtest(sdk: &BreezSdk) -> Result<()> {
+    let info = sdk.get_info(GetInfoRequest {}).await?;
+    if !matches!(info.network, Network::Regtest) {
+        bail!("This command is only available on regtest");
+    }
+    Ok(())
+}
+
+/// Sends funds from the bitcoind wallet to an address, returning the transaction ID
+pub(crate) fn send_to_address(address: &str, amount_sat: u64) -> Result<String> {
+    let amount_btc = Amount::from_sat(amount_sat).to_btc().to_string();
+    bitcoin_cli(&["sendtoaddress", address, &amount_btc])
+}
+
+/// Mines blocks to the bitcoind wallet, returning the hashes of the mined blocks
+pub(crate) fn mine(blocks: u32) -> Result<Vec<String>> {
+    let address = bitcoin_cli(&["getnewaddress"])?;
+    let hashes = bitcoin_cli(&["generatetoaddress", &blocks.to_string(), &address])?;
+    Ok(serde_json::from_str(&hashes)?)
+}
+
+fn bitcoin_cli(args: &[&str]) -> Result<String> {
+    let command = std::env::var(BITCOIN_CLI_ENV).unwrap_or(DEFAULT_BITCOIN_CLI.to_string());
+    let mut parts = shlex::split(&command)
+        .ok_or_else(|| anyhow!("Invalid {BITCOIN_CLI_ENV}: {command}"))?
+        .into_iter();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow!("{BITCOIN_CLI_ENV} is empty"))?;
+
+    let output = Command::new(&program)
+        .args(parts)
+        .args(args)
+        .output()
+        .map_err(|e| {
+            anyhow!("Failed to run {command}, set {BITCOIN_CLI_ENV} to reach bitcoind: {e}")
+        })?;
+    if !output.status.success() {
+        bail!(
+            "{command} {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,160 @@
+use anyhow::Result;
+use breez_sdk_ark::{BreezSdk, EventListener, SdkEvent};
+use console::{style, StyledObject};
+use tokio::sync::mpsc;
+
+/// Forwards events to the `watch` command, which prints them from the CLI task
+struct WatchListener {
+    sender: mpsc::UnboundedSender<SdkEvent>,
+}
+
+impl EventListener for WatchListener {
+    fn on_event(&self, event: &SdkEvent) {
+        // The receiver is gone once watching stopped, before the listener is removed
+        let _ = self.sender.send(event.clone());
+    }
+}
+
+/// Prints events as they happen until Ctrl-C is pressed, returning how many were printed
+pub(crate) async fn watch_events(sdk: &BreezSdk) -> Result<u32> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let listener_id = sdk.add_event_listener(Box::new(WatchListener { sender }));
+    println!("Watching events, press Ctrl-C to stop");
+
+    let mut count = 0;
+    let interrupted = tokio::signal::ctrl_c();
+    tokio::pin!(interrupted);
+    loop {
+        tokio::select! {
+            result = &mut interrupted => {
+                result?;
+                break;
+            }
+            Some(event) = receiver.recv() => {
+                print_event(&event);
+                count += 1;
+            }
+        }
+    }
+
+    sdk.remove_event_listener(&listener_id);
+    Ok(count)
+}
+
+fn print_event(event: &SdkEvent) {
+    let time = chrono::Local::now().format("%H:%M:%S");
+    let (label, details) = describe(event);
+    println!("{} {} {details}", style(time).dim(), label);
+}
+
+/// Colored label and one-line summary of an event
+fn describe(event: &SdkEvent) -> (StyledObject<&'static str>, String) {
+    match event {
+        SdkEvent::InitialStateLoaded { balance } => (
+            style("STATE").blue(),
+            format!(
+                "Cached balance: {} sats",
+                balance.pending_sats + balance.confirmed_sats
+            ),
+        ),
+        SdkEvent::Synced {} => (style("SYNC").blue(), "Wallet synced".to_string()),
+        SdkEvent::SyncedFirstTime {} => (
+            style("SYNC").blue(),
+            "Wallet synced for the first time".to_string(),
+        ),
+        SdkEvent::NewBlock { height } => (style("BLOCK").blue(), format!("Height {height}")),
+        SdkEvent::BalanceChanged {
+            current,
+            delta_sats,
+            ..
+        } => (
+            style("BALANCE").cyan(),
+            format!(
+                "{delta_sats:+} sats, now {} sats",
+                current.pending_sats + current.confirmed_sats
+            ),
+        ),
+        SdkEvent::PaymentSucceeded { payment } => (
+            style("PAYMENT").green().bold(),
+            format!(
+                "{} {} sats completed ({})",
+                payment.payment_type, payment.amount, payment.id
+            ),
+        ),
+        SdkEvent::PaymentPending { payment, .. } => (
+            style("PAYMENT").green(),
+            format!(
+                "{} {} sats pending ({})",
+                payment.payment_type, payment.amount, payment.id
+            ),
+        ),
+        SdkEvent::PaymentReceived {
+            payment,
+            payment_request_id,
+        } => (
+            style("RECEIVED").green().bold(),
+            format!(
+                "{} sats for request {payment_request_id}{}",
+                payment.amount,
+                payment
+                    .deposit_source
+                    .as_ref()
+                    .map(|source| format!(", deposited from {source}"))
+                    .unwrap_or_default()
+            ),
+        ),
+        SdkEvent::PaymentSending {
+            destination,
+            amount_sat,
+            ..
+        } => (
+            style("SENDING").green(),
+            format!("{amount_sat} sats to {destination}"),
+        ),
+        SdkEvent::PaymentProgress { send_id, step } => {
+            (style("SENDING").green(), format!("{step:?} ({send_id})"))
+        }
+        SdkEvent::PaymentCancelled { payment } => (
+            style("CANCELLED").yellow(),
+            format!("{} sats ({})", payment.amount, payment.id),
+        ),
+        SdkEvent::PaymentExpired { payment } => (
+            style("EXPIRED").yellow(),
+            format!("{} sats ({})", payment.amount, payment.id),
+        ),
+        SdkEvent::RoundStarted {} => (
+            style("ROUND").magenta(),
+            "Registered in a round".to_string(),
+        ),
+        SdkEvent::RoundFinished { success } => (
+            style("ROUND").magenta(),
+            match success {
+                true => "Round completed".to_string(),
+                false => "Round failed".to_string(),
+            },
+        ),
+        SdkEvent::ServerInconsistency { details } => {
+            (style("SERVER").red().bold(), details.clone())
+        }
+        SdkEvent::RoundVerificationFailed { txid, reason } => (
+            style("ROUND").red().bold(),
+            format!("Verification of {txid} failed: {reason}"),
+        ),
+        SdkEvent::ClockSkewDetected { skew_secs } => (
+            style("CLOCK").red().bold(),
+            format!("Local clock is off by {skew_secs}s from the chain"),
+        ),
+        SdkEvent::ConfigUpdated { fields } => (
+            style("CONFIG").blue(),
+            format!("Updated {}", fields.join(", ")),
+        ),
+        SdkEvent::ConnectionStateChanged {
+            service,
+            previous,
+            current,
+        } => (
+            style("CONNECTION").yellow(),
+            format!("{service:?}: {previous:?} -> {current:?}"),
+        ),
+    }
+}
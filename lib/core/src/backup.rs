@@ -0,0 +1,256 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::error::SdkError;
+use crate::models::{Note, Payment};
+
+/// Prefix of every exported state chunk
+const CHUNK_PREFIX: &str = "arkstate";
+/// Version of the exported state format
+const STATE_VERSION: u8 = 1;
+/// Domain separator for the export key, so it can't collide with any other key derived from the seed
+const KEY_DOMAIN: &[u8] = b"breez-sdk-ark/state-export";
+const NONCE_LEN: usize = 12;
+/// Default size of a chunk's payload, small enough for a comfortably scannable QR code
+pub(crate) const DEFAULT_CHUNK_SIZE: usize = 300;
+/// Smallest payload accepted per chunk
+const MIN_CHUNK_SIZE: usize = 32;
+/// Most chunks an export can be split into, bounding what an import allocates for the chunk
+/// count a chunk claims
+const MAX_CHUNKS: usize = 10_000;
+
+/// Wallet state that can't be recovered from the mnemonic and the Ark server alone
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ExportedState {
+    pub(crate) version: u8,
+    /// Payments that are still pending or carry a user provided description
+    pub(crate) payments: Vec<Payment>,
+    /// Notes created by the wallet, including the keys needed to reclaim them
+    pub(crate) notes: Vec<Note>,
+}
+
+/// Derives the key used to encrypt exported state from the wallet seed
+///
+/// Only a wallet restored from the same mnemonic can decrypt the export.
+pub(crate) fn derive_key(seed: &[u8]) -> [u8; 32] {
+    let mut engine = sha256::Hash::engine();
+    engine.input(KEY_DOMAIN);
+    engine.input(seed);
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+/// Encrypts the state and splits it into chunks of at most `chunk_size` payload characters
+pub(crate) fn export(
+    key: &[u8; 32],
+    state: &ExportedState,
+    chunk_size: usize,
+) -> Result<Vec<String>, SdkError> {
+    if chunk_size < MIN_CHUNK_SIZE {
+        return Err(SdkError::GenericError(format!(
+            "Chunk size must be at least {MIN_CHUNK_SIZE}"
+        )));
+    }
+
+    let plaintext = serde_json::to_vec(state)
+        .map_err(|e| SdkError::GenericError(format!("Failed to serialize state: {e}")))?;
+
+    let mut nonce = [0u8; NONCE_LEN];
+    StdRng::from_entropy().fill_bytes(&mut nonce);
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|e| SdkError::GenericError(format!("Failed to encrypt state: {e}")))?;
+
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    let encoded = URL_SAFE_NO_PAD.encode(blob);
+
+    // The encoding is ASCII, so splitting on bytes never breaks a character
+    let parts: Vec<&[u8]> = encoded.as_bytes().chunks(chunk_size).collect();
+    let total = parts.len();
+    if total > MAX_CHUNKS {
+        return Err(SdkError::GenericError(format!(
+            "The state needs {total} chunks, more than {MAX_CHUNKS}, use a larger chunk size"
+        )));
+    }
+    Ok(parts
+        .into_iter()
+        .enumerate()
+        .map(|(index, part)| {
+            format!(
+                "{CHUNK_PREFIX}:{}/{total}:{}",
+                index + 1,
+                String::from_utf8_lossy(part)
+            )
+        })
+        .collect())
+}
+
+/// Reassembles and decrypts chunks produced by [export]
+///
+/// Chunks can be given in any order and may repeat, as happens when scanning an animated QR code.
+pub(crate) fn import(key: &[u8; 32], chunks: &[String]) -> Result<ExportedState, SdkError> {
+    let mut parts: Vec<Option<&str>> = Vec::new();
+    for chunk in chunks {
+        let (index, total, payload) = parse_chunk(chunk)?;
+        if parts.is_empty() {
+            parts = vec![None; total];
+        } else if parts.len() != total {
+            return Err(SdkError::InvalidInput(
+                "Chunks belong to different exports".to_string(),
+            ));
+        }
+        parts[index - 1] = Some(payload);
+    }
+
+    let missing: Vec<String> = parts
+        .iter()
+        .enumerate()
+        .filter(|(_, part)| part.is_none())
+        .map(|(index, _)| (index + 1).to_string())
+        .collect();
+    if parts.is_empty() || !missing.is_empty() {
+        return Err(SdkError::InvalidInput(format!(
+            "Missing chunks: {}",
+            missing.join(", ")
+        )));
+    }
+
+    let encoded: String = parts.into_iter().flatten().collect();
+    let blob = URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(|e| SdkError::InvalidInput(format!("Invalid state encoding: {e}")))?;
+    if blob.len() < NONCE_LEN {
+        return Err(SdkError::InvalidInput("State is too short".to_string()));
+    }
+
+    let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+    let plaintext = ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            SdkError::InvalidInput(
+                "State can't be decrypted, it was exported by a different wallet".to_string(),
+            )
+        })?;
+
+    let state: ExportedState = serde_json::from_slice(&plaintext)
+        .map_err(|e| SdkError::InvalidInput(format!("Invalid state: {e}")))?;
+    if state.version != STATE_VERSION {
+        return Err(SdkError::InvalidInput(format!(
+            "Unsupported state version: {}",
+            state.version
+        )));
+    }
+    Ok(state)
+}
+
+/// Creates a new state ready to be exported
+pub(crate) fn new_state(payments: Vec<Payment>, notes: Vec<Note>) -> ExportedState {
+    ExportedState {
+        version: STATE_VERSION,
+        payments,
+        notes,
+    }
+}
+
+/// Splits a chunk into its 1-based index, the total number of chunks and its payload
+fn parse_chunk(chunk: &str) -> Result<(usize, usize, &str), SdkError> {
+    let invalid = || SdkError::InvalidInput(format!("Invalid state chunk: {chunk}"));
+
+    let mut fields = chunk.trim().splitn(3, ':');
+    if fields.next() != Some(CHUNK_PREFIX) {
+        return Err(invalid());
+    }
+    let (index, total) = fields
+        .next()
+        .and_then(|f| f.split_once('/'))
+        .ok_or_else(invalid)?;
+    let index: usize = index.parse().map_err(|_| invalid())?;
+    let total: usize = total.parse().map_err(|_| invalid())?;
+    let payload = fields.next().ok_or_else(invalid)?;
+    if index == 0 || index > total || total > MAX_CHUNKS {
+        return Err(invalid());
+    }
+    Ok((index, total, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NoteStatus, PaymentStatus, PaymentType};
+
+    fn test_state() -> ExportedState {
+        new_state(
+            vec![Payment {
+                id: "payment".to_string(),
+                payment_type: PaymentType::Sent,
+                status: PaymentStatus::Pending,
+                amount: 1_000,
+                fees: 10,
+                timestamp: 1_700_000_000,
+                description: Some("Coffee".to_string()),
+                destination: None,
+                is_retryable: false,
+                details: None,
+                onchain_txs: Vec::new(),
+                order_id: None,
+                deposit_source: None,
+                failure_reason: None,
+            }],
+            vec![Note {
+                id: "note".to_string(),
+                note: "arkgift1".to_string(),
+                amount_sat: 21_000,
+                created_at: 1_700_000_000,
+                expires_at: 1_700_086_400,
+                status: NoteStatus::Reserved,
+            }],
+        )
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let key = derive_key(&[1u8; 64]);
+        let mut chunks = export(&key, &test_state(), MIN_CHUNK_SIZE).unwrap();
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.starts_with(CHUNK_PREFIX)));
+
+        // Animated QR codes are scanned out of order and with repeats
+        chunks.reverse();
+        chunks.push(chunks[0].clone());
+        let imported = import(&key, &chunks).unwrap();
+        assert_eq!(
+            serde_json::to_string(&test_state()).unwrap(),
+            serde_json::to_string(&imported).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_import_rejects_missing_chunks() {
+        let key = derive_key(&[1u8; 64]);
+        let mut chunks = export(&key, &test_state(), MIN_CHUNK_SIZE).unwrap();
+        chunks.remove(1);
+        assert!(import(&key, &chunks).is_err());
+    }
+
+    #[test]
+    fn test_import_rejects_too_many_chunks() {
+        let key = derive_key(&[1u8; 64]);
+        let chunk = format!("{CHUNK_PREFIX}:1/{}:payload", usize::MAX);
+        assert!(matches!(
+            import(&key, &[chunk]),
+            Err(SdkError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_import_rejects_other_wallet() {
+        let chunks = export(&derive_key(&[1u8; 64]), &test_state(), DEFAULT_CHUNK_SIZE).unwrap();
+        assert!(import(&derive_key(&[2u8; 64]), &chunks).is_err());
+    }
+}
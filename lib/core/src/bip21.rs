@@ -0,0 +1,212 @@
+use bitcoin::{Amount, Denomination};
+
+use crate::error::SdkError;
+use crate::models::PayAmount;
+
+/// URI scheme of BIP21 payment requests
+const BIP21_SCHEME: &str = "bitcoin:";
+/// Query parameter carrying an Ark address the receiver can be paid offchain with
+const ARK_PARAM: &str = "ark";
+
+/// A parsed BIP21 payment request
+#[derive(Debug, PartialEq)]
+pub(crate) struct Bip21 {
+    /// The onchain address, which may be left out when an Ark address is given
+    pub(crate) address: Option<String>,
+    /// The requested amount in satoshis
+    pub(crate) amount_sat: Option<u64>,
+    /// The Ark address from the `ark` parameter
+    pub(crate) ark_address: Option<String>,
+}
+
+/// Whether the input is a BIP21 URI
+pub(crate) fn is_bip21(input: &str) -> bool {
+    input
+        .trim()
+        .get(..BIP21_SCHEME.len())
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case(BIP21_SCHEME))
+}
+
+/// Parses a BIP21 URI, keeping the parameters relevant to paying it
+///
+/// Parameters starting with `req-` must be understood by the payer, so URIs with any of them
+/// are rejected.
+pub(crate) fn parse_bip21(uri: &str) -> Result<Bip21, SdkError> {
+    let uri = uri.trim();
+    if !is_bip21(uri) {
+        return Err(SdkError::InvalidInput(format!("Not a BIP21 URI: {uri}")));
+    }
+
+    let (address, query) = uri[BIP21_SCHEME.len()..]
+        .split_once('?')
+        .unwrap_or((&uri[BIP21_SCHEME.len()..], ""));
+    let mut bip21 = Bip21 {
+        address: (!address.is_empty()).then(|| address.to_string()),
+        amount_sat: None,
+        ark_address: None,
+    };
+
+    for param in query.split('&').filter(|param| !param.is_empty()) {
+        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+        let key = key.to_lowercase();
+        match key.as_str() {
+            "amount" => {
+                let amount = Amount::from_str_in(value, Denomination::Bitcoin).map_err(|e| {
+                    SdkError::InvalidInput(format!("Invalid BIP21 amount {value}: {e}"))
+                })?;
+                bip21.amount_sat = Some(amount.to_sat());
+            }
+            ARK_PARAM => bip21.ark_address = Some(value.to_string()),
+            _ if key.starts_with("req-") => {
+                return Err(SdkError::InvalidInput(format!(
+                    "Unsupported required BIP21 parameter: {key}"
+                )))
+            }
+            _ => {}
+        }
+    }
+
+    if bip21.address.is_none() && bip21.ark_address.is_none() {
+        return Err(SdkError::InvalidInput(format!(
+            "BIP21 URI has no address to pay: {uri}"
+        )));
+    }
+    Ok(bip21)
+}
+
+/// Composes a BIP21 URI from an onchain and/or an Ark address
+///
+/// The onchain address is left out of URIs only paying an Ark address, as `bitcoin:?ark=...`.
+pub(crate) fn compose_bip21(
+    address: Option<&str>,
+    amount_sat: Option<u64>,
+    ark_address: Option<&str>,
+) -> String {
+    let mut params = Vec::new();
+    if let Some(amount_sat) = amount_sat {
+        params.push(format!(
+            "amount={}",
+            Amount::from_sat(amount_sat).to_string_in(Denomination::Bitcoin)
+        ));
+    }
+    if let Some(ark_address) = ark_address {
+        params.push(format!("{ARK_PARAM}={ark_address}"));
+    }
+
+    let mut uri = format!("{BIP21_SCHEME}{}", address.unwrap_or_default());
+    if !params.is_empty() {
+        uri.push('?');
+        uri.push_str(&params.join("&"));
+    }
+    uri
+}
+
+/// Resolves the amount to pay for a BIP21 URI
+///
+/// A URI with an amount rejects any other amount, while a URI without one requires the caller
+/// to specify it.
+pub(crate) fn resolve_amount(
+    uri_amount_sat: Option<u64>,
+    amount: Option<&PayAmount>,
+) -> Result<u64, SdkError> {
+    match (uri_amount_sat, amount) {
+        (Some(amount_sat), None) => Ok(amount_sat),
+        (
+            Some(amount_sat),
+            Some(PayAmount::Specific {
+                receiver_amount_sat,
+            }),
+        ) if *receiver_amount_sat == amount_sat => Ok(amount_sat),
+        (Some(amount_sat), Some(_)) => Err(SdkError::AmountMismatch(format!(
+            "The URI requests {amount_sat} sats, a different amount can't be paid"
+        ))),
+        (
+            None,
+            Some(PayAmount::Specific {
+                receiver_amount_sat,
+            }),
+        ) => Ok(*receiver_amount_sat),
+        (None, Some(PayAmount::Drain)) => Err(SdkError::AmountRequired(
+            "BIP21 URIs can't be drained to, specify the amount to pay".to_string(),
+        )),
+        (None, None) => Err(SdkError::AmountRequired(
+            "The URI has no amount, specify the amount to pay".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bip21() {
+        assert_eq!(
+            Bip21 {
+                address: Some("bcrt1qaddress".to_string()),
+                amount_sat: Some(150_000),
+                ark_address: Some("tark1address".to_string()),
+            },
+            parse_bip21("BITCOIN:bcrt1qaddress?amount=0.0015&label=Shop&ark=tark1address").unwrap()
+        );
+
+        // The onchain address can be left out when an Ark address is given
+        let bip21 = parse_bip21("bitcoin:?ark=tark1address").unwrap();
+        assert_eq!(None, bip21.address);
+        assert_eq!(None, bip21.amount_sat);
+
+        assert!(parse_bip21("bitcoin:?label=Shop").is_err());
+        assert!(parse_bip21("bitcoin:bcrt1qaddress?amount=abc").is_err());
+        assert!(parse_bip21("bitcoin:bcrt1qaddress?req-unknown=1").is_err());
+        assert!(parse_bip21("tark1address").is_err());
+    }
+
+    #[test]
+    fn test_compose_bip21() {
+        let address = "bcrt1q9ylz8ld5yqz7quevzp6ruzhsmpyvxhkwdfx2mq";
+        let uri = compose_bip21(Some(address), Some(12_345), None);
+        assert_eq!(format!("bitcoin:{address}?amount=0.00012345"), uri);
+        assert_eq!(
+            Bip21 {
+                address: Some(address.to_string()),
+                amount_sat: Some(12_345),
+                ark_address: None,
+            },
+            parse_bip21(&uri).unwrap()
+        );
+
+        let ark_address = "tark1qq4hfssprtcgnjzf8qlw2f78yvjau5kldfugg29k34y7j96q2w4t5";
+        let uri = compose_bip21(None, None, Some(ark_address));
+        assert_eq!(format!("bitcoin:?ark={ark_address}"), uri);
+        assert_eq!(
+            Some(ark_address.to_string()),
+            parse_bip21(&uri).unwrap().ark_address
+        );
+    }
+
+    #[test]
+    fn test_resolve_amount() {
+        let specific = |receiver_amount_sat| PayAmount::Specific {
+            receiver_amount_sat,
+        };
+
+        assert_eq!(1_000, resolve_amount(Some(1_000), None).unwrap());
+        assert_eq!(
+            1_000,
+            resolve_amount(Some(1_000), Some(&specific(1_000))).unwrap()
+        );
+        assert_eq!(2_000, resolve_amount(None, Some(&specific(2_000))).unwrap());
+        assert!(matches!(
+            resolve_amount(Some(1_000), Some(&specific(2_000))),
+            Err(SdkError::AmountMismatch(_))
+        ));
+        assert!(matches!(
+            resolve_amount(None, Some(&PayAmount::Drain)),
+            Err(SdkError::AmountRequired(_))
+        ));
+        assert!(matches!(
+            resolve_amount(None, None),
+            Err(SdkError::AmountRequired(_))
+        ));
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ark_client::{error::IntoError, Blockchain, Error, ExplorerUtxo, SpendStatus};
+use bitcoin::{Address, OutPoint, Transaction, Txid};
+use log::warn;
+
+use crate::chain::{self, ChainService, ChainTip};
+use crate::error::SdkError;
+
+/// How long the chain tip is trusted before checking for a new block
+const TIP_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+fn ark_error(e: SdkError) -> Error {
+    e.to_string().into_error()
+}
+
+/// Caches the chain data the wallet queries on every sync
+///
+/// This is how the Ark client reaches the [ChainService] the SDK was built with.
+///
+/// Transactions and spent outputs don't change until a reorg, so they're kept until the chain
+/// tip changes, either advancing or replaced by another block at the same height. Lookups that
+/// can change with the mempool, such as the outputs of an address or an output that is still
+/// unspent, always go to the wrapped blockchain.
+pub(crate) struct ChainCache {
+    inner: Arc<dyn ChainService>,
+    tip_check_interval: Duration,
+    min_confirmations: u32,
+    boarding_address: Mutex<Option<Address>>,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    tip: Option<ChainTip>,
+    tip_checked_at: Option<Instant>,
+    txs: HashMap<Txid, Transaction>,
+    spent_outputs: HashMap<OutPoint, Option<Txid>>,
+}
+
+impl ChainCache {
+    pub(crate) fn new(inner: Arc<dyn ChainService>) -> Self {
+        Self::with_tip_check_interval(inner, TIP_CHECK_INTERVAL)
+    }
+
+    fn with_tip_check_interval(inner: Arc<dyn ChainService>, tip_check_interval: Duration) -> Self {
+        Self {
+            inner,
+            tip_check_interval,
+            min_confirmations: 1,
+            boarding_address: Mutex::default(),
+            state: Mutex::default(),
+        }
+    }
+
+    /// Sets the confirmations a boarding output needs before the Ark client sees it as confirmed
+    ///
+    /// The Ark client boards every confirmed output of the boarding address, so its outputs with
+    /// fewer confirmations are reported as unconfirmed. Outputs of other addresses, such as
+    /// unilateral exits, are reported as they are.
+    pub(crate) fn min_confirmations(mut self, min_confirmations: u32) -> Self {
+        self.min_confirmations = min_confirmations;
+        self
+    }
+
+    /// Sets the boarding address [ChainCache::min_confirmations] applies to
+    ///
+    /// The boarding address is only known once the Ark client is connected, until then no
+    /// outputs are held back.
+    pub(crate) fn set_boarding_address(&self, address: Address) {
+        *self.boarding_address.lock().unwrap() = Some(address);
+    }
+
+    fn is_boarding_address(&self, address: &Address) -> bool {
+        self.boarding_address.lock().unwrap().as_ref() == Some(address)
+    }
+
+    /// Clears the cache if the given tip isn't the one it was filled at
+    ///
+    /// Lets whoever watches the chain tip invalidate the cache right away, instead of after the
+    /// tip check interval.
+    pub(crate) fn observe_tip(&self, tip: ChainTip) {
+        let mut state = self.state.lock().unwrap();
+        if state.tip != Some(tip) {
+            *state = CacheState {
+                tip: Some(tip),
+                ..Default::default()
+            };
+        }
+        state.tip_checked_at = Some(Instant::now());
+    }
+
+    /// Clears the cache if a new block was found since it was filled
+    async fn refresh_tip(&self) {
+        let checked_recently = self
+            .state
+            .lock()
+            .unwrap()
+            .tip_checked_at
+            .is_some_and(|checked_at| checked_at.elapsed() < self.tip_check_interval);
+        if checked_recently {
+            return;
+        }
+
+        match self.inner.chain_tip().await {
+            Ok(tip) => self.observe_tip(tip),
+            // Keep serving cached data, the tip is checked again on the next lookup
+            Err(e) => warn!("Failed to check the chain tip: {e}"),
+        }
+    }
+}
+
+impl Blockchain for ChainCache {
+    async fn find_outpoints(&self, address: &Address) -> Result<Vec<ExplorerUtxo>, Error> {
+        let mut utxos = self
+            .inner
+            .find_outpoints(address)
+            .await
+            .map_err(ark_error)?;
+        let is_confirmed = |utxo: &ExplorerUtxo| utxo.confirmation_blocktime.is_some();
+        if self.min_confirmations <= 1
+            || !self.is_boarding_address(address)
+            || !utxos.iter().any(is_confirmed)
+        {
+            return Ok(utxos);
+        }
+
+        let tip_height = self.inner.tip_height().await.map_err(ark_error)?;
+        for utxo in utxos.iter_mut().filter(|utxo| is_confirmed(utxo)) {
+            let height = self
+                .inner
+                .confirmation_height(&utxo.outpoint.txid)
+                .await
+                .map_err(ark_error)?;
+            if height.is_none_or(|height| {
+                chain::confirmations(tip_height, height) < self.min_confirmations
+            }) {
+                utxo.confirmation_blocktime = None;
+            }
+        }
+        Ok(utxos)
+    }
+
+    async fn find_tx(&self, txid: &Txid) -> Result<Option<Transaction>, Error> {
+        self.refresh_tip().await;
+        if let Some(tx) = self.state.lock().unwrap().txs.get(txid) {
+            return Ok(Some(tx.clone()));
+        }
+
+        let tx = self.inner.find_tx(txid).await.map_err(ark_error)?;
+        if let Some(tx) = &tx {
+            self.state.lock().unwrap().txs.insert(*txid, tx.clone());
+        }
+        Ok(tx)
+    }
+
+    async fn get_output_status(&self, txid: &Txid, vout: u32) -> Result<SpendStatus, Error> {
+        self.refresh_tip().await;
+        let outpoint = OutPoint::new(*txid, vout);
+        if let Some(spend_txid) = self.state.lock().unwrap().spent_outputs.get(&outpoint) {
+            return Ok(SpendStatus {
+                spend_txid: *spend_txid,
+            });
+        }
+
+        let status = self
+            .inner
+            .get_output_status(txid, vout)
+            .await
+            .map_err(ark_error)?;
+        if status.spend_txid.is_some() {
+            self.state
+                .lock()
+                .unwrap()
+                .spent_outputs
+                .insert(outpoint, status.spend_txid);
+        }
+        Ok(status)
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> Result<(), Error> {
+        self.inner.broadcast(tx).await.map_err(ark_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+
+    use async_trait::async_trait;
+    use bitcoin::{absolute::LockTime, hashes::Hash, transaction::Version, BlockHash};
+    use std::str::FromStr;
+
+    #[derive(Default)]
+    struct FakeBlockchain {
+        tip_height: AtomicU32,
+        /// Byte the hash of the tip block is filled with, changed to replace the tip block
+        tip_hash: AtomicU8,
+        /// Height the single output of every address is confirmed at, unconfirmed if 0
+        confirmation_height: AtomicU32,
+        tx_lookups: AtomicU32,
+        unreachable: AtomicBool,
+    }
+
+    impl FakeBlockchain {
+        fn check_reachable(&self) -> Result<(), SdkError> {
+            match self.unreachable.load(Ordering::SeqCst) {
+                true => Err(SdkError::NetworkError("Esplora is unreachable".to_string())),
+                false => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ChainService for FakeBlockchain {
+        async fn find_outpoints(&self, _address: &Address) -> Result<Vec<ExplorerUtxo>, SdkError> {
+            self.check_reachable()?;
+            let confirmed = self.confirmation_height.load(Ordering::SeqCst) > 0;
+            Ok(vec![ExplorerUtxo {
+                outpoint: OutPoint::new(Txid::all_zeros(), 0),
+                amount: bitcoin::Amount::from_sat(10_000),
+                confirmation_blocktime: confirmed.then_some(1_700_000_000),
+                is_spent: false,
+            }])
+        }
+
+        async fn find_tx(&self, _txid: &Txid) -> Result<Option<Transaction>, SdkError> {
+            self.check_reachable()?;
+            self.tx_lookups.fetch_add(1, Ordering::SeqCst);
+            Ok(Some(Transaction {
+                version: Version::TWO,
+                lock_time: LockTime::ZERO,
+                input: vec![],
+                output: vec![],
+            }))
+        }
+
+        async fn get_output_status(
+            &self,
+            _txid: &Txid,
+            _vout: u32,
+        ) -> Result<SpendStatus, SdkError> {
+            self.check_reachable()?;
+            Ok(SpendStatus { spend_txid: None })
+        }
+
+        async fn broadcast(&self, _tx: &Transaction) -> Result<(), SdkError> {
+            Ok(())
+        }
+
+        async fn tip_height(&self) -> Result<u32, SdkError> {
+            Ok(self.tip_height.load(Ordering::SeqCst))
+        }
+
+        async fn chain_tip(&self) -> Result<ChainTip, SdkError> {
+            Ok(ChainTip {
+                hash: Some(BlockHash::from_byte_array(
+                    [self.tip_hash.load(Ordering::SeqCst); 32],
+                )),
+                ..ChainTip::new(self.tip_height().await?)
+            })
+        }
+
+        async fn confirmation_height(&self, _txid: &Txid) -> Result<Option<u32>, SdkError> {
+            let height = self.confirmation_height.load(Ordering::SeqCst);
+            Ok((height > 0).then_some(height))
+        }
+
+        async fn tx_inclusion_proof(
+            &self,
+            _txid: &Txid,
+        ) -> Result<Option<bitcoin::MerkleBlock>, SdkError> {
+            Ok(None)
+        }
+
+        async fn fee_estimates(&self) -> Result<HashMap<u16, f64>, SdkError> {
+            Ok(HashMap::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_invalidated_on_new_block() {
+        let chain_service = Arc::new(FakeBlockchain::default());
+        let cache = ChainCache::with_tip_check_interval(chain_service.clone(), Duration::ZERO);
+        let txid = Txid::all_zeros();
+
+        cache.find_tx(&txid).await.unwrap();
+        cache.find_tx(&txid).await.unwrap();
+        assert_eq!(1, chain_service.tx_lookups.load(Ordering::SeqCst));
+
+        // A new block may have reorged the transaction out
+        chain_service.tip_height.store(1, Ordering::SeqCst);
+        cache.find_tx(&txid).await.unwrap();
+        assert_eq!(2, chain_service.tx_lookups.load(Ordering::SeqCst));
+
+        // So may a block replacing the tip at the same height
+        chain_service.tip_hash.store(1, Ordering::SeqCst);
+        cache.find_tx(&txid).await.unwrap();
+        assert_eq!(3, chain_service.tx_lookups.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_cache_invalidated_on_observed_tip() {
+        let chain_service = Arc::new(FakeBlockchain::default());
+        let cache = ChainCache::new(chain_service.clone());
+        let txid = Txid::all_zeros();
+
+        cache.find_tx(&txid).await.unwrap();
+        assert_eq!(1, chain_service.tx_lookups.load(Ordering::SeqCst));
+
+        // The tip watcher saw the tip replaced before the tip check interval passed
+        chain_service.tip_hash.store(1, Ordering::SeqCst);
+        cache.observe_tip(chain_service.chain_tip().await.unwrap());
+        cache.find_tx(&txid).await.unwrap();
+        assert_eq!(2, chain_service.tx_lookups.load(Ordering::SeqCst));
+
+        // The same tip keeps the cache
+        cache.observe_tip(chain_service.chain_tip().await.unwrap());
+        cache.find_tx(&txid).await.unwrap();
+        assert_eq!(2, chain_service.tx_lookups.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_chain_errors_returned() {
+        let chain_service = Arc::new(FakeBlockchain::default());
+        let cache = ChainCache::new(chain_service.clone());
+        let txid = Txid::all_zeros();
+        let address = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")
+            .unwrap()
+            .assume_checked();
+
+        // Failed lookups reach the Ark client as errors, so a sync fails instead of panicking
+        chain_service.unreachable.store(true, Ordering::SeqCst);
+        assert!(cache.find_outpoints(&address).await.is_err());
+        assert!(cache.get_output_status(&txid, 0).await.is_err());
+        assert!(cache.find_tx(&txid).await.is_err());
+
+        // Nothing was cached, lookups succeed once the chain service is back
+        chain_service.unreachable.store(false, Ordering::SeqCst);
+        assert!(cache.find_tx(&txid).await.unwrap().is_some());
+        assert_eq!(1, chain_service.tx_lookups.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_min_confirmations() {
+        let chain_service = Arc::new(FakeBlockchain::default());
+        let cache = ChainCache::new(chain_service.clone()).min_confirmations(3);
+        let address = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")
+            .unwrap()
+            .assume_checked();
+        let other_address = Address::from_str("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq")
+            .unwrap()
+            .assume_checked();
+        let is_confirmed = |utxos: Vec<ExplorerUtxo>| utxos[0].confirmation_blocktime.is_some();
+        cache.set_boarding_address(address.clone());
+
+        chain_service.tip_height.store(101, Ordering::SeqCst);
+        assert!(!is_confirmed(cache.find_outpoints(&address).await.unwrap()));
+
+        // Two confirmations aren't enough yet
+        chain_service
+            .confirmation_height
+            .store(100, Ordering::SeqCst);
+        assert!(!is_confirmed(cache.find_outpoints(&address).await.unwrap()));
+
+        // Only the boarding address is held back
+        assert!(is_confirmed(
+            cache.find_outpoints(&other_address).await.unwrap()
+        ));
+
+        chain_service.tip_height.store(102, Ordering::SeqCst);
+        assert!(is_confirmed(cache.find_outpoints(&address).await.unwrap()));
+    }
+}
@@ -1,26 +1,73 @@
-use ark_client::{error::IntoError, Blockchain, Error, ExplorerUtxo, SpendStatus};
-use bitcoin::{Address, Amount, OutPoint, Transaction, Txid};
+use ark_client::{ExplorerUtxo, SpendStatus};
+use async_trait::async_trait;
+use bitcoin::{Address, Amount, BlockHash, MerkleBlock, OutPoint, Transaction, Txid};
 use esplora_client::Builder;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::chain::{ChainService, ChainTip};
+use crate::error::SdkError;
+
+fn network_error(context: &str, e: esplora_client::Error) -> SdkError {
+    SdkError::NetworkError(format!("{context}: {e}"))
+}
+
 pub struct EsploraBlockchain {
-    client: Arc<esplora_client::BlockingClient>,
+    client: Arc<esplora_client::AsyncClient>,
 }
 
 impl EsploraBlockchain {
-    pub fn new(url: String) -> Result<Self, Error> {
-        let client = Builder::new(&url).build_blocking();
+    pub fn new(url: String) -> Result<Self, SdkError> {
+        Self::with_headers(url, &HashMap::new())
+    }
 
+    /// Creates a client sending the given HTTP headers with every request, e.g. to authenticate
+    /// with a hosted Esplora instance
+    pub fn with_headers(url: String, headers: &HashMap<String, String>) -> Result<Self, SdkError> {
+        let builder = headers
+            .iter()
+            .fold(Builder::new(&url), |builder, (key, value)| {
+                builder.header(key, value)
+            });
+
+        let client = builder
+            .build_async()
+            .map_err(|e| network_error("Failed to create Esplora client", e))?;
         Ok(Self {
             client: Arc::new(client),
         })
     }
+
+    /// Creates a client sending its requests through the given HTTP client, e.g. one set up
+    /// with a proxy, custom TLS roots or certificate pinning
+    ///
+    /// Headers to authenticate with a hosted Esplora instance have to be set as default headers
+    /// of the HTTP client.
+    pub fn with_http_client(url: String, http_client: reqwest::Client) -> Self {
+        Self {
+            client: Arc::new(esplora_client::AsyncClient::from_client(url, http_client)),
+        }
+    }
+
+    /// Hash of the genesis block of the chain the Esplora instance follows, identifying its
+    /// network
+    pub(crate) async fn genesis_hash(&self) -> Result<BlockHash, SdkError> {
+        self.client
+            .get_block_hash(0)
+            .await
+            .map_err(|e| network_error("Failed to get genesis block", e))
+    }
 }
 
-impl Blockchain for EsploraBlockchain {
-    async fn find_outpoints(&self, address: &Address) -> Result<Vec<ExplorerUtxo>, Error> {
+#[async_trait]
+impl ChainService for EsploraBlockchain {
+    async fn find_outpoints(&self, address: &Address) -> Result<Vec<ExplorerUtxo>, SdkError> {
         let script_pubkey = address.script_pubkey();
-        let txs = self.client.scripthash_txs(&script_pubkey, None).unwrap();
+        let txs = self
+            .client
+            .scripthash_txs(&script_pubkey, None)
+            .await
+            .map_err(|e| network_error("Failed to get address transactions", e))?;
 
         let outputs = txs
             .into_iter()
@@ -47,55 +94,110 @@ impl Blockchain for EsploraBlockchain {
             })
             .collect::<Vec<_>>();
 
-        let mut utxos = Vec::new();
+        // Fetch the spend status of all outputs of a transaction in a single request
+        let mut outspends: HashMap<Txid, Vec<esplora_client::OutputStatus>> = HashMap::new();
         for output in outputs.iter() {
-            let outpoint = output.outpoint;
-            let status = self
-                .client
-                .get_output_status(&outpoint.txid, outpoint.vout as u64)
-                .unwrap();
-
-            match status {
-                Some(esplora_client::OutputStatus { spent: false, .. }) | None => {
-                    utxos.push(*output);
-                }
-                Some(esplora_client::OutputStatus { spent: true, .. }) => {
-                    utxos.push(ExplorerUtxo {
-                        is_spent: true,
-                        ..*output
-                    })
-                }
+            let txid = output.outpoint.txid;
+            if outspends.contains_key(&txid) {
+                continue;
             }
+            let statuses = self
+                .client
+                .get_tx_outspends(&txid)
+                .await
+                .map_err(|e| network_error("Failed to get output spends", e))?;
+            outspends.insert(txid, statuses);
         }
 
+        let utxos = outputs
+            .into_iter()
+            .map(|output| {
+                let is_spent = outspends
+                    .get(&output.outpoint.txid)
+                    .and_then(|statuses| statuses.get(output.outpoint.vout as usize))
+                    .is_some_and(|status| status.spent);
+                ExplorerUtxo { is_spent, ..output }
+            })
+            .collect();
+
         Ok(utxos)
     }
 
-    async fn find_tx(&self, txid: &Txid) -> Result<Option<Transaction>, Error> {
+    async fn find_tx(&self, txid: &Txid) -> Result<Option<Transaction>, SdkError> {
         let tx = self
             .client
             .get_tx(txid)
-            .map_err(|e| e.to_string().into_error())?;
+            .await
+            .map_err(|e| network_error("Failed to get tx", e))?;
 
         Ok(tx)
     }
 
-    async fn get_output_status(&self, txid: &Txid, vout: u32) -> Result<SpendStatus, Error> {
+    async fn get_output_status(&self, txid: &Txid, vout: u32) -> Result<SpendStatus, SdkError> {
         let status = self
             .client
             .get_output_status(txid, vout as u64)
-            .map_err(|e| e.to_string().into_error())?;
+            .await
+            .map_err(|e| network_error("Failed to get output status", e))?;
 
         Ok(SpendStatus {
             spend_txid: status.and_then(|s| s.txid),
         })
     }
 
-    async fn broadcast(&self, tx: &Transaction) -> Result<(), Error> {
+    async fn broadcast(&self, tx: &Transaction) -> Result<(), SdkError> {
         self.client
             .broadcast(tx)
-            .map_err(|e| e.to_string().into_error())?;
+            .await
+            .map_err(|e| network_error("Failed to broadcast tx", e))?;
 
         Ok(())
     }
+
+    async fn tip_height(&self) -> Result<u32, SdkError> {
+        self.client
+            .get_height()
+            .await
+            .map_err(|e| network_error("Failed to get chain tip", e))
+    }
+
+    async fn chain_tip(&self) -> Result<ChainTip, SdkError> {
+        // The hash is looked up by height, so both describe the same block even if a new one
+        // is found in between
+        let height = self.tip_height().await?;
+        let hash = self
+            .client
+            .get_block_hash(height)
+            .await
+            .map_err(|e| network_error("Failed to get chain tip hash", e))?;
+        let header = self
+            .client
+            .get_header_by_hash(&hash)
+            .await
+            .map_err(|e| network_error("Failed to get chain tip header", e))?;
+        Ok(ChainTip::new(height).with_hash(hash).with_time(header.time))
+    }
+
+    async fn confirmation_height(&self, txid: &Txid) -> Result<Option<u32>, SdkError> {
+        let status = self
+            .client
+            .get_tx_status(txid)
+            .await
+            .map_err(|e| network_error("Failed to get tx status", e))?;
+        Ok(status.block_height)
+    }
+
+    async fn tx_inclusion_proof(&self, txid: &Txid) -> Result<Option<MerkleBlock>, SdkError> {
+        self.client
+            .get_merkle_block(txid)
+            .await
+            .map_err(|e| network_error("Failed to get merkle block", e))
+    }
+
+    async fn fee_estimates(&self) -> Result<HashMap<u16, f64>, SdkError> {
+        self.client
+            .get_fee_estimates()
+            .await
+            .map_err(|e| network_error("Failed to get fee estimates", e))
+    }
 }
@@ -1 +1,156 @@
-pub(crate) mod esplora;
+pub(crate) mod cache;
+pub mod esplora;
+#[cfg(test)]
+pub(crate) mod simulated;
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use ark_client::{ExplorerUtxo, SpendStatus};
+use async_trait::async_trait;
+use bitcoin::{Address, BlockHash, MerkleBlock, Transaction, Txid};
+
+use crate::error::SdkError;
+
+/// The block at the tip of the chain
+///
+/// Backends that only know the height leave the rest unset, create it with [ChainTip::new].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct ChainTip {
+    pub height: u32,
+    /// Hash of the block, needed to detect a block replaced at the same height
+    pub hash: Option<BlockHash>,
+    /// Unix timestamp the miner set in the block header, needed to detect a skewed local clock
+    pub time: Option<u32>,
+}
+
+impl ChainTip {
+    /// Creates a tip at the given height, without the hash or time of its block
+    pub fn new(height: u32) -> Self {
+        Self {
+            height,
+            hash: None,
+            time: None,
+        }
+    }
+
+    /// Sets the hash of the block at the tip
+    pub fn with_hash(mut self, hash: BlockHash) -> Self {
+        self.hash = Some(hash);
+        self
+    }
+
+    /// Sets the timestamp of the block at the tip
+    pub fn with_time(mut self, time: u32) -> Self {
+        self.time = Some(time);
+        self
+    }
+}
+
+/// Chain backend used by the SDK and the Ark client to inspect and publish onchain data
+///
+/// The SDK uses [esplora::EsploraBlockchain] by default. Implement this trait to use another
+/// backend, or a mock in tests, and pass it to [crate::sdk_builder::SdkBuilder::chain_service].
+#[async_trait]
+pub trait ChainService: Send + Sync {
+    /// Finds the outputs paying to an address, including whether they were spent
+    async fn find_outpoints(&self, address: &Address) -> Result<Vec<ExplorerUtxo>, SdkError>;
+
+    /// Gets a transaction, if it is known
+    async fn find_tx(&self, txid: &Txid) -> Result<Option<Transaction>, SdkError>;
+
+    /// Gets the transaction spending an output, if it was spent
+    async fn get_output_status(&self, txid: &Txid, vout: u32) -> Result<SpendStatus, SdkError>;
+
+    /// Publishes a transaction
+    async fn broadcast(&self, tx: &Transaction) -> Result<(), SdkError>;
+
+    /// Height of the current chain tip
+    async fn tip_height(&self) -> Result<u32, SdkError>;
+
+    /// Height, hash and time of the current chain tip
+    ///
+    /// Defaults to the [ChainService::tip_height], without the hash and time of the block.
+    /// Without them a block replaced at the same height and a skewed local clock go undetected.
+    async fn chain_tip(&self) -> Result<ChainTip, SdkError> {
+        Ok(ChainTip::new(self.tip_height().await?))
+    }
+
+    /// Height of the block confirming a transaction, if it is confirmed
+    async fn confirmation_height(&self, txid: &Txid) -> Result<Option<u32>, SdkError>;
+
+    /// Merkle proof that a transaction is included in its block, if it is confirmed
+    async fn tx_inclusion_proof(&self, txid: &Txid) -> Result<Option<MerkleBlock>, SdkError>;
+
+    /// Fee rate estimates in sat/vB, keyed by confirmation target in blocks
+    async fn fee_estimates(&self) -> Result<HashMap<u16, f64>, SdkError>;
+}
+
+/// Confirmations of a transaction confirmed in the block at `height`
+pub(crate) fn confirmations(tip_height: u32, height: u32) -> u32 {
+    tip_height.saturating_sub(height) + 1
+}
+
+/// Chain service that can be swapped while the SDK is running, see
+/// [crate::BreezSdk::update_config]
+///
+/// The Ark client and the fee provider hold clones of it, so they use the new service too.
+pub struct ReplaceableChainService {
+    current: RwLock<Arc<dyn ChainService>>,
+}
+
+impl ReplaceableChainService {
+    pub fn new(chain_service: Arc<dyn ChainService>) -> Self {
+        Self {
+            current: RwLock::new(chain_service),
+        }
+    }
+
+    pub(crate) fn replace(&self, chain_service: Arc<dyn ChainService>) {
+        *self.current.write().unwrap() = chain_service;
+    }
+
+    pub(crate) fn current(&self) -> Arc<dyn ChainService> {
+        self.current.read().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl ChainService for ReplaceableChainService {
+    async fn find_outpoints(&self, address: &Address) -> Result<Vec<ExplorerUtxo>, SdkError> {
+        self.current().find_outpoints(address).await
+    }
+
+    async fn find_tx(&self, txid: &Txid) -> Result<Option<Transaction>, SdkError> {
+        self.current().find_tx(txid).await
+    }
+
+    async fn get_output_status(&self, txid: &Txid, vout: u32) -> Result<SpendStatus, SdkError> {
+        self.current().get_output_status(txid, vout).await
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> Result<(), SdkError> {
+        self.current().broadcast(tx).await
+    }
+
+    async fn tip_height(&self) -> Result<u32, SdkError> {
+        self.current().tip_height().await
+    }
+
+    async fn chain_tip(&self) -> Result<ChainTip, SdkError> {
+        self.current().chain_tip().await
+    }
+
+    async fn confirmation_height(&self, txid: &Txid) -> Result<Option<u32>, SdkError> {
+        self.current().confirmation_height(txid).await
+    }
+
+    async fn tx_inclusion_proof(&self, txid: &Txid) -> Result<Option<MerkleBlock>, SdkError> {
+        self.current().tx_inclusion_proof(txid).await
+    }
+
+    async fn fee_estimates(&self) -> Result<HashMap<u16, f64>, SdkError> {
+        self.current().fee_estimates().await
+    }
+}
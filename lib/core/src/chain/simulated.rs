@@ -0,0 +1,285 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ark_client::{ExplorerUtxo, SpendStatus};
+use async_trait::async_trait;
+use bitcoin::{Address, MerkleBlock, Transaction, Txid};
+
+use crate::chain::{ChainService, ChainTip};
+use crate::error::SdkError;
+
+/// What happens to the next call made through a [SimulatedChainService]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum NetworkCondition {
+    /// The call goes through right away
+    Deliver,
+    /// The call goes through after a delay. Concurrent calls with decreasing delays complete in
+    /// the reverse order they were made
+    Delay(Duration),
+    /// The connection drops, the call fails with a network error without reaching the backend
+    Drop,
+}
+
+/// Chain backend keeping a tip height and transactions in memory
+#[derive(Default)]
+pub(crate) struct MemoryChain {
+    pub(crate) tip_height: AtomicU32,
+    pub(crate) txs: Mutex<HashMap<Txid, Transaction>>,
+}
+
+#[async_trait]
+impl ChainService for MemoryChain {
+    async fn find_outpoints(&self, _address: &Address) -> Result<Vec<ExplorerUtxo>, SdkError> {
+        Ok(Vec::new())
+    }
+
+    async fn find_tx(&self, txid: &Txid) -> Result<Option<Transaction>, SdkError> {
+        Ok(self.txs.lock().unwrap().get(txid).cloned())
+    }
+
+    async fn get_output_status(&self, _txid: &Txid, _vout: u32) -> Result<SpendStatus, SdkError> {
+        Ok(SpendStatus { spend_txid: None })
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> Result<(), SdkError> {
+        self.txs
+            .lock()
+            .unwrap()
+            .insert(tx.compute_txid(), tx.clone());
+        Ok(())
+    }
+
+    async fn tip_height(&self) -> Result<u32, SdkError> {
+        Ok(self.tip_height.load(Ordering::SeqCst))
+    }
+
+    async fn confirmation_height(&self, _txid: &Txid) -> Result<Option<u32>, SdkError> {
+        Ok(None)
+    }
+
+    async fn tx_inclusion_proof(&self, _txid: &Txid) -> Result<Option<MerkleBlock>, SdkError> {
+        Ok(None)
+    }
+
+    async fn fee_estimates(&self) -> Result<HashMap<u16, f64>, SdkError> {
+        Ok(HashMap::new())
+    }
+}
+
+/// Wraps a chain backend to exercise retry, timeout and reconnection logic deterministically
+///
+/// Each call takes the next scripted [NetworkCondition], calls beyond the script are delivered
+/// right away. As the script is consumed in call order, the outcome of every call is known
+/// upfront regardless of scheduling.
+pub(crate) struct SimulatedChainService {
+    inner: Arc<dyn ChainService>,
+    script: Mutex<VecDeque<NetworkCondition>>,
+    calls: AtomicU32,
+}
+
+impl SimulatedChainService {
+    pub(crate) fn new(inner: Arc<dyn ChainService>) -> Self {
+        Self {
+            inner,
+            script: Mutex::default(),
+            calls: AtomicU32::default(),
+        }
+    }
+
+    /// Appends conditions for the calls following the already scripted ones
+    pub(crate) fn script(&self, conditions: impl IntoIterator<Item = NetworkCondition>) {
+        self.script.lock().unwrap().extend(conditions);
+    }
+
+    /// Number of calls made so far, including dropped ones
+    pub(crate) fn calls(&self) -> u32 {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    async fn simulate(&self) -> Result<(), SdkError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let condition = self.script.lock().unwrap().pop_front();
+        match condition.unwrap_or(NetworkCondition::Deliver) {
+            NetworkCondition::Deliver => Ok(()),
+            NetworkCondition::Delay(delay) => {
+                tokio::time::sleep(delay).await;
+                Ok(())
+            }
+            NetworkCondition::Drop => Err(SdkError::NetworkError(
+                "Simulated connection drop".to_string(),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl ChainService for SimulatedChainService {
+    async fn find_outpoints(&self, address: &Address) -> Result<Vec<ExplorerUtxo>, SdkError> {
+        self.simulate().await?;
+        self.inner.find_outpoints(address).await
+    }
+
+    async fn find_tx(&self, txid: &Txid) -> Result<Option<Transaction>, SdkError> {
+        self.simulate().await?;
+        self.inner.find_tx(txid).await
+    }
+
+    async fn get_output_status(&self, txid: &Txid, vout: u32) -> Result<SpendStatus, SdkError> {
+        self.simulate().await?;
+        self.inner.get_output_status(txid, vout).await
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> Result<(), SdkError> {
+        self.simulate().await?;
+        self.inner.broadcast(tx).await
+    }
+
+    async fn tip_height(&self) -> Result<u32, SdkError> {
+        self.simulate().await?;
+        self.inner.tip_height().await
+    }
+
+    async fn chain_tip(&self) -> Result<ChainTip, SdkError> {
+        self.simulate().await?;
+        self.inner.chain_tip().await
+    }
+
+    async fn confirmation_height(&self, txid: &Txid) -> Result<Option<u32>, SdkError> {
+        self.simulate().await?;
+        self.inner.confirmation_height(txid).await
+    }
+
+    async fn tx_inclusion_proof(&self, txid: &Txid) -> Result<Option<MerkleBlock>, SdkError> {
+        self.simulate().await?;
+        self.inner.tx_inclusion_proof(txid).await
+    }
+
+    async fn fee_estimates(&self) -> Result<HashMap<u16, f64>, SdkError> {
+        self.simulate().await?;
+        self.inner.fee_estimates().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::connection::ConnectionMonitor;
+    use crate::models::{ConnectionState, RemoteService};
+    use crate::tasks::TaskSupervisor;
+    use bitcoin::{absolute::LockTime, transaction::Version};
+    use tokio::sync::watch;
+
+    fn tx(lock_time: u32) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::from_consensus(lock_time),
+            input: vec![],
+            output: vec![],
+        }
+    }
+
+    fn simulated_chain() -> (Arc<MemoryChain>, Arc<SimulatedChainService>) {
+        let chain = Arc::new(MemoryChain::default());
+        let simulated = Arc::new(SimulatedChainService::new(chain.clone()));
+        (chain, simulated)
+    }
+
+    #[tokio::test]
+    async fn test_dropped_calls_retried() {
+        let (chain, simulated) = simulated_chain();
+        chain.tip_height.store(100, Ordering::SeqCst);
+        simulated.script([NetworkCondition::Drop, NetworkCondition::Drop]);
+
+        let (shutdown_sender, shutdown_receiver) = watch::channel(());
+        let supervisor = TaskSupervisor::new(
+            shutdown_receiver,
+            Arc::new(SystemClock),
+            tokio::runtime::Handle::current(),
+        );
+        let (height_sender, mut height_receiver) = watch::channel(None);
+        let task_chain = simulated.clone();
+        supervisor.spawn_periodic("watch_tip", Duration::from_millis(1), move || {
+            let chain = task_chain.clone();
+            let height_sender = height_sender.clone();
+            async move {
+                height_sender.send_replace(Some(chain.tip_height().await?));
+                Ok(())
+            }
+        });
+
+        // The task backs off after each drop and gets through on the third attempt
+        height_receiver
+            .wait_for(|height| *height == Some(100))
+            .await
+            .unwrap();
+        assert!(simulated.calls() >= 3);
+        shutdown_sender.send(()).unwrap();
+        supervisor.join().await;
+    }
+
+    #[tokio::test]
+    async fn test_delayed_calls_time_out() {
+        let (_, simulated) = simulated_chain();
+        simulated.script([NetworkCondition::Delay(Duration::from_secs(60))]);
+
+        let result = tokio::time::timeout(Duration::from_millis(10), simulated.tip_height()).await;
+        assert!(result.is_err());
+        // The next call isn't held up by the stalled one
+        let result = tokio::time::timeout(Duration::from_secs(1), simulated.tip_height()).await;
+        assert_eq!(0, result.unwrap().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_responses_reordered() {
+        let (chain, simulated) = simulated_chain();
+        let (first, second) = (tx(1), tx(2));
+        chain.broadcast(&first).await.unwrap();
+        chain.broadcast(&second).await.unwrap();
+        simulated.script([
+            NetworkCondition::Delay(Duration::from_millis(50)),
+            NetworkCondition::Deliver,
+        ]);
+
+        let completed = Mutex::new(Vec::new());
+        let lookup = |tx: &Transaction| {
+            let txid = tx.compute_txid();
+            let simulated = simulated.clone();
+            let completed = &completed;
+            async move {
+                simulated.find_tx(&txid).await.unwrap().unwrap();
+                completed.lock().unwrap().push(txid);
+            }
+        };
+        futures::join!(lookup(&first), lookup(&second));
+
+        assert_eq!(
+            vec![second.compute_txid(), first.compute_txid()],
+            *completed.lock().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reconnection_tracked() {
+        let (_, simulated) = simulated_chain();
+        simulated.script(vec![NetworkCondition::Drop; 3]);
+        let monitor = ConnectionMonitor::default();
+
+        let mut states = Vec::new();
+        for _ in 0..4 {
+            let success = simulated.tip_height().await.is_ok();
+            let transition = monitor.record(RemoteService::Esplora, Duration::ZERO, success, 1_000);
+            states.extend(transition.map(|t| t.current));
+        }
+        assert_eq!(
+            vec![
+                ConnectionState::Degraded,
+                ConnectionState::Disconnected,
+                ConnectionState::Connected
+            ],
+            states
+        );
+    }
+}
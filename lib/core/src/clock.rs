@@ -0,0 +1,75 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of the current time
+///
+/// Payment timestamps, expiry checks and background task statuses all read the time through
+/// the clock given to [crate::sdk_builder::SdkBuilder::clock], so they can be tested without
+/// waiting for real time to pass.
+pub trait Clock: Send + Sync {
+    /// Current Unix timestamp in seconds
+    fn now_secs(&self) -> u64;
+}
+
+/// Reads the time from the system clock, the default [Clock]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_secs(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
+
+/// How far the tip's timestamp may be ahead of the local clock. Nodes reject blocks more than
+/// two hours in the future, so a tip further ahead means the local clock is behind
+const MAX_TIP_AHEAD_SECS: i64 = 2 * 60 * 60;
+/// How long ago the tip may have been mined before the local clock is considered ahead. Blocks
+/// are ten minutes apart on average, gaps of several hours don't happen in practice
+const MAX_TIP_AGE_SECS: i64 = 6 * 60 * 60;
+
+/// Estimates how far the local clock is off, from the timestamp of the chain tip
+///
+/// Block timestamps are only accurate to a few hours, so only a large skew is detected.
+/// Returns the local time minus the tip's time in seconds, positive when the local clock is
+/// ahead, or `None` if the clock looks right. Regtest blocks are mined on demand, so a stale
+/// tip there doesn't mean the clock is ahead.
+pub(crate) fn detect_skew(now: u64, tip_time: u32, network: bitcoin::Network) -> Option<i64> {
+    let skew = now as i64 - tip_time as i64;
+    let is_ahead = skew > MAX_TIP_AGE_SECS && network != bitcoin::Network::Regtest;
+    (skew < -MAX_TIP_AHEAD_SECS || is_ahead).then_some(skew)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_skew() {
+        let tip_time = 1_700_000_000;
+        let now = tip_time as u64;
+        assert_eq!(
+            None,
+            detect_skew(now + 600, tip_time, bitcoin::Network::Bitcoin)
+        );
+        // Miners may set timestamps somewhat ahead
+        assert_eq!(
+            None,
+            detect_skew(now - 3_600, tip_time, bitcoin::Network::Bitcoin)
+        );
+        assert_eq!(
+            Some(-3 * 3_600),
+            detect_skew(now - 3 * 3_600, tip_time, bitcoin::Network::Bitcoin)
+        );
+        assert_eq!(
+            Some(86_400),
+            detect_skew(now + 86_400, tip_time, bitcoin::Network::Signet)
+        );
+        assert_eq!(
+            None,
+            detect_skew(now + 86_400, tip_time, bitcoin::Network::Regtest)
+        );
+    }
+}
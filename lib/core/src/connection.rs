@@ -0,0 +1,133 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::models::{ConnectionState, ConnectionStatus, RemoteService};
+
+/// Round trips slower than this leave a service degraded even though the call succeeded
+const DEGRADED_LATENCY: Duration = Duration::from_secs(2);
+/// Failed calls in a row after which a service is considered disconnected
+const DISCONNECTED_AFTER_FAILURES: u32 = 3;
+
+/// A change of the connection state of a service
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ConnectionTransition {
+    pub(crate) service: RemoteService,
+    pub(crate) previous: ConnectionState,
+    pub(crate) current: ConnectionState,
+}
+
+/// Tracks the health of the connections to the Ark server and Esplora
+///
+/// Every timed call updates the state of its service: a fast successful call means connected,
+/// a slow one or a few failures degraded, and repeated failures disconnected. A service is
+/// disconnected until its first call completes.
+#[derive(Default)]
+pub(crate) struct ConnectionMonitor {
+    ark_server: Mutex<ConnectionStatus>,
+    esplora: Mutex<ConnectionStatus>,
+}
+
+impl ConnectionMonitor {
+    fn status_of(&self, service: RemoteService) -> &Mutex<ConnectionStatus> {
+        match service {
+            RemoteService::ArkServer => &self.ark_server,
+            RemoteService::Esplora => &self.esplora,
+        }
+    }
+
+    pub(crate) fn status(&self, service: RemoteService) -> ConnectionStatus {
+        self.status_of(service).lock().unwrap().clone()
+    }
+
+    /// Records the outcome of a call, returning the transition if the state changed
+    pub(crate) fn record(
+        &self,
+        service: RemoteService,
+        latency: Duration,
+        success: bool,
+        now: u64,
+    ) -> Option<ConnectionTransition> {
+        let mut status = self.status_of(service).lock().unwrap();
+        let previous = status.state.clone();
+        if success {
+            status.latency_ms = Some(latency.as_millis() as u64);
+            status.last_success_at = Some(now);
+            status.consecutive_failures = 0;
+            status.state = match latency > DEGRADED_LATENCY {
+                true => ConnectionState::Degraded,
+                false => ConnectionState::Connected,
+            };
+        } else {
+            status.consecutive_failures += 1;
+            status.state = match status.consecutive_failures >= DISCONNECTED_AFTER_FAILURES {
+                true => ConnectionState::Disconnected,
+                false => ConnectionState::Degraded,
+            };
+        }
+
+        (status.state != previous).then(|| ConnectionTransition {
+            service,
+            previous,
+            current: status.state.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_transitions() {
+        let monitor = ConnectionMonitor::default();
+        let fast = Duration::from_millis(50);
+        let state = |transition: Option<ConnectionTransition>| transition.map(|t| t.current);
+
+        assert_eq!(
+            ConnectionState::Disconnected,
+            monitor.status(RemoteService::ArkServer).state
+        );
+        assert_eq!(
+            Some(ConnectionState::Connected),
+            state(monitor.record(RemoteService::ArkServer, fast, true, 100))
+        );
+        assert_eq!(
+            None,
+            monitor.record(RemoteService::ArkServer, fast, true, 100)
+        );
+        // Esplora is tracked separately
+        assert_eq!(
+            ConnectionState::Disconnected,
+            monitor.status(RemoteService::Esplora).state
+        );
+
+        assert_eq!(
+            Some(ConnectionState::Degraded),
+            state(monitor.record(RemoteService::ArkServer, Duration::from_secs(5), true, 100))
+        );
+        assert_eq!(
+            Some(ConnectionState::Connected),
+            state(monitor.record(RemoteService::ArkServer, fast, true, 100))
+        );
+
+        assert_eq!(
+            Some(ConnectionState::Degraded),
+            state(monitor.record(RemoteService::ArkServer, fast, false, 200))
+        );
+        assert_eq!(
+            None,
+            monitor.record(RemoteService::ArkServer, fast, false, 200)
+        );
+        let transition = monitor
+            .record(RemoteService::ArkServer, fast, false, 200)
+            .unwrap();
+        assert_eq!(ConnectionState::Degraded, transition.previous);
+        assert_eq!(ConnectionState::Disconnected, transition.current);
+
+        // Failures keep the latency of the last successful call
+        let status = monitor.status(RemoteService::ArkServer);
+        assert_eq!(Some(50), status.latency_ms);
+        assert_eq!(3, status.consecutive_failures);
+        assert_eq!(Some(100), status.last_success_at);
+    }
+}
@@ -0,0 +1,88 @@
+/// VTXOs below this amount are consolidated when no threshold is given, in satoshis
+pub(crate) const DEFAULT_DUST_THRESHOLD_SAT: u64 = 10_000;
+
+/// Expected cost and benefit of merging the small VTXOs of the wallet in a round
+///
+/// The round settles every spendable VTXO, not only the small ones, so the plan covers all of
+/// them.
+#[derive(Debug, PartialEq)]
+pub(crate) struct ConsolidationPlan {
+    /// Number of VTXOs the round spends
+    pub(crate) vtxo_count: u32,
+    /// Number of those below the threshold
+    pub(crate) dust_vtxo_count: u32,
+    /// Total amount held in the VTXOs the round spends, in satoshis
+    pub(crate) amount_sat: u64,
+    /// Round fees paid to merge them, in satoshis
+    pub(crate) fee_sat: u64,
+    /// Onchain fees no longer needed to exit them separately, in satoshis
+    pub(crate) exit_savings_sat: u64,
+}
+
+impl ConsolidationPlan {
+    /// Whether merging saves more in future exit costs than it costs now
+    pub(crate) fn is_worth_it(&self) -> bool {
+        self.exit_savings_sat > self.fee_sat
+    }
+}
+
+/// Plans merging the given VTXOs into a single VTXO, when at least two of them are below
+/// `dust_threshold_sat`
+///
+/// Each VTXO the round spends pays the round fee, while every VTXO but the merged one no longer
+/// has to be exited on its own. Returns `None` if there are fewer than two VTXOs to merge.
+pub(crate) fn plan(
+    vtxo_amounts: &[u64],
+    dust_threshold_sat: u64,
+    round_fee_sat: u64,
+    exit_fee_sat: u64,
+) -> Option<ConsolidationPlan> {
+    let dust_count = vtxo_amounts
+        .iter()
+        .filter(|amount| **amount < dust_threshold_sat)
+        .count();
+    if dust_count < 2 {
+        return None;
+    }
+
+    let vtxo_count = vtxo_amounts.len() as u64;
+    Some(ConsolidationPlan {
+        vtxo_count: u32::try_from(vtxo_count).unwrap_or(u32::MAX),
+        dust_vtxo_count: u32::try_from(dust_count).unwrap_or(u32::MAX),
+        amount_sat: vtxo_amounts.iter().sum(),
+        fee_sat: round_fee_sat * vtxo_count,
+        exit_savings_sat: exit_fee_sat * (vtxo_count - 1),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        let vtxos = [500, 1_200, 50_000, 3_000];
+
+        // The round spends the large VTXO as well
+        let consolidation = plan(&vtxos, DEFAULT_DUST_THRESHOLD_SAT, 10, 300).unwrap();
+        assert_eq!(
+            ConsolidationPlan {
+                vtxo_count: 4,
+                dust_vtxo_count: 3,
+                amount_sat: 54_700,
+                fee_sat: 40,
+                exit_savings_sat: 900,
+            },
+            consolidation
+        );
+        assert!(consolidation.is_worth_it());
+
+        // Expensive rounds don't pay off
+        assert!(!plan(&vtxos, DEFAULT_DUST_THRESHOLD_SAT, 400, 300)
+            .unwrap()
+            .is_worth_it());
+
+        // A single small VTXO has nothing to merge with
+        assert!(plan(&vtxos, 1_000, 10, 300).is_none());
+    }
+}
@@ -0,0 +1,612 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use ark_client::{ExplorerUtxo, SpendStatus};
+use async_trait::async_trait;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::{Address, MerkleBlock, Transaction, Txid};
+use log::info;
+
+use crate::chain::ChainService;
+use crate::clock::Clock;
+use crate::error::SdkError;
+use crate::events::{EventEmitter, SdkEvent};
+use crate::fees::FeeProvider;
+use crate::models::{
+    OffchainBalance, PayAmount, Payment, PaymentDetails, PaymentMethod, PaymentRoute,
+    PaymentStatus, PaymentType, PrepareSendPaymentRequest, PrepareSendPaymentResponse,
+    ReceivePaymentRequest, ReceivePaymentResponse, SendDestination, SendPaymentRequest,
+    SendPaymentResponse, SendStep, ServiceFees,
+};
+use crate::persist::Storage;
+
+/// Fee charged for every demo send, in satoshis
+const DEMO_FEE_SAT: u64 = 100;
+/// Chain fee rate of the demo chain, in sat/vB
+const DEMO_FEE_RATE: f64 = 2.0;
+/// How long demo payment requests are outstanding, in seconds
+const DEMO_REQUEST_EXPIRY_SECS: u64 = 24 * 60 * 60;
+/// Timestamp of the genesis block, the demo chain has a block every 10 minutes since
+const DEMO_GENESIS_SECS: u64 = 1_231_006_505;
+
+/// Ark backend of a wallet built with [crate::sdk_builder::SdkBuilder::demo], fabricating
+/// balances, payments and events instead of connecting to an Ark server
+///
+/// Everything is derived from a counter and the [Clock], so with a fixed clock every run
+/// produces the same payments, IDs and events:
+/// - the wallet starts with a confirmed deposit, a completed send and a received payment
+///   pending settlement
+/// - [DemoArk::settle_pending] settles pending payments, as the next round would
+/// - [DemoArk::simulate_incoming_payment] fabricates a payment from someone else
+///
+/// Destinations returned by [DemoArk::receive_payment] aren't real and can't be paid.
+pub(crate) struct DemoArk {
+    storage: Arc<dyn Storage>,
+    clock: Arc<dyn Clock>,
+    event_emitter: Arc<EventEmitter>,
+    next_index: AtomicU32,
+    /// Serializes balance updates, so events report consistent balances
+    balance_lock: tokio::sync::Mutex<()>,
+}
+
+impl DemoArk {
+    /// Creates the demo backend, filling the storage with the initial payments
+    pub(crate) async fn new(
+        storage: Arc<dyn Storage>,
+        clock: Arc<dyn Clock>,
+        event_emitter: Arc<EventEmitter>,
+    ) -> Result<Self, SdkError> {
+        let demo = Self {
+            storage,
+            clock,
+            event_emitter,
+            next_index: AtomicU32::default(),
+            balance_lock: tokio::sync::Mutex::new(()),
+        };
+        demo.seed().await?;
+        Ok(demo)
+    }
+
+    /// Settles the payments pending settlement, as the next round would
+    ///
+    /// Emits [SdkEvent::PaymentSucceeded] for each settled payment, then
+    /// [SdkEvent::BalanceChanged].
+    pub(crate) async fn settle_pending(&self) -> Result<(), SdkError> {
+        let _balance_guard = self.balance_lock.lock().await;
+        let mut settled_sats = 0;
+        for payment in self.storage.list_payments(0, u32::MAX).await? {
+            if payment.status != PaymentStatus::Pending {
+                continue;
+            }
+            let payment = Payment {
+                status: PaymentStatus::Completed,
+                details: match payment.details {
+                    Some(PaymentDetails::Ark { txid, .. }) => Some(PaymentDetails::Ark {
+                        round_txid: Some(self.next_txid()),
+                        txid,
+                    }),
+                    details => details,
+                },
+                ..payment
+            };
+            self.storage.save_payment(&payment).await?;
+            settled_sats += payment.amount;
+            self.event_emitter
+                .emit(&SdkEvent::PaymentSucceeded { payment });
+        }
+        if settled_sats > 0 {
+            let previous = self.storage.get_offchain_balance().await?;
+            let current = OffchainBalance::new(
+                previous.pending_sats.saturating_sub(settled_sats),
+                previous.confirmed_sats + settled_sats,
+            );
+            self.update_balance(previous, current).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns a made-up destination for the payment method
+    pub(crate) async fn receive_payment(
+        &self,
+        request: ReceivePaymentRequest,
+    ) -> Result<ReceivePaymentResponse, SdkError> {
+        let expiry_secs = request.expiry_secs.unwrap_or(DEMO_REQUEST_EXPIRY_SECS);
+        let expires_at = self
+            .clock
+            .now_secs()
+            .checked_add(expiry_secs)
+            .ok_or_else(|| {
+                SdkError::InvalidInput(format!("Expiry of {expiry_secs} seconds is too long"))
+            })?;
+        let index = self.next_index.fetch_add(1, Ordering::SeqCst);
+        let destination = match &request.payment_method {
+            PaymentMethod::Bolt11Invoice { .. } => format!("lnbc-demo-invoice-{index}"),
+            PaymentMethod::Bolt12Offer => format!("lno-demo-offer-{index}"),
+            PaymentMethod::BitcoinAddress { .. } | PaymentMethod::ExternalDeposit { .. } => {
+                format!("bc1-demo-address-{index}")
+            }
+            PaymentMethod::ArkAddress { .. } => format!("ark-demo-address-{index}"),
+        };
+        info!("Demo receive to {destination}");
+        Ok(ReceivePaymentResponse {
+            destination,
+            fee_sat: 0,
+            address_index: match request.payment_method {
+                PaymentMethod::ArkAddress { .. } => Some(index),
+                _ => None,
+            },
+            correlation_id: format!("demo-request-{index}"),
+            payment_method: request.payment_method,
+            expires_at,
+            min_amount_sat: 1,
+            bip21: None,
+            deposit_verification: None,
+            warning: None,
+        })
+    }
+
+    /// Prepares a demo send to any destination, paid offchain for a fixed fee
+    pub(crate) async fn prepare_send_payment(
+        &self,
+        request: PrepareSendPaymentRequest,
+    ) -> Result<PrepareSendPaymentResponse, SdkError> {
+        let receiver_amount_sat = match request.amount {
+            Some(PayAmount::Specific {
+                receiver_amount_sat,
+            }) => receiver_amount_sat,
+            Some(PayAmount::Drain) => {
+                let balance = self.storage.get_offchain_balance().await?;
+                balance.confirmed_sats.saturating_sub(DEMO_FEE_SAT)
+            }
+            None => {
+                return Err(SdkError::AmountRequired(
+                    "Demo sends need an amount".to_string(),
+                ))
+            }
+        };
+        Ok(PrepareSendPaymentResponse {
+            destination: SendDestination::ArkAddress {
+                address: request.destination,
+                receiver_amount_sat,
+            },
+            fees_sat: Some(DEMO_FEE_SAT),
+            route: PaymentRoute::Ark,
+            correlation_id: format!(
+                "demo-send-{}",
+                self.next_index.fetch_add(1, Ordering::SeqCst)
+            ),
+        })
+    }
+
+    /// Completes a prepared demo send right away, spending the confirmed balance
+    pub(crate) async fn send_payment(
+        &self,
+        request: SendPaymentRequest,
+    ) -> Result<SendPaymentResponse, SdkError> {
+        let prepared = request.prepare_response;
+        let SendDestination::ArkAddress {
+            address,
+            receiver_amount_sat,
+        } = prepared.destination
+        else {
+            return Err(SdkError::InvalidInput(
+                "Demo sends must be prepared with BreezSdk::prepare_send_payment".to_string(),
+            ));
+        };
+        let fees_sat = prepared.fees_sat.unwrap_or_default();
+        let total_sat = receiver_amount_sat.checked_add(fees_sat).ok_or_else(|| {
+            SdkError::InvalidInput("The amount and fees of the send overflow".to_string())
+        })?;
+
+        let _balance_guard = self.balance_lock.lock().await;
+        let previous = self.storage.get_offchain_balance().await?;
+        if previous.confirmed_sats < total_sat {
+            return Err(SdkError::PaymentError(format!(
+                "Insufficient funds: the send needs {total_sat} sats, {} sats are spendable",
+                previous.confirmed_sats
+            )));
+        }
+        // Demo sends complete at once, so they're identified by their txid from the start
+        let txid = self.next_txid();
+        self.event_emitter.emit(&SdkEvent::PaymentProgress {
+            send_id: txid.clone(),
+            step: SendStep::FundsReserved,
+        });
+        self.event_emitter.emit(&SdkEvent::PaymentSending {
+            destination: address.clone(),
+            amount_sat: receiver_amount_sat,
+            correlation_id: prepared.correlation_id,
+            send_id: txid.clone(),
+        });
+        self.event_emitter.emit(&SdkEvent::PaymentProgress {
+            send_id: txid.clone(),
+            step: SendStep::AcceptedByServer,
+        });
+        let payment = Payment {
+            destination: Some(address),
+            fees: fees_sat,
+            ..self.payment(
+                PaymentType::Sent,
+                PaymentStatus::Completed,
+                receiver_amount_sat,
+                self.clock.now_secs(),
+                PaymentDetails::Ark {
+                    round_txid: None,
+                    txid,
+                },
+            )
+        };
+        self.storage.save_payment(&payment).await?;
+        self.event_emitter.emit(&SdkEvent::PaymentSucceeded {
+            payment: payment.clone(),
+        });
+        let current =
+            OffchainBalance::new(previous.pending_sats, previous.confirmed_sats - total_sat);
+        self.update_balance(previous, current).await?;
+        Ok(SendPaymentResponse { payment })
+    }
+
+    /// Fabricates an offchain payment received from someone else, pending settlement until
+    /// the next [DemoArk::settle_pending]
+    pub(crate) async fn simulate_incoming_payment(
+        &self,
+        amount_sat: u64,
+    ) -> Result<Payment, SdkError> {
+        let _balance_guard = self.balance_lock.lock().await;
+        let payment = self.payment(
+            PaymentType::Received,
+            PaymentStatus::Pending,
+            amount_sat,
+            self.clock.now_secs(),
+            PaymentDetails::Ark {
+                txid: self.next_txid(),
+                round_txid: None,
+            },
+        );
+        self.storage.save_payment(&payment).await?;
+        self.event_emitter.emit(&SdkEvent::PaymentPending {
+            payment: payment.clone(),
+            correlation_id: payment.id.clone(),
+            send_id: None,
+        });
+        let previous = self.storage.get_offchain_balance().await?;
+        let current =
+            OffchainBalance::new(previous.pending_sats + amount_sat, previous.confirmed_sats);
+        self.update_balance(previous, current).await?;
+        Ok(payment)
+    }
+
+    /// Fills the wallet with a confirmed deposit, a completed send and a received payment
+    /// pending settlement
+    async fn seed(&self) -> Result<(), SdkError> {
+        const DAY_SECS: u64 = 24 * 60 * 60;
+        let now = self.clock.now_secs();
+        let deposit_txid = self.next_txid();
+        let deposit = self.payment(
+            PaymentType::Received,
+            PaymentStatus::Completed,
+            100_000,
+            now.saturating_sub(3 * DAY_SECS),
+            PaymentDetails::Onchain {
+                txid: deposit_txid,
+                confirmed_at: Some(now.saturating_sub(3 * DAY_SECS)),
+            },
+        );
+        let send = Payment {
+            destination: Some("ark-demo-merchant".to_string()),
+            description: Some("Coffee beans".to_string()),
+            fees: DEMO_FEE_SAT,
+            ..self.payment(
+                PaymentType::Sent,
+                PaymentStatus::Completed,
+                25_000,
+                now.saturating_sub(2 * DAY_SECS),
+                PaymentDetails::Ark {
+                    txid: self.next_txid(),
+                    round_txid: None,
+                },
+            )
+        };
+        let pending = Payment {
+            description: Some("Dinner split".to_string()),
+            ..self.payment(
+                PaymentType::Received,
+                PaymentStatus::Pending,
+                10_000,
+                now.saturating_sub(60 * 60),
+                PaymentDetails::Ark {
+                    txid: self.next_txid(),
+                    round_txid: None,
+                },
+            )
+        };
+        let balance =
+            OffchainBalance::new(pending.amount, deposit.amount - send.amount - send.fees);
+        self.storage
+            .save_payments(&[deposit, send, pending])
+            .await?;
+        self.storage.save_offchain_balance(&balance).await
+    }
+
+    /// Persists the new balance and reports the change
+    async fn update_balance(
+        &self,
+        previous: OffchainBalance,
+        current: OffchainBalance,
+    ) -> Result<(), SdkError> {
+        self.storage.save_offchain_balance(&current).await?;
+        self.event_emitter.emit(&SdkEvent::BalanceChanged {
+            delta_sats: current.total_sats() as i64 - previous.total_sats() as i64,
+            previous,
+            current,
+        });
+        Ok(())
+    }
+
+    fn payment(
+        &self,
+        payment_type: PaymentType,
+        status: PaymentStatus,
+        amount: u64,
+        timestamp: u64,
+        details: PaymentDetails,
+    ) -> Payment {
+        let id = match &details {
+            PaymentDetails::Onchain { txid, .. } | PaymentDetails::Ark { txid, .. } => txid.clone(),
+            PaymentDetails::Lightning { payment_hash } => payment_hash.clone(),
+        };
+        Payment {
+            id,
+            payment_type,
+            status,
+            amount,
+            fees: 0,
+            timestamp,
+            description: None,
+            destination: None,
+            is_retryable: false,
+            details: Some(details),
+            onchain_txs: Vec::new(),
+            order_id: None,
+            deposit_source: None,
+            failure_reason: None,
+        }
+    }
+
+    /// Deterministic transaction ID, derived from the number of IDs handed out before
+    fn next_txid(&self) -> String {
+        let index = self.next_index.fetch_add(1, Ordering::SeqCst);
+        sha256::Hash::hash(format!("breez-sdk-ark-demo/{index}").as_bytes()).to_string()
+    }
+}
+
+/// Chain backend of a demo wallet, without any transactions and with a block every 10 minutes
+/// of the [Clock]
+pub(crate) struct DemoChainService {
+    clock: Arc<dyn Clock>,
+}
+
+impl DemoChainService {
+    pub(crate) fn new(clock: Arc<dyn Clock>) -> Self {
+        Self { clock }
+    }
+}
+
+#[async_trait]
+impl ChainService for DemoChainService {
+    async fn find_outpoints(&self, _address: &Address) -> Result<Vec<ExplorerUtxo>, SdkError> {
+        Ok(Vec::new())
+    }
+
+    async fn find_tx(&self, _txid: &Txid) -> Result<Option<Transaction>, SdkError> {
+        Ok(None)
+    }
+
+    async fn get_output_status(&self, _txid: &Txid, _vout: u32) -> Result<SpendStatus, SdkError> {
+        Ok(SpendStatus { spend_txid: None })
+    }
+
+    async fn broadcast(&self, _tx: &Transaction) -> Result<(), SdkError> {
+        Err(SdkError::GenericError(
+            "Demo wallets can't broadcast transactions".to_string(),
+        ))
+    }
+
+    async fn tip_height(&self) -> Result<u32, SdkError> {
+        let elapsed_secs = self.clock.now_secs().saturating_sub(DEMO_GENESIS_SECS);
+        Ok((elapsed_secs / 600) as u32)
+    }
+
+    async fn confirmation_height(&self, _txid: &Txid) -> Result<Option<u32>, SdkError> {
+        Ok(None)
+    }
+
+    async fn tx_inclusion_proof(&self, _txid: &Txid) -> Result<Option<MerkleBlock>, SdkError> {
+        Ok(None)
+    }
+
+    async fn fee_estimates(&self) -> Result<HashMap<u16, f64>, SdkError> {
+        Ok(HashMap::from([(1, DEMO_FEE_RATE)]))
+    }
+}
+
+/// Fee provider of a demo wallet, charging the fixed demo fee offchain
+pub(crate) struct DemoFeeProvider;
+
+#[async_trait]
+impl FeeProvider for DemoFeeProvider {
+    async fn service_fees(&self) -> Result<ServiceFees, SdkError> {
+        Ok(ServiceFees {
+            offchain_fee_sat: DEMO_FEE_SAT,
+            ..Default::default()
+        })
+    }
+
+    async fn chain_fee_rate(&self, _target_blocks: u16) -> Result<f64, SdkError> {
+        Ok(DEMO_FEE_RATE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventListener;
+    use crate::models::{
+        Config, GetBalanceRequest, ListPaymentsRequest, Network, SyncMode, SyncWalletRequest,
+    };
+    use crate::sdk_builder::SdkBuilder;
+    use crate::BreezSdk;
+    use std::sync::Mutex;
+
+    const MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    struct FixedClock;
+
+    impl Clock for FixedClock {
+        fn now_secs(&self) -> u64 {
+            1_700_000_000
+        }
+    }
+
+    struct RecordingListener {
+        events: Arc<Mutex<Vec<SdkEvent>>>,
+    }
+
+    impl EventListener for RecordingListener {
+        fn on_event(&self, event: &SdkEvent) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+
+    async fn demo_sdk() -> BreezSdk {
+        let config = Config::default_config(Network::Regtest, String::new()).unwrap();
+        SdkBuilder::new(config, MNEMONIC.to_string())
+            .demo()
+            .clock(Arc::new(FixedClock))
+            .build()
+            .await
+            .unwrap()
+    }
+
+    fn list_all() -> ListPaymentsRequest {
+        ListPaymentsRequest {
+            offset: 0,
+            limit: 10,
+            cursor: None,
+            sort: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_demo_is_deterministic() {
+        let first = demo_sdk().await;
+        let second = demo_sdk().await;
+        let ids = |payments: Vec<Payment>| {
+            payments
+                .into_iter()
+                .map(|payment| (payment.id, payment.timestamp))
+                .collect::<Vec<_>>()
+        };
+        let payments = ids(first.list_payments(list_all()).await.unwrap().payments);
+        assert_eq!(3, payments.len());
+        assert_eq!(
+            payments,
+            ids(second.list_payments(list_all()).await.unwrap().payments)
+        );
+
+        let balance = first.get_balance(GetBalanceRequest {}).await.unwrap();
+        assert_eq!(74_900, balance.spendable_sats);
+        assert_eq!(10_000, balance.pending_settlement_sats);
+    }
+
+    #[tokio::test]
+    async fn test_demo_send_and_settle() {
+        let sdk = demo_sdk().await;
+        let events = Arc::new(Mutex::new(Vec::new()));
+        sdk.add_event_listener(Box::new(RecordingListener {
+            events: events.clone(),
+        }));
+
+        let prepared = sdk
+            .prepare_send_payment(PrepareSendPaymentRequest {
+                destination: "ark-demo-friend".to_string(),
+                amount: Some(PayAmount::Specific {
+                    receiver_amount_sat: 4_900,
+                }),
+            })
+            .await
+            .unwrap();
+        sdk.send_payment(SendPaymentRequest {
+            prepare_response: prepared,
+        })
+        .await
+        .unwrap();
+        sdk.sync_wallet(SyncWalletRequest {
+            mode: SyncMode::Full,
+        })
+        .await
+        .unwrap();
+
+        let balance = sdk.get_balance(GetBalanceRequest {}).await.unwrap();
+        assert_eq!(OffchainBalance::new(0, 79_900), balance.balance);
+        let events = events.lock().unwrap();
+        let SdkEvent::PaymentSucceeded { payment } = &events[3] else {
+            panic!("Unexpected event {:?}", events[3]);
+        };
+        assert!(matches!(
+            &events[0],
+            SdkEvent::PaymentProgress { send_id, step: SendStep::FundsReserved }
+                if send_id == &payment.id
+        ));
+        assert!(matches!(
+            &events[1],
+            SdkEvent::PaymentSending { send_id, .. } if send_id == &payment.id
+        ));
+        assert!(matches!(
+            &events[2],
+            SdkEvent::PaymentProgress { send_id, step: SendStep::AcceptedByServer }
+                if send_id == &payment.id
+        ));
+        assert!(matches!(events.last(), Some(SdkEvent::Synced {})));
+    }
+
+    #[tokio::test]
+    async fn test_demo_send_overflow_rejected() {
+        let sdk = demo_sdk().await;
+        let mut prepared = sdk
+            .prepare_send_payment(PrepareSendPaymentRequest {
+                destination: "ark-demo-friend".to_string(),
+                amount: Some(PayAmount::Specific {
+                    receiver_amount_sat: u64::MAX,
+                }),
+            })
+            .await
+            .unwrap();
+        prepared.fees_sat = Some(1);
+
+        let result = sdk
+            .send_payment(SendPaymentRequest {
+                prepare_response: prepared,
+            })
+            .await;
+        assert!(matches!(result, Err(SdkError::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn test_demo_receive_expiry_overflow_rejected() {
+        let sdk = demo_sdk().await;
+        let result = sdk
+            .receive_payment(ReceivePaymentRequest {
+                payment_method: PaymentMethod::ArkAddress {
+                    receiver_amount_sat: None,
+                },
+                expiry_secs: Some(u64::MAX),
+                order_id: None,
+                description: None,
+            })
+            .await;
+        assert!(matches!(result, Err(SdkError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::models::DepositVerification;
+
+/// Characters of an address shown per group, matching how hardware wallets display addresses
+const ADDRESS_GROUP_LEN: usize = 4;
+
+/// Characters allowed in output descriptors, as defined by BIP380
+const DESCRIPTOR_INPUT_CHARSET: &str =
+    "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+/// Characters the descriptor checksum is encoded with
+const DESCRIPTOR_CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// What a user checks before sending to the boarding address from an external wallet
+///
+/// The address is split in groups the way hardware wallets show it, so it can be compared on
+/// the device screen group by group. The `addr()` descriptor lets watch-only wallets track the
+/// deposit until it is boarded.
+pub(crate) fn verification(address: &str, server_pubkey: String) -> DepositVerification {
+    DepositVerification {
+        address_groups: address_groups(address),
+        descriptor: address_descriptor(address),
+        server_pubkey,
+    }
+}
+
+fn address_groups(address: &str) -> Vec<String> {
+    address
+        .as_bytes()
+        .chunks(ADDRESS_GROUP_LEN)
+        .map(|group| String::from_utf8_lossy(group).into_owned())
+        .collect()
+}
+
+/// The `addr()` output descriptor of an address, with its checksum
+fn address_descriptor(address: &str) -> String {
+    let descriptor = format!("addr({address})");
+    match descriptor_checksum(&descriptor) {
+        Some(checksum) => format!("{descriptor}#{checksum}"),
+        None => descriptor,
+    }
+}
+
+/// Computes the BIP380 checksum of a descriptor, `None` if it contains invalid characters
+fn descriptor_checksum(descriptor: &str) -> Option<String> {
+    let mut c = 1u64;
+    let mut class = 0u64;
+    let mut class_count = 0;
+    for ch in descriptor.chars() {
+        let position = DESCRIPTOR_INPUT_CHARSET.find(ch)? as u64;
+        c = polymod(c, position & 31);
+        class = class * 3 + (position >> 5);
+        class_count += 1;
+        if class_count == 3 {
+            c = polymod(c, class);
+            class = 0;
+            class_count = 0;
+        }
+    }
+    if class_count > 0 {
+        c = polymod(c, class);
+    }
+    for _ in 0..8 {
+        c = polymod(c, 0);
+    }
+    c ^= 1;
+
+    Some(
+        (0..8)
+            .map(|j| DESCRIPTOR_CHECKSUM_CHARSET[((c >> (5 * (7 - j))) & 31) as usize] as char)
+            .collect(),
+    )
+}
+
+fn polymod(c: u64, value: u64) -> u64 {
+    const GENERATOR: [u64; 5] = [
+        0xf5dee51989,
+        0xa9fdca3312,
+        0x1bab10e32d,
+        0x3706b1677a,
+        0x644d626ffd,
+    ];
+    let c0 = c >> 35;
+    let mut c = ((c & 0x7ffffffff) << 5) ^ value;
+    for (bit, generator) in GENERATOR.iter().enumerate() {
+        if (c0 >> bit) & 1 == 1 {
+            c ^= generator;
+        }
+    }
+    c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deposit_verification() {
+        // Test vector of BIP380
+        assert_eq!(
+            Some("89f8spxm".to_string()),
+            descriptor_checksum("raw(deadbeef)")
+        );
+        assert_eq!(None, descriptor_checksum("addr(ünicode)"));
+
+        let address = "bcrt1q9ylz8ld5yqz7quevzp6ruzhsmpyvxhkwdfx2mq";
+        let verification = verification(address, "02server".to_string());
+        assert_eq!("bcrt", verification.address_groups[0]);
+        assert_eq!("x2mq", verification.address_groups[10]);
+        assert_eq!(address, verification.address_groups.concat());
+        assert!(verification
+            .descriptor
+            .starts_with(&format!("addr({address})#")));
+    }
+}
@@ -46,14 +46,217 @@ pub enum SdkError {
 
     #[error("Invalid network")]
     InvalidNetwork,
+
+    /// Error when an operation didn't complete in time
+    #[error("Timeout: {0}")]
+    Timeout(String),
+
+    /// Error when a user provided input can't be parsed
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// Error when the Ark server reported state that doesn't match the chain
+    #[error("Server inconsistency: {0}")]
+    ServerInconsistency(String),
+
+    /// Error when the Ark server doesn't sign with the key the wallet expects
+    #[error("Untrusted server: {0}")]
+    UntrustedServer(String),
+
+    /// Error when a round transaction doesn't match what the wallet requested
+    #[error("Round verification failed: {0}")]
+    RoundVerificationFailed(String),
+
+    /// Error when a payment needs an amount the caller didn't specify
+    #[error("Amount required: {0}")]
+    AmountRequired(String),
+
+    /// Error when the specified amount conflicts with the amount of the destination
+    #[error("Amount mismatch: {0}")]
+    AmountMismatch(String),
+
+    /// Error when the amount is outside the range the payment method supports
+    #[error(
+        "Amount {amount_sat} sats is out of range, must be between {min_sat} and {max_sat} sats"
+    )]
+    AmountOutOfRange {
+        amount_sat: u64,
+        min_sat: u64,
+        max_sat: u64,
+    },
+
+    /// Error when the amount is below the smallest one the payment method accepts, e.g. the
+    /// server minimum or the dust limit
+    #[error("Amount {amount_sat} sats is too small, must be at least {min_sat} sats")]
+    AmountTooSmall { amount_sat: u64, min_sat: u64 },
+
+    /// Error when the amount is above the largest one the payment method accepts
+    #[error("Amount {amount_sat} sats is too large, must be at most {max_sat} sats")]
+    AmountTooLarge { amount_sat: u64, max_sat: u64 },
+
+    /// Error when a prepared quote is executed after it expired
+    #[error("Quote {0} expired, prepare it again")]
+    QuoteExpired(String),
+
+    /// Error when the mnemonic can't be used to restore a wallet
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+
+    /// Error when another process is using the same data directory
+    #[error("Already running: {0}")]
+    AlreadyRunning(String),
+
+    /// Error when the SDK can't be started again after it was disconnected or dropped
+    #[error("SDK already stopped")]
+    AlreadyStopped,
+}
+
+impl SdkError {
+    /// Stable numeric code of the error, for apps to map errors to localized UI strings
+    ///
+    /// Codes never change or get reused, new variants get new codes.
+    pub fn code(&self) -> u32 {
+        self.identity().0
+    }
+
+    /// Stable key of the error, e.g. `amount_too_small`, usable as a localization key
+    pub fn message_key(&self) -> &'static str {
+        self.identity().1
+    }
+
+    /// Values to fill in the localized message of the error, keyed by placeholder name
+    ///
+    /// Only errors with structured details have arguments, the free-form details of the other
+    /// errors are technical and not meant to be shown to users.
+    pub fn message_args(&self) -> Vec<(String, String)> {
+        let arg = |name: &str, value: &u64| (name.to_string(), value.to_string());
+        match self {
+            SdkError::AmountOutOfRange {
+                amount_sat,
+                min_sat,
+                max_sat,
+            } => vec![
+                arg("amount_sat", amount_sat),
+                arg("min_sat", min_sat),
+                arg("max_sat", max_sat),
+            ],
+            SdkError::AmountTooSmall {
+                amount_sat,
+                min_sat,
+            } => vec![arg("amount_sat", amount_sat), arg("min_sat", min_sat)],
+            SdkError::AmountTooLarge {
+                amount_sat,
+                max_sat,
+            } => vec![arg("amount_sat", amount_sat), arg("max_sat", max_sat)],
+            _ => Vec::new(),
+        }
+    }
+
+    /// English message suitable to show to users, for apps without their own translations
+    pub fn user_message(&self) -> String {
+        match self {
+            SdkError::ConnectError(_) => "Couldn't connect to the wallet services".to_string(),
+            SdkError::GenericError(_) => "Something went wrong".to_string(),
+            SdkError::ArkClientError(_) => {
+                "The Ark server couldn't process the request".to_string()
+            }
+            SdkError::StorageError(_) => "Couldn't access the wallet data".to_string(),
+            SdkError::PaymentError(_) => "The payment failed".to_string(),
+            SdkError::WalletError(_) => "The wallet couldn't complete the operation".to_string(),
+            SdkError::NetworkError(_) => "Network error, check your connection".to_string(),
+            SdkError::NotInitialized => "The wallet isn't ready yet".to_string(),
+            SdkError::AddressParsingError(_) => "The address isn't valid".to_string(),
+            SdkError::TransactionError(_) => "The transaction couldn't be created".to_string(),
+            SdkError::InvalidNetwork => "The address is for a different network".to_string(),
+            SdkError::Timeout(_) => "The operation took too long, try again".to_string(),
+            SdkError::InvalidInput(_) => "The input isn't valid".to_string(),
+            SdkError::ServerInconsistency(_) => {
+                "The Ark server reported inconsistent data".to_string()
+            }
+            SdkError::UntrustedServer(_) => "The Ark server couldn't be verified".to_string(),
+            SdkError::RoundVerificationFailed(_) => {
+                "The Ark server proposed an invalid transaction".to_string()
+            }
+            SdkError::AmountRequired(_) => "Enter an amount".to_string(),
+            SdkError::AmountMismatch(_) => {
+                "The amount doesn't match the payment request".to_string()
+            }
+            SdkError::AmountOutOfRange {
+                min_sat, max_sat, ..
+            } => format!("The amount must be between {min_sat} and {max_sat} sats"),
+            SdkError::AmountTooSmall { min_sat, .. } => {
+                format!("The amount must be at least {min_sat} sats")
+            }
+            SdkError::AmountTooLarge { max_sat, .. } => {
+                format!("The amount must be at most {max_sat} sats")
+            }
+            SdkError::QuoteExpired(_) => "The quote expired, try again".to_string(),
+            SdkError::InvalidMnemonic(_) => "The recovery phrase isn't valid".to_string(),
+            SdkError::AlreadyRunning(_) => "The wallet is already open elsewhere".to_string(),
+            SdkError::AlreadyStopped => "The wallet was closed".to_string(),
+        }
+    }
+
+    fn identity(&self) -> (u32, &'static str) {
+        match self {
+            SdkError::ConnectError(_) => (1, "connect_error"),
+            SdkError::GenericError(_) => (2, "generic_error"),
+            SdkError::ArkClientError(_) => (3, "ark_client_error"),
+            SdkError::StorageError(_) => (4, "storage_error"),
+            SdkError::PaymentError(_) => (5, "payment_error"),
+            SdkError::WalletError(_) => (6, "wallet_error"),
+            SdkError::NetworkError(_) => (7, "network_error"),
+            SdkError::NotInitialized => (8, "not_initialized"),
+            SdkError::AddressParsingError(_) => (9, "address_parsing_error"),
+            SdkError::TransactionError(_) => (10, "transaction_error"),
+            SdkError::InvalidNetwork => (11, "invalid_network"),
+            SdkError::Timeout(_) => (12, "timeout"),
+            SdkError::InvalidInput(_) => (13, "invalid_input"),
+            SdkError::ServerInconsistency(_) => (14, "server_inconsistency"),
+            SdkError::UntrustedServer(_) => (15, "untrusted_server"),
+            SdkError::RoundVerificationFailed(_) => (16, "round_verification_failed"),
+            SdkError::AmountRequired(_) => (17, "amount_required"),
+            SdkError::AmountMismatch(_) => (18, "amount_mismatch"),
+            SdkError::AmountOutOfRange { .. } => (19, "amount_out_of_range"),
+            SdkError::AmountTooSmall { .. } => (20, "amount_too_small"),
+            SdkError::AmountTooLarge { .. } => (21, "amount_too_large"),
+            SdkError::QuoteExpired(_) => (22, "quote_expired"),
+            SdkError::InvalidMnemonic(_) => (23, "invalid_mnemonic"),
+            SdkError::AlreadyRunning(_) => (24, "already_running"),
+            SdkError::AlreadyStopped => (25, "already_stopped"),
+        }
+    }
 }
 
 impl From<ark_client::Error> for SdkError {
     fn from(err: ark_client::Error) -> Self {
-        SdkError::ArkClientError(err.to_string())
+        match is_transport_error(&err) {
+            true => SdkError::NetworkError(err.to_string()),
+            false => SdkError::ArkClientError(err.to_string()),
+        }
     }
 }
 
+/// Whether an error was caused by the connection to a service rather than reported by it
+///
+/// Dropped connections and timeouts surface as I/O errors or elapsed timeouts among the
+/// sources of the error, while rejections such as gRPC statuses sent by the server don't.
+fn is_transport_error(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if err.is::<std::io::Error>() || err.is::<tokio::time::error::Elapsed>() {
+            return true;
+        }
+        if let Some(err) = err.downcast_ref::<reqwest::Error>() {
+            if err.is_connect() || err.is_timeout() {
+                return true;
+            }
+        }
+        source = err.source();
+    }
+    false
+}
+
 impl From<rusqlite::Error> for SdkError {
     fn from(err: rusqlite::Error) -> Self {
         SdkError::StorageError(err.to_string())
@@ -89,3 +292,90 @@ impl From<ExtractTxError> for SdkError {
         SdkError::TransactionError(err.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_error_codes_unique() {
+        let details = || "details".to_string();
+        let errors = [
+            SdkError::ConnectError(details()),
+            SdkError::GenericError(details()),
+            SdkError::ArkClientError(details()),
+            SdkError::StorageError(details()),
+            SdkError::PaymentError(details()),
+            SdkError::WalletError(details()),
+            SdkError::NetworkError(details()),
+            SdkError::NotInitialized,
+            SdkError::AddressParsingError(details()),
+            SdkError::TransactionError(details()),
+            SdkError::InvalidNetwork,
+            SdkError::Timeout(details()),
+            SdkError::InvalidInput(details()),
+            SdkError::ServerInconsistency(details()),
+            SdkError::UntrustedServer(details()),
+            SdkError::RoundVerificationFailed(details()),
+            SdkError::AmountRequired(details()),
+            SdkError::AmountMismatch(details()),
+            SdkError::AmountOutOfRange {
+                amount_sat: 1,
+                min_sat: 2,
+                max_sat: 3,
+            },
+            SdkError::AmountTooSmall {
+                amount_sat: 1,
+                min_sat: 2,
+            },
+            SdkError::AmountTooLarge {
+                amount_sat: 3,
+                max_sat: 2,
+            },
+            SdkError::QuoteExpired(details()),
+            SdkError::InvalidMnemonic(details()),
+            SdkError::AlreadyRunning(details()),
+            SdkError::AlreadyStopped,
+        ];
+        let codes: HashSet<u32> = errors.iter().map(SdkError::code).collect();
+        let keys: HashSet<&str> = errors.iter().map(SdkError::message_key).collect();
+        assert_eq!(errors.len(), codes.len());
+        assert_eq!(errors.len(), keys.len());
+        // User messages never leak the technical details
+        assert!(errors.iter().all(|e| !e.user_message().contains("details")));
+    }
+
+    #[test]
+    fn test_error_codes_stable() {
+        let err = SdkError::AmountTooSmall {
+            amount_sat: 100,
+            min_sat: 330,
+        };
+        assert_eq!(20, err.code());
+        assert_eq!("amount_too_small", err.message_key());
+        assert_eq!(
+            vec![
+                ("amount_sat".to_string(), "100".to_string()),
+                ("min_sat".to_string(), "330".to_string())
+            ],
+            err.message_args()
+        );
+        assert_eq!("The amount must be at least 330 sats", err.user_message());
+        assert_eq!(12, SdkError::Timeout("Esplora".to_string()).code());
+    }
+
+    #[derive(Debug, Error)]
+    #[error("request failed")]
+    struct RequestError(#[source] Option<std::io::Error>);
+
+    #[test]
+    fn test_transport_errors() {
+        let dropped = RequestError(Some(std::io::Error::from(
+            std::io::ErrorKind::ConnectionReset,
+        )));
+        assert!(is_transport_error(&dropped));
+        assert!(!is_transport_error(&RequestError(None)));
+    }
+}
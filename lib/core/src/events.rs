@@ -1,45 +1,343 @@
-use crate::models::Payment;
+use crate::clock::{Clock, SystemClock};
+use crate::models::{
+    ConnectionState, LocalizedText, OffchainBalance, Payment, PaymentType, QuietHours,
+    RemoteService, SendStep,
+};
+use log::error;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::mpsc;
+
+/// Consecutive panics after which a listener is removed
+const MAX_LISTENER_PANICS: u32 = 3;
 
 /// Enum representing different SDK events
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum SdkEvent {
+    /// The cached state was loaded from storage and can be queried, before the first sync
+    InitialStateLoaded {
+        /// The last persisted balance
+        balance: OffchainBalance,
+    },
+
     /// Wallet has been synced with the network
     Synced {},
 
+    /// Wallet has been synced with the network for the first time since connecting
+    SyncedFirstTime {},
+
+    /// A new block was found, the wallet is synced right away
+    NewBlock {
+        /// Height of the new chain tip
+        height: u32,
+    },
+
+    /// The persisted balance changed, either in total or between pending and confirmed
+    BalanceChanged {
+        /// The balance before the change
+        previous: OffchainBalance,
+        /// The balance after the change
+        current: OffchainBalance,
+        /// Change of the total balance in satoshis, negative when it decreased
+        delta_sats: i64,
+    },
+
     /// Sucesfull Payment
     PaymentSucceeded {
         /// The payment details
         payment: Payment,
     },
 
-    /// A pending payment
+    /// The wallet registered in an Ark round and is waiting for it to complete
+    RoundStarted {},
+
+    /// The Ark round the wallet took part in is over
+    RoundFinished {
+        /// Whether the round completed successfully
+        success: bool,
+    },
+
+    /// A payment is being signed with the Ark server
+    PaymentSending {
+        /// The payment destination
+        destination: String,
+        /// Amount in satoshis
+        amount_sat: u64,
+        /// Identifies the payment flow in the SDK logs
+        correlation_id: String,
+        /// ID of the send as listed by [crate::BreezSdk::list_payments] until the Ark server
+        /// accepts it
+        #[serde(default)]
+        send_id: String,
+    },
+
+    /// A payment being sent completed a step, for apps to show its progress
+    PaymentProgress {
+        /// ID of the send as listed by [crate::BreezSdk::list_payments] until the Ark server
+        /// accepts it, or the quote ID for onchain sends. [SdkEvent::PaymentPending] links it
+        /// to the payment the send results in.
+        send_id: String,
+        /// The step completed
+        step: SendStep,
+    },
+
+    /// A sent payment was accepted by the Ark server and is pending
     PaymentPending {
         /// The payment details
         payment: Payment,
+        /// Identifies the payment flow in the SDK logs
+        correlation_id: String,
+        /// ID the send was listed with before the Ark server accepted it, as reported by
+        /// [SdkEvent::PaymentSending] and [SdkEvent::PaymentProgress]. Not set for received
+        /// payments.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        send_id: Option<String>,
+    },
+
+    /// A payment to a destination generated by [crate::BreezSdk::receive_payment] was received
+    PaymentReceived {
+        /// The payment details, including the order ID of the request it paid
+        payment: Payment,
+        /// ID of the payment request the payment was matched to
+        payment_request_id: String,
+    },
+
+    /// A send was cancelled before it reached the Ark server
+    PaymentCancelled {
+        /// The payment details
+        payment: Payment,
+    },
+
+    /// A pending received payment wasn't settled in time and can no longer be claimed
+    PaymentExpired {
+        /// The payment details
+        payment: Payment,
+    },
+
+    /// The Ark server reported state that doesn't match the chain. Sends are paused until
+    /// the inconsistency is resolved
+    ServerInconsistency {
+        /// What doesn't match
+        details: String,
+    },
+
+    /// A round transaction doesn't pay what the wallet requested
+    ///
+    /// Rounds are signed within the Ark client, so this is detected after the round completed
+    /// and the payment it belongs to isn't failed.
+    RoundVerificationFailed {
+        /// The round transaction ID
+        txid: String,
+        /// Why the verification failed
+        reason: String,
+    },
+
+    /// The local clock is far off from the time of the chain tip. Rounds and signing are time
+    /// sensitive and may fail until the device clock is corrected
+    ClockSkewDetected {
+        /// Local time minus the time of the chain tip in seconds, positive when the local clock
+        /// is ahead
+        skew_secs: i64,
+    },
+
+    /// Settings were changed with [crate::BreezSdk::update_config] and are now in use
+    ConfigUpdated {
+        /// Names of the changed [crate::models::Config] fields
+        fields: Vec<String>,
+    },
+
+    /// The connection to a remote service changed state, e.g. from connected to degraded
+    ConnectionStateChanged {
+        /// The service whose connection changed
+        service: RemoteService,
+        /// The state before the change
+        previous: ConnectionState,
+        /// The state after the change
+        current: ConnectionState,
     },
 }
 
+impl SdkEvent {
+    /// Describes the event for display, e.g. in a notification
+    ///
+    /// Events about a payment have the `payment_id` and `amount_sat` parameters, connection
+    /// events the `service` and `state` parameters, e.g. `ark_server` and `degraded`.
+    pub fn description(&self) -> LocalizedText {
+        let payment_text = |key: &str, payment: &Payment| {
+            LocalizedText::new(key)
+                .param("payment_id", &payment.id)
+                .param("amount_sat", payment.amount)
+        };
+        match self {
+            SdkEvent::InitialStateLoaded { balance } => {
+                LocalizedText::new("event.state_loaded").param("balance_sat", balance.total_sats())
+            }
+            SdkEvent::Synced {} => LocalizedText::new("event.synced"),
+            SdkEvent::SyncedFirstTime {} => LocalizedText::new("event.synced_first_time"),
+            SdkEvent::NewBlock { height } => {
+                LocalizedText::new("event.new_block").param("height", height)
+            }
+            SdkEvent::BalanceChanged {
+                current,
+                delta_sats,
+                ..
+            } => LocalizedText::new(match delta_sats.is_negative() {
+                true => "event.balance_decreased",
+                false => "event.balance_increased",
+            })
+            .param("delta_sat", delta_sats.unsigned_abs())
+            .param("balance_sat", current.total_sats()),
+            SdkEvent::PaymentSucceeded { payment } => match payment.payment_type {
+                PaymentType::Sent => payment_text("event.payment_sent", payment),
+                PaymentType::Received => payment_text("event.payment_received", payment),
+            },
+            SdkEvent::RoundStarted {} => LocalizedText::new("event.round_started"),
+            SdkEvent::RoundFinished { success: true } => {
+                LocalizedText::new("event.round_completed")
+            }
+            SdkEvent::RoundFinished { success: false } => LocalizedText::new("event.round_failed"),
+            SdkEvent::PaymentSending { amount_sat, .. } => {
+                LocalizedText::new("event.payment_sending").param("amount_sat", amount_sat)
+            }
+            SdkEvent::PaymentProgress { send_id, step } => LocalizedText::new(match step {
+                SendStep::FundsReserved => "event.send_progress.funds_reserved",
+                SendStep::AcceptedByServer => "event.send_progress.accepted_by_server",
+                SendStep::Broadcast => "event.send_progress.broadcast",
+            })
+            .param("send_id", send_id),
+            // Says what the payment waits for, e.g. `payment.pending_settlement`
+            SdkEvent::PaymentPending { payment, .. } => {
+                payment_text(payment.status_description().key.as_str(), payment)
+            }
+            SdkEvent::PaymentReceived { payment, .. } => {
+                payment_text("event.payment_request_paid", payment)
+            }
+            SdkEvent::PaymentCancelled { payment } => {
+                payment_text("event.payment_cancelled", payment)
+            }
+            SdkEvent::PaymentExpired { payment } => payment_text("event.payment_expired", payment),
+            SdkEvent::ServerInconsistency { .. } => {
+                LocalizedText::new("event.server_inconsistency")
+            }
+            SdkEvent::RoundVerificationFailed { .. } => {
+                LocalizedText::new("event.round_verification_failed")
+            }
+            SdkEvent::ClockSkewDetected { skew_secs } => {
+                LocalizedText::new("event.clock_skew_detected").param("skew_secs", skew_secs)
+            }
+            SdkEvent::ConfigUpdated { .. } => LocalizedText::new("event.config_updated"),
+            SdkEvent::ConnectionStateChanged {
+                service, current, ..
+            } => LocalizedText::new("event.connection_changed")
+                .param(
+                    "service",
+                    match service {
+                        RemoteService::ArkServer => "ark_server",
+                        RemoteService::Esplora => "esplora",
+                    },
+                )
+                .param(
+                    "state",
+                    match current {
+                        ConnectionState::Connected => "connected",
+                        ConnectionState::Degraded => "degraded",
+                        ConnectionState::Disconnected => "disconnected",
+                    },
+                ),
+        }
+    }
+
+    /// Whether the event can be held back during quiet hours
+    ///
+    /// Only notifications about things the user didn't initiate are deferred. Events the app
+    /// needs to drive its UI or to protect funds are always delivered right away.
+    fn is_deferrable(&self) -> bool {
+        match self {
+            SdkEvent::PaymentSucceeded { payment } | SdkEvent::PaymentPending { payment, .. } => {
+                payment.payment_type == PaymentType::Received
+            }
+            SdkEvent::PaymentExpired { .. }
+            | SdkEvent::PaymentReceived { .. }
+            | SdkEvent::NewBlock { .. } => true,
+            _ => false,
+        }
+    }
+}
+
 /// Trait for event listeners
 pub trait EventListener: Send + Sync {
     /// Called when an event occurs
     fn on_event(&self, event: &SdkEvent);
 }
 
+/// Forwards events to a channel, for SDK methods waiting on them
+pub(crate) struct ChannelListener {
+    pub(crate) sender: mpsc::UnboundedSender<SdkEvent>,
+}
+
+impl EventListener for ChannelListener {
+    fn on_event(&self, event: &SdkEvent) {
+        // The receiver is gone once the waiting method returned, before the listener is removed
+        let _ = self.sender.send(event.clone());
+    }
+}
+
 /// Event emitter for SDK events
 pub struct EventEmitter {
-    listeners: Mutex<HashMap<String, Box<dyn EventListener>>>,
+    listeners: Mutex<HashMap<String, RegisteredListener>>,
+    quiet_hours: Mutex<Option<QuietHours>>,
+    deferred: Mutex<Vec<SdkEvent>>,
+    clock: Arc<dyn Clock>,
+}
+
+struct RegisteredListener {
+    listener: Box<dyn EventListener>,
+    /// Panics since the listener last handled an event successfully
+    panics: u32,
 }
 
 impl EventEmitter {
     /// Creates a new event emitter
     pub fn new() -> Self {
+        Self::with_quiet_hours(None, Arc::new(SystemClock))
+    }
+
+    /// Creates a new event emitter holding back non-critical events during quiet hours
+    ///
+    /// # Arguments
+    ///
+    /// * `quiet_hours` - The daily window during which non-critical events are deferred
+    /// * `clock` - The clock telling whether it's quiet hours
+    pub fn with_quiet_hours(quiet_hours: Option<QuietHours>, clock: Arc<dyn Clock>) -> Self {
         Self {
             listeners: Mutex::new(HashMap::new()),
+            quiet_hours: Mutex::new(quiet_hours),
+            deferred: Mutex::new(Vec::new()),
+            clock,
         }
     }
 
+    /// Changes the quiet hours, `None` delivering all events right away from now on
+    ///
+    /// Events deferred so far are delivered by the next flush outside the new window.
+    ///
+    /// # Arguments
+    ///
+    /// * `quiet_hours` - The daily window during which non-critical events are deferred
+    pub fn set_quiet_hours(&self, quiet_hours: Option<QuietHours>) {
+        *self.quiet_hours.lock().unwrap() = quiet_hours;
+    }
+
+    fn is_quiet(&self) -> bool {
+        self.quiet_hours
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|quiet_hours| quiet_hours.contains_time(self.clock.now_secs()))
+    }
+
     /// Adds a listener to the event emitter
     ///
     /// # Arguments
@@ -51,8 +349,13 @@ impl EventEmitter {
     /// A unique ID for the listener
     pub fn add_listener(&self, listener: Box<dyn EventListener>) -> String {
         let id = uuid::Uuid::new_v4().to_string();
-        let mut listeners = self.listeners.lock().unwrap();
-        listeners.insert(id.clone(), listener);
+        self.lock_listeners().insert(
+            id.clone(),
+            RegisteredListener {
+                listener,
+                panics: 0,
+            },
+        );
         id
     }
 
@@ -66,19 +369,318 @@ impl EventEmitter {
     ///
     /// `true` if the listener was found and removed, `false` otherwise
     pub fn remove_listener(&self, id: &str) -> bool {
-        let mut listeners = self.listeners.lock().unwrap();
-        listeners.remove(id).is_some()
+        self.lock_listeners().remove(id).is_some()
     }
 
     /// Emits an event to all listeners
     ///
+    /// During quiet hours, non-critical events are queued and delivered by
+    /// [EventEmitter::flush_deferred] once the quiet hours are over.
+    ///
     /// # Arguments
     ///
     /// * `event` - The event to emit
     pub fn emit(&self, event: &SdkEvent) {
-        let listeners = self.listeners.lock().unwrap();
-        for listener in listeners.values() {
-            listener.on_event(event);
+        if self.is_quiet() {
+            if event.is_deferrable() {
+                self.deferred.lock().unwrap().push(event.clone());
+                return;
+            }
+        } else {
+            self.flush_deferred();
+        }
+
+        self.deliver(event);
+    }
+
+    /// Delivers the events held back during quiet hours, once they're over
+    pub fn flush_deferred(&self) {
+        if self.is_quiet() {
+            return;
+        }
+
+        let deferred = std::mem::take(&mut *self.deferred.lock().unwrap());
+        for event in &deferred {
+            self.deliver(event);
+        }
+    }
+
+    /// Delivers an event to a single listener, e.g. the cached state to a listener just added
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the listener
+    /// * `event` - The event to deliver
+    pub fn emit_to(&self, id: &str, event: &SdkEvent) {
+        let mut listeners = self.lock_listeners();
+        let Some(registered) = listeners.get_mut(id) else {
+            return;
+        };
+        if !Self::deliver_to(id, registered, event) {
+            listeners.remove(id);
+        }
+    }
+
+    /// Delivers an event to every listener, isolating them from each other's panics
+    fn deliver(&self, event: &SdkEvent) {
+        self.lock_listeners()
+            .retain(|id, registered| Self::deliver_to(id, registered, event));
+    }
+
+    /// Delivers an event to a listener, catching its panics
+    ///
+    /// Returns `false` for a listener that keeps panicking, which is removed so it can't flood
+    /// the logs.
+    fn deliver_to(id: &str, registered: &mut RegisteredListener, event: &SdkEvent) -> bool {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| registered.listener.on_event(event)));
+        if result.is_ok() {
+            registered.panics = 0;
+            return true;
+        }
+
+        registered.panics += 1;
+        error!("Event listener {id} panicked handling {event:?}");
+        if registered.panics >= MAX_LISTENER_PANICS {
+            error!("Removing event listener {id} after {MAX_LISTENER_PANICS} panics in a row");
+            return false;
         }
+        true
+    }
+
+    /// Locks the listeners, recovering them if a previous holder panicked
+    fn lock_listeners(&self) -> MutexGuard<'_, HashMap<String, RegisteredListener>> {
+        self.listeners
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PaymentStatus;
+
+    struct RecordingListener {
+        events: Arc<Mutex<Vec<SdkEvent>>>,
+    }
+
+    impl EventListener for RecordingListener {
+        fn on_event(&self, event: &SdkEvent) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+
+    fn payment(payment_type: PaymentType) -> Payment {
+        Payment {
+            id: "payment".to_string(),
+            payment_type,
+            status: PaymentStatus::Completed,
+            amount: 1_000,
+            fees: 0,
+            timestamp: 1_700_000_000,
+            description: None,
+            destination: None,
+            is_retryable: false,
+            details: None,
+            onchain_txs: Vec::new(),
+            order_id: None,
+            deposit_source: None,
+            failure_reason: None,
+        }
+    }
+
+    #[test]
+    fn test_quiet_hours_defer_notifications() {
+        // Quiet all day long
+        let emitter = EventEmitter::with_quiet_hours(
+            Some(QuietHours {
+                start_hour: 0,
+                end_hour: 24,
+                utc_offset_minutes: None,
+            }),
+            Arc::new(SystemClock),
+        );
+        let events = Arc::new(Mutex::new(Vec::new()));
+        emitter.add_listener(Box::new(RecordingListener {
+            events: events.clone(),
+        }));
+
+        emitter.emit(&SdkEvent::PaymentSucceeded {
+            payment: payment(PaymentType::Received),
+        });
+        emitter.emit(&SdkEvent::PaymentSucceeded {
+            payment: payment(PaymentType::Sent),
+        });
+        emitter.flush_deferred();
+
+        // Only the payment the user sent gets through
+        let events = events.lock().unwrap();
+        assert_eq!(1, events.len());
+        assert!(matches!(
+            &events[0],
+            SdkEvent::PaymentSucceeded { payment } if payment.payment_type == PaymentType::Sent
+        ));
+    }
+
+    struct PanickingListener {}
+
+    impl EventListener for PanickingListener {
+        fn on_event(&self, _event: &SdkEvent) {
+            panic!("Listener failure");
+        }
+    }
+
+    #[test]
+    fn test_panicking_listener_is_isolated() {
+        let emitter = EventEmitter::new();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let panicking_id = emitter.add_listener(Box::new(PanickingListener {}));
+        emitter.add_listener(Box::new(RecordingListener {
+            events: events.clone(),
+        }));
+
+        for _ in 0..MAX_LISTENER_PANICS {
+            emitter.emit(&SdkEvent::Synced {});
+        }
+
+        // The other listener got every event and the failing one was removed
+        assert_eq!(MAX_LISTENER_PANICS as usize, events.lock().unwrap().len());
+        assert!(!emitter.remove_listener(&panicking_id));
+    }
+
+    #[test]
+    fn test_emit_to_single_listener() {
+        let emitter = EventEmitter::new();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let panicking_id = emitter.add_listener(Box::new(PanickingListener {}));
+        let recording_id = emitter.add_listener(Box::new(RecordingListener {
+            events: events.clone(),
+        }));
+
+        emitter.emit_to(&recording_id, &SdkEvent::Synced {});
+        assert_eq!(1, events.lock().unwrap().len());
+
+        // Panics are caught like for any other event
+        for _ in 0..MAX_LISTENER_PANICS {
+            emitter.emit_to(&panicking_id, &SdkEvent::Synced {});
+        }
+        assert_eq!(1, events.lock().unwrap().len());
+        assert!(!emitter.remove_listener(&panicking_id));
+    }
+
+    #[test]
+    fn test_quiet_hours_span_midnight() {
+        let quiet_hours = QuietHours {
+            start_hour: 22,
+            end_hour: 7,
+            utc_offset_minutes: None,
+        };
+        assert!(quiet_hours.contains(23));
+        assert!(quiet_hours.contains(3));
+        assert!(!quiet_hours.contains(7));
+        assert!(!quiet_hours.contains(12));
+    }
+
+    struct FixedClock;
+
+    impl Clock for FixedClock {
+        fn now_secs(&self) -> u64 {
+            // 2023-11-14 22:13:20 UTC
+            1_700_000_000
+        }
+    }
+
+    #[test]
+    fn test_quiet_hours_follow_clock_and_offset() {
+        let quiet_hours = |utc_offset_minutes| QuietHours {
+            start_hour: 22,
+            end_hour: 23,
+            utc_offset_minutes: Some(utc_offset_minutes),
+        };
+        assert!(quiet_hours(0).contains_time(FixedClock.now_secs()));
+        // 23:13 at UTC+1, 21:13 at UTC-1
+        assert!(!quiet_hours(60).contains_time(FixedClock.now_secs()));
+        assert!(!quiet_hours(-60).contains_time(FixedClock.now_secs()));
+        assert!(QuietHours {
+            start_hour: 23,
+            end_hour: 24,
+            utc_offset_minutes: Some(60),
+        }
+        .contains_time(FixedClock.now_secs()));
+
+        let emitter = EventEmitter::with_quiet_hours(Some(quiet_hours(0)), Arc::new(FixedClock));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        emitter.add_listener(Box::new(RecordingListener {
+            events: events.clone(),
+        }));
+        emitter.emit(&SdkEvent::PaymentSucceeded {
+            payment: payment(PaymentType::Received),
+        });
+        assert!(events.lock().unwrap().is_empty());
+
+        // Leaving the window, e.g. after travelling, delivers the held back event
+        emitter.set_quiet_hours(Some(quiet_hours(60)));
+        emitter.flush_deferred();
+        assert_eq!(1, events.lock().unwrap().len());
+    }
+
+    #[test]
+    fn test_event_description() {
+        let description = SdkEvent::PaymentSucceeded {
+            payment: payment(PaymentType::Sent),
+        }
+        .description();
+        assert_eq!("event.payment_sent", description.key);
+        assert_eq!(
+            Some(&"payment".to_string()),
+            description.params.get("payment_id")
+        );
+
+        let description = SdkEvent::BalanceChanged {
+            previous: OffchainBalance::new(0, 3_000),
+            current: OffchainBalance::new(0, 2_000),
+            delta_sats: -1_000,
+        }
+        .description();
+        assert_eq!("event.balance_decreased", description.key);
+        assert_eq!(
+            Some(&"1000".to_string()),
+            description.params.get("delta_sat")
+        );
+    }
+
+    #[test]
+    fn test_send_id_links_progress_to_payment() {
+        let description = SdkEvent::PaymentProgress {
+            send_id: "send".to_string(),
+            step: SendStep::AcceptedByServer,
+        }
+        .description();
+        assert_eq!("event.send_progress.accepted_by_server", description.key);
+        assert_eq!(Some(&"send".to_string()), description.params.get("send_id"));
+
+        // Events serialized before sends were identified read without the ID
+        let event: SdkEvent = serde_json::from_value(serde_json::json!({
+            "PaymentSending": {
+                "destination": "ark1address",
+                "amount_sat": 1_000,
+                "correlation_id": "flow",
+            }
+        }))
+        .unwrap();
+        assert!(matches!(event, SdkEvent::PaymentSending { send_id, .. } if send_id.is_empty()));
+
+        let event = SdkEvent::PaymentPending {
+            payment: payment(PaymentType::Sent),
+            correlation_id: "flow".to_string(),
+            send_id: Some("send".to_string()),
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!("send", json["PaymentPending"]["send_id"]);
+        let event: SdkEvent = serde_json::from_value(json).unwrap();
+        assert!(matches!(
+            event,
+            SdkEvent::PaymentPending { send_id: Some(id), .. } if id == "send"
+        ));
     }
 }
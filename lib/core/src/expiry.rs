@@ -0,0 +1,31 @@
+use bitcoin::{relative, Sequence};
+
+/// Seconds per block, used to estimate height based timelocks
+const BLOCK_INTERVAL_SECS: u64 = 600;
+/// Granularity of time based relative timelocks
+const TIMELOCK_GRANULARITY_SECS: u64 = 512;
+
+/// Approximates the duration of a relative timelock in seconds
+///
+/// Returns `None` if the sequence doesn't encode a relative timelock.
+pub(crate) fn timelock_secs(sequence: Sequence) -> Option<u64> {
+    match sequence.to_relative_lock_time()? {
+        relative::LockTime::Blocks(height) => Some(u64::from(height.value()) * BLOCK_INTERVAL_SECS),
+        relative::LockTime::Time(time) => Some(u64::from(time.value()) * TIMELOCK_GRANULARITY_SECS),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timelock_secs() {
+        assert_eq!(Some(144 * 600), timelock_secs(Sequence::from_height(144)));
+        assert_eq!(
+            Some(1024),
+            timelock_secs(Sequence::from_512_second_intervals(2))
+        );
+        assert_eq!(None, timelock_secs(Sequence::MAX));
+    }
+}
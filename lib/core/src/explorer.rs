@@ -0,0 +1,46 @@
+use crate::models::Network;
+
+/// Base URL of the public mempool.space explorer for the network, if it has one
+fn default_explorer_url(network: &Network) -> Option<&'static str> {
+    match network {
+        Network::Bitcoin => Some("https://mempool.space"),
+        Network::Testnet => Some("https://mempool.space/testnet"),
+        Network::Signet => Some("https://mempool.space/signet"),
+        Network::Regtest => None,
+    }
+}
+
+/// Links to a transaction on a mempool.space-style explorer
+///
+/// Uses `explorer_url` if given, otherwise the public mempool.space instance of the network.
+/// Returns `None` on regtest without a configured explorer.
+pub(crate) fn tx_url(explorer_url: Option<&str>, network: &Network, txid: &str) -> Option<String> {
+    let base_url = explorer_url.or_else(|| default_explorer_url(network))?;
+    Some(format!("{}/tx/{txid}", base_url.trim_end_matches('/')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tx_url() {
+        let txid = "5c1e4b3ad07c6fbb0c1fc6a6a3f8d5ec4c2bd2a3e8e8d3c3f4e6a2a1c0b9d8e7";
+
+        assert_eq!(
+            Some(format!("https://mempool.space/tx/{txid}")),
+            tx_url(None, &Network::Bitcoin, txid)
+        );
+        assert_eq!(
+            Some(format!("https://mempool.space/signet/tx/{txid}")),
+            tx_url(None, &Network::Signet, txid)
+        );
+        assert_eq!(None, tx_url(None, &Network::Regtest, txid));
+
+        // A configured explorer takes precedence
+        assert_eq!(
+            Some(format!("http://localhost:5000/tx/{txid}")),
+            tx_url(Some("http://localhost:5000/"), &Network::Regtest, txid)
+        );
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ark_client::{ExplorerUtxo, SpendStatus};
+use async_trait::async_trait;
+use bitcoin::{Address, MerkleBlock, Transaction, Txid};
+use log::warn;
+
+use crate::chain::{ChainService, ChainTip};
+use crate::error::SdkError;
+use crate::models::{
+    BoardingBalance, Note, OffchainBalance, Payment, PaymentRequest, PendingSend, RetentionAction,
+    ServerBalance,
+};
+use crate::persist::{HistoryCheckpoint, PaymentsQuery, Storage};
+
+/// Injects realistic failures into a running SDK, so apps can test their own error handling
+///
+/// Get the injector of an SDK with [crate::BreezSdk::fault_injector]. Faults only affect
+/// operations started after they were injected, and each counted fault is used up by the
+/// operation it fails.
+#[derive(Debug, Default)]
+pub struct FaultInjector {
+    storage_write_failures: AtomicU32,
+    round_failures: AtomicU32,
+    broadcast_delay: Mutex<Option<Duration>>,
+}
+
+impl FaultInjector {
+    /// Fails the next storage writes with [SdkError::StorageError], reads still succeed
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - How many writes fail, replacing any failures not used up yet
+    pub fn fail_storage_writes(&self, count: u32) {
+        self.storage_write_failures.store(count, Ordering::SeqCst);
+    }
+
+    /// Fails the next rounds the wallet joins right after [crate::SdkEvent::RoundStarted],
+    /// before anything is sent to the Ark server
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - How many rounds fail, replacing any failures not used up yet
+    pub fn fail_next_rounds(&self, count: u32) {
+        self.round_failures.store(count, Ordering::SeqCst);
+    }
+
+    /// Holds back every transaction broadcast, both by the SDK and its Ark clients
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - How long each broadcast is held back, `None` to broadcast right away again
+    pub fn delay_broadcasts(&self, delay: Option<Duration>) {
+        *self.broadcast_delay.lock().unwrap() = delay;
+    }
+
+    /// Removes all injected faults
+    pub fn clear(&self) {
+        self.fail_storage_writes(0);
+        self.fail_next_rounds(0);
+        self.delay_broadcasts(None);
+    }
+
+    pub(crate) fn storage_write(&self) -> Result<(), SdkError> {
+        match take(&self.storage_write_failures) {
+            true => Err(SdkError::StorageError(
+                "Injected storage write failure".to_string(),
+            )),
+            false => Ok(()),
+        }
+    }
+
+    pub(crate) fn round(&self) -> Result<(), SdkError> {
+        match take(&self.round_failures) {
+            true => Err(SdkError::ArkClientError(
+                "Injected round failure".to_string(),
+            )),
+            false => Ok(()),
+        }
+    }
+
+    async fn broadcast(&self) {
+        let delay = *self.broadcast_delay.lock().unwrap();
+        if let Some(delay) = delay {
+            warn!("Delaying broadcast by {delay:?} for an injected fault");
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// Uses up one of the faults of a counter, returning `false` if none is left
+fn take(counter: &AtomicU32) -> bool {
+    counter
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+            count.checked_sub(1)
+        })
+        .is_ok()
+}
+
+/// Storage failing writes injected with [FaultInjector::fail_storage_writes]
+pub(crate) struct FaultyStorage {
+    inner: Arc<dyn Storage>,
+    faults: Arc<FaultInjector>,
+}
+
+impl FaultyStorage {
+    pub(crate) fn new(inner: Arc<dyn Storage>, faults: Arc<FaultInjector>) -> Self {
+        Self { inner, faults }
+    }
+}
+
+#[async_trait]
+impl Storage for FaultyStorage {
+    async fn save_payment(&self, payment: &Payment) -> Result<(), SdkError> {
+        self.faults.storage_write()?;
+        self.inner.save_payment(payment).await
+    }
+
+    async fn save_payments(&self, payments: &[Payment]) -> Result<(), SdkError> {
+        self.faults.storage_write()?;
+        self.inner.save_payments(payments).await
+    }
+
+    async fn get_payment(&self, id: &str) -> Result<Option<Payment>, SdkError> {
+        self.inner.get_payment(id).await
+    }
+
+    async fn list_payments(&self, offset: u32, limit: u32) -> Result<Vec<Payment>, SdkError> {
+        self.inner.list_payments(offset, limit).await
+    }
+
+    async fn list_payments_page(&self, query: &PaymentsQuery) -> Result<Vec<Payment>, SdkError> {
+        self.inner.list_payments_page(query).await
+    }
+
+    async fn archive_payments(
+        &self,
+        before: u64,
+        action: RetentionAction,
+    ) -> Result<u32, SdkError> {
+        self.faults.storage_write()?;
+        self.inner.archive_payments(before, action).await
+    }
+
+    async fn archived_payment_ids(&self) -> Result<HashSet<String>, SdkError> {
+        self.inner.archived_payment_ids().await
+    }
+
+    async fn list_archived_payments(&self) -> Result<Vec<Payment>, SdkError> {
+        self.inner.list_archived_payments().await
+    }
+
+    async fn save_offchain_balance(&self, balance: &OffchainBalance) -> Result<(), SdkError> {
+        self.faults.storage_write()?;
+        self.inner.save_offchain_balance(balance).await
+    }
+
+    async fn get_offchain_balance(&self) -> Result<OffchainBalance, SdkError> {
+        self.inner.get_offchain_balance().await
+    }
+
+    async fn save_server_balances(&self, balances: &[ServerBalance]) -> Result<(), SdkError> {
+        self.faults.storage_write()?;
+        self.inner.save_server_balances(balances).await
+    }
+
+    async fn get_server_balances(&self) -> Result<Vec<ServerBalance>, SdkError> {
+        self.inner.get_server_balances().await
+    }
+
+    async fn save_boarding_balance(&self, balance: &BoardingBalance) -> Result<(), SdkError> {
+        self.faults.storage_write()?;
+        self.inner.save_boarding_balance(balance).await
+    }
+
+    async fn get_boarding_balance(&self) -> Result<BoardingBalance, SdkError> {
+        self.inner.get_boarding_balance().await
+    }
+
+    async fn save_history_checkpoint(
+        &self,
+        checkpoint: &HistoryCheckpoint,
+    ) -> Result<(), SdkError> {
+        self.faults.storage_write()?;
+        self.inner.save_history_checkpoint(checkpoint).await
+    }
+
+    async fn get_history_checkpoint(&self) -> Result<Option<HistoryCheckpoint>, SdkError> {
+        self.inner.get_history_checkpoint().await
+    }
+
+    async fn save_note(&self, note: &Note) -> Result<(), SdkError> {
+        self.faults.storage_write()?;
+        self.inner.save_note(note).await
+    }
+
+    async fn get_note(&self, id: &str) -> Result<Option<Note>, SdkError> {
+        self.inner.get_note(id).await
+    }
+
+    async fn list_notes(&self) -> Result<Vec<Note>, SdkError> {
+        self.inner.list_notes().await
+    }
+
+    async fn get_or_insert_receive_address(
+        &self,
+        address: &str,
+        created_at: u64,
+    ) -> Result<u32, SdkError> {
+        self.faults.storage_write()?;
+        self.inner
+            .get_or_insert_receive_address(address, created_at)
+            .await
+    }
+
+    async fn save_payment_request(&self, request: &PaymentRequest) -> Result<(), SdkError> {
+        self.faults.storage_write()?;
+        self.inner.save_payment_request(request).await
+    }
+
+    async fn list_payment_requests(&self) -> Result<Vec<PaymentRequest>, SdkError> {
+        self.inner.list_payment_requests().await
+    }
+
+    async fn save_pending_send(&self, send: &PendingSend) -> Result<(), SdkError> {
+        self.faults.storage_write()?;
+        self.inner.save_pending_send(send).await
+    }
+
+    async fn delete_pending_send(&self, id: &str) -> Result<(), SdkError> {
+        self.faults.storage_write()?;
+        self.inner.delete_pending_send(id).await
+    }
+
+    async fn list_pending_sends(&self) -> Result<Vec<PendingSend>, SdkError> {
+        self.inner.list_pending_sends().await
+    }
+
+    async fn reserve_funds(
+        &self,
+        id: &str,
+        amount_sat: u64,
+        available_sat: u64,
+    ) -> Result<bool, SdkError> {
+        self.faults.storage_write()?;
+        self.inner
+            .reserve_funds(id, amount_sat, available_sat)
+            .await
+    }
+
+    async fn release_funds(&self, id: &str) -> Result<(), SdkError> {
+        self.faults.storage_write()?;
+        self.inner.release_funds(id).await
+    }
+
+    async fn get_reserved_sats(&self) -> Result<u64, SdkError> {
+        self.inner.get_reserved_sats().await
+    }
+
+    async fn clear_reservations(&self) -> Result<(), SdkError> {
+        self.faults.storage_write()?;
+        self.inner.clear_reservations().await
+    }
+}
+
+/// Chain service holding back broadcasts as set with [FaultInjector::delay_broadcasts]
+pub(crate) struct FaultyChainService {
+    inner: Arc<dyn ChainService>,
+    faults: Arc<FaultInjector>,
+}
+
+impl FaultyChainService {
+    pub(crate) fn new(inner: Arc<dyn ChainService>, faults: Arc<FaultInjector>) -> Self {
+        Self { inner, faults }
+    }
+}
+
+#[async_trait]
+impl ChainService for FaultyChainService {
+    async fn find_outpoints(&self, address: &Address) -> Result<Vec<ExplorerUtxo>, SdkError> {
+        self.inner.find_outpoints(address).await
+    }
+
+    async fn find_tx(&self, txid: &Txid) -> Result<Option<Transaction>, SdkError> {
+        self.inner.find_tx(txid).await
+    }
+
+    async fn get_output_status(&self, txid: &Txid, vout: u32) -> Result<SpendStatus, SdkError> {
+        self.inner.get_output_status(txid, vout).await
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> Result<(), SdkError> {
+        self.faults.broadcast().await;
+        self.inner.broadcast(tx).await
+    }
+
+    async fn tip_height(&self) -> Result<u32, SdkError> {
+        self.inner.tip_height().await
+    }
+
+    async fn chain_tip(&self) -> Result<ChainTip, SdkError> {
+        self.inner.chain_tip().await
+    }
+
+    async fn confirmation_height(&self, txid: &Txid) -> Result<Option<u32>, SdkError> {
+        self.inner.confirmation_height(txid).await
+    }
+
+    async fn tx_inclusion_proof(&self, txid: &Txid) -> Result<Option<MerkleBlock>, SdkError> {
+        self.inner.tx_inclusion_proof(txid).await
+    }
+
+    async fn fee_estimates(&self) -> Result<HashMap<u16, f64>, SdkError> {
+        self.inner.fee_estimates().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persist::sqlite::SqliteStorage;
+
+    #[tokio::test]
+    async fn test_storage_writes_fail() {
+        let faults = Arc::new(FaultInjector::default());
+        let storage = FaultyStorage::new(
+            Arc::new(SqliteStorage::new_in_memory().unwrap()),
+            faults.clone(),
+        );
+        let balance = OffchainBalance::new(0, 1_000);
+
+        faults.fail_storage_writes(2);
+        assert!(storage.save_offchain_balance(&balance).await.is_err());
+        // Reads aren't affected and don't use up the failures
+        assert!(storage.get_offchain_balance().await.is_ok());
+        assert!(storage.save_offchain_balance(&balance).await.is_err());
+        storage.save_offchain_balance(&balance).await.unwrap();
+        assert_eq!(balance, storage.get_offchain_balance().await.unwrap());
+    }
+
+    #[test]
+    fn test_rounds_failed() {
+        let faults = FaultInjector::default();
+        faults.fail_next_rounds(1);
+        assert!(matches!(faults.round(), Err(SdkError::ArkClientError(_))));
+        assert!(faults.round().is_ok());
+
+        faults.fail_next_rounds(3);
+        faults.clear();
+        assert!(faults.round().is_ok());
+    }
+}
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use ark_core::server::Info;
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use crate::chain::ChainService;
+use crate::error::SdkError;
+use crate::models::ServiceFees;
+
+/// Confirmation target, in blocks, used for onchain fee estimates
+pub(crate) const DEFAULT_CONFIRMATION_TARGET: u16 = 6;
+/// Fee rate used when the chain service has no estimate, in sat/vB
+const MIN_RELAY_FEE_RATE: f64 = 1.0;
+/// Virtual size of a taproot output, added to a round when off-boarding
+pub(crate) const ONCHAIN_OUTPUT_VBYTES: u64 = 43;
+/// Virtual size of a boarding input spent collaboratively with the Ark server
+pub(crate) const BOARDING_INPUT_VBYTES: u64 = 111;
+/// Approximate virtual size of the transactions exiting a single VTXO without the Ark server
+pub(crate) const UNILATERAL_EXIT_VBYTES: u64 = 300;
+/// How long the Ark operator's fee schedule is cached before being fetched again
+const FEE_SCHEDULE_TTL: Duration = Duration::from_secs(10 * 60);
+/// Routing margin reserved for Lightning payments, which the Ark operator doesn't report
+const DEFAULT_LN_ROUTING_MARGIN_PPM: u32 = 5_000;
+
+/// Trait for estimating the fees of SDK operations
+///
+/// Fees are made of the Ark operator's service fees plus, for operations touching the chain,
+/// the cost of the onchain data they add at the current fee rate.
+#[async_trait]
+pub trait FeeProvider: Send + Sync {
+    /// Get the fees charged by the Ark operator
+    async fn service_fees(&self) -> Result<ServiceFees, SdkError>;
+
+    /// Get the fee rate, in sat/vB, to confirm a transaction within `target_blocks`
+    async fn chain_fee_rate(&self, target_blocks: u16) -> Result<f64, SdkError>;
+}
+
+/// Estimates the cost of `vbytes` of onchain data at the default confirmation target
+pub(crate) async fn chain_fee_sat(
+    fee_provider: &dyn FeeProvider,
+    vbytes: u64,
+) -> Result<u64, SdkError> {
+    let fee_rate = fee_provider
+        .chain_fee_rate(DEFAULT_CONFIRMATION_TARGET)
+        .await?;
+    Ok((fee_rate * vbytes as f64).ceil() as u64)
+}
+
+/// Default fee provider, using the fee schedule reported by the Ark server and estimating
+/// chain fees with the configured chain service
+pub struct DefaultFeeProvider {
+    ark_server_url: String,
+    blockchain: Arc<dyn ChainService>,
+    fee_schedule: Mutex<Option<(Instant, ServiceFees)>>,
+}
+
+impl DefaultFeeProvider {
+    pub fn new(ark_server_url: String, blockchain: Arc<dyn ChainService>) -> Self {
+        Self {
+            ark_server_url,
+            blockchain,
+            fee_schedule: Mutex::new(None),
+        }
+    }
+}
+
+/// Fetches the current info of an Ark server, including its fees and signing key
+pub(crate) async fn fetch_server_info(ark_server_url: &str) -> Result<Info, SdkError> {
+    let mut client = ark_grpc::Client::new(ark_server_url.to_string());
+    client
+        .connect()
+        .await
+        .map_err(|e| SdkError::NetworkError(format!("Failed to connect to Ark server: {e}")))?;
+    client
+        .get_info()
+        .await
+        .map_err(|e| SdkError::NetworkError(format!("Failed to get Ark server info: {e}")))
+}
+
+/// Maps the intent fees reported by the Ark server to the fees of each SDK operation
+fn fee_schedule_from_info(info: &Info) -> ServiceFees {
+    let Some(fees) = &info.fees else {
+        return ServiceFees {
+            ln_routing_margin_ppm: DEFAULT_LN_ROUTING_MARGIN_PPM,
+            ..Default::default()
+        };
+    };
+
+    let intent_fee = &fees.intent_fee;
+    ServiceFees {
+        boarding_fee_sat: intent_fee.onchain_input.to_sat(),
+        offboard_fee_sat: intent_fee.onchain_output.to_sat(),
+        offchain_fee_sat: intent_fee.offchain_output.to_sat(),
+        round_fee_sat: intent_fee.offchain_input.to_sat(),
+        ln_routing_margin_ppm: DEFAULT_LN_ROUTING_MARGIN_PPM,
+    }
+}
+
+#[async_trait]
+impl FeeProvider for DefaultFeeProvider {
+    async fn service_fees(&self) -> Result<ServiceFees, SdkError> {
+        // Holding the lock while fetching avoids concurrent requests for the same schedule
+        let mut fee_schedule = self.fee_schedule.lock().await;
+        if let Some((fetched_at, fees)) = fee_schedule.as_ref() {
+            if fetched_at.elapsed() < FEE_SCHEDULE_TTL {
+                return Ok(fees.clone());
+            }
+        }
+
+        let fees = fee_schedule_from_info(&fetch_server_info(&self.ark_server_url).await?);
+        *fee_schedule = Some((Instant::now(), fees.clone()));
+        Ok(fees)
+    }
+
+    async fn chain_fee_rate(&self, target_blocks: u16) -> Result<f64, SdkError> {
+        let estimates = self.blockchain.fee_estimates().await?;
+
+        // Use the estimate for the closest target that confirms at least as fast as requested
+        let fee_rate = estimates
+            .into_iter()
+            .filter(|(target, _)| *target <= target_blocks)
+            .max_by_key(|(target, _)| *target)
+            .map_or(MIN_RELAY_FEE_RATE, |(_, rate)| rate);
+        Ok(fee_rate.max(MIN_RELAY_FEE_RATE))
+    }
+}
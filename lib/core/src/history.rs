@@ -0,0 +1,177 @@
+use ark_core::ArkTransaction;
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+
+use crate::models::{BoardingBalance, ServerBalance};
+
+/// Version of the conversion of Ark transactions into payments, bumped whenever it changes so
+/// payments saved by an older SDK version are converted again
+pub(crate) const PAYMENT_CONVERSION_VERSION: u32 = 1;
+/// How long the transaction history goes unfetched while the balances stay the same, in seconds
+///
+/// A change that doesn't move any balance, e.g. a send and a receive of the same amount between
+/// two syncs, is picked up after this long.
+pub(crate) const HISTORY_REFRESH_SECS: u64 = 60 * 60;
+
+/// Fingerprint of the Ark transaction history, changing whenever a transaction is added or
+/// changes state, e.g. a redeem settles or a boarding transaction confirms
+///
+/// The fingerprint is persisted in a [crate::persist::HistoryCheckpoint], so it is a SHA256
+/// hash, stable across SDK versions.
+pub(crate) fn fingerprint(history: &[ArkTransaction]) -> String {
+    let mut engine = sha256::Hash::engine();
+    engine.input(&(history.len() as u64).to_be_bytes());
+    for tx in history {
+        match tx {
+            ArkTransaction::Boarding {
+                txid,
+                amount,
+                confirmed_at,
+            } => {
+                engine.input(&[0]);
+                engine.input(txid.as_byte_array());
+                engine.input(&amount.to_sat().to_be_bytes());
+                engine.input(&confirmed_at.unwrap_or(-1).to_be_bytes());
+            }
+            ArkTransaction::Round {
+                txid,
+                amount,
+                created_at,
+            } => {
+                engine.input(&[1]);
+                engine.input(txid.as_byte_array());
+                engine.input(&amount.to_sat().to_be_bytes());
+                engine.input(&created_at.to_be_bytes());
+            }
+            ArkTransaction::Redeem {
+                txid,
+                amount,
+                is_settled,
+                created_at,
+            } => {
+                engine.input(&[2]);
+                engine.input(txid.as_byte_array());
+                engine.input(&amount.to_sat().to_be_bytes());
+                engine.input(&[u8::from(*is_settled)]);
+                engine.input(&created_at.to_be_bytes());
+            }
+        }
+    }
+    sha256::Hash::from_engine(engine).to_string()
+}
+
+/// Fingerprint of the balances held with each Ark server and on the boarding address
+///
+/// Every payment moves a balance, e.g. a redeem settling moves funds from pending to confirmed,
+/// so the transaction history is only fetched when the fingerprint changes.
+pub(crate) fn balances_fingerprint(
+    server_balances: &[ServerBalance],
+    boarding_balance: &BoardingBalance,
+) -> String {
+    let mut engine = sha256::Hash::engine();
+    for server_balance in server_balances {
+        engine.input(&(server_balance.ark_server_url.len() as u64).to_be_bytes());
+        engine.input(server_balance.ark_server_url.as_bytes());
+        engine.input(&server_balance.balance.pending_sats.to_be_bytes());
+        engine.input(&server_balance.balance.confirmed_sats.to_be_bytes());
+    }
+    engine.input(&boarding_balance.eligible_sats.to_be_bytes());
+    engine.input(&boarding_balance.waiting_sats.to_be_bytes());
+    sha256::Hash::from_engine(engine).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persist::HistoryCheckpoint;
+    use bitcoin::{SignedAmount, Txid};
+
+    fn redeem(is_settled: bool) -> ArkTransaction {
+        ArkTransaction::Redeem {
+            txid: Txid::all_zeros(),
+            amount: SignedAmount::from_sat(1_000),
+            is_settled,
+            created_at: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_changes() {
+        let pending = fingerprint(&[redeem(false)]);
+        assert_eq!(pending, fingerprint(&[redeem(false)]));
+        assert_ne!(pending, fingerprint(&[redeem(true)]));
+        assert_ne!(pending, fingerprint(&[redeem(false), redeem(false)]));
+        assert_ne!(pending, fingerprint(&[]));
+    }
+
+    fn checkpoint(revisit_at: Option<u64>) -> HistoryCheckpoint {
+        HistoryCheckpoint {
+            fingerprint: fingerprint(&[redeem(false)]),
+            revisit_at,
+            conversion_version: PAYMENT_CONVERSION_VERSION,
+            balances_fingerprint: "balances".to_string(),
+            fetched_at: 100,
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_revisited() {
+        let checkpoint = checkpoint(Some(200));
+        assert!(checkpoint.is_current(&checkpoint.fingerprint, 100));
+        assert!(!checkpoint.is_current(&fingerprint(&[redeem(true)]), 100));
+        // A pending payment expires, so the payments need saving again
+        assert!(!checkpoint.is_current(&checkpoint.fingerprint, 200));
+
+        let settled = HistoryCheckpoint {
+            revisit_at: None,
+            ..checkpoint
+        };
+        assert!(settled.is_current(&settled.fingerprint, u64::MAX));
+
+        // Payments saved by an older conversion are converted again
+        let outdated = HistoryCheckpoint {
+            conversion_version: 0,
+            ..settled
+        };
+        assert!(!outdated.is_current(&outdated.fingerprint, 100));
+    }
+
+    #[test]
+    fn test_checkpoint_covers_balances() {
+        let checkpoint = checkpoint(None);
+        assert!(checkpoint.covers_balances("balances", 100));
+        assert!(!checkpoint.covers_balances("moved", 100));
+        // The history is fetched again once in a while, even if no balance moved
+        assert!(!checkpoint.covers_balances("balances", 100 + HISTORY_REFRESH_SECS));
+
+        let revisited = HistoryCheckpoint {
+            revisit_at: Some(150),
+            ..checkpoint.clone()
+        };
+        assert!(!revisited.covers_balances("balances", 150));
+        let outdated = HistoryCheckpoint {
+            conversion_version: 0,
+            ..checkpoint
+        };
+        assert!(!outdated.covers_balances("balances", 100));
+    }
+
+    #[test]
+    fn test_balances_fingerprint() {
+        let balances = |pending_sats| {
+            vec![ServerBalance {
+                ark_server_url: "https://ark.example.com".to_string(),
+                balance: crate::models::OffchainBalance::new(pending_sats, 5_000),
+            }]
+        };
+        let boarding = BoardingBalance::default();
+        let unchanged = balances_fingerprint(&balances(1_000), &boarding);
+        assert_eq!(unchanged, balances_fingerprint(&balances(1_000), &boarding));
+        // A redeem settling moves funds from pending to confirmed
+        assert_ne!(unchanged, balances_fingerprint(&balances(0), &boarding));
+        let deposit = BoardingBalance {
+            waiting_sats: 2_000,
+            ..BoardingBalance::default()
+        };
+        assert_ne!(unchanged, balances_fingerprint(&balances(1_000), &deposit));
+    }
+}
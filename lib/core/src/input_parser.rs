@@ -0,0 +1,140 @@
+use ark_core::ArkNote;
+use sdk_common::prelude::{parse, InputType as CommonInputType};
+
+use crate::bip21;
+use crate::error::SdkError;
+use crate::models::{InputType, Network};
+use crate::notes::{GiftNote, GIFT_NOTE_HRP};
+use crate::qr;
+
+/// Human readable prefix of an encoded Ark note
+const ARK_NOTE_PREFIX: &str = "arknote";
+
+/// Parses a user provided string into one of the supported input types
+///
+/// Ark specific inputs are recognized here, everything else is left to the sdk_common parser
+/// shared with the other Breez SDKs, so e.g. invoices, BIP21 URIs, LNURLs and lightning
+/// addresses are supported and rejected the same way. Ark addresses, also those in BIP21 URIs,
+/// must be for the network and are returned in lowercase, even if scanned from an upper case
+/// QR code.
+pub(crate) async fn parse_input(input: &str, network: Network) -> Result<InputType, SdkError> {
+    let input = input.trim();
+
+    if input.to_lowercase().starts_with(GIFT_NOTE_HRP) {
+        let note = GiftNote::decode(input)?;
+        return Ok(InputType::ArkNote {
+            note: input.to_string(),
+            amount_sat: note.amount_sat,
+            expires_at: Some(note.expires_at),
+        });
+    }
+
+    if input.to_lowercase().starts_with(ARK_NOTE_PREFIX) {
+        let note = parse_ark_note(input)?;
+        return Ok(InputType::ArkNote {
+            note: input.to_string(),
+            amount_sat: note.value().to_sat(),
+            expires_at: None,
+        });
+    }
+
+    match qr::normalize_ark_address(input, network.clone()) {
+        Ok(address) => return Ok(InputType::ArkAddress { address }),
+        Err(SdkError::InvalidNetwork) => return Err(SdkError::InvalidNetwork),
+        Err(_) => {}
+    }
+
+    // A BIP21 URI with only an Ark address is an Ark payment, which sdk_common doesn't know of
+    if bip21::is_bip21(input) {
+        if let Ok(bip21::Bip21 {
+            address: None,
+            ark_address: Some(address),
+            ..
+        }) = bip21::parse_bip21(input)
+        {
+            return Ok(InputType::ArkAddress {
+                address: qr::normalize_ark_address(&address, network)?,
+            });
+        }
+    }
+
+    let input_type = parse(input, None)
+        .await
+        .map_err(|e| SdkError::InvalidInput(e.to_string()))?;
+    from_common_input_type(input_type)
+}
+
+fn from_common_input_type(input_type: CommonInputType) -> Result<InputType, SdkError> {
+    Ok(match input_type {
+        CommonInputType::BitcoinAddress { address } => InputType::BitcoinAddress { address },
+        CommonInputType::LiquidAddress { .. } => {
+            return Err(SdkError::InvalidInput(
+                "Liquid addresses are not supported".to_string(),
+            ))
+        }
+        CommonInputType::Bolt11 { invoice } => InputType::Bolt11 { invoice },
+        CommonInputType::Bolt12Offer {
+            offer,
+            bip353_address,
+        } => InputType::Bolt12Offer {
+            offer,
+            bip353_address,
+        },
+        CommonInputType::NodeId { node_id } => InputType::NodeId { node_id },
+        CommonInputType::Url { url } => InputType::Url { url },
+        CommonInputType::LnUrlPay {
+            data,
+            bip353_address,
+        } => InputType::LnUrlPay {
+            data,
+            bip353_address,
+        },
+        CommonInputType::LnUrlWithdraw { data } => InputType::LnUrlWithdraw { data },
+        CommonInputType::LnUrlAuth { data } => InputType::LnUrlAuth { data },
+        CommonInputType::LnUrlError { data } => InputType::LnUrlError { data },
+    })
+}
+
+/// Decodes an Ark note from its bearer string encoding
+pub(crate) fn parse_ark_note(note: &str) -> Result<ArkNote, SdkError> {
+    ArkNote::from_string(note.trim())
+        .map_err(|e| SdkError::InvalidInput(format!("Invalid Ark note: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARK_ADDRESS: &str = "tark1qr9f0qgjegdmmjh6cgcm8x3rm3x60ph0lq28cnnjhxq80pd0aeytk03raqtqqw2efgecjnm9vnsmzdyth4aqpzx5939vkulw4m2ecqyat5gxn8";
+
+    #[tokio::test]
+    async fn test_parse_upper_case_ark_address() {
+        let input = parse_input(&ARK_ADDRESS.to_uppercase(), Network::Regtest)
+            .await
+            .unwrap();
+        assert!(matches!(input, InputType::ArkAddress { address } if address == ARK_ADDRESS));
+
+        let uri = format!("bitcoin:?ark={}", ARK_ADDRESS.to_uppercase());
+        let input = parse_input(&uri, Network::Regtest).await.unwrap();
+        assert!(matches!(input, InputType::ArkAddress { address } if address == ARK_ADDRESS));
+    }
+
+    #[tokio::test]
+    async fn test_parse_destination_formatted_for_qr() {
+        let uri = bip21::compose_bip21(None, None, Some(ARK_ADDRESS));
+        for destination in [ARK_ADDRESS, uri.as_str()] {
+            let input = parse_input(&qr::format_for_qr(destination), Network::Regtest)
+                .await
+                .unwrap();
+            assert!(matches!(input, InputType::ArkAddress { address } if address == ARK_ADDRESS));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_ark_address_of_other_network_rejected() {
+        assert!(matches!(
+            parse_input(ARK_ADDRESS, Network::Bitcoin).await,
+            Err(SdkError::InvalidNetwork)
+        ));
+    }
+}
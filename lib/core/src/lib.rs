@@ -1,63 +1,248 @@
+mod backup;
+mod bip21;
 pub mod chain;
+pub mod clock;
+mod connection;
+mod consolidation;
+#[cfg(feature = "demo")]
+mod demo;
+mod deposit;
 pub mod error;
 pub mod events;
+mod expiry;
+mod explorer;
+#[cfg(feature = "test-utils")]
+pub mod faults;
+pub mod fees;
+mod history;
+mod input_parser;
+mod lightning;
+mod lightning_address;
 mod logger;
+mod mnemonic;
 pub mod models;
+mod notes;
+mod payment_requests;
 pub mod persist;
+mod preflight;
+mod proof;
+mod qr;
+mod quotes;
+mod redact;
 pub mod sdk_builder;
+mod sends;
+mod state;
+mod tasks;
+pub mod telemetry;
+mod verification;
 
 use ark_bdk_wallet::Wallet;
-use ark_client::{Client, OfflineClient};
-use ark_core::ArkAddress;
+use ark_client::{wallet::Persistence, Client, OfflineClient};
+use ark_core::{ArkAddress, ArkTransaction};
 use bitcoin::{
     key::Secp256k1,
     secp256k1::{Keypair, SecretKey},
-    Address, Amount,
+    Address, Amount, TxOut, Txid,
 };
+use chain::cache::ChainCache;
 use chain::esplora::EsploraBlockchain;
+use chain::{ChainService, ChainTip, ReplaceableChainService};
+use connection::ConnectionMonitor;
 use error::SdkError;
-use log::{error, info};
+use events::ChannelListener;
+use log::{debug, error, info, trace, warn};
 use models::{Config, ConnectRequest, PrepareSendOnchainRequest, PrepareSendOnchainResponse};
-use persist::ark::InMemoryDb;
+use notes::GiftNote;
+use persist::ark::{InMemoryDb, SharedPersistence};
+use persist::lock::WalletLock;
+use persist::{HistoryCheckpoint, PaymentCursor, PaymentsQuery};
+use quotes::OnchainQuotes;
 use rand::{rngs::StdRng, SeedableRng};
+use sdk_common::prelude::{
+    parse_invoice, validate_lnurl_pay, ReqwestRestClient, ValidatedCallbackResponse,
+};
+use sends::{ReconciledSend, Reconciliation};
+use state::RuntimeState;
 use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
     str::FromStr,
-    sync::Arc,
+    sync::{Arc, Mutex, RwLock, Weak},
     time::{Duration, Instant},
 };
+use tasks::TaskSupervisor;
+use telemetry::{Telemetry, TelemetryListener, TelemetryMetric};
+use verification::SettlementVerifier;
 
 // Export the persist module for external use
 pub use persist::Storage;
 // Export events module for external use
 pub use events::{EventEmitter, EventListener, SdkEvent};
+// Export the fees module for external use
+pub use clock::{Clock, SystemClock};
+pub use fees::{DefaultFeeProvider, FeeProvider};
 
 pub use models::{
-    GetBalanceRequest, GetBalanceResponse, ListPaymentsRequest, ListPaymentsResponse, PayAmount,
-    Payment, PaymentMethod, PaymentStatus, PaymentType, PrepareSendPaymentRequest,
-    PrepareSendPaymentResponse, ReceiveArkRequest, ReceiveArkResponse, ReceiveOnchainRequest,
-    ReceiveOnchainResponse, ReceivePaymentRequest, ReceivePaymentResponse, SendDestination,
-    SendOnchainRequest, SendOnchainResponse, SendPaymentRequest, SendPaymentResponse,
-    SyncWalletRequest, SyncWalletResponse,
+    ArkOperator, ArkServerConfig, BackgroundTaskState, BackgroundTaskStatus, BoardingBalance,
+    CancelPaymentRequest, CancelPaymentResponse, ConnectionCheck, ConnectionState,
+    ConnectionStatus, ConsolidateRequest, ConsolidateResponse, ConsolidationPolicy,
+    CreateNoteRequest, CreateNoteResponse, DepositVerification, ExportPaymentsRequest,
+    ExportPaymentsResponse, ExportRoundProofRequest, ExportRoundProofResponse, ExportStateRequest,
+    ExportStateResponse, GenerateMnemonicRequest, GenerateMnemonicResponse, GetBalanceRequest,
+    GetBalanceResponse, GetChainTipRequest, GetChainTipResponse, GetInfoRequest, GetInfoResponse,
+    GetLimitsRequest, GetLimitsResponse, GetRecentLogsRequest, GetRecentLogsResponse,
+    ImportStateRequest, ImportStateResponse, InputType, Limits, ListNotesRequest,
+    ListNotesResponse, ListOnchainUtxosRequest, ListOnchainUtxosResponse,
+    ListPaymentRequestsRequest, ListPaymentRequestsResponse, ListPaymentsRequest,
+    ListPaymentsResponse, LocalizedText, LogEntry, LoggingConfig, MnemonicLanguage, MnemonicSource,
+    MnemonicValidationError, MnemonicWordCount, Note, NoteStatus, OnchainTx, OnchainTxKind,
+    OnchainUtxo, OnchainUtxoKind, PayAmount, Payment, PaymentDetails, PaymentFailureKind,
+    PaymentFailureReason, PaymentMethod, PaymentRequest, PaymentRequestStatus, PaymentRetention,
+    PaymentRoute, PaymentStatus, PaymentType, PendingSend, PrepareConsolidateRequest,
+    PrepareConsolidateResponse, PrepareLnUrlPayRequest, PrepareLnUrlPayResponse,
+    PrepareSendPaymentRequest, PrepareSendPaymentResponse, QuietHours, ReceiveArkRequest,
+    ReceiveArkResponse, ReceiveOnchainRequest, ReceiveOnchainResponse, ReceivePaymentRequest,
+    ReceivePaymentResponse, ReceiveWarning, ReclaimNoteRequest, ReclaimNoteResponse,
+    RedeemNoteRequest, RedeemNoteResponse, RegisterLightningAddressRequest,
+    RegisterLightningAddressResponse, RemoteService, RetentionAction, RetryPaymentRequest,
+    RetryPaymentResponse, RoundInclusionProof, RouteOption, RoutePaymentRequest,
+    RoutePaymentResponse, SdkState, SendDestination, SendOnchainRequest, SendOnchainResponse,
+    SendPaymentRequest, SendPaymentResponse, SendStep, SendSubmission, ServerBalance, ServiceFees,
+    SortDirection, SyncMode, SyncWalletRequest, SyncWalletResponse, TestConnectionRequest,
+    TestConnectionResponse, UpdateConfigRequest, UpdateConfigResponse, ValidateMnemonicRequest,
+    ValidateMnemonicResponse, WaitForPaymentRequest, WaitForPaymentResponse,
 };
-use tokio::sync::watch;
+use tokio::sync::{mpsc, watch};
 
 // Export the builder module
+#[cfg(feature = "test-utils")]
+pub use faults::FaultInjector;
 pub use sdk_builder::SdkBuilder;
 
+/// Ark client used for every Ark server, reading the chain through a cache
+type ArkClient = Client<ChainCache, Wallet<SharedPersistence>>;
+
+/// How long a created note can be redeemed for, unless specified otherwise
+const DEFAULT_NOTE_EXPIRY_SECS: u64 = 30 * 24 * 60 * 60;
+/// How often the chain tip is checked for new blocks
+const CHAIN_TIP_POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// How often the wallet is synced in the background, unless [Config::sync_interval_secs] is set
+const PERIODIC_SYNC_INTERVAL: Duration = Duration::from_secs(10);
+/// How often old payments are checked against [Config::payment_retention]
+const PAYMENT_RETENTION_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Length of a month in [PaymentRetention::after_months]
+const RETENTION_MONTH_SECS: u64 = 30 * 24 * 60 * 60;
+/// How many times a send is attempted before it's left for the user to retry
+const MAX_AUTOMATIC_SEND_ATTEMPTS: u32 = 3;
+/// Estimated time for an offchain payment to reach its receiver
+const OFFCHAIN_SETTLEMENT_SECS: u64 = 5;
+/// How long the Ark server may take to confirm a receive destination is payable
+const SERVER_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Average time between blocks, used to estimate how long onchain payments take to confirm
+const BLOCK_INTERVAL_SECS: u64 = 10 * 60;
+
+/// The SDK entry point
+///
+/// `BreezSdk` is a cheap handle: clones share the same connection, storage and runtime
+/// state, so it can be freely cloned and moved between tasks. Dropping the last clone stops
+/// the background tasks, as [BreezSdk::disconnect] does, without waiting for them.
 #[derive(Clone)]
 pub struct BreezSdk {
-    ark_client: Arc<Client<EsploraBlockchain, Wallet<InMemoryDb>>>,
-    config: Config,
-    storage: Arc<dyn Storage + Send + Sync>,
+    inner: Arc<SdkInner>,
+    /// Shared by the app's handles only, the background tasks hold handles without it so they
+    /// don't keep the SDK running
+    _shutdown_on_drop: Option<Arc<ShutdownOnDrop>>,
+}
+
+/// Signals the background tasks to stop when dropped
+struct ShutdownOnDrop {
+    inner: Weak<SdkInner>,
+}
+
+impl Drop for ShutdownOnDrop {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.upgrade() {
+            if inner.state.mark_stopped() {
+                info!("Last SDK handle dropped, stopping the background tasks");
+                inner.shutdown_sender.send_replace(());
+            }
+        }
+    }
+}
+
+struct SdkInner {
+    /// Client of the main Ark server, `None` for wallets built with [SdkBuilder::demo]
+    ark_client: Option<Arc<ArkClient>>,
+    /// Changed at runtime by [BreezSdk::update_config]
+    config: RwLock<Config>,
+    storage: Arc<dyn Storage>,
+    chain_service: Arc<ReplaceableChainService>,
+    /// Chain lookups of the main client, invalidated by `watch_chain_tip` when the tip changes
+    chain_cache: Arc<ChainCache>,
+    fee_provider: Arc<dyn FeeProvider>,
+    clock: Arc<dyn Clock>,
     event_emitter: Arc<EventEmitter>,
     shutdown_sender: watch::Sender<()>,
-    shutdown_receiver: watch::Receiver<()>,
+    tasks: TaskSupervisor,
+    generated_mnemonic: Mutex<Option<String>>,
+    state: RuntimeState,
+    settlement_verifier: SettlementVerifier,
+    ark_servers: Vec<ArkServer>,
+    initial_state: Mutex<Option<SdkEvent>>,
+    onchain_quotes: OnchainQuotes,
+    telemetry: Telemetry,
+    connections: ConnectionMonitor,
+    /// Key encrypting exported state, derived from the wallet seed
+    state_key: [u8; 32],
+    /// Key identifying the wallet to the LNURL server, see [lightning_address::derive_keypair]
+    lnurl_keypair: Keypair,
+    /// Held until the SDK is dropped, so no other process uses the data directory meanwhile
+    wallet_lock: WalletLock,
+    /// Set with [SdkBuilder::http_client], used for HTTP requests instead of a default client
+    http_client: Option<reqwest::Client>,
+    /// Fabricates what the Ark server would report, set by [SdkBuilder::demo]
+    #[cfg(feature = "demo")]
+    demo: Option<demo::DemoArk>,
+    #[cfg(feature = "test-utils")]
+    faults: Arc<faults::FaultInjector>,
+}
+
+impl SdkInner {
+    /// The client of the main Ark server, failing in demo wallets which have none
+    fn ark_client(&self) -> Result<&Arc<ArkClient>, SdkError> {
+        self.ark_client
+            .as_ref()
+            .ok_or_else(|| SdkError::GenericError("Not available in demo wallets".to_string()))
+    }
+}
+
+/// An additional Ark server and the client connected to it
+struct ArkServer {
+    config: ArkServerConfig,
+    client: Arc<ArkClient>,
 }
 
 pub async fn connect(request: ConnectRequest) -> Result<BreezSdk, SdkError> {
-    let sdk = SdkBuilder::new(request.config, request.mnemonic)
+    let (mnemonic, generated) = match request.mnemonic {
+        MnemonicSource::Existing { mnemonic } => (mnemonic, false),
+        MnemonicSource::GenerateNew {
+            word_count,
+            language,
+        } => {
+            let response = BreezSdk::generate_mnemonic(GenerateMnemonicRequest {
+                word_count,
+                language,
+            })?;
+            (response.mnemonic, true)
+        }
+    };
+
+    let sdk = SdkBuilder::new(request.config, mnemonic.clone())
         .build()
         .await?;
+    if generated {
+        *sdk.inner.generated_mnemonic.lock().unwrap() = Some(mnemonic);
+    }
     sdk.start()?;
     Ok(sdk)
 }
@@ -69,53 +254,278 @@ impl BreezSdk {
     ///
     /// * `config` - The Sdk configuration object
     /// * `storage` - Storage implementation for persistent data    
+    /// * `chain_service` - Chain service used to inspect onchain funds, shared with the Ark client
+    ///   and the fee provider
+    /// * `wallet_persistence` - Storage of the wallet's boarding outputs and their keys
+    /// * `fee_provider` - Fee estimation implementation
+    /// * `clock` - Source of the current time
+    /// * `wallet_lock` - Lock held on the data directory while the SDK exists
+    /// * `http_client` - HTTP client to make requests with, if not the default one
+    /// * `demo` - Whether to fabricate what the Ark servers would report instead of connecting
+    /// * `runtime` - Runtime the background tasks are spawned on
     /// * `shutdown_sender` - Sender for shutdown signal
     /// * `shutdown_receiver` - Receiver for shutdown signal
     ///
     /// # Returns
     ///
     /// Result containing either the initialized `BreezSdk` or an `SdkError`
-    pub async fn new(
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn new(
         config: Config,
         mnemonic: String,
-        storage: Arc<dyn Storage + Send + Sync>,
+        storage: Arc<dyn Storage>,
+        chain_service: Arc<ReplaceableChainService>,
+        wallet_persistence: Arc<dyn Persistence + Send + Sync>,
+        fee_provider: Arc<dyn FeeProvider>,
+        clock: Arc<dyn Clock>,
+        wallet_lock: WalletLock,
+        http_client: Option<reqwest::Client>,
+        demo: bool,
+        runtime: tokio::runtime::Handle,
         shutdown_sender: watch::Sender<()>,
         shutdown_receiver: watch::Receiver<()>,
     ) -> Result<Self, SdkError> {
+        // Route storage and broadcasts through the fault injector before anything uses them
+        #[cfg(feature = "test-utils")]
+        let faults = Arc::new(faults::FaultInjector::default());
+        #[cfg(feature = "test-utils")]
+        let storage: Arc<dyn Storage> =
+            Arc::new(faults::FaultyStorage::new(storage, faults.clone()));
+        #[cfg(feature = "test-utils")]
+        chain_service.replace(Arc::new(faults::FaultyChainService::new(
+            chain_service.current(),
+            faults.clone(),
+        )));
+
         // Initialize the Ark client with the server URL and mnemonic from the config
-        let mnemonic: bip39::Mnemonic = mnemonic
-            .parse()
-            .map_err(|e: bip39::Error| SdkError::ConnectError(e.to_string()))?;
+        let mnemonic = mnemonic::parse_mnemonic(&mnemonic).map_err(|e| {
+            let validation = Self::validate_mnemonic(ValidateMnemonicRequest {
+                mnemonic: mnemonic.clone(),
+            });
+            match validation.errors.first() {
+                Some(error) => SdkError::InvalidMnemonic(error.to_string()),
+                None => SdkError::InvalidMnemonic(e.to_string()),
+            }
+        })?;
         let seed = mnemonic.to_seed("").to_vec();
-        let ark_client = Arc::new(Self::init_client(config.clone(), seed).await?);
+        let state_key = backup::derive_key(&seed);
+        let lnurl_keypair =
+            lightning_address::derive_keypair(&seed, config.network.clone().into())?;
+        // Demo wallets don't connect to any Ark server
+        let mut ark_servers = Vec::with_capacity(config.additional_ark_servers.len());
+        for server in config.additional_ark_servers.iter().filter(|_| !demo) {
+            let server_config = Config {
+                ark_server_url: server.url.clone(),
+                expected_server_pubkey: server.expected_server_pubkey.clone(),
+                ..config.clone()
+            };
+            ark_servers.push(ArkServer {
+                config: server.clone(),
+                client: Arc::new(
+                    Self::init_client(
+                        server_config,
+                        seed.clone(),
+                        chain_service.clone(),
+                        Arc::new(InMemoryDb::default()),
+                    )
+                    .await?,
+                ),
+            });
+        }
+        // No send can be in flight before the SDK is created. A read-only SDK shares the storage
+        // of the process holding the lock, whose sends may well be in flight
+        if !wallet_lock.is_read_only() {
+            storage.clear_reservations().await?;
+        }
+        let chain_cache = Arc::new(
+            ChainCache::new(chain_service.clone())
+                .min_confirmations(config.min_boarding_confirmations()),
+        );
+        // Only the main client uses the injected wallet persistence, the boarding outputs of
+        // additional servers are kept in memory
+        let ark_client = match demo {
+            true => None,
+            false => Some(Arc::new(
+                Self::init_client_with_cache(
+                    config.clone(),
+                    seed,
+                    chain_cache.clone(),
+                    wallet_persistence,
+                )
+                .await?,
+            )),
+        };
+        let event_emitter = Arc::new(EventEmitter::with_quiet_hours(
+            config.quiet_hours.clone(),
+            clock.clone(),
+        ));
+        #[cfg(feature = "demo")]
+        let demo = match demo {
+            true => Some(
+                demo::DemoArk::new(storage.clone(), clock.clone(), event_emitter.clone()).await?,
+            ),
+            false => None,
+        };
 
-        Ok(Self {
+        let inner = Arc::new(SdkInner {
             ark_client,
-            config,
+            config: RwLock::new(config),
             storage,
-            event_emitter: Arc::new(EventEmitter::new()),
+            chain_service,
+            chain_cache,
+            fee_provider,
+            clock: clock.clone(),
+            event_emitter,
             shutdown_sender,
-            shutdown_receiver,
+            tasks: TaskSupervisor::new(shutdown_receiver, clock, runtime),
+            generated_mnemonic: Mutex::new(None),
+            state: RuntimeState::default(),
+            settlement_verifier: SettlementVerifier::default(),
+            ark_servers,
+            initial_state: Mutex::new(None),
+            onchain_quotes: OnchainQuotes::default(),
+            telemetry: Telemetry::default(),
+            connections: ConnectionMonitor::default(),
+            state_key,
+            lnurl_keypair,
+            wallet_lock,
+            http_client,
+            #[cfg(feature = "demo")]
+            demo,
+            #[cfg(feature = "test-utils")]
+            faults,
+        });
+        Ok(Self {
+            _shutdown_on_drop: Some(Arc::new(ShutdownOnDrop {
+                inner: Arc::downgrade(&inner),
+            })),
+            inner,
         })
     }
 
+    /// Injects failures into this SDK, to test how the app handles them
+    ///
+    /// # Returns
+    ///
+    /// The fault injector shared by the SDK's storage, chain service and rounds
+    #[cfg(feature = "test-utils")]
+    pub fn fault_injector(&self) -> Arc<FaultInjector> {
+        self.inner.faults.clone()
+    }
+
+    /// Handle for the background tasks, which doesn't keep them running once the app dropped
+    /// its own handles
+    fn task_handle(&self) -> BreezSdk {
+        BreezSdk {
+            inner: self.inner.clone(),
+            _shutdown_on_drop: None,
+        }
+    }
+
+    /// Checks the Ark server and Esplora of a configuration can be connected to, without
+    /// creating a wallet
+    ///
+    /// Both services are checked concurrently: their URL must be valid, they must respond and
+    /// be on the configured network, and the Ark server must sign with
+    /// [Config::expected_server_pubkey] if set. This lets onboarding flows validate custom
+    /// server settings before calling [connect].
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the configuration to check
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TestConnectionResponse)` - The outcome of checking each service
+    /// * `Err(SdkError)` - Never for now, failed checks are reported in the response
+    pub async fn test_connection(
+        request: TestConnectionRequest,
+    ) -> Result<TestConnectionResponse, SdkError> {
+        let config = &request.config;
+        let (ark_server, esplora) = futures::join!(
+            preflight::check_ark_server(config),
+            preflight::check_esplora(config)
+        );
+        Ok(TestConnectionResponse {
+            ark_server,
+            esplora,
+        })
+    }
+
+    /// Generates a new BIP39 mnemonic
+    ///
+    /// This allows apps to onboard users without depending on a BIP39 library themselves.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the word count and optional wordlist language (English by default)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(GenerateMnemonicResponse)` - Contains the generated mnemonic
+    /// * `Err(SdkError)` - If the mnemonic could not be generated
+    pub fn generate_mnemonic(
+        request: GenerateMnemonicRequest,
+    ) -> Result<GenerateMnemonicResponse, SdkError> {
+        mnemonic::generate_mnemonic(request)
+    }
+
+    /// Validates a mnemonic before using it to restore a wallet
+    ///
+    /// Unlike `connect`, which only fails with a single error, this reports every problem found
+    /// so restore screens can point the user at the exact word to fix.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the mnemonic phrase to validate
+    ///
+    /// # Returns
+    ///
+    /// A `ValidateMnemonicResponse` listing unknown words with suggested corrections,
+    /// an invalid word count or a checksum failure
+    pub fn validate_mnemonic(request: ValidateMnemonicRequest) -> ValidateMnemonicResponse {
+        mnemonic::validate_mnemonic(request)
+    }
+
+    /// Returns the mnemonic generated by `connect` when called with [MnemonicSource::GenerateNew]
+    ///
+    /// The mnemonic is only returned once, so the app should back it up immediately.
+    /// Subsequent calls, or calls on an SDK connected with an existing mnemonic, return `None`.
+    pub fn take_generated_mnemonic(&self) -> Option<String> {
+        self.inner.generated_mnemonic.lock().unwrap().take()
+    }
+
     async fn init_client(
         config: Config,
         seed: Vec<u8>,
-    ) -> Result<Client<EsploraBlockchain, Wallet<InMemoryDb>>, SdkError> {
+        chain_service: Arc<dyn ChainService>,
+        wallet_persistence: Arc<dyn Persistence + Send + Sync>,
+    ) -> Result<ArkClient, SdkError> {
+        let blockchain = Arc::new(
+            ChainCache::new(chain_service).min_confirmations(config.min_boarding_confirmations()),
+        );
+        Self::init_client_with_cache(config, seed, blockchain, wallet_persistence).await
+    }
+
+    /// Like [BreezSdk::init_client], with a chain cache the caller keeps to invalidate it
+    async fn init_client_with_cache(
+        config: Config,
+        seed: Vec<u8>,
+        blockchain: Arc<ChainCache>,
+        wallet_persistence: Arc<dyn Persistence + Send + Sync>,
+    ) -> Result<ArkClient, SdkError> {
         let secp = Secp256k1::new();
         let secret_key = SecretKey::from_slice(&seed[..32])?;
 
         let keypair = Keypair::from_secret_key(&secp, &secret_key);
 
-        // Initialize blockchain and wallet implementations
-        let blockchain = Arc::new(EsploraBlockchain::new(config.esplora_url.to_string())?);
+        // Initialize the wallet implementation
         let wallet = Wallet::new(
             keypair,
             secp,
             config.network.into(),
             &config.esplora_url,
-            InMemoryDb::default(),
+            SharedPersistence(wallet_persistence),
         )
         .map_err(|e| SdkError::WalletError(e.to_string()))?;
         let wallet = Arc::new(wallet);
@@ -124,13 +534,21 @@ impl BreezSdk {
         let offline_client = OfflineClient::new(
             "breez-sdk-ark-client".to_string(),
             keypair,
-            blockchain,
+            blockchain.clone(),
             wallet,
-            config.ark_server_url,
+            config.ark_server_url.clone(),
         );
 
         // Connect to the Ark server and get server info
         let client = offline_client.connect().await?;
+        blockchain.set_boarding_address(client.get_boarding_address()?);
+        if let Some(expected) = &config.expected_server_pubkey {
+            verification::verify_server_pubkey(
+                &config.ark_server_url,
+                expected,
+                client.server_info.pk,
+            )?;
+        }
 
         Ok(client)
     }
@@ -145,7 +563,13 @@ impl BreezSdk {
     ///
     /// A unique identifier for the listener, which can be used to remove it later
     pub fn add_event_listener(&self, listener: Box<dyn EventListener>) -> String {
-        self.event_emitter.add_listener(listener)
+        let id = self.inner.event_emitter.add_listener(listener);
+        // Listeners registered after the cached state was loaded still get notified about it
+        let initial_state = self.inner.initial_state.lock().unwrap().clone();
+        if let Some(event) = initial_state {
+            self.inner.event_emitter.emit_to(&id, &event);
+        }
+        id
     }
 
     /// Removes a previously registered event listener
@@ -158,410 +582,3470 @@ impl BreezSdk {
     ///
     /// `true` if the listener was found and removed, `false` otherwise
     pub fn remove_event_listener(&self, id: &str) -> bool {
-        self.event_emitter.remove_listener(id)
+        self.inner.event_emitter.remove_listener(id)
+    }
+
+    /// Registers a listener to receive anonymized operational metrics
+    ///
+    /// Reporting is opt-in: without a listener no metrics are recorded, and the SDK never sends
+    /// them anywhere itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - An implementation of the `TelemetryListener` trait, replacing any
+    ///   previously registered one, or `None` to stop reporting metrics
+    pub fn set_telemetry_listener(&self, listener: Option<Box<dyn TelemetryListener>>) {
+        self.inner.telemetry.set_listener(listener);
     }
 
     /// Starts the SDK's background tasks
     ///
     /// This method initiates the following background tasks:
-    /// 1. `periodic_sync`: the wallet with the Ark network    
+    /// 1. `periodic_sync`: loads the cached state from storage, emitting
+    ///    [SdkEvent::InitialStateLoaded], then periodically syncs the wallet with the Ark network
+    /// 2. `resume_sends`: once the wallet is synced, resumes sends that were interrupted
+    /// 3. `watch_chain_tip`: emits [SdkEvent::NewBlock] and syncs the wallet whenever a new
+    ///    block is found, so confirmations show up without waiting for the next periodic sync
+    /// 4. `payment_retention`: moves old payments out of the payment history once a day, if
+    ///    [Config::payment_retention] is set
+    ///
+    /// Failing tasks are retried with exponential backoff, their status is listed in
+    /// [GetInfoResponse::background_tasks]. Fails for wallets opened with
+    /// [SdkBuilder::read_only], as the tasks write to storage, and with
+    /// [SdkError::AlreadyStopped] once the SDK was disconnected.
     ///
     pub fn start(&self) -> Result<(), SdkError> {
-        // TODO: Implement start functionality
+        self.inner.state.ensure_not_stopped()?;
+        if self.inner.wallet_lock.is_read_only() {
+            return Err(SdkError::GenericError(
+                "Background tasks can't run on a wallet opened read-only".to_string(),
+            ));
+        }
+        // Demo wallets only change when called, so every run is the same
+        #[cfg(feature = "demo")]
+        if self.inner.demo.is_some() {
+            self.inner.state.set_connected(true);
+            return Ok(());
+        }
         self.periodic_sync();
+        self.resume_sends();
+        self.watch_chain_tip();
+        self.payment_retention();
+        self.inner.state.set_connected(true);
         Ok(())
     }
 
     fn periodic_sync(&self) {
-        let sdk = self.clone();
-        let mut shutdown_receiver = sdk.shutdown_receiver.clone();
-        let mut interval = tokio::time::interval(Duration::from_secs(10));
-        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
-        tokio::spawn(async move {
-            loop {
-                tokio::select! {
-                    _ = shutdown_receiver.changed() => {
-                        info!("Periodic sync loop shutdown signal received");
-                        return;
-                    }
-                    _ = interval.tick() => {
-                        // Perform the sync operation
-                        if let Err(e) = sdk.sync_wallet_internal().await {
-                            error!("Periodic wallet sync failed: {e:?}");
-                        }
-                    }
-                }
-            }
-        });
+        let sdk = self.task_handle();
+        self.inner
+            .tasks
+            .spawn_periodic("periodic_sync", self.sync_interval(), move || {
+                let sdk = sdk.clone();
+                async move { sdk.periodic_sync_run().await }
+            });
     }
 
-    /// Stops the SDK's background tasks
-    ///
-    /// This method stops the background tasks started by the `start()` method.
-    /// It should be called before your application terminates to ensure proper cleanup.
-    ///
-    /// # Returns
-    ///
-    /// Result containing either success or an `SdkError` if the background task couldn't be stopped
-    pub fn disconnect(&self) -> Result<(), SdkError> {
-        self.shutdown_sender
-            .send(())
-            .map_err(|_| SdkError::GenericError("Failed to send shutdown signal".to_string()))?;
-
+    async fn periodic_sync_run(&self) -> Result<(), SdkError> {
+        // Serve the cached state right away, the first sync then reconciles it
+        if self.inner.initial_state.lock().unwrap().is_none() {
+            if let Err(e) = self.load_initial_state().await {
+                error!("Failed to load cached state: {e:?}");
+            }
+        }
+        {
+            // Skip this run if a sync is already running
+            let Some(_sync_guard) = self.inner.state.try_begin_sync() else {
+                return Ok(());
+            };
+            self.sync_wallet_internal().await?;
+        }
+        // A failed consolidation is retried after the next sync, it doesn't slow syncing down
+        if let Err(e) = self.auto_consolidate().await {
+            error!("Automatic VTXO consolidation failed: {e:?}");
+        }
         Ok(())
     }
 
-    /// Returns the balance of the wallet in satoshis
-    pub async fn get_balance(
-        &self,
-        _request: GetBalanceRequest,
-    ) -> Result<GetBalanceResponse, SdkError> {
-        // Retrieve the persisted offchain balance from storage
-        let balance = self.storage.get_offchain_balance()?;
-
-        Ok(GetBalanceResponse { balance })
+    fn watch_chain_tip(&self) {
+        let sdk = self.task_handle();
+        let last_tip = Arc::new(Mutex::new(None));
+        self.inner
+            .tasks
+            .spawn_periodic("watch_chain_tip", CHAIN_TIP_POLL_INTERVAL, move || {
+                let sdk = sdk.clone();
+                let last_tip = last_tip.clone();
+                async move { sdk.watch_chain_tip_run(&last_tip).await }
+            });
     }
 
-    /// Synchronizes the wallet with the Ark network
-    /// As part of this sync we also attempt to join a round
-    pub async fn sync_wallet(
+    async fn watch_chain_tip_run(
         &self,
-        _request: SyncWalletRequest,
-    ) -> Result<SyncWalletResponse, SdkError> {
-        let mut rng = StdRng::from_entropy();
-        if let Err(e) = self.ark_client.board(&mut rng).await {
-            error!("Failed to board: {e:?}");
-            return Err(SdkError::GenericError(e.to_string()));
+        last_tip: &Mutex<Option<ChainTip>>,
+    ) -> Result<(), SdkError> {
+        let tip = self
+            .track_connection(RemoteService::Esplora, self.inner.chain_service.chain_tip())
+            .await?;
+        self.check_clock_skew(&tip);
+        // The first tip is only the starting point, it's not a new block. A tip with another
+        // hash at the same height is a new block too, replacing the previous one in a reorg
+        let previous = last_tip.lock().unwrap().replace(tip);
+        if previous.is_none_or(|previous| previous == tip) {
+            return Ok(());
         }
-        self.sync_wallet_internal().await?;
-        Ok(SyncWalletResponse {})
-    }
-
-    async fn sync_wallet_internal(&self) -> Result<(), SdkError> {
-        let start_time = Instant::now();
-
-        // 1. Sync balance
-        let ark_balance = self.ark_client.offchain_balance().await?;
-        info!("Synced balance: {}", ark_balance.total().to_sat());
+        let height = tip.height;
+        // The sync below re-checks the transactions and confirmations of the payments, so it
+        // mustn't be served what was cached for the previous tip
+        self.inner.chain_cache.observe_tip(tip);
 
-        // Convert to our OffchainBalance model
-        let offchain_balance = models::OffchainBalance {
-            pending_sats: ark_balance.pending().to_sat(),
-            confirmed_sats: ark_balance.confirmed().to_sat(),
+        info!("New block found at height {height}");
+        self.inner
+            .event_emitter
+            .emit(&SdkEvent::NewBlock { height });
+        // A running sync already sees the new block
+        let Some(_sync_guard) = self.inner.state.try_begin_sync() else {
+            return Ok(());
         };
-
-        // Persist the balance to storage
-        self.storage.save_offchain_balance(&offchain_balance)?;
-
-        // 2. Sync transactions
-        self.sync_payments_to_storage().await?;
-
-        let elapsed = start_time.elapsed();
-        info!("Wallet sync completed in {:?}", elapsed);
-        self.event_emitter.emit(&SdkEvent::Synced {});
-
-        Ok(())
+        self.sync_wallet_internal().await
     }
 
-    /// Generates a new deposit address for receiving funds into the Ark wallet
-    pub async fn receive_onchain(
-        &self,
-        _request: ReceiveOnchainRequest,
-    ) -> Result<ReceiveOnchainResponse, SdkError> {
-        let boarding_address = self.ark_client.get_boarding_address()?;
-        Ok(ReceiveOnchainResponse {
-            deposit_address: boarding_address.to_string(),
-        })
+    /// Compares the local clock to the time of the chain tip, emitting
+    /// [SdkEvent::ClockSkewDetected] when it's found to be off
+    fn check_clock_skew(&self, tip: &ChainTip) {
+        let Some(time) = tip.time else {
+            return;
+        };
+        let skew_secs = clock::detect_skew(self.now(), time, self.config().network.into());
+        if self.inner.state.set_clock_skew(skew_secs) {
+            let skew_secs = skew_secs.unwrap_or_default();
+            warn!(
+                "Local clock is off by {skew_secs}s from the chain tip at {}",
+                tip.height
+            );
+            self.inner
+                .event_emitter
+                .emit(&SdkEvent::ClockSkewDetected { skew_secs });
+        }
     }
 
-    /// Prepares a transaction to send funds on-chain without broadcasting it
-    pub async fn prepare_send_onchain(
-        &self,
-        request: PrepareSendOnchainRequest,
-    ) -> Result<PrepareSendOnchainResponse, SdkError> {
-        info!(
-            "Preparing on-chain transaction for amount: {}",
-            request.receiver_amount_sats
+    fn payment_retention(&self) {
+        let sdk = self.task_handle();
+        self.inner.tasks.spawn_periodic(
+            "payment_retention",
+            PAYMENT_RETENTION_INTERVAL,
+            move || {
+                let sdk = sdk.clone();
+                async move { sdk.payment_retention_run().await }
+            },
         );
+    }
 
-        // TODO: We should calculat the correct fees or get from the user the fee preference.
-        Ok(PrepareSendOnchainResponse {
-            receiver_amount_sats: request.receiver_amount_sats,
-            fee_sats: 0,
-        })
+    async fn payment_retention_run(&self) -> Result<(), SdkError> {
+        let Some(retention) = self.config().payment_retention else {
+            return Ok(());
+        };
+        let now = self.now();
+        let before = now.saturating_sub(retention.after_months as u64 * RETENTION_MONTH_SECS);
+
+        // A sync saving payments at the same time would bring the moved ones back
+        let _sync_guard = self.inner.state.begin_sync().await;
+        let moved = self
+            .inner
+            .storage
+            .archive_payments(before, retention.action)
+            .await?;
+        if moved > 0 {
+            info!("Moved {moved} payments out of the payment history: {retention:?}");
+        }
+        Ok(())
     }
 
-    /// Initiates a withdrawal to move funds from Ark to on-chain Bitcoin
-    pub async fn send_onchain(
-        &self,
-        request: SendOnchainRequest,
-    ) -> Result<SendOnchainResponse, SdkError> {
-        info!(
-            "Initiating on-chain withdrawal to address: {}",
-            request.onchain_address
+    fn resume_sends(&self) {
+        let sdk = self.task_handle();
+        let ready_sdk = self.task_handle();
+        self.inner.tasks.spawn_after(
+            "resume_sends",
+            async move { ready_sdk.inner.state.wait_ready().await },
+            move || async move { sdk.resume_pending_sends().await },
         );
+    }
 
-        // let mut rng = StdRng::from_entropy();
-        // let txid = self
-        //     .ark_client
-        //     .off_board(
-        //         &mut rng,
-        //         Address::from_str(&request.onchain_address)?
-        //             .require_network(self.config.clone().network.into())?,
-        //         Amount::from_sat(request.prepare_send_onchain_response.receiver_amount_sats),
-        //     )
-        //     .await?;
-
-        let txid = self
-            .ark_client
-            .send_on_chain(
-                Address::from_str(&request.onchain_address)?
-                    .require_network(self.config.clone().network.into())?,
-                Amount::from_sat(request.prepare_send_onchain_response.receiver_amount_sats),
-            )
-            .await?;
+    /// Loads the persisted balance and notifies listeners that cached data can be queried
+    async fn load_initial_state(&self) -> Result<(), SdkError> {
+        let balance = self.inner.storage.get_offchain_balance().await?;
+        info!("Loaded cached balance: {}", balance.total_sats());
 
-        Ok(SendOnchainResponse {
-            tx_id: txid.to_string(),
-        })
+        let event = SdkEvent::InitialStateLoaded { balance };
+        *self.inner.initial_state.lock().unwrap() = Some(event.clone());
+        self.inner.event_emitter.emit(&event);
+        Ok(())
     }
 
-    /// Generates a payment destination based on the requested payment method
-    ///
-    /// This method handles different payment methods (Ark address, Bitcoin address, BOLT11, BOLT12)
-    /// and returns the appropriate destination for receiving funds.
+    /// Stops the SDK's background tasks
     ///
-    /// # Arguments
+    /// This method stops the background tasks started by the `start()` method.
+    /// It should be called before your application terminates to ensure proper cleanup.
+    /// Tasks finish their current run, e.g. a sync in progress, in the background, use
+    /// [BreezSdk::disconnect_async] to wait for them.
     ///
-    /// * `request` - Contains the payment method and amount information
+    /// Disconnecting is idempotent. Once disconnected, [BreezSdk::start] fails with
+    /// [SdkError::AlreadyStopped].
     ///
     /// # Returns
     ///
-    /// * `Ok(ReceivePaymentResponse)` - Contains the destination and fee information
-    /// * `Err(SdkError)` - If there was an error generating the payment destination
-    pub async fn receive_payment(
-        &self,
-        request: ReceivePaymentRequest,
-    ) -> Result<ReceivePaymentResponse, SdkError> {
-        info!(
-            "Generating payment destination for method: {:?}",
-            request.payment_method
-        );
-
-        match request.payment_method {
-            PaymentMethod::ArkAddress {
-                receiver_amount_sat,
-            } => {
-                // For Ark payments, we just need to return the Ark address
-                let (ark_address, _) = self.ark_client.get_offchain_address()?;
-
-                let fee_sat = 0;
-
-                // TODO: in case of amount is given we should return bip21 url
-                Ok(ReceivePaymentResponse {
-                    destination: ark_address.encode(),
-                    fee_sat,
-                })
-            }
-            PaymentMethod::BitcoinAddress {
-                receiver_amount_sat,
-            } => {
-                // For Bitcoin address payments, we generate an on-chain address
-                let address = self.ark_client.get_boarding_address()?.to_string();
-
-                let fee_sat = 0;
-
-                // TODO: in case of amount is given we should return bip21 url
-                Ok(ReceivePaymentResponse {
-                    destination: address,
-                    fee_sat,
-                })
-            }
-            PaymentMethod::Bolt11Invoice { .. } => Err(SdkError::GenericError(
-                "BOLT11 invoice generation is not yet implemented".to_string(),
-            )),
-            PaymentMethod::Bolt12Offer => Err(SdkError::GenericError(
-                "BOLT12 offer generation is not yet implemented".to_string(),
-            )),
+    /// Result containing success once the background tasks were signaled to stop
+    pub fn disconnect(&self) -> Result<(), SdkError> {
+        if self.inner.state.mark_stopped() {
+            self.inner.shutdown_sender.send_replace(());
         }
+        Ok(())
     }
 
-    /// Synchronizes payments to persistent storage
-    async fn sync_payments_to_storage(&self) -> Result<(), SdkError> {
-        let ark_transactions = self.ark_client.transaction_history().await?;
-        info!("Syncing ark_transactions: {:#?}", ark_transactions);
-
-        // Convert all transactions to payments
-        let mut payments = Vec::with_capacity(ark_transactions.len());
-        for ark_transaction in ark_transactions {
-            let payment = Payment::from(ark_transaction);
-            info!("Converted payment: {:?}", payment);
-            payments.push(payment);
-        }
-
-        // Save all payments at once and delete any that don't exist in the list
-        self.storage.save_payments(&payments)?;
+    /// Stops the SDK's background tasks like [BreezSdk::disconnect], waiting until they stopped
+    ///
+    /// Calling it again waits for any task still stopping and succeeds.
+    ///
+    /// # Returns
+    ///
+    /// Result containing success once all background tasks have stopped
+    pub async fn disconnect_async(&self) -> Result<(), SdkError> {
+        self.on_runtime(async move {
+            self.disconnect()?;
+            self.inner.tasks.join().await;
 
-        Ok(())
+            Ok(())
+        })
+        .await
     }
 
-    /// Lists payments from the storage with pagination
+    /// Waits until the wallet has been synced for the first time
     ///
-    /// This method provides direct access to the payment history stored in the database.
-    /// It returns payments in reverse chronological order (newest first).
+    /// Applications can use this to gate UI on up-to-date data instead of polling.
     ///
     /// # Arguments
     ///
-    /// * `request` - Contains pagination parameters (offset and limit)
+    /// * `timeout` - How long to wait for the first sync
     ///
     /// # Returns
     ///
-    /// * `Ok(ListPaymentsResponse)` - Contains the list of payments if successful
-    /// * `Err(SdkError)` - If there was an error accessing the storage
-    ///
-    pub async fn list_payments(
-        &self,
-        request: ListPaymentsRequest,
-    ) -> Result<ListPaymentsResponse, SdkError> {
-        info!("Listing payments with filter: {:?}", request);
-
-        // Retrieve payments from storage with pagination parameters
-        let payments = self.storage.list_payments(request.offset, request.limit)?;
-
-        // Return the payments in the response
-        Ok(ListPaymentsResponse { payments })
+    /// * `Ok(())` - Once the first sync has completed, immediately if it already has
+    /// * `Err(SdkError::Timeout)` - If the first sync didn't complete within `timeout`
+    pub async fn wait_ready(&self, timeout: Duration) -> Result<(), SdkError> {
+        self.on_runtime(async move {
+            tokio::time::timeout(timeout, self.inner.state.wait_ready())
+                .await
+                .map_err(|_| SdkError::Timeout("Waiting for the first sync".to_string()))
+        })
+        .await
     }
 
-    /// Prepares a payment to a destination
-    ///
-    /// This method analyzes the destination string and prepares the appropriate payment type.
-    /// It supports Ark addresses and potentially other payment types in the future.
+    /// Returns information about the SDK and what it is currently doing
     ///
     /// # Arguments
     ///
-    /// * `request` - Contains the destination and optional amount information
+    /// * `request` - The get info request
     ///
     /// # Returns
     ///
-    /// * `Ok(PrepareSendResponse)` - Contains the parsed destination and estimated fees
-    /// * `Err(SdkError)` - If there was an error preparing the payment
-    pub async fn prepare_send_payment(
-        &self,
-        request: PrepareSendPaymentRequest,
-    ) -> Result<PrepareSendPaymentResponse, SdkError> {
-        info!("Preparing payment to destination: {}", request.destination);
-
-        // Try to parse as an Ark address. TODO: We should use input parser to parse this.
-        if let Ok(ark_address) = ArkAddress::decode(&request.destination) {
-            // Get the amount to send
-            let receiver_amount_sat = match request.amount {
-                Some(PayAmount::Specific {
-                    receiver_amount_sat,
-                }) => receiver_amount_sat,
-                Some(PayAmount::Drain) => {
-                    // Get the current balance and use all available funds
-                    let balance_response = self.get_balance(GetBalanceRequest {}).await?;
-                    let total_balance = balance_response.balance.total_sats();
-                    // We don't handle fees here, they'll be calculated during actual sending
-                    total_balance
-                }
-                None => {
-                    return Err(SdkError::GenericError(
-                        "Amount is required for Ark address payments".to_string(),
-                    ))
+    /// * `Ok(GetInfoResponse)` - Contains the network, Ark server, runtime state and fee schedule.
+    ///   The fee schedule is left out while the Ark server can't be reached, the connection
+    ///   statuses tell why
+    /// * `Err(SdkError)` - If the information couldn't be retrieved
+    pub async fn get_info(&self, _request: GetInfoRequest) -> Result<GetInfoResponse, SdkError> {
+        self.on_runtime(async move {
+            let config = self.config();
+            let service_fees = match self.inner.fee_provider.service_fees().await {
+                Ok(service_fees) => Some(service_fees),
+                Err(e) => {
+                    warn!("Failed to get the service fees: {e}");
+                    None
                 }
             };
-
-            // For Ark payments, we don't have separate fees
-            // The fees will be calculated during the actual send operation
-            let fees_sat = None;
-
-            Ok(PrepareSendPaymentResponse {
-                destination: SendDestination::ArkAddress {
-                    address: ark_address.to_string(),
-                    receiver_amount_sat,
-                },
-                fees_sat,
+            Ok(GetInfoResponse {
+                network: config.network,
+                ark_server_url: config.ark_server_url,
+                state: self.inner.state.snapshot(),
+                service_fees,
+                background_tasks: self.inner.tasks.statuses(),
+                ark_server_connection: self.inner.connections.status(RemoteService::ArkServer),
+                esplora_connection: self.inner.connections.status(RemoteService::Esplora),
+                clock_skew_secs: self.inner.state.clock_skew(),
             })
-        } else {
-            // Could add support for other destination types here (BOLT11, BOLT12, etc.)
-            Err(SdkError::GenericError(format!(
-                "Unsupported destination format: {}",
-                request.destination
-            )))
-        }
+        })
+        .await
     }
 
-    /// Sends a payment based on a previously prepared payment request
+    /// Changes settings of the running SDK, without connecting again
+    ///
+    /// A new Esplora instance has to be reachable before it replaces the current one, both for
+    /// the SDK and its Ark clients. The boarding wallet keeps using the instance it was connected
+    /// with until the next `connect`. A new sync interval applies from the next periodic sync.
+    /// Emits [SdkEvent::ConfigUpdated] listing the changed fields.
     ///
     /// # Arguments
     ///
-    /// * `request` - Contains the prepared payment information from prepare_send_payment
+    /// * `request` - The settings to change, fields left `None` are kept
     ///
     /// # Returns
     ///
-    /// * `Ok(SendPaymentResponse)` - Contains the payment details if successful
-    /// * `Err(SdkError)` - If there was an error sending the payment
-    pub async fn send_payment(
+    /// * `Ok(UpdateConfigResponse)` - Contains the configuration now in use
+    /// * `Err(SdkError)` - If the sync interval is zero or the new Esplora instance can't be
+    ///   reached, in which case nothing is changed
+    pub async fn update_config(
         &self,
-        request: SendPaymentRequest,
-    ) -> Result<SendPaymentResponse, SdkError> {
-        info!(
-            "Sending payment with prepared response: {:?}",
-            request.prepare_response
-        );
+        request: UpdateConfigRequest,
+    ) -> Result<UpdateConfigResponse, SdkError> {
+        self.on_runtime(async move {
+            if request.sync_interval_secs == Some(0) {
+                return Err(SdkError::GenericError(
+                    "The sync interval must be at least one second".to_string(),
+                ));
+            }
+
+            let sets_headers = request
+                .esplora_headers
+                .as_ref()
+                .is_some_and(|headers| !headers.is_empty());
+            if sets_headers && self.inner.http_client.is_some() {
+                return Err(SdkError::InvalidInput(
+                    "Esplora headers can't be set with a custom HTTP client".to_string(),
+                ));
+            }
+
+            let chain_service =
+                if request.esplora_url.is_some() || request.esplora_headers.is_some() {
+                    let current = self.config();
+                    let esplora_url = request.esplora_url.clone().unwrap_or(current.esplora_url);
+                    let chain_service = match &self.inner.http_client {
+                        Some(http_client) => {
+                            EsploraBlockchain::with_http_client(esplora_url, http_client.clone())
+                        }
+                        None => EsploraBlockchain::with_headers(
+                            esplora_url,
+                            request
+                                .esplora_headers
+                                .as_ref()
+                                .unwrap_or(&current.esplora_headers),
+                        )?,
+                    };
+                    chain_service.tip_height().await?;
+                    Some(chain_service)
+                } else {
+                    None
+                };
+
+            // Fields are applied to the latest config, so concurrent updates of other fields are kept
+            let mut fields = Vec::new();
+            let config = {
+                let mut config = self.inner.config.write().unwrap();
+                if let Some(esplora_url) = request.esplora_url {
+                    config.esplora_url = esplora_url;
+                    fields.push("esplora_url");
+                }
+                if let Some(esplora_headers) = request.esplora_headers {
+                    config.esplora_headers = esplora_headers;
+                    fields.push("esplora_headers");
+                }
+                if let Some(sync_interval_secs) = request.sync_interval_secs {
+                    config.sync_interval_secs = Some(sync_interval_secs);
+                    fields.push("sync_interval_secs");
+                }
+                if let Some(quiet_hours) = request.quiet_hours {
+                    config.quiet_hours = quiet_hours;
+                    fields.push("quiet_hours");
+                }
+                if let Some(consolidation_policy) = request.consolidation_policy {
+                    config.consolidation_policy = consolidation_policy;
+                    fields.push("consolidation_policy");
+                }
+                if let Some(explorer_url) = request.explorer_url {
+                    config.explorer_url = explorer_url;
+                    fields.push("explorer_url");
+                }
+                if let Some(payment_retention) = request.payment_retention {
+                    config.payment_retention = payment_retention;
+                    fields.push("payment_retention");
+                }
+                config.clone()
+            };
+            if fields.is_empty() {
+                return Ok(UpdateConfigResponse { config });
+            }
+
+            if let Some(chain_service) = chain_service {
+                let chain_service: Arc<dyn ChainService> = Arc::new(chain_service);
+                #[cfg(feature = "test-utils")]
+                let chain_service = Arc::new(faults::FaultyChainService::new(
+                    chain_service,
+                    self.inner.faults.clone(),
+                ));
+                self.inner.chain_service.replace(chain_service);
+            }
+            self.inner
+                .tasks
+                .set_interval("periodic_sync", self.sync_interval());
+            self.inner
+                .event_emitter
+                .set_quiet_hours(config.quiet_hours.clone());
+            self.inner.event_emitter.flush_deferred();
+
+            info!("Updated config fields: {fields:?}");
+            self.inner.event_emitter.emit(&SdkEvent::ConfigUpdated {
+                fields: fields.into_iter().map(str::to_string).collect(),
+            });
+            Ok(UpdateConfigResponse { config })
+        })
+        .await
+    }
+
+    /// Returns the current limits of each payment method
+    ///
+    /// Offchain sends are bounded by the spendable balance and the largest VTXO the Ark server
+    /// accepts, deposits by the UTXO amounts the server accepts for boarding. Amounts below dust
+    /// can't be sent or boarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The get limits request
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(GetLimitsResponse)` - Contains the limits of each payment method
+    /// * `Err(SdkError)` - If the balance couldn't be read from storage
+    pub async fn get_limits(
+        &self,
+        _request: GetLimitsRequest,
+    ) -> Result<GetLimitsResponse, SdkError> {
+        self.on_runtime(async move {
+            let (vtxo, onchain_receive) = self.server_limits()?;
+            let spendable_sat = self.get_balance(GetBalanceRequest {}).await?.spendable_sats;
+
+            Ok(GetLimitsResponse {
+                offchain_send: Limits {
+                    min_sat: vtxo.min_sat,
+                    max_sat: Some(
+                        vtxo.max_sat
+                            .map_or(spendable_sat, |max| max.min(spendable_sat)),
+                    ),
+                },
+                onchain_receive,
+                lightning_send: None,
+                max_vtxo_sat: vtxo.max_sat,
+            })
+        })
+        .await
+    }
+
+    /// Limits set by the main Ark server, read from the info it reported when the SDK connected
+    /// so they're known while it's unreachable
+    ///
+    /// Returns the limits of a single VTXO and of onchain deposits.
+    fn server_limits(&self) -> Result<(Limits, Limits), SdkError> {
+        let server_info = &self.inner.ark_client()?.server_info;
+        let dust_sat = server_info.dust.to_sat();
+        let vtxo = Limits {
+            min_sat: server_info
+                .vtxo_min_amount
+                .map_or(dust_sat, |amount| amount.to_sat().max(dust_sat)),
+            max_sat: server_info.vtxo_max_amount.map(|amount| amount.to_sat()),
+        };
+        let onchain = Limits {
+            min_sat: server_info
+                .utxo_min_amount
+                .map_or(dust_sat, |amount| amount.to_sat().max(dust_sat)),
+            max_sat: server_info.utxo_max_amount.map(|amount| amount.to_sat()),
+        };
+        Ok((vtxo, onchain))
+    }
+
+    /// Returns the current tip of the chain, as seen by the configured Esplora instance
+    ///
+    /// Apps can show it next to [BreezSdk::get_info] to tell how up to date the wallet is.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The get chain tip request
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(GetChainTipResponse)` - Contains the height and hash of the tip
+    /// * `Err(SdkError)` - If Esplora couldn't be reached
+    pub async fn get_chain_tip(
+        &self,
+        _request: GetChainTipRequest,
+    ) -> Result<GetChainTipResponse, SdkError> {
+        self.on_runtime(async move {
+            let tip = self
+                .track_connection(RemoteService::Esplora, self.inner.chain_service.chain_tip())
+                .await?;
+            Ok(GetChainTipResponse {
+                height: tip.height,
+                block_hash: tip.hash.map(|hash| hash.to_string()),
+            })
+        })
+        .await
+    }
+
+    /// Returns the balance of the wallet in satoshis
+    ///
+    /// Besides the total, this breaks down which funds can be sent right now: funds pending
+    /// settlement in a round and funds reserved by sends that didn't complete are excluded.
+    /// Deposits not boarded yet are listed apart, split by whether they have the
+    /// [Config::min_boarding_confirmations] to be boarded in the next round.
+    pub async fn get_balance(
+        &self,
+        _request: GetBalanceRequest,
+    ) -> Result<GetBalanceResponse, SdkError> {
+        self.on_runtime(async move {
+            // Retrieve the persisted offchain balance from storage
+            let balance = self.inner.storage.get_offchain_balance().await?;
+            let server_balances = self.inner.storage.get_server_balances().await?;
+            let reserved_sats = self.inner.storage.get_reserved_sats().await?;
+            let boarding = self.inner.storage.get_boarding_balance().await?;
+
+            Ok(GetBalanceResponse {
+                spendable_sats: balance.confirmed_sats.saturating_sub(reserved_sats),
+                reserved_sats,
+                pending_settlement_sats: balance.pending_sats,
+                boarding,
+                balance,
+                server_balances,
+            })
+        })
+        .await
+    }
+
+    /// Synchronizes the wallet with the Ark network
+    /// As part of a full sync we also attempt to join a round, while a balance-only
+    /// sync just refreshes the offchain balance
+    pub async fn sync_wallet(
+        &self,
+        request: SyncWalletRequest,
+    ) -> Result<SyncWalletResponse, SdkError> {
+        self.on_runtime(async move {
+            #[cfg(feature = "demo")]
+            if let Some(demo) = &self.inner.demo {
+                let _sync_guard = self.inner.state.begin_sync().await;
+                let start_time = Instant::now();
+                demo.settle_pending().await?;
+                self.finish_sync(start_time)?;
+                return Ok(SyncWalletResponse {});
+            }
+
+            if request.mode == SyncMode::BalanceOnly {
+                let _sync_guard = self.inner.state.begin_sync().await;
+                let start_time = Instant::now();
+                let result = self.sync_balance_to_storage().await.map(|_| ());
+                self.inner.telemetry.record_sync(
+                    SyncMode::BalanceOnly,
+                    start_time.elapsed(),
+                    &result,
+                );
+                result?;
+                info!("Balance sync completed in {:?}", start_time.elapsed());
+                return Ok(SyncWalletResponse {});
+            }
+
+            self.join_round().await?;
+            let _sync_guard = self.inner.state.begin_sync().await;
+            self.sync_wallet_internal().await?;
+            Ok(SyncWalletResponse {})
+        })
+        .await
+    }
+
+    /// Joins the next round, boarding onchain deposits and settling the VTXOs into a new one
+    async fn join_round(&self) -> Result<(), SdkError> {
+        let _round_guard = self.inner.state.begin_round().await;
+        self.start_round()?;
+        let mut rng = StdRng::from_entropy();
+        let result = self.inner.ark_client()?.board(&mut rng).await;
+        self.emit_round_finished(&result);
+        if let Err(e) = result {
+            error!("Failed to board: {e:?}");
+            return Err(SdkError::GenericError(e.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Estimates the cost and benefit of merging small VTXOs, without joining a round
+    ///
+    /// Every VTXO has to be exited on its own when leaving Ark without the server, so many
+    /// small VTXOs make exits expensive. Merging them costs a round fee per VTXO instead. The
+    /// round spends every spendable VTXO, so the fees and savings cover all of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the amount below which VTXOs count as small
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PrepareConsolidateResponse)` - The VTXOs to merge, the fees and the exit savings.
+    ///   No VTXOs are listed if fewer than two are below the threshold
+    /// * `Err(SdkError)` - If the VTXOs or fees couldn't be fetched
+    pub async fn prepare_consolidate(
+        &self,
+        request: PrepareConsolidateRequest,
+    ) -> Result<PrepareConsolidateResponse, SdkError> {
+        self.on_runtime(async move {
+            let dust_threshold_sat = request
+                .dust_threshold_sat
+                .unwrap_or(consolidation::DEFAULT_DUST_THRESHOLD_SAT);
+            Ok(match self.consolidation_plan(dust_threshold_sat).await? {
+                Some(plan) => PrepareConsolidateResponse {
+                    vtxo_count: plan.vtxo_count,
+                    dust_vtxo_count: plan.dust_vtxo_count,
+                    amount_sat: plan.amount_sat,
+                    fee_sat: plan.fee_sat,
+                    exit_savings_sat: plan.exit_savings_sat,
+                    is_worth_it: plan.is_worth_it(),
+                },
+                None => PrepareConsolidateResponse {
+                    vtxo_count: 0,
+                    dust_vtxo_count: 0,
+                    amount_sat: 0,
+                    fee_sat: 0,
+                    exit_savings_sat: 0,
+                    is_worth_it: false,
+                },
+            })
+        })
+        .await
+    }
+
+    /// Merges the wallet's VTXOs into a single one in the next round
+    ///
+    /// Use [BreezSdk::prepare_consolidate] first to check it's worth the fees. The round
+    /// settles all VTXOs held with the main Ark server, as well as pending deposits.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The consolidate request
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ConsolidateResponse)` - Once the round completed and the wallet was synced
+    /// * `Err(SdkError)` - If the round failed
+    pub async fn consolidate(
+        &self,
+        _request: ConsolidateRequest,
+    ) -> Result<ConsolidateResponse, SdkError> {
+        self.on_runtime(async move {
+            self.join_round().await?;
+            let _sync_guard = self.inner.state.begin_sync().await;
+            self.sync_wallet_internal().await?;
+            Ok(ConsolidateResponse {})
+        })
+        .await
+    }
+
+    /// Consolidates small VTXOs if the configured policy calls for it
+    async fn auto_consolidate(&self) -> Result<(), SdkError> {
+        let Some(policy) = self.config().consolidation_policy else {
+            return Ok(());
+        };
+        let Some(plan) = self.consolidation_plan(policy.dust_threshold_sat).await? else {
+            return Ok(());
+        };
+        if plan.vtxo_count < policy.min_vtxos || !plan.is_worth_it() {
+            return Ok(());
+        }
+
+        info!(
+            "Consolidating {} small VTXOs holding {} sats",
+            plan.vtxo_count, plan.amount_sat
+        );
+        self.consolidate(ConsolidateRequest {}).await?;
+        Ok(())
+    }
+
+    /// Plans merging the spendable VTXOs of the main Ark server, if enough are below the
+    /// threshold
+    async fn consolidation_plan(
+        &self,
+        dust_threshold_sat: u64,
+    ) -> Result<Option<consolidation::ConsolidationPlan>, SdkError> {
+        let vtxo_amounts: Vec<u64> = self
+            .inner
+            .ark_client()?
+            .spendable_vtxos()
+            .await?
+            .into_iter()
+            .flat_map(|(outpoints, _)| outpoints)
+            .map(|outpoint| outpoint.amount.to_sat())
+            .collect();
+        let round_fee_sat = self.inner.fee_provider.service_fees().await?.round_fee_sat;
+        let exit_fee_sat = fees::chain_fee_sat(
+            self.inner.fee_provider.as_ref(),
+            fees::UNILATERAL_EXIT_VBYTES,
+        )
+        .await?;
+        Ok(consolidation::plan(
+            &vtxo_amounts,
+            dust_threshold_sat,
+            round_fee_sat,
+            exit_fee_sat,
+        ))
+    }
+
+    /// Syncs balance and payments into storage. Callers must hold the sync guard.
+    async fn sync_wallet_internal(&self) -> Result<(), SdkError> {
+        let start_time = Instant::now();
+        let (result, _) = futures::join!(self.sync_balance_and_payments(), self.check_server_key());
+        self.inner
+            .telemetry
+            .record_sync(SyncMode::Full, start_time.elapsed(), &result);
+        result
+    }
+
+    async fn sync_balance_and_payments(&self) -> Result<(), SdkError> {
+        let start_time = Instant::now();
+
+        // 1. Sync balance
+        let mut balances_fingerprint = self.sync_balance_to_storage().await?;
+
+        // 2. Sync transactions, unless no balance moved since they were last fetched
+        if self.history_covers_balances(&balances_fingerprint).await? {
+            debug!("Balances unchanged, skipping the transaction history");
+            return self.finish_sync(start_time);
+        }
+        let mut history = self
+            .track_connection(
+                RemoteService::ArkServer,
+                self.inner.ark_client()?.transaction_history(),
+            )
+            .await?;
+        // An unreachable additional server doesn't fail the sync. The balances aren't recorded
+        // as covered then, so its history is fetched again on the next sync
+        for server in &self.inner.ark_servers {
+            match server.client.transaction_history().await {
+                Ok(server_history) => history.extend(server_history),
+                Err(e) => {
+                    warn!(
+                        "Failed to fetch the transaction history of {}: {e}",
+                        server.config.url
+                    );
+                    balances_fingerprint = String::new();
+                }
+            }
+        }
+        self.verify_server_state(&history).await;
+        self.sync_payments_to_storage(history, balances_fingerprint)
+            .await?;
+        self.finish_sync(start_time)
+    }
+
+    fn finish_sync(&self, start_time: Instant) -> Result<(), SdkError> {
+        let elapsed = start_time.elapsed();
+        info!("Wallet sync completed in {:?}", elapsed);
+        if self.inner.state.mark_ready() {
+            self.inner.event_emitter.emit(&SdkEvent::SyncedFirstTime {});
+        }
+        self.inner.event_emitter.emit(&SdkEvent::Synced {});
+
+        Ok(())
+    }
+
+    /// Whether the payments saved at the last [HistoryCheckpoint] are up to date for the
+    /// balances, so the transaction history doesn't need fetching
+    async fn history_covers_balances(&self, balances_fingerprint: &str) -> Result<bool, SdkError> {
+        let now = self.now();
+        let request_expired = self
+            .inner
+            .storage
+            .list_payment_requests()
+            .await?
+            .iter()
+            .any(|payment_request| {
+                payment_request.status == PaymentRequestStatus::Pending
+                    && payment_request.expires_at <= now
+            });
+        let checkpoint = self.inner.storage.get_history_checkpoint().await?;
+        Ok(!request_expired
+            && checkpoint
+                .is_some_and(|checkpoint| checkpoint.covers_balances(balances_fingerprint, now)))
+    }
+
+    /// Times a call to a remote service, emitting [SdkEvent::ConnectionStateChanged] when the
+    /// health of the connection to it changes
+    async fn track_connection<T, E>(
+        &self,
+        service: RemoteService,
+        call: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let start_time = Instant::now();
+        let result = call.await;
+        let transition = self.inner.connections.record(
+            service,
+            start_time.elapsed(),
+            result.is_ok(),
+            self.now(),
+        );
+        if let Some(transition) = transition {
+            info!(
+                "Connection to {:?} is now {:?}",
+                transition.service, transition.current
+            );
+            self.inner
+                .event_emitter
+                .emit(&SdkEvent::ConnectionStateChanged {
+                    service: transition.service,
+                    previous: transition.previous,
+                    current: transition.current,
+                });
+        }
+        result
+    }
+
+    /// Announces the wallet is joining a round, failing it right away if a round failure was
+    /// injected
+    fn start_round(&self) -> Result<(), SdkError> {
+        self.inner.event_emitter.emit(&SdkEvent::RoundStarted {});
+        #[cfg(feature = "test-utils")]
+        if let Err(e) = self.inner.faults.round() {
+            self.emit_round_finished(&Err::<(), _>(&e));
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Runs a public call in the context of the runtime the SDK was built with, see
+    /// [tasks::on_runtime]
+    async fn on_runtime<F: Future>(&self, future: F) -> F::Output {
+        tasks::on_runtime(self.inner.tasks.runtime(), future).await
+    }
+
+    fn emit_send_progress(&self, send_id: &str, step: SendStep) {
+        self.inner.event_emitter.emit(&SdkEvent::PaymentProgress {
+            send_id: send_id.to_string(),
+            step,
+        });
+    }
+
+    /// Notifies listeners that the round the wallet took part in is over
+    fn emit_round_finished<T, E>(&self, result: &Result<T, E>) {
+        self.inner.event_emitter.emit(&SdkEvent::RoundFinished {
+            success: result.is_ok(),
+        });
+    }
+
+    /// Checks the server reported history against the chain, pausing sends on any mismatch
+    ///
+    /// The chain backend failing doesn't fail the sync, sends are left paused or allowed as they
+    /// were until every round can be checked.
+    async fn verify_server_state(&self, history: &[ArkTransaction]) {
+        let now = self.now() as i64;
+        let check = self
+            .inner
+            .settlement_verifier
+            .verify_history(self.inner.chain_service.as_ref(), history, now)
+            .await;
+        let inconsistencies = check.inconsistencies;
+        if inconsistencies.is_empty() && check.unchecked_rounds > 0 {
+            warn!(
+                "Couldn't check {} rounds onchain, leaving sends as they were",
+                check.unchecked_rounds
+            );
+            return;
+        }
+
+        let paused = !inconsistencies.is_empty();
+        let was_paused = self.inner.state.set_sends_paused(paused);
+        if paused && !was_paused {
+            error!("Ark server state doesn't match the chain, pausing sends");
+            self.inner
+                .event_emitter
+                .emit(&SdkEvent::ServerInconsistency {
+                    details: inconsistencies.join("; "),
+                });
+        } else if !paused && was_paused {
+            info!("Ark server state matches the chain again, resuming sends");
+        }
+    }
+
+    /// Fetches the offchain balance and persists it to storage
+    ///
+    /// Returns the fingerprint of the balances, see [history::balances_fingerprint].
+    async fn sync_balance_to_storage(&self) -> Result<String, SdkError> {
+        let mut server_balances = vec![ServerBalance {
+            ark_server_url: self.config().ark_server_url,
+            balance: Self::fetch_offchain_balance(self.inner.ark_client()?).await?,
+        }];
+        // An unreachable additional server doesn't fail the sync, its last known balance is kept
+        let previous_server_balances = self.inner.storage.get_server_balances().await?;
+        for server in &self.inner.ark_servers {
+            let balance = match Self::fetch_offchain_balance(&server.client).await {
+                Ok(balance) => balance,
+                Err(e) => {
+                    warn!(
+                        "Failed to fetch the balance held with {}, keeping the previous one: {e}",
+                        server.config.url
+                    );
+                    previous_server_balances
+                        .iter()
+                        .find(|b| b.ark_server_url == server.config.url)
+                        .map(|b| b.balance.clone())
+                        .unwrap_or_default()
+                }
+            };
+            server_balances.push(ServerBalance {
+                ark_server_url: server.config.url.clone(),
+                balance,
+            });
+        }
+
+        // The wallet balance is the sum of what is held with each server
+        let offchain_balance = models::OffchainBalance {
+            pending_sats: server_balances.iter().map(|b| b.balance.pending_sats).sum(),
+            confirmed_sats: server_balances
+                .iter()
+                .map(|b| b.balance.confirmed_sats)
+                .sum(),
+        };
+        info!("Synced balance: {}", offchain_balance.total_sats());
+        // The offchain balance doesn't depend on the chain service, keep the last boarding balance
+        // if it can't be reached
+        let boarding_balance = match self.fetch_boarding_balance().await {
+            Ok(balance) => balance,
+            Err(e) => {
+                warn!("Failed to fetch the boarding balance, keeping the previous one: {e}");
+                self.inner.storage.get_boarding_balance().await?
+            }
+        };
+
+        // Persist the balances to storage
+        let previous = self.inner.storage.get_offchain_balance().await?;
+        self.inner
+            .storage
+            .save_server_balances(&server_balances)
+            .await?;
+        self.inner
+            .storage
+            .save_offchain_balance(&offchain_balance)
+            .await?;
+        self.inner
+            .storage
+            .save_boarding_balance(&boarding_balance)
+            .await?;
+
+        if previous != offchain_balance {
+            let delta_sats = offchain_balance.total_sats() as i64 - previous.total_sats() as i64;
+            self.inner.event_emitter.emit(&SdkEvent::BalanceChanged {
+                previous,
+                current: offchain_balance,
+                delta_sats,
+            });
+        }
+        Ok(history::balances_fingerprint(
+            &server_balances,
+            &boarding_balance,
+        ))
+    }
+
+    /// Splits the deposits waiting on the boarding address by whether they have the
+    /// confirmations to be boarded in the next round
+    async fn fetch_boarding_balance(&self) -> Result<BoardingBalance, SdkError> {
+        let address = self.inner.ark_client()?.get_boarding_address()?;
+        let min_confirmations = self.config().min_boarding_confirmations();
+        let chain_service = &self.inner.chain_service;
+        let tip_height = chain_service.chain_tip().await?.height;
+
+        let mut balance = BoardingBalance::default();
+        for utxo in chain_service.find_outpoints(&address).await? {
+            if utxo.is_spent {
+                continue;
+            }
+            let confirmations = chain_service
+                .confirmation_height(&utxo.outpoint.txid)
+                .await?
+                .map_or(0, |height| chain::confirmations(tip_height, height));
+            match confirmations >= min_confirmations {
+                true => balance.eligible_sats += utxo.amount.to_sat(),
+                false => balance.waiting_sats += utxo.amount.to_sat(),
+            }
+        }
+        Ok(balance)
+    }
+
+    /// Fetches the offchain balance held with the server of the given client
+    async fn fetch_offchain_balance(
+        client: &ArkClient,
+    ) -> Result<models::OffchainBalance, SdkError> {
+        let ark_balance = client.offchain_balance().await?;
+        Ok(models::OffchainBalance {
+            pending_sats: ark_balance.pending().to_sat(),
+            confirmed_sats: ark_balance.confirmed().to_sat(),
+        })
+    }
+
+    /// The configuration currently in use
+    fn config(&self) -> Config {
+        self.inner.config.read().unwrap().clone()
+    }
+
+    /// Current Unix timestamp in seconds, read from the [Clock] the SDK was built with
+    fn now(&self) -> u64 {
+        self.inner.clock.now_secs()
+    }
+
+    fn sync_interval(&self) -> Duration {
+        self.config()
+            .sync_interval_secs
+            .map_or(PERIODIC_SYNC_INTERVAL, Duration::from_secs)
+    }
+
+    /// Picks the Ark server handling an Ark address, falling back to the main server
+    ///
+    /// Returns the server URL and its client.
+    fn route_ark_address(&self, address: &str) -> Result<(String, &Arc<ArkClient>), SdkError> {
+        let address = address.to_lowercase();
+        match self
+            .inner
+            .ark_servers
+            .iter()
+            .find(|server| address.starts_with(&server.config.address_prefix.to_lowercase()))
+        {
+            Some(server) => Ok((server.config.url.clone(), &server.client)),
+            None => Ok((self.config().ark_server_url, self.inner.ark_client()?)),
+        }
+    }
+
+    /// Whether an Ark address belongs to the main Ark server or one of the additional ones
+    fn is_own_server_address(&self, address: &ArkAddress) -> bool {
+        let network = self.config().network.into();
+        self.inner
+            .ark_client
+            .iter()
+            .chain(self.inner.ark_servers.iter().map(|server| &server.client))
+            .any(|client| {
+                let server_pk = client.server_info.pk.x_only_public_key().0;
+                ArkAddress::new(network, server_pk, address.vtxo_tap_key()).encode()
+                    == address.encode()
+            })
+    }
+
+    /// Generates a new deposit address for receiving funds into the Ark wallet
+    pub async fn receive_onchain(
+        &self,
+        _request: ReceiveOnchainRequest,
+    ) -> Result<ReceiveOnchainResponse, SdkError> {
+        self.on_runtime(async move {
+            let boarding_address = self.inner.ark_client()?.get_boarding_address()?;
+            Ok(ReceiveOnchainResponse {
+                deposit_address: boarding_address.to_string(),
+            })
+        })
+        .await
+    }
+
+    /// Lists the unspent onchain outputs held by the wallet
+    ///
+    /// This covers funds waiting on the boarding address and outputs of completed unilateral
+    /// exits. They are looked up directly on the chain, so the onchain holdings can be audited
+    /// independently of the Ark server.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The list onchain UTXOs request
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ListOnchainUtxosResponse)` - Contains the unspent outputs and their confirmations
+    /// * `Err(SdkError)` - If the chain service couldn't be queried
+    pub async fn list_onchain_utxos(
+        &self,
+        _request: ListOnchainUtxosRequest,
+    ) -> Result<ListOnchainUtxosResponse, SdkError> {
+        self.on_runtime(async move {
+            let addresses = [
+                (
+                    self.inner.ark_client()?.get_boarding_address()?,
+                    OnchainUtxoKind::Boarding,
+                ),
+                (
+                    self.inner.ark_client()?.get_onchain_address()?,
+                    OnchainUtxoKind::Exit,
+                ),
+            ];
+
+            let chain_service = &self.inner.chain_service;
+            let tip_height = chain_service.chain_tip().await?.height;
+            let mut utxos = Vec::new();
+            for (address, kind) in addresses {
+                for utxo in chain_service.find_outpoints(&address).await? {
+                    if utxo.is_spent {
+                        continue;
+                    }
+
+                    let confirmations = chain_service
+                        .confirmation_height(&utxo.outpoint.txid)
+                        .await?
+                        .map_or(0, |height| chain::confirmations(tip_height, height));
+                    utxos.push(OnchainUtxo {
+                        txid: utxo.outpoint.txid.to_string(),
+                        vout: utxo.outpoint.vout,
+                        amount_sat: utxo.amount.to_sat(),
+                        address: address.to_string(),
+                        kind: kind.clone(),
+                        confirmations,
+                    });
+                }
+            }
+
+            Ok(ListOnchainUtxosResponse { utxos })
+        })
+        .await
+    }
+
+    /// Quotes the fees of sending funds on-chain, without sending anything
+    ///
+    /// The quote is bound to the destination and amount and can be executed once with
+    /// [BreezSdk::send_onchain] within ten minutes, before the fee estimate goes stale.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the Bitcoin address and the amount to send
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PrepareSendOnchainResponse)` - The quote, including the fees
+    /// * `Err(SdkError)` - If the address is invalid or the fees couldn't be estimated
+    pub async fn prepare_send_onchain(
+        &self,
+        request: PrepareSendOnchainRequest,
+    ) -> Result<PrepareSendOnchainResponse, SdkError> {
+        self.on_runtime(async move {
+            info!(
+                "Preparing on-chain transaction of {} sats to {}",
+                request.receiver_amount_sats, request.onchain_address
+            );
+            Address::from_str(&request.onchain_address)?
+                .require_network(self.config().network.into())?;
+
+            // The off-board output is added to a round transaction, so it pays for its own size
+            let service_fees = self.inner.fee_provider.service_fees().await?;
+            let chain_fee_sat = fees::chain_fee_sat(
+                self.inner.fee_provider.as_ref(),
+                fees::ONCHAIN_OUTPUT_VBYTES,
+            )
+            .await?;
+
+            let now = self.now();
+            let quote = PrepareSendOnchainResponse {
+                quote_id: uuid::Uuid::new_v4().to_string(),
+                onchain_address: request.onchain_address,
+                receiver_amount_sats: request.receiver_amount_sats,
+                fee_sats: service_fees.offboard_fee_sat + chain_fee_sat,
+                expires_at: now + quotes::ONCHAIN_QUOTE_TTL_SECS,
+            };
+            self.inner.onchain_quotes.insert(quote.clone(), now);
+            Ok(quote)
+        })
+        .await
+    }
+
+    /// Executes an on-chain send quoted by [BreezSdk::prepare_send_onchain], moving funds from
+    /// Ark to on-chain Bitcoin
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the unmodified quote
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(SendOnchainResponse)` - Contains the transaction ID
+    /// * `Err(SdkError)` - If the quote is unknown, altered, expired or already used, if the
+    ///   balance no longer covers it, or if the send failed
+    pub async fn send_onchain(
+        &self,
+        request: SendOnchainRequest,
+    ) -> Result<SendOnchainResponse, SdkError> {
+        self.on_runtime(async move {
+            let now = self.now();
+            let quote = self
+                .inner
+                .onchain_quotes
+                .take(&request.prepare_send_onchain_response, now)?;
+            info!(
+                "Initiating on-chain withdrawal of quote {} to address: {}",
+                quote.quote_id, quote.onchain_address
+            );
+
+            // let mut rng = StdRng::from_entropy();
+            // let txid = self
+            //     .ark_client
+            //     .off_board(
+            //         &mut rng,
+            //         Address::from_str(&request.onchain_address)?
+            //             .require_network(self.inner.config.clone().network.into())?,
+            //         Amount::from_sat(request.prepare_send_onchain_response.receiver_amount_sats),
+            //     )
+            //     .await?;
+
+            self.inner.state.ensure_sends_allowed()?;
+            let address = Address::from_str(&quote.onchain_address)?
+                .require_network(self.config().network.into())?;
+            self.reserve_funds(
+                &quote.quote_id,
+                sends::total_sat(quote.receiver_amount_sats, quote.fee_sats)?,
+                self.inner.ark_client()?,
+            )
+            .await?;
+            self.emit_send_progress(&quote.quote_id, SendStep::FundsReserved);
+            let result = self.offboard(&quote, address).await;
+            self.release_reserved_funds(&quote.quote_id).await;
+            self.record_send_failure(&result);
+            result
+        })
+        .await
+    }
+
+    /// Sends the funds of a reserved onchain send quote in a round
+    async fn offboard(
+        &self,
+        quote: &PrepareSendOnchainResponse,
+        address: Address,
+    ) -> Result<SendOnchainResponse, SdkError> {
+        let amount = Amount::from_sat(quote.receiver_amount_sats);
+        let _round_guard = self.inner.state.begin_round().await;
+        self.start_round()?;
+        let result = self
+            .inner
+            .ark_client()?
+            .send_on_chain(address.clone(), amount)
+            .await;
+        self.emit_round_finished(&result);
+        let txid = result?;
+        self.emit_send_progress(&quote.quote_id, SendStep::AcceptedByServer);
+        if self.verify_offboard(txid, &address, amount).await {
+            self.emit_send_progress(&quote.quote_id, SendStep::Broadcast);
+        }
+
+        Ok(SendOnchainResponse {
+            tx_id: txid.to_string(),
+        })
+    }
+
+    /// Checks that the round transaction of an off-board pays the requested output
+    ///
+    /// The round is signed within the Ark client, so this can't prevent a bad round. It only
+    /// detects one after the fact, reporting it with [SdkEvent::RoundVerificationFailed] while
+    /// the send itself completed. A round that isn't published yet is left to the settlement
+    /// verification.
+    ///
+    /// # Returns
+    ///
+    /// Whether the round transaction was found on the chain
+    async fn verify_offboard(&self, txid: Txid, address: &Address, amount: Amount) -> bool {
+        let tx = match self.inner.chain_service.find_tx(&txid).await {
+            Ok(Some(tx)) => tx,
+            Ok(None) => return false,
+            Err(e) => {
+                warn!("Failed to look up off-board round {txid}: {e}");
+                return false;
+            }
+        };
+
+        let expected = TxOut {
+            value: amount,
+            script_pubkey: address.script_pubkey(),
+        };
+        if let Err(e) = verification::verify_round_outputs(&tx, &[expected]) {
+            error!("Off-board round {txid} failed verification: {e}");
+            self.inner
+                .event_emitter
+                .emit(&SdkEvent::RoundVerificationFailed {
+                    txid: txid.to_string(),
+                    reason: e.to_string(),
+                });
+        }
+        true
+    }
+
+    /// Returns an Ark address along with the details needed to check what received VTXOs are
+    /// worth
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The receive request
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ReceiveArkResponse)` - The address, its VTXO taproot key and the Ark server's expiry
+    ///   parameters
+    /// * `Err(SdkError)` - If there was an error generating the address
+    pub async fn receive_ark(
+        &self,
+        _request: ReceiveArkRequest,
+    ) -> Result<ReceiveArkResponse, SdkError> {
+        self.on_runtime(async move {
+            let (ark_address, _) = self.inner.ark_client()?.get_offchain_address()?;
+            let server_info = &self.inner.ark_client()?.server_info;
+
+            Ok(ReceiveArkResponse {
+                address: ark_address.encode(),
+                vtxo_pubkey: ark_address.vtxo_tap_key().to_string(),
+                vtxo_expiry_secs: expiry::timelock_secs(server_info.vtxo_tree_expiry),
+                exit_delay_secs: expiry::timelock_secs(server_info.unilateral_exit_delay),
+            })
+        })
+        .await
+    }
+
+    /// Generates a payment destination based on the requested payment method
+    ///
+    /// This method handles different payment methods (Ark address, Bitcoin address, BOLT11, BOLT12)
+    /// and returns the appropriate destination for receiving funds.
+    ///
+    /// For deposits from a hardware wallet or other cold storage, request
+    /// [PaymentMethod::ExternalDeposit]. Its response carries a [DepositVerification] to check
+    /// the boarding address on the device before sending, and the deposit shows up in the
+    /// payment history with its [Payment::deposit_source].
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the payment method and amount information
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ReceivePaymentResponse)` - Contains the destination, its fee, expiry and minimum
+    ///   amount, and a BIP21 URI for onchain and Ark addresses. A warning is set if the Ark
+    ///   server couldn't confirm it accepts new destinations at the last sync
+    /// * `Err(SdkError::AmountTooSmall)` - If the amount is below the server minimum or dust
+    /// * `Err(SdkError::AmountTooLarge)` - If the amount is above the server maximum, or for
+    ///   Lightning what a single HTLC can carry
+    /// * `Err(SdkError::InvalidInput)` - If the request is for an order without an amount, or
+    ///   its payment couldn't be told apart from that of an open order, see
+    ///   [ReceivePaymentRequest::order_id]
+    /// * `Err(SdkError)` - If there was an error generating the payment destination
+    pub async fn receive_payment(
+        &self,
+        request: ReceivePaymentRequest,
+    ) -> Result<ReceivePaymentResponse, SdkError> {
+        self.on_runtime(async move {
+            #[cfg(feature = "demo")]
+            if let Some(demo) = &self.inner.demo {
+                return demo.receive_payment(request).await;
+            }
+
+            let correlation_id = logger::new_correlation_id();
+            info!(
+                "[{correlation_id}] Generating payment destination for method: {:?}",
+                request.payment_method
+            );
+
+            let created_at = self.now();
+            let expiry_secs = request
+                .expiry_secs
+                .unwrap_or(payment_requests::DEFAULT_PAYMENT_REQUEST_EXPIRY_SECS);
+            let expires_at = created_at.checked_add(expiry_secs).ok_or_else(|| {
+                SdkError::InvalidInput(format!("Expiry of {expiry_secs} seconds is too long"))
+            })?;
+            // A received VTXO is bounded by the server's VTXO amounts, not by the balance like
+            // sends. The limits are cached, so receiving doesn't need the server to respond
+            let (offchain_receive, onchain_receive) = self.server_limits()?;
+            match &request.payment_method {
+                PaymentMethod::ArkAddress {
+                    receiver_amount_sat: Some(amount_sat),
+                    ..
+                } => offchain_receive.check(*amount_sat)?,
+                PaymentMethod::BitcoinAddress {
+                    receiver_amount_sat: Some(amount_sat),
+                }
+                | PaymentMethod::ExternalDeposit {
+                    receiver_amount_sat: Some(amount_sat),
+                    ..
+                } => onchain_receive.check(*amount_sat)?,
+                // The invoice is paid with a single HTLC over the inbound channel
+                PaymentMethod::Bolt11Invoice {
+                    receiver_amount_sat,
+                } => Limits {
+                    min_sat: lightning::MIN_HTLC_SAT,
+                    max_sat: Some(lightning::MAX_HTLC_SAT),
+                }
+                .check(*receiver_amount_sat)?,
+                _ => {}
+            }
+            let amount_sat = match &request.payment_method {
+                PaymentMethod::ArkAddress {
+                    receiver_amount_sat,
+                    ..
+                }
+                | PaymentMethod::BitcoinAddress {
+                    receiver_amount_sat,
+                }
+                | PaymentMethod::ExternalDeposit {
+                    receiver_amount_sat,
+                    ..
+                } => *receiver_amount_sat,
+                PaymentMethod::Bolt11Invoice {
+                    receiver_amount_sat,
+                } => Some(*receiver_amount_sat),
+                PaymentMethod::Bolt12Offer => None,
+            };
+            // Payments are matched to orders by amount, as Ark and boarding addresses are shared
+            if request.order_id.is_some() && amount_sat.is_none() {
+                return Err(SdkError::InvalidInput(
+                    "A payment request for an order needs an amount".to_string(),
+                ));
+            }
+            let payment_requests = self.inner.storage.list_payment_requests().await?;
+            if let Some(conflict) = payment_requests::conflicting_order(
+                &payment_requests,
+                &request.payment_method,
+                amount_sat,
+                request.order_id.is_some(),
+                created_at,
+            ) {
+                return Err(SdkError::InvalidInput(format!(
+                    "Payment request {} is open for the same amount on the same shared address, \
+                     their payments couldn't be told apart",
+                    conflict.id
+                )));
+            }
+
+            let mut response = match request.payment_method.clone() {
+                PaymentMethod::ArkAddress {
+                    receiver_amount_sat,
+                } => {
+                    // For Ark payments, we just need to return the Ark address
+                    let (ark_address, _) = self.inner.ark_client()?.get_offchain_address()?;
+                    let destination = ark_address.encode();
+                    let address_index = self
+                        .inner
+                        .storage
+                        .get_or_insert_receive_address(&destination, created_at)
+                        .await?;
+
+                    let fee_sat = 0;
+
+                    Ok(ReceivePaymentResponse {
+                        bip21: Some(bip21::compose_bip21(
+                            None,
+                            receiver_amount_sat,
+                            Some(&destination),
+                        )),
+                        destination,
+                        fee_sat,
+                        address_index: Some(address_index),
+                        correlation_id,
+                        payment_method: request.payment_method.clone(),
+                        expires_at,
+                        min_amount_sat: offchain_receive.min_sat,
+                        deposit_verification: None,
+                        warning: None,
+                    })
+                }
+                PaymentMethod::BitcoinAddress {
+                    receiver_amount_sat,
+                }
+                | PaymentMethod::ExternalDeposit {
+                    receiver_amount_sat,
+                    ..
+                } => {
+                    // For Bitcoin address payments, we generate an on-chain address
+                    let ark_client = self.inner.ark_client()?;
+                    let address = ark_client.get_boarding_address()?.to_string();
+                    let deposit_verification = matches!(
+                        request.payment_method,
+                        PaymentMethod::ExternalDeposit { .. }
+                    )
+                    .then(|| {
+                        deposit::verification(&address, ark_client.server_info.pk.to_string())
+                    });
+
+                    // Received funds are boarded in a round, spending the boarding output
+                    let service_fees = self.inner.fee_provider.service_fees().await?;
+                    let fee_sat = service_fees.boarding_fee_sat
+                        + fees::chain_fee_sat(
+                            self.inner.fee_provider.as_ref(),
+                            fees::BOARDING_INPUT_VBYTES,
+                        )
+                        .await?;
+
+                    Ok(ReceivePaymentResponse {
+                        bip21: Some(bip21::compose_bip21(
+                            Some(&address),
+                            receiver_amount_sat,
+                            None,
+                        )),
+                        destination: address,
+                        fee_sat,
+                        address_index: None,
+                        correlation_id,
+                        payment_method: request.payment_method.clone(),
+                        expires_at,
+                        min_amount_sat: onchain_receive.min_sat,
+                        deposit_verification,
+                        warning: None,
+                    })
+                }
+                PaymentMethod::Bolt11Invoice { .. } => Err(SdkError::GenericError(
+                    "BOLT11 invoice generation is not yet implemented".to_string(),
+                )),
+                PaymentMethod::Bolt12Offer => Err(SdkError::GenericError(
+                    "BOLT12 offer generation is not yet implemented".to_string(),
+                )),
+            }?;
+            response.warning = self.inner.state.receive_warning();
+            if let Some(warning) = &response.warning {
+                warn!(
+                    "[{}] The destination may not be payable: {warning:?}",
+                    response.correlation_id
+                );
+            }
+
+            // Track the destination until a matching payment is received or it expires
+            self.inner
+                .storage
+                .save_payment_request(&PaymentRequest {
+                    id: response.correlation_id.clone(),
+                    payment_method: request.payment_method,
+                    destination: response.destination.clone(),
+                    amount_sat,
+                    created_at,
+                    expires_at,
+                    status: PaymentRequestStatus::Pending,
+                    payment_id: None,
+                    order_id: request.order_id,
+                    description: request.description,
+                })
+                .await?;
+
+            Ok(response)
+        })
+        .await
+    }
+
+    /// Asks the Ark server for its current key, as Ark and boarding addresses commit to the key
+    /// the server had when the SDK connected, and are only payable while it still signs with it
+    ///
+    /// Done along with every sync, [BreezSdk::receive_payment] reports the outcome of the last
+    /// check without waiting for the server.
+    async fn check_server_key(&self) {
+        let Ok(ark_client) = self.inner.ark_client() else {
+            return;
+        };
+        let server_url = self.config().ark_server_url;
+        let fetch = async {
+            tokio::time::timeout(SERVER_CHECK_TIMEOUT, fees::fetch_server_info(&server_url))
+                .await
+                .map_err(|_| {
+                    SdkError::NetworkError("Timed out waiting for the Ark server".to_string())
+                })?
+        };
+        let current = self
+            .track_connection(RemoteService::ArkServer, fetch)
+            .await
+            .map(|info| info.pk);
+        let warning = verification::receive_warning(ark_client.server_info.pk, current);
+        self.inner.state.set_receive_warning(warning);
+    }
+
+    /// Lists the payment destinations generated by [BreezSdk::receive_payment]
+    ///
+    /// Requests are marked as paid when a payment of exactly their amount is received, as part
+    /// of the wallet sync. Ark and boarding addresses are shared, so requests without an amount
+    /// aren't matched to payments and expire.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Optionally filters the requests by status
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ListPaymentRequestsResponse)` - Contains the payment requests, newest first
+    /// * `Err(SdkError)` - If there was an error accessing the storage
+    pub async fn list_payment_requests(
+        &self,
+        request: ListPaymentRequestsRequest,
+    ) -> Result<ListPaymentRequestsResponse, SdkError> {
+        self.on_runtime(async move {
+            let mut payment_requests = self.inner.storage.list_payment_requests().await?;
+            if let Some(status) = request.status {
+                payment_requests.retain(|payment_request| payment_request.status == status);
+            }
+            if let Some(order_id) = request.order_id {
+                payment_requests
+                    .retain(|payment_request| payment_request.order_id.as_ref() == Some(&order_id));
+            }
+            Ok(ListPaymentRequestsResponse { payment_requests })
+        })
+        .await
+    }
+
+    /// Exports the proof that the round a payment settled in is confirmed onchain
+    ///
+    /// The proof holds the round transaction and the merkle path of its confirmation, so a
+    /// third party, e.g. an auditor, can check the round against its own node without trusting
+    /// the Ark server. The Ark client doesn't expose the virtual transactions of the VTXO tree,
+    /// so the proof ends at the round transaction: it proves the round's inclusion, not that
+    /// the round pays the payment.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the ID of the payment to prove
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ExportRoundProofResponse)` - Contains the proof
+    /// * `Err(SdkError::InvalidInput)` - If the payment didn't settle in a round
+    /// * `Err(SdkError::RoundVerificationFailed)` - If the chain backend's data doesn't prove the
+    ///   round is confirmed
+    /// * `Err(SdkError)` - If the round isn't confirmed yet or the chain couldn't be queried
+    pub async fn export_round_proof(
+        &self,
+        request: ExportRoundProofRequest,
+    ) -> Result<ExportRoundProofResponse, SdkError> {
+        self.on_runtime(async move {
+            let payment = self
+                .inner
+                .storage
+                .get_payment(&request.payment_id)
+                .await?
+                .ok_or_else(|| {
+                    SdkError::InvalidInput(format!("No payment with ID {}", request.payment_id))
+                })?;
+            let Some(PaymentDetails::Ark {
+                round_txid: Some(round_txid),
+                ..
+            }) = &payment.details
+            else {
+                return Err(SdkError::InvalidInput(format!(
+                    "Payment {} didn't settle in a round",
+                    payment.id
+                )));
+            };
+            let round_txid: Txid = round_txid
+                .parse()
+                .map_err(|_| SdkError::GenericError(format!("Invalid round txid {round_txid}")))?;
+
+            let chain_service = &self.inner.chain_service;
+            let not_confirmed =
+                || SdkError::GenericError(format!("Round {round_txid} isn't confirmed yet"));
+            let round_tx = chain_service
+                .find_tx(&round_txid)
+                .await?
+                .ok_or_else(not_confirmed)?;
+            let block_height = chain_service
+                .confirmation_height(&round_txid)
+                .await?
+                .ok_or_else(not_confirmed)?;
+            let inclusion_proof = chain_service
+                .tx_inclusion_proof(&round_txid)
+                .await?
+                .ok_or_else(not_confirmed)?;
+
+            let proof = proof::build_proof(proof::ProofParts {
+                payment_id: payment.id,
+                amount_sat: payment.amount,
+                round_txid,
+                round_tx,
+                block_height,
+                inclusion_proof,
+                server_pubkey: self.inner.ark_client()?.server_info.pk.to_string(),
+            })?;
+            Ok(ExportRoundProofResponse { proof })
+        })
+        .await
+    }
+
+    /// Waits until a destination generated by [BreezSdk::receive_payment] is paid
+    ///
+    /// The latest request for the destination is checked after every sync, so this resolves
+    /// once the sync matching a payment to it also sees the payment completed. Ark and boarding
+    /// addresses are shared between requests, see [BreezSdk::list_payment_requests] for how
+    /// payments are matched to them.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the destination and how long to wait
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(WaitForPaymentResponse)` - Contains the completed payment
+    /// * `Err(SdkError::Timeout)` - If the payment didn't settle in time
+    /// * `Err(SdkError)` - If no request was generated for the destination, or it expired unpaid
+    pub async fn wait_for_payment(
+        &self,
+        request: WaitForPaymentRequest,
+    ) -> Result<WaitForPaymentResponse, SdkError> {
+        self.on_runtime(async move {
+            // Listen before the first check, so a sync completing in between isn't missed
+            let (sender, mut receiver) = mpsc::unbounded_channel();
+            let listener_id = self
+                .inner
+                .event_emitter
+                .add_listener(Box::new(ChannelListener { sender }));
+
+            let wait = async {
+                loop {
+                    if let Some(payment) = self.settled_payment_to(&request.destination).await? {
+                        return Ok(payment);
+                    }
+                    // Payment requests are only matched with payments during syncs
+                    loop {
+                        match receiver.recv().await {
+                            Some(SdkEvent::Synced {}) => break,
+                            Some(_) => {}
+                            None => {
+                                return Err(SdkError::GenericError(
+                                    "Stopped receiving SDK events".to_string(),
+                                ))
+                            }
+                        }
+                    }
+                }
+            };
+            let result =
+                tokio::time::timeout(Duration::from_secs(request.timeout_secs), wait).await;
+            self.inner.event_emitter.remove_listener(&listener_id);
+
+            match result {
+                Ok(result) => result.map(|payment| WaitForPaymentResponse { payment }),
+                Err(_) => Err(SdkError::Timeout(format!(
+                    "Waiting for a payment to {}",
+                    request.destination
+                ))),
+            }
+        })
+        .await
+    }
+
+    /// The completed payment of the latest request for a destination, if it was paid
+    async fn settled_payment_to(&self, destination: &str) -> Result<Option<Payment>, SdkError> {
+        let payment_request = self
+            .inner
+            .storage
+            .list_payment_requests()
+            .await?
+            .into_iter()
+            .filter(|payment_request| payment_request.destination == destination)
+            .max_by_key(|payment_request| payment_request.created_at)
+            .ok_or_else(|| {
+                SdkError::InvalidInput(format!("No payment request for {destination}"))
+            })?;
+
+        match (payment_request.status, payment_request.payment_id) {
+            (PaymentRequestStatus::Paid, Some(payment_id)) => Ok(self
+                .inner
+                .storage
+                .get_payment(&payment_id)
+                .await?
+                .filter(|payment| payment.status == PaymentStatus::Completed)),
+            (PaymentRequestStatus::Expired, _) => Err(SdkError::GenericError(format!(
+                "The payment request for {destination} expired unpaid"
+            ))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Registers a lightning address for the wallet with the configured LNURL server
+    ///
+    /// The server answers LNURL-pay requests for `username@domain` and pays what it receives
+    /// to the wallet's Ark address, so the payments land as VTXOs. Registering again replaces
+    /// the wallet's username.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the username to register
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RegisterLightningAddressResponse)` - Contains the registered lightning address
+    /// * `Err(SdkError)` - If no LNURL server is configured, the username is invalid or taken,
+    ///   or the server couldn't be reached
+    pub async fn register_lightning_address(
+        &self,
+        request: RegisterLightningAddressRequest,
+    ) -> Result<RegisterLightningAddressResponse, SdkError> {
+        self.on_runtime(async move {
+            let server_url = self
+                .config()
+                .lnurl_server_url
+                .ok_or_else(|| SdkError::GenericError("No LNURL server configured".to_string()))?;
+            let (ark_address, _) = self.inner.ark_client()?.get_offchain_address()?;
+            let time = self.now();
+
+            let http_client = self.inner.http_client.clone().unwrap_or_default();
+            let lightning_address = lightning_address::register(
+                &http_client,
+                &server_url,
+                &self.inner.lnurl_keypair,
+                &request.username,
+                &ark_address.encode(),
+                time,
+            )
+            .await?;
+            info!("Registered lightning address {lightning_address}");
+
+            Ok(RegisterLightningAddressResponse { lightning_address })
+        })
+        .await
+    }
+
+    /// Synchronizes payments to persistent storage
+    ///
+    /// Pending received payments past their VTXO expiry are marked as expired, so the periodic
+    /// sync moves stale payments to `Expired` in the background.
+    ///
+    /// The payments are only converted and saved again when the history changed since the
+    /// [HistoryCheckpoint] of the last sync, or when a pending payment or payment request
+    /// expired meanwhile. The checkpoint records the balances the history was fetched at.
+    async fn sync_payments_to_storage(
+        &self,
+        ark_transactions: Vec<ArkTransaction>,
+        balances_fingerprint: String,
+    ) -> Result<(), SdkError> {
+        debug!("Syncing {} Ark transactions", ark_transactions.len());
+        let now = self.now();
+        let fingerprint = history::fingerprint(&ark_transactions);
+        let mut payment_requests = self.inner.storage.list_payment_requests().await?;
+        let request_expired = payment_requests.iter().any(|payment_request| {
+            payment_request.status == PaymentRequestStatus::Pending
+                && payment_request.expires_at <= now
+        });
+        let checkpoint = self.inner.storage.get_history_checkpoint().await?;
+        if let Some(checkpoint) = checkpoint
+            .filter(|checkpoint| !request_expired && checkpoint.is_current(&fingerprint, now))
+        {
+            debug!("Ark transaction history unchanged, skipping the payments sync");
+            return self
+                .inner
+                .storage
+                .save_history_checkpoint(&HistoryCheckpoint {
+                    balances_fingerprint,
+                    fetched_at: now,
+                    ..checkpoint
+                })
+                .await;
+        }
+
+        // Descriptions and counterparties only exist locally, keep them for known payments
+        let stored: HashMap<String, Payment> = self
+            .inner
+            .storage
+            .list_payments(0, u32::MAX)
+            .await?
+            .into_iter()
+            .map(|p| (p.id.clone(), p))
+            .collect();
+
+        // Received VTXOs that aren't settled before the VTXO tree expires can't be claimed
+        let vtxo_expiry_secs =
+            expiry::timelock_secs(self.inner.ark_client()?.server_info.vtxo_tree_expiry);
+
+        // Convert all transactions to payments
+        let mut payments = Vec::with_capacity(ark_transactions.len());
+        let mut expired = Vec::new();
+        // The next time a pending payment expires, when the payments need saving again
+        let mut revisit_at: Option<u64> = None;
+        for ark_transaction in ark_transactions {
+            let expires_at = match &ark_transaction {
+                ArkTransaction::Redeem {
+                    is_settled: false,
+                    created_at,
+                    ..
+                } => vtxo_expiry_secs.map(|secs| *created_at as u64 + secs),
+                _ => None,
+            };
+
+            let mut payment = Payment::from(ark_transaction);
+            let stored_payment = stored.get(&payment.id);
+            if let Some(stored_payment) = stored_payment {
+                payment.keep_local_details(stored_payment);
+            }
+            if payment.payment_type == PaymentType::Received
+                && payment.status == PaymentStatus::Pending
+                && expires_at.is_some_and(|expires_at| expires_at <= now)
+            {
+                payment.status = PaymentStatus::Expired;
+                payment.failure_reason = Some(PaymentFailureReason::new(
+                    PaymentFailureKind::Expired,
+                    "The VTXO expired before it was settled",
+                ));
+                if stored_payment.map(|p| &p.status) != Some(&PaymentStatus::Expired) {
+                    expired.push(payment.clone());
+                }
+            }
+            if payment.payment_type == PaymentType::Received
+                && payment.status == PaymentStatus::Pending
+            {
+                if let Some(expires_at) = expires_at {
+                    revisit_at = Some(revisit_at.map_or(expires_at, |at| at.min(expires_at)));
+                }
+            }
+            trace!("Converted payment: {:?}", payment);
+            payments.push(payment);
+        }
+
+        // Cancelled sends never reached the server, so only storage knows about them
+        payments.extend(
+            stored
+                .into_values()
+                .filter(|payment| payment.status == PaymentStatus::Cancelled),
+        );
+
+        // Match the payments to requests before saving, so they carry the order and description of
+        // their request
+        let updated_requests = payment_requests::reconcile(&payment_requests, &payments, now);
+        payment_requests.extend(updated_requests.iter().cloned());
+        payment_requests::tag_payments(&payment_requests, &mut payments);
+
+        // Payments moved out of the payment history stay out of it
+        let archived = self.inner.storage.archived_payment_ids().await?;
+        payments.retain(|payment| !archived.contains(&payment.id));
+
+        // Save all payments at once and delete any that don't exist in the list
+        self.inner.storage.save_payments(&payments).await?;
+        self.inner
+            .storage
+            .save_history_checkpoint(&HistoryCheckpoint {
+                fingerprint,
+                revisit_at,
+                conversion_version: history::PAYMENT_CONVERSION_VERSION,
+                balances_fingerprint,
+                fetched_at: now,
+            })
+            .await?;
+
+        for payment_request in updated_requests {
+            info!(
+                "Payment request {} is now {}",
+                payment_request.id, payment_request.status
+            );
+            self.inner
+                .storage
+                .save_payment_request(&payment_request)
+                .await?;
+            let payment = payment_request
+                .payment_id
+                .as_ref()
+                .and_then(|payment_id| payments.iter().find(|p| &p.id == payment_id));
+            if let Some(payment) = payment {
+                self.inner.event_emitter.emit(&SdkEvent::PaymentReceived {
+                    payment: payment.clone(),
+                    payment_request_id: payment_request.id,
+                });
+            }
+        }
+
+        for payment in expired {
+            info!("Pending payment {} expired", payment.id);
+            self.inner
+                .event_emitter
+                .emit(&SdkEvent::PaymentExpired { payment });
+        }
+
+        Ok(())
+    }
+
+    /// Lists payments from the storage with pagination
+    ///
+    /// This method provides direct access to the payment history stored in the database.
+    /// It returns payments in reverse chronological order (newest first), unless ascending order
+    /// is requested.
+    ///
+    /// Pages can be requested by offset, or by the cursor returned with the previous page. The
+    /// cursor is preferable when paging through the history, as payments arriving in between
+    /// shift the offsets.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains pagination parameters (offset or cursor, and limit) and the order
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ListPaymentsResponse)` - Contains the list of payments and the cursor of the next
+    ///   page if successful
+    /// * `Err(SdkError)` - If the cursor is invalid or there was an error accessing the storage
+    ///
+    pub async fn list_payments(
+        &self,
+        request: ListPaymentsRequest,
+    ) -> Result<ListPaymentsResponse, SdkError> {
+        self.on_runtime(async move {
+            info!("Listing payments with filter: {:?}", request);
+
+            // Retrieve payments from storage with pagination parameters
+            let cursor = request
+                .cursor
+                .as_deref()
+                .map(PaymentCursor::decode)
+                .transpose()?;
+            let is_first_page = request.offset == 0 && cursor.is_none();
+            let mut payments = self
+                .inner
+                .storage
+                .list_payments_page(&PaymentsQuery {
+                    offset: request.offset,
+                    limit: request.limit,
+                    cursor,
+                    sort: request.sort,
+                })
+                .await?;
+
+            // Sends that haven't completed are the most recent activity, list them first
+            let mut pending_count = 0;
+            if is_first_page && request.sort == SortDirection::Descending {
+                let pending_sends = self.inner.storage.list_pending_sends().await?;
+                pending_count = pending_sends.len();
+                let pending_payments = pending_sends.into_iter().rev().map(sends::pending_payment);
+                payments.splice(0..0, pending_payments);
+                payments.truncate(request.limit as usize);
+            }
+
+            // The next page continues after the last stored payment listed, or from the newest one
+            // if only pending sends fit this page
+            let next_cursor =
+                (payments.len() == request.limit as usize && request.limit > 0).then(|| {
+                    match payments.last() {
+                        Some(last) if payments.len() > pending_count => PaymentCursor::after(last),
+                        _ => PaymentCursor::newest(),
+                    }
+                    .encode()
+                });
+
+            // Return the payments in the response
+            Ok(ListPaymentsResponse {
+                payments,
+                next_cursor,
+            })
+        })
+        .await
+    }
+
+    /// Parses a user provided input
+    ///
+    /// Ark addresses are returned in lowercase, also when scanned from an upper case QR code.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The string to parse, e.g. an Ark address or an Ark note
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(InputType)` - The detected input type and its details
+    /// * `Err(SdkError)` - If the input is not recognized or is an Ark address of another network
+    pub async fn parse(&self, input: &str) -> Result<InputType, SdkError> {
+        self.on_runtime(
+            async move { input_parser::parse_input(input, self.config().network).await },
+        )
+        .await
+    }
+
+    /// Normalizes an Ark address to lowercase, e.g. one scanned from an upper case QR code
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The Ark address, in either lower or upper case
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The lowercase address
+    /// * `Err(SdkError)` - If the address mixes cases, is malformed or is for another network
+    pub fn normalize_ark_address(&self, address: &str) -> Result<String, SdkError> {
+        qr::normalize_ark_address(address, self.config().network)
+    }
+
+    /// Formats a destination to be shown as a QR code
+    ///
+    /// Ark addresses, segwit addresses and BOLT11 invoices are upper-cased, also within BIP21
+    /// URIs, so the QR code can use its denser alphanumeric mode. Destinations that are
+    /// case-sensitive are returned unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `destination` - The destination, e.g. from [BreezSdk::receive_payment]
+    ///
+    /// # Returns
+    ///
+    /// The destination to encode in the QR code, still parsed the same by wallets
+    pub fn format_destination_for_qr(destination: &str) -> String {
+        qr::format_for_qr(destination)
+    }
+
+    /// Redeems an Ark note, claiming its value into the wallet
+    ///
+    /// Ark notes are bearer VTXOs encoded as strings, handy for faucets and gifting. The value
+    /// is claimed by joining the next round and is recorded as a received payment.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the encoded note
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RedeemNoteResponse)` - Contains the received payment
+    /// * `Err(SdkError)` - If the note is invalid or couldn't be redeemed
+    pub async fn redeem_note(
+        &self,
+        request: RedeemNoteRequest,
+    ) -> Result<RedeemNoteResponse, SdkError> {
+        self.on_runtime(async move {
+            let timestamp = self.now();
+
+            let (txid, amount_sat, round_txid) = if request
+                .note
+                .trim()
+                .to_lowercase()
+                .starts_with(notes::GIFT_NOTE_HRP)
+            {
+                let note = GiftNote::decode(&request.note)?;
+                if timestamp > note.expires_at {
+                    return Err(SdkError::PaymentError("The note has expired".to_string()));
+                }
+                info!("Redeeming gift note worth {} sats", note.amount_sat);
+                let (txid, amount_sat) = self.sweep_gift_note(&note).await?.ok_or_else(|| {
+                    SdkError::PaymentError("The note has already been claimed".to_string())
+                })?;
+
+                // Redeeming a note we created ourselves is effectively a reclaim
+                if let Some(mut stored) = self.inner.storage.get_note(&note.id()).await? {
+                    stored.status = NoteStatus::Reclaimed;
+                    self.inner.storage.save_note(&stored).await?;
+                }
+                (txid, amount_sat, None)
+            } else {
+                let note = input_parser::parse_ark_note(&request.note)?;
+                let amount_sat = note.value().to_sat();
+                info!("Redeeming Ark note worth {amount_sat} sats");
+
+                let _round_guard = self.inner.state.begin_round().await;
+                self.start_round()?;
+                let mut rng = StdRng::from_entropy();
+                let result = self
+                    .inner
+                    .ark_client()?
+                    .redeem_notes(&mut rng, vec![note])
+                    .await;
+                self.emit_round_finished(&result);
+                let txid = result?.ok_or_else(|| {
+                    SdkError::PaymentError("Note redemption did not produce a round".to_string())
+                })?;
+                (txid, amount_sat, Some(txid))
+            };
+
+            let payment = Payment {
+                id: txid.to_string(),
+                payment_type: PaymentType::Received,
+                status: PaymentStatus::Completed,
+                amount: amount_sat,
+                fees: 0,
+                timestamp,
+                description: Some(notes::REDEEMED_NOTE_DESCRIPTION.to_string()),
+                destination: None,
+                is_retryable: false,
+                details: Some(PaymentDetails::Ark {
+                    txid: txid.to_string(),
+                    round_txid: round_txid.map(|txid| txid.to_string()),
+                }),
+                onchain_txs: round_txid
+                    .map(|txid| OnchainTx {
+                        txid: txid.to_string(),
+                        kind: OnchainTxKind::RoundCommitment,
+                    })
+                    .into_iter()
+                    .collect(),
+                order_id: None,
+                deposit_source: None,
+                failure_reason: None,
+            };
+
+            // Saved before the sync, so its description keeps it from paying a payment request
+            let _sync_guard = self.inner.state.begin_sync().await;
+            self.inner.storage.save_payment(&payment).await?;
+            self.sync_wallet_internal().await?;
+            self.inner.event_emitter.emit(&SdkEvent::PaymentSucceeded {
+                payment: payment.clone(),
+            });
+
+            Ok(RedeemNoteResponse { payment })
+        })
+        .await
+    }
+
+    /// Creates a bearer note that anyone holding it can redeem
+    ///
+    /// The amount is sent to a VTXO locked to a fresh ephemeral key, which is encoded in the
+    /// returned note string. The funds stay reserved in storage until the note is redeemed
+    /// by its recipient or reclaimed with [BreezSdk::reclaim_note].
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the amount and optional expiry of the note
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(CreateNoteResponse)` - Contains the created note
+    /// * `Err(SdkError)` - If the funds couldn't be sent to the note
+    pub async fn create_note(
+        &self,
+        request: CreateNoteRequest,
+    ) -> Result<CreateNoteResponse, SdkError> {
+        self.on_runtime(async move {
+            if request.amount_sat == 0 {
+                return Err(SdkError::PaymentError(
+                    "Note amount must be greater than zero".to_string(),
+                ));
+            }
+
+            let created_at = self.now();
+            let expiry_secs = request.expiry_secs.unwrap_or(DEFAULT_NOTE_EXPIRY_SECS);
+            let expires_at = created_at.checked_add(expiry_secs).ok_or_else(|| {
+                SdkError::InvalidInput(format!("Expiry of {expiry_secs} seconds is too long"))
+            })?;
+            let gift_note = GiftNote {
+                secret_key: SecretKey::new(&mut StdRng::from_entropy()),
+                amount_sat: request.amount_sat,
+                expires_at,
+            };
+
+            // Fund a VTXO owned by the note's ephemeral key
+            let note_client = Self::init_client(
+                self.config(),
+                gift_note.secret_key.secret_bytes().to_vec(),
+                self.inner.chain_service.clone(),
+                Arc::new(InMemoryDb::default()),
+            )
+            .await?;
+            let (note_address, _) = note_client.get_offchain_address()?;
+            {
+                self.inner.state.ensure_sends_allowed()?;
+                let _send_guard = self.inner.state.begin_send().await;
+                self.inner
+                    .ark_client()?
+                    .send_vtxo(note_address, Amount::from_sat(request.amount_sat))
+                    .await
+                    .map_err(|e| SdkError::PaymentError(format!("Failed to fund note: {e}")))?;
+            }
+
+            let note = Note {
+                id: gift_note.id(),
+                note: gift_note.encode()?,
+                amount_sat: request.amount_sat,
+                created_at,
+                expires_at: gift_note.expires_at,
+                status: NoteStatus::Reserved,
+            };
+            self.inner.storage.save_note(&note).await?;
+
+            let _sync_guard = self.inner.state.begin_sync().await;
+            self.sync_wallet_internal().await?;
+
+            Ok(CreateNoteResponse { note })
+        })
+        .await
+    }
+
+    /// Reclaims the funds of a note that wasn't redeemed by its recipient
+    ///
+    /// The note is only marked as redeemed once the Ark server reports it empty. If reclaiming
+    /// fails for another reason, e.g. the server can't be reached, it can be tried again.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the ID of the note to reclaim
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ReclaimNoteResponse)` - Contains the received payment
+    /// * `Err(SdkError)` - If the note is unknown or was already claimed
+    pub async fn reclaim_note(
+        &self,
+        request: ReclaimNoteRequest,
+    ) -> Result<ReclaimNoteResponse, SdkError> {
+        self.on_runtime(async move {
+            let mut note = self
+                .inner
+                .storage
+                .get_note(&request.id)
+                .await?
+                .ok_or_else(|| SdkError::GenericError(format!("Note not found: {}", request.id)))?;
+            if note.status != NoteStatus::Reserved {
+                return Err(SdkError::PaymentError(format!(
+                    "Note is already {}",
+                    note.status.to_string().to_lowercase()
+                )));
+            }
+
+            let gift_note = GiftNote::decode(&note.note)?;
+            // Failed sweeps leave the note reserved to be reclaimed again, only a note the Ark
+            // server reports as empty was redeemed by the recipient
+            let Some((txid, amount_sat)) = self.sweep_gift_note(&gift_note).await? else {
+                note.status = NoteStatus::Redeemed;
+                self.inner.storage.save_note(&note).await?;
+                return Err(SdkError::PaymentError(
+                    "The note has already been redeemed".to_string(),
+                ));
+            };
+            note.status = NoteStatus::Reclaimed;
+            self.inner.storage.save_note(&note).await?;
+
+            let payment = Payment {
+                id: txid.to_string(),
+                payment_type: PaymentType::Received,
+                status: PaymentStatus::Completed,
+                amount: amount_sat,
+                fees: 0,
+                timestamp: self.now(),
+                description: Some(notes::RECLAIMED_NOTE_DESCRIPTION.to_string()),
+                destination: None,
+                is_retryable: false,
+                details: Some(PaymentDetails::Ark {
+                    txid: txid.to_string(),
+                    round_txid: None,
+                }),
+                onchain_txs: Vec::new(),
+                order_id: None,
+                deposit_source: None,
+                failure_reason: None,
+            };
+
+            // Saved before the sync, so its description keeps it from paying a payment request
+            let _sync_guard = self.inner.state.begin_sync().await;
+            self.inner.storage.save_payment(&payment).await?;
+            self.sync_wallet_internal().await?;
+
+            Ok(ReclaimNoteResponse { payment })
+        })
+        .await
+    }
+
+    /// Exports the wallet state that can't be restored from the mnemonic alone
+    ///
+    /// This covers payment labels, pending payments and created notes. Boarding addresses are
+    /// derived from the mnemonic and VTXOs are recovered from the Ark server, so they don't need
+    /// to be exported. The state is encrypted with a key derived from the wallet seed and split
+    /// into chunks small enough to be shown as an animated QR code, allowing a move to another
+    /// device without any backup service.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the optional maximum chunk size
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ExportStateResponse)` - Contains the encrypted chunks
+    /// * `Err(SdkError)` - If the state couldn't be read or encrypted
+    pub async fn export_state(
+        &self,
+        request: ExportStateRequest,
+    ) -> Result<ExportStateResponse, SdkError> {
+        self.on_runtime(async move {
+            let payments = self
+                .inner
+                .storage
+                .list_payments(0, u32::MAX)
+                .await?
+                .into_iter()
+                .filter(|p| p.status == PaymentStatus::Pending || p.description.is_some())
+                .collect();
+            let notes = self.inner.storage.list_notes().await?;
+
+            let chunk_size = request
+                .max_chunk_size
+                .map_or(backup::DEFAULT_CHUNK_SIZE, |size| size as usize);
+            let chunks = backup::export(
+                &self.inner.state_key,
+                &backup::new_state(payments, notes),
+                chunk_size,
+            )?;
+            Ok(ExportStateResponse { chunks })
+        })
+        .await
+    }
+
+    /// Exports the whole payment history, including payments archived by
+    /// [Config::payment_retention]
+    ///
+    /// Meant to keep a copy of the payments before they are pruned, as pruned payments are gone
+    /// for good.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The export request, currently without options
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ExportPaymentsResponse)` - Contains every payment, oldest first
+    /// * `Err(SdkError)` - If the payments couldn't be read
+    pub async fn export_payments(
+        &self,
+        _request: ExportPaymentsRequest,
+    ) -> Result<ExportPaymentsResponse, SdkError> {
+        self.on_runtime(async move {
+            let mut payments = self.inner.storage.list_archived_payments().await?;
+            payments.extend(
+                self.inner
+                    .storage
+                    .list_payments_page(&PaymentsQuery {
+                        offset: 0,
+                        limit: u32::MAX,
+                        cursor: None,
+                        sort: SortDirection::Ascending,
+                    })
+                    .await?,
+            );
+            payments.sort_by(|a, b| (a.timestamp, &a.id).cmp(&(b.timestamp, &b.id)));
+            Ok(ExportPaymentsResponse { payments })
+        })
+        .await
+    }
+
+    /// Imports a wallet state exported by [BreezSdk::export_state]
+    ///
+    /// The exporting wallet must use the same mnemonic. Unknown payments and notes are added,
+    /// while known payments only receive a description if they don't have one yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the exported chunks, in any order
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ImportStateResponse)` - Contains how many payments and notes were imported
+    /// * `Err(SdkError)` - If chunks are missing or the state can't be decrypted
+    pub async fn import_state(
+        &self,
+        request: ImportStateRequest,
+    ) -> Result<ImportStateResponse, SdkError> {
+        self.on_runtime(async move {
+            let state = backup::import(&self.inner.state_key, &request.chunks)?;
+
+            let mut payments_imported = 0;
+            for payment in state.payments {
+                match self.inner.storage.get_payment(&payment.id).await? {
+                    None => self.inner.storage.save_payment(&payment).await?,
+                    Some(mut existing) if existing.description.is_none() => {
+                        existing.description = payment.description;
+                        self.inner.storage.save_payment(&existing).await?;
+                    }
+                    Some(_) => continue,
+                }
+                payments_imported += 1;
+            }
+
+            let mut notes_imported = 0;
+            for note in state.notes {
+                if self.inner.storage.get_note(&note.id).await?.is_none() {
+                    self.inner.storage.save_note(&note).await?;
+                    notes_imported += 1;
+                }
+            }
+
+            Ok(ImportStateResponse {
+                payments_imported,
+                notes_imported,
+            })
+        })
+        .await
+    }
+
+    /// Lists the notes created by the wallet
+    pub async fn list_notes(
+        &self,
+        _request: ListNotesRequest,
+    ) -> Result<ListNotesResponse, SdkError> {
+        self.on_runtime(async move {
+            let notes = self.inner.storage.list_notes().await?;
+            Ok(ListNotesResponse { notes })
+        })
+        .await
+    }
+
+    /// Moves the funds of a gift note to the wallet, returning `None` if the note is empty
+    async fn sweep_gift_note(&self, note: &GiftNote) -> Result<Option<(Txid, u64)>, SdkError> {
+        let note_client = Self::init_client(
+            self.config(),
+            note.secret_key.secret_bytes().to_vec(),
+            self.inner.chain_service.clone(),
+            Arc::new(InMemoryDb::default()),
+        )
+        .await?;
+        let amount = note_client.offchain_balance().await?.total();
+        if amount == Amount::ZERO {
+            return Ok(None);
+        }
+
+        let (ark_address, _) = self.inner.ark_client()?.get_offchain_address()?;
+        let psbt = note_client
+            .send_vtxo(ark_address, amount)
+            .await
+            .map_err(|e| SdkError::PaymentError(format!("Failed to claim note: {e}")))?;
+        let txid = psbt.extract_tx()?.compute_txid();
 
-        match &request.prepare_response.destination {
-            SendDestination::ArkAddress {
-                address,
-                receiver_amount_sat,
-            } => {
-                let ark_address = ArkAddress::decode(address)
-                    .map_err(|_| SdkError::AddressParsingError(address.clone()))?;
+        Ok(Some((txid, amount.to_sat())))
+    }
+
+    /// Lists the routes a payment to a destination can take, with their estimated fees and
+    /// timing
+    ///
+    /// Only routes the SDK can pay are listed, and only if the balance covers the amount and
+    /// fees. A BIP21 URI can list both an Ark and an onchain route, letting apps offer the
+    /// choice. The route [BreezSdk::prepare_send_payment] picks is marked as the default.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the destination and the amount to send
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RoutePaymentResponse)` - The feasible routes, cheapest first
+    /// * `Err(SdkError)` - If the destination isn't supported or the fees couldn't be estimated
+    pub async fn route_payment(
+        &self,
+        request: RoutePaymentRequest,
+    ) -> Result<RoutePaymentResponse, SdkError> {
+        self.on_runtime(async move {
+            let destination = request.destination.trim();
+            let service_fees = self.inner.fee_provider.service_fees().await?;
+            let offchain = |route| RouteOption {
+                route,
+                fees_sat: service_fees.offchain_fee_sat,
+                estimated_settlement_secs: OFFCHAIN_SETTLEMENT_SECS,
+                is_default: false,
+            };
+
+            let mut options = Vec::new();
+            let mut onchain_address = None;
+            if ArkAddress::decode(destination).is_ok() {
+                options.push(offchain(PaymentRoute::Ark));
+            } else if parse_invoice(destination).is_ok() {
+                // Lightning payments aren't supported yet, so invoices have no feasible route
+            } else if bip21::is_bip21(destination) {
+                let uri = bip21::parse_bip21(destination)?;
+                let payable_ark_address = uri
+                    .ark_address
+                    .and_then(|address| {
+                        qr::normalize_ark_address(&address, self.config().network).ok()
+                    })
+                    .and_then(|address| ArkAddress::decode(&address).ok())
+                    .is_some_and(|address| self.is_own_server_address(&address));
+                if payable_ark_address {
+                    options.push(offchain(PaymentRoute::Ark));
+                }
+                onchain_address = uri.address;
+            } else if Address::from_str(destination).is_ok() {
+                onchain_address = Some(destination.to_string());
+            } else {
+                return Err(SdkError::InvalidInput(format!(
+                    "Unsupported destination format: {destination}"
+                )));
+            }
 
-                let amount = Amount::from_sat(*receiver_amount_sat);
+            if let Some(address) = onchain_address {
+                Address::from_str(&address)?.require_network(self.config().network.into())?;
+                let chain_fee_sat = fees::chain_fee_sat(
+                    self.inner.fee_provider.as_ref(),
+                    fees::ONCHAIN_OUTPUT_VBYTES,
+                )
+                .await?;
+                options.push(RouteOption {
+                    route: PaymentRoute::Onchain,
+                    fees_sat: service_fees.offboard_fee_sat + chain_fee_sat,
+                    estimated_settlement_secs: u64::from(fees::DEFAULT_CONFIRMATION_TARGET)
+                        * BLOCK_INTERVAL_SECS,
+                    is_default: false,
+                });
+            }
 
-                // Use the Ark client to send the VTXO
-                let psbt = self
-                    .ark_client
-                    .send_vtxo(ark_address, amount)
-                    .await
-                    .map_err(|e| {
-                        SdkError::GenericError(format!("Failed to send payment: {}", e))
-                    })?;
-                let txid = psbt.extract_tx()?.compute_txid();
-                // Create a payment record
-                let timestamp = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs();
-
-                let payment = Payment {
-                    id: txid.to_string(),
-                    payment_type: PaymentType::Sent,
-                    status: PaymentStatus::Pending,
-                    amount: *receiver_amount_sat,
-                    fees: 0, // We don't have separate fees for Ark payments
-                    timestamp,
-                    description: None,
-                    destination: Some(address.clone()),
+            // Every route is paid from VTXOs, so the amount and fees must fit the offchain limits
+            let limits = self.get_limits(GetLimitsRequest {}).await?;
+            options.retain(|option| {
+                request
+                    .amount_sat
+                    .checked_add(option.fees_sat)
+                    .is_some_and(|total_sat| limits.offchain_send.check(total_sat).is_ok())
+            });
+            // Offchain routes come first, as does the route preferred when preparing the payment
+            if let Some(option) = options.first_mut() {
+                option.is_default = true;
+            }
+            options.sort_by_key(|option| option.fees_sat);
+            Ok(RoutePaymentResponse { options })
+        })
+        .await
+    }
+
+    /// Prepares a payment to a destination
+    ///
+    /// This method analyzes the destination string and prepares the appropriate payment type.
+    /// It supports Ark addresses and potentially other payment types in the future.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the destination and optional amount information
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PrepareSendResponse)` - Contains the parsed destination and estimated fees
+    /// * `Err(SdkError)` - If there was an error preparing the payment
+    pub async fn prepare_send_payment(
+        &self,
+        request: PrepareSendPaymentRequest,
+    ) -> Result<PrepareSendPaymentResponse, SdkError> {
+        self.on_runtime(async move {
+            #[cfg(feature = "demo")]
+            if let Some(demo) = &self.inner.demo {
+                return demo.prepare_send_payment(request).await;
+            }
+
+            let correlation_id = logger::new_correlation_id();
+            info!(
+                "[{correlation_id}] Preparing payment to destination: {}",
+                request.destination
+            );
+
+            match input_parser::parse_input(&request.destination, self.config().network).await? {
+                // sdk_common ignores the Ark address of BIP21 URIs, so they're parsed here
+                _ if bip21::is_bip21(&request.destination) => {
+                let mut uri = bip21::parse_bip21(&request.destination)?;
+                // Ark addresses scanned from upper case QR codes are lowercased, invalid ones
+                // are left out so the onchain address is paid
+                uri.ark_address = uri.ark_address.and_then(|address| {
+                    qr::normalize_ark_address(&address, self.config().network).ok()
+                });
+                let receiver_amount_sat =
+                    bip21::resolve_amount(uri.amount_sat, request.amount.as_ref())?;
+
+                // The Ark address is only paid when the receiver shares one of our Ark servers,
+                // otherwise the payment falls back to the onchain address
+                let payable_ark_address = uri
+                    .ark_address
+                    .as_deref()
+                    .and_then(|address| ArkAddress::decode(address).ok())
+                    .filter(|address| self.is_own_server_address(address));
+                let (fees_sat, route, onchain_quote) = match (payable_ark_address, &uri.address) {
+                    (Some(_), _) => {
+                        let service_fees = self.inner.fee_provider.service_fees().await?;
+                        (service_fees.offchain_fee_sat, PaymentRoute::Ark, None)
+                    }
+                    (None, Some(address)) => {
+                        let quote = self
+                            .prepare_send_onchain(PrepareSendOnchainRequest {
+                                onchain_address: address.clone(),
+                                receiver_amount_sats: receiver_amount_sat,
+                            })
+                            .await?;
+                        (quote.fee_sats, PaymentRoute::Onchain, Some(quote))
+                    }
+                    (None, None) => return Err(SdkError::PaymentError(
+                        "The URI's Ark address uses another Ark server and it has no onchain address"
+                            .to_string(),
+                    )),
+                };
+                info!("[{correlation_id}] Paying BIP21 URI with route {route:?}");
+
+                Ok(PrepareSendPaymentResponse {
+                    destination: SendDestination::Bip21 {
+                        uri: request.destination.trim().to_string(),
+                        onchain_address: uri.address,
+                        ark_address: uri.ark_address,
+                        receiver_amount_sat,
+                        onchain_quote,
+                    },
+                    fees_sat: Some(fees_sat),
+                    route,
+                    correlation_id,
+                })
+                }
+                InputType::ArkAddress { address } => {
+                let service_fees = self.inner.fee_provider.service_fees().await?;
+
+                // Get the amount to send
+                let receiver_amount_sat = match request.amount {
+                    Some(PayAmount::Specific {
+                        receiver_amount_sat,
+                    }) => receiver_amount_sat,
+                    Some(PayAmount::Drain) => {
+                        // Use all spendable funds held with the server handling the destination.
+                        // Reservations aren't tracked per server, so all of them are deducted
+                        let (server_url, _) = self.route_ark_address(&address)?;
+                        let balance_response = self.get_balance(GetBalanceRequest {}).await?;
+                        let spendable_sats = balance_response
+                            .server_balances
+                            .iter()
+                            .find(|b| b.ark_server_url == server_url)
+                            .map_or(balance_response.spendable_sats, |b| {
+                                b.balance
+                                    .confirmed_sats
+                                    .saturating_sub(balance_response.reserved_sats)
+                            });
+                        spendable_sats.saturating_sub(service_fees.offchain_fee_sat)
+                    }
+                    None => {
+                        return Err(SdkError::GenericError(
+                            "Amount is required for Ark address payments".to_string(),
+                        ))
+                    }
                 };
 
-                // Save the payment to storage
-                self.sync_wallet_internal().await?;
+                let fees_sat = Some(service_fees.offchain_fee_sat);
+
+                Ok(PrepareSendPaymentResponse {
+                    destination: SendDestination::ArkAddress {
+                        address,
+                        receiver_amount_sat,
+                    },
+                    fees_sat,
+                    route: PaymentRoute::Ark,
+                    correlation_id,
+                })
+                }
+                InputType::Bolt11 { invoice } => {
+                let receiver_amount_sat =
+                    lightning::resolve_invoice_amount(invoice.amount_msat, request.amount.as_ref())?;
 
-                Ok(SendPaymentResponse { payment })
+                // Reserve the routing margin, as the actual routing fee is only known once paid
+                let service_fees = self.inner.fee_provider.service_fees().await?;
+                let fees_sat = (receiver_amount_sat
+                    * u64::from(service_fees.ln_routing_margin_ppm))
+                .div_ceil(1_000_000);
+
+                Ok(PrepareSendPaymentResponse {
+                    destination: SendDestination::Bolt11 {
+                        invoice,
+                        receiver_amount_sat,
+                        bip353_address: None,
+                    },
+                    fees_sat: Some(fees_sat),
+                    route: PaymentRoute::Lightning,
+                    correlation_id,
+                })
+                }
+                    InputType::LnUrlPay { data, .. } => {
+                        let Some(PayAmount::Specific {
+                            receiver_amount_sat,
+                        }) = request.amount
+                        else {
+                            return Err(SdkError::AmountRequired(
+                                "A specific amount is required for LNURL-pay".to_string(),
+                            ));
+                        };
+                        info!(
+                            "[{correlation_id}] Fetching invoice from LNURL service {}",
+                            data.domain
+                        );
+                        // Boxed, as preparing the fetched invoice recurses into this method
+                        let response = Box::pin(self.prepare_lnurl_pay(PrepareLnUrlPayRequest {
+                            data,
+                            amount_sat: receiver_amount_sat,
+                            comment: None,
+                            validate_success_action_url: None,
+                        }))
+                        .await?;
+                        Ok(response.prepare_response)
+                    }
+                    // Could add support for other destination types here (BOLT12, etc.)
+                    _ => Err(SdkError::GenericError(format!(
+                        "Unsupported destination format: {}",
+                        request.destination
+                    ))),
             }
-            SendDestination::Bolt11 { invoice, .. } => {
-                return Err(SdkError::GenericError(
+        })
+        .await
+    }
+
+    /// Prepares to pay an LNURL-pay request or lightning address
+    ///
+    /// The LNURL service is asked for an invoice of the given amount, which is then prepared like
+    /// any other invoice. The request and the service's answer are validated by sdk_common, as
+    /// in the other Breez SDKs. Until BOLT11 invoices can be paid this fails before the service is
+    /// contacted.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the request data returned by [BreezSdk::parse], the amount and an
+    ///   optional comment
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PrepareLnUrlPayResponse)` - Contains the prepared payment and the success action
+    /// * `Err(SdkError)` - If the amount is out of the request's bounds or the service failed
+    pub async fn prepare_lnurl_pay(
+        &self,
+        request: PrepareLnUrlPayRequest,
+    ) -> Result<PrepareLnUrlPayResponse, SdkError> {
+        self.on_runtime(async move {
+            // The service is only asked for an invoice once the invoice can be paid
+            lightning::ensure_sends_supported()?;
+            let amount_msat = request.amount_sat.checked_mul(1_000).ok_or_else(|| {
+                SdkError::InvalidInput(format!(
+                    "Amount of {} sats is too large",
+                    request.amount_sat
+                ))
+            })?;
+
+            let rest_client =
+                ReqwestRestClient::new().map_err(|e| SdkError::NetworkError(e.to_string()))?;
+            let callback = validate_lnurl_pay(
+                &rest_client,
+                amount_msat,
+                &request.comment,
+                &request.data,
+                self.config().network.into(),
+                request.validate_success_action_url,
+            )
+            .await
+            .map_err(|e| SdkError::PaymentError(e.to_string()))?;
+            let data = match callback {
+                ValidatedCallbackResponse::EndpointSuccess { data } => data,
+                ValidatedCallbackResponse::EndpointError { data } => {
+                    return Err(SdkError::PaymentError(data.reason))
+                }
+            };
+
+            let prepare_response = self
+                .prepare_send_payment(PrepareSendPaymentRequest {
+                    destination: data.pr,
+                    amount: None,
+                })
+                .await?;
+            Ok(PrepareLnUrlPayResponse {
+                prepare_response,
+                success_action: data.success_action,
+            })
+        })
+        .await
+    }
+
+    /// Sends a payment based on a previously prepared payment request
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the prepared payment information from prepare_send_payment
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(SendPaymentResponse)` - Contains the payment details if successful
+    /// * `Err(SdkError)` - If there was an error sending the payment
+    pub async fn send_payment(
+        &self,
+        request: SendPaymentRequest,
+    ) -> Result<SendPaymentResponse, SdkError> {
+        self.on_runtime(async move {
+            #[cfg(feature = "demo")]
+            if let Some(demo) = &self.inner.demo {
+                return demo.send_payment(request).await;
+            }
+
+            let correlation_id = &request.prepare_response.correlation_id;
+            info!(
+                "[{correlation_id}] Sending payment with prepared response: {:?}",
+                request.prepare_response
+            );
+
+            match &request.prepare_response.destination {
+                SendDestination::ArkAddress {
+                    address,
+                    receiver_amount_sat,
+                } => {
+                    let fees_sat = request.prepare_response.fees_sat.unwrap_or_default();
+                    let payment = self
+                        .send_offchain(address, *receiver_amount_sat, fees_sat, correlation_id)
+                        .await?;
+
+                    Ok(SendPaymentResponse { payment })
+                }
+                SendDestination::Bolt11 { .. } => Err(SdkError::GenericError(
                     "BOLT11 payments are not yet implemented".to_string(),
-                ));
+                )),
+                SendDestination::Bip21 {
+                    ark_address,
+                    receiver_amount_sat,
+                    onchain_quote,
+                    ..
+                } => {
+                    let fees_sat = request.prepare_response.fees_sat.unwrap_or_default();
+                    let payment =
+                        match (&request.prepare_response.route, ark_address, onchain_quote) {
+                            (PaymentRoute::Ark, Some(address), _) => {
+                                self.send_offchain(
+                                    address,
+                                    *receiver_amount_sat,
+                                    fees_sat,
+                                    correlation_id,
+                                )
+                                .await?
+                            }
+                            (PaymentRoute::Onchain, _, Some(quote)) => {
+                                let response = self
+                                    .send_onchain(SendOnchainRequest {
+                                        prepare_send_onchain_response: quote.clone(),
+                                    })
+                                    .await?;
+                                let payment = Payment {
+                                    id: response.tx_id.clone(),
+                                    payment_type: PaymentType::Sent,
+                                    status: PaymentStatus::Pending,
+                                    amount: quote.receiver_amount_sats,
+                                    fees: quote.fee_sats,
+                                    timestamp: self.now(),
+                                    description: None,
+                                    destination: Some(quote.onchain_address.clone()),
+                                    is_retryable: false,
+                                    details: Some(PaymentDetails::Onchain {
+                                        txid: response.tx_id.clone(),
+                                        confirmed_at: None,
+                                    }),
+                                    onchain_txs: vec![OnchainTx {
+                                        txid: response.tx_id,
+                                        kind: OnchainTxKind::Exit,
+                                    }],
+                                    order_id: None,
+                                    deposit_source: None,
+                                    failure_reason: None,
+                                };
+                                // Record the address paid, which the synced round doesn't report
+                                self.inner.storage.save_payment(&payment).await?;
+                                payment
+                            }
+                            _ => {
+                                return Err(SdkError::InvalidInput(
+                                    "The prepared route doesn't match the BIP21 destination"
+                                        .to_string(),
+                                ))
+                            }
+                        };
+
+                    Ok(SendPaymentResponse { payment })
+                }
+                SendDestination::Bolt12 { offer, .. } => {
+                    return Err(SdkError::GenericError(
+                        "BOLT12 payments are not yet implemented".to_string(),
+                    ));
+                }
             }
-            SendDestination::Bolt12 { offer, .. } => {
-                return Err(SdkError::GenericError(
-                    "BOLT12 payments are not yet implemented".to_string(),
+        })
+        .await
+    }
+
+    /// Fabricates an offchain payment received from someone else in a wallet built with
+    /// [SdkBuilder::demo], pending settlement until the next [BreezSdk::sync_wallet]
+    ///
+    /// # Arguments
+    ///
+    /// * `amount_sat` - The amount received
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Payment)` - The received payment
+    /// * `Err(SdkError)` - If the wallet isn't a demo wallet
+    #[cfg(feature = "demo")]
+    pub async fn simulate_incoming_payment(&self, amount_sat: u64) -> Result<Payment, SdkError> {
+        self.on_runtime(async move {
+            let Some(demo) = &self.inner.demo else {
+                return Err(SdkError::InvalidInput(
+                    "Only demo wallets can simulate incoming payments".to_string(),
                 ));
+            };
+            demo.simulate_incoming_payment(amount_sat).await
+        })
+        .await
+    }
+
+    /// Links to an onchain transaction on the configured explorer, e.g. one of a payment's
+    /// [Payment::onchain_txs]
+    ///
+    /// # Arguments
+    ///
+    /// * `txid` - The transaction ID
+    ///
+    /// # Returns
+    ///
+    /// The URL of the transaction on the [Config::explorer_url], or on the public mempool.space
+    /// instance of the network. `None` on regtest without a configured explorer
+    pub fn explorer_tx_url(&self, txid: &str) -> Option<String> {
+        let config = self.config();
+        explorer::tx_url(config.explorer_url.as_deref(), &config.network, txid)
+    }
+
+    /// Retries a send that failed midway
+    ///
+    /// A send whose last attempt may have reached the Ark server is first reconciled with the
+    /// wallet's VTXOs and history. It's only sent again once it's known not to have gone
+    /// through, and the payment it went through as is returned otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The ID of a payment listed with `is_retryable` set
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RetryPaymentResponse)` - The sent payment
+    /// * `Err(SdkError)` - If no retryable payment has this ID, the last attempt may still have
+    ///   reached the server, or the send failed again
+    pub async fn retry_payment(
+        &self,
+        request: RetryPaymentRequest,
+    ) -> Result<RetryPaymentResponse, SdkError> {
+        self.on_runtime(async move {
+            self.inner.state.ensure_sends_allowed()?;
+
+            let send = self
+                .inner
+                .storage
+                .list_pending_sends()
+                .await?
+                .into_iter()
+                .find(|send| send.id == request.id)
+                .ok_or_else(|| {
+                    SdkError::InvalidInput(format!("No retryable payment with ID {}", request.id))
+                })?;
+            info!("[{}] Retrying payment {}", send.correlation_id, request.id);
+            let payment = self.execute_pending_send(send).await?;
+
+            Ok(RetryPaymentResponse { payment })
+        })
+        .await
+    }
+
+    /// Cancels a send that didn't reach the Ark server, e.g. one that failed midway
+    ///
+    /// The send is dropped instead of being resumed or retried, its reserved funds are released
+    /// and it's listed as a [PaymentStatus::Cancelled] payment. Emits
+    /// [SdkEvent::PaymentCancelled].
+    ///
+    /// Only sends that were never attempted or whose last attempt is known not to have reached
+    /// the server can be cancelled. Others are first reconciled with the wallet's VTXOs and
+    /// history, see [SendSubmission].
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The ID of a pending or retryable payment
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(CancelPaymentResponse)` - The cancelled payment
+    /// * `Err(SdkError)` - If no pending send has this ID, it is being sent right now, or it may
+    ///   have reached the Ark server
+    pub async fn cancel_payment(
+        &self,
+        request: CancelPaymentRequest,
+    ) -> Result<CancelPaymentResponse, SdkError> {
+        self.on_runtime(async move {
+            let _claim = self.inner.state.claim_send(&request.id).ok_or_else(|| {
+                SdkError::PaymentError(format!(
+                    "Payment {} is being sent and can't be cancelled",
+                    request.id
+                ))
+            })?;
+            let send = self
+                .inner
+                .storage
+                .list_pending_sends()
+                .await?
+                .into_iter()
+                .find(|send| send.id == request.id)
+                .ok_or_else(|| {
+                    SdkError::InvalidInput(format!("No cancellable payment with ID {}", request.id))
+                })?;
+            let send = match self.reconcile_send(send).await? {
+                ReconciledSend::Pending(send)
+                    if send.submission == SendSubmission::NotSubmitted =>
+                {
+                    send
+                }
+                ReconciledSend::Pending(send) => {
+                    return Err(SdkError::PaymentError(format!(
+                        "Payment {} may have reached the Ark server and can't be cancelled",
+                        send.id
+                    )))
+                }
+                ReconciledSend::Sent(payment) => {
+                    return Err(SdkError::PaymentError(format!(
+                        "Payment {} was already sent in Ark transaction {}",
+                        request.id, payment.id
+                    )))
+                }
+            };
+
+            self.inner.storage.delete_pending_send(&send.id).await?;
+            self.release_reserved_funds(&send.id).await;
+            let payment = Payment {
+                id: send.id,
+                payment_type: PaymentType::Sent,
+                status: PaymentStatus::Cancelled,
+                amount: send.amount_sat,
+                fees: send.fees_sat,
+                timestamp: send.created_at,
+                description: None,
+                destination: Some(send.destination),
+                is_retryable: false,
+                details: None,
+                onchain_txs: Vec::new(),
+                order_id: None,
+                deposit_source: None,
+                failure_reason: Some(PaymentFailureReason::new(
+                    PaymentFailureKind::Cancelled,
+                    "Cancelled before reaching the Ark server",
+                )),
+            };
+            self.inner.storage.save_payment(&payment).await?;
+            info!("[{}] Cancelled payment {}", send.correlation_id, payment.id);
+            self.inner.event_emitter.emit(&SdkEvent::PaymentCancelled {
+                payment: payment.clone(),
+            });
+
+            Ok(CancelPaymentResponse { payment })
+        })
+        .await
+    }
+
+    /// Resumes the sends that were interrupted or failed, e.g. because the app was closed
+    ///
+    /// Sends whose last attempt may have reached the Ark server are reconciled with the wallet's
+    /// VTXOs and history, see [SendSubmission]. Those that went through are recorded as
+    /// payments, the others are attempted again up to three times in total. Sends that keep
+    /// failing are left for the user to retry with [BreezSdk::retry_payment].
+    ///
+    /// This runs automatically once the wallet has been synced after [BreezSdk::start].
+    pub async fn resume_pending_sends(&self) -> Result<(), SdkError> {
+        self.on_runtime(async move {
+            let pending_sends = self.inner.storage.list_pending_sends().await?;
+            if pending_sends.is_empty() {
+                return Ok(());
+            }
+
+            for send in pending_sends {
+                let correlation_id = send.correlation_id.clone();
+                let Some(claim) = self.inner.state.claim_send(&send.id) else {
+                    continue;
+                };
+                // The server may have accepted the send even though the SDK never got the result
+                let send = match self.reconcile_send(send).await {
+                    Ok(ReconciledSend::Pending(send)) => send,
+                    Ok(ReconciledSend::Sent(_)) => continue,
+                    Err(e) => {
+                        error!("[{correlation_id}] Failed to reconcile pending send: {e:?}");
+                        continue;
+                    }
+                };
+                drop(claim);
+
+                if send.submission == SendSubmission::Unknown
+                    || send.attempts >= MAX_AUTOMATIC_SEND_ATTEMPTS
+                {
+                    continue;
+                }
+                info!(
+                    "[{}] Resuming pending send {}",
+                    send.correlation_id, send.id
+                );
+                if let Err(e) = self.execute_pending_send(send).await {
+                    error!("[{correlation_id}] Failed to resume pending send: {e:?}");
+                }
+            }
+
+            Ok(())
+        })
+        .await
+    }
+
+    /// Sends to an Ark address, persisting the send so it can be resumed if it fails midway
+    async fn send_offchain(
+        &self,
+        address: &str,
+        amount_sat: u64,
+        fees_sat: u64,
+        correlation_id: &str,
+    ) -> Result<Payment, SdkError> {
+        ArkAddress::decode(address)
+            .map_err(|_| SdkError::AddressParsingError(address.to_string()))?;
+        self.inner.state.ensure_sends_allowed()?;
+
+        let send = PendingSend {
+            id: uuid::Uuid::new_v4().to_string(),
+            destination: address.to_string(),
+            amount_sat,
+            fees_sat,
+            created_at: self.now(),
+            attempts: 0,
+            last_error: None,
+            failure_reason: None,
+            correlation_id: correlation_id.to_string(),
+            submission: SendSubmission::NotSubmitted,
+            attempt_inputs: Vec::new(),
+            attempt_started_at: None,
+        };
+        self.execute_pending_send(send).await
+    }
+
+    /// Sends a persisted send to its Ark address, keeping it persisted until it succeeds
+    ///
+    /// The funds are reserved until the send completed and the balance was synced, so
+    /// concurrent sends can't spend them too.
+    async fn execute_pending_send(&self, send: PendingSend) -> Result<Payment, SdkError> {
+        let _claim = self.inner.state.claim_send(&send.id).ok_or_else(|| {
+            SdkError::PaymentError(format!(
+                "Payment {} is already being sent or cancelled",
+                send.id
+            ))
+        })?;
+        // The send may have been cancelled or attempted since it was loaded
+        let Some(send) = self
+            .inner
+            .storage
+            .list_pending_sends()
+            .await?
+            .into_iter()
+            .find(|pending| pending.id == send.id)
+        else {
+            return Err(SdkError::PaymentError(format!(
+                "Payment {} was cancelled",
+                send.id
+            )));
+        };
+        let send = match self.reconcile_send(send).await? {
+            ReconciledSend::Pending(send) => send,
+            ReconciledSend::Sent(payment) => return Ok(payment),
+        };
+        if send.submission == SendSubmission::Unknown {
+            return Err(SdkError::PaymentError(format!(
+                "Payment {} may have reached the Ark server, it can't be sent again until it's \
+                 known not to have gone through",
+                send.id
+            )));
+        }
+
+        let (_, ark_client) = self.route_ark_address(&send.destination)?;
+        let total_sat = sends::total_sat(send.amount_sat, send.fees_sat)?;
+        self.reserve_funds(&send.id, total_sat, ark_client).await?;
+        self.emit_send_progress(&send.id, SendStep::FundsReserved);
+        let result = self.send_reserved(send.clone()).await;
+        self.release_reserved_funds(&send.id).await;
+        self.record_send_failure(&result);
+        result
+    }
+
+    /// Releases the funds reserved for a send once its outcome is known
+    ///
+    /// A failure is only logged, so it doesn't hide the outcome of the send. The reservation
+    /// left behind is released on the next startup.
+    async fn release_reserved_funds(&self, id: &str) {
+        if let Err(e) = self.inner.storage.release_funds(id).await {
+            error!("Failed to release the funds reserved for send {id}: {e}");
+        }
+    }
+
+    /// Reports a failed send to the telemetry listener
+    fn record_send_failure<T>(&self, result: &Result<T, SdkError>) {
+        if let Err(e) = result {
+            self.inner
+                .telemetry
+                .record(TelemetryMetric::SendFailed { category: e.into() });
+        }
+    }
+
+    /// Reserves funds for a send, failing if the spendable balance not reserved yet is too low
+    async fn reserve_funds(
+        &self,
+        id: &str,
+        amount_sat: u64,
+        client: &ArkClient,
+    ) -> Result<(), SdkError> {
+        let available_sat = Self::fetch_offchain_balance(client).await?.confirmed_sats;
+        let reserved = self
+            .inner
+            .storage
+            .reserve_funds(id, amount_sat, available_sat)
+            .await?;
+        if !reserved {
+            let reserved_sat = self.inner.storage.get_reserved_sats().await?;
+            return Err(SdkError::PaymentError(format!(
+                "Insufficient funds: the send needs {amount_sat} sats, {} sats are spendable",
+                available_sat.saturating_sub(reserved_sat)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reconciles a send whose last attempt may have reached the Ark server, see
+    /// [sends::reconcile]
+    ///
+    /// A send that went through is replaced by its payment. One that never reached the server
+    /// is marked [SendSubmission::NotSubmitted], so it can be retried or cancelled.
+    async fn reconcile_send(&self, mut send: PendingSend) -> Result<ReconciledSend, SdkError> {
+        if send.submission == SendSubmission::NotSubmitted {
+            return Ok(ReconciledSend::Pending(send));
+        }
+
+        let (_, ark_client) = self.route_ark_address(&send.destination)?;
+        // Nothing else may spend the VTXOs while they're checked
+        let _send_guard = self.inner.state.begin_send().await;
+        let spendable = Self::spendable_outpoints(ark_client).await?;
+        let history = ark_client.transaction_history().await?;
+        // Sent payments with a destination were recorded by their own send
+        let recorded_txids = self
+            .inner
+            .storage
+            .list_payments(0, u32::MAX)
+            .await?
+            .into_iter()
+            .filter(|payment| {
+                payment.payment_type == PaymentType::Sent && payment.destination.is_some()
+            })
+            .map(|payment| payment.id)
+            .collect();
+
+        match sends::reconcile(&send, &spendable, &history, &recorded_txids) {
+            Reconciliation::NotSubmitted => {
+                info!(
+                    "[{}] Send {} never reached the Ark server",
+                    send.correlation_id, send.id
+                );
+                send.submission = SendSubmission::NotSubmitted;
+                self.inner.storage.save_pending_send(&send).await?;
+                Ok(ReconciledSend::Pending(send))
+            }
+            Reconciliation::Sent(txid) => {
+                info!(
+                    "[{}] Send {} went through in Ark transaction {txid}",
+                    send.correlation_id, send.id
+                );
+                let timestamp = send.attempt_started_at.unwrap_or(send.created_at);
+                let payment = sends::sent_payment(&send, txid, timestamp);
+                self.inner.storage.save_payment(&payment).await?;
+                self.inner.storage.delete_pending_send(&send.id).await?;
+                self.release_reserved_funds(&send.id).await;
+                self.inner.event_emitter.emit(&SdkEvent::PaymentPending {
+                    payment: payment.clone(),
+                    correlation_id: send.correlation_id.clone(),
+                    send_id: Some(send.id.clone()),
+                });
+                Ok(ReconciledSend::Sent(payment))
+            }
+            Reconciliation::Unknown => Ok(ReconciledSend::Pending(send)),
+        }
+    }
+
+    /// Outpoints of the VTXOs the given client can spend
+    async fn spendable_outpoints(client: &ArkClient) -> Result<HashSet<String>, SdkError> {
+        Ok(client
+            .spendable_vtxos()
+            .await?
+            .into_iter()
+            .flat_map(|(outpoints, _)| outpoints)
+            .map(|vtxo| vtxo.outpoint.to_string())
+            .collect())
+    }
+
+    /// Sends a pending send whose funds were reserved
+    ///
+    /// The VTXOs the attempt can spend are persisted before it starts, so if its outcome is
+    /// unknown it can be reconciled with them, see [BreezSdk::reconcile_send].
+    async fn send_reserved(&self, mut send: PendingSend) -> Result<Payment, SdkError> {
+        let ark_address = ArkAddress::decode(&send.destination)
+            .map_err(|_| SdkError::AddressParsingError(send.destination.clone()))?;
+        let amount = Amount::from_sat(send.amount_sat);
+
+        let (_, ark_client) = self.route_ark_address(&send.destination)?;
+        let send_guard = self.inner.state.begin_send().await;
+        send.attempt_inputs = Self::spendable_outpoints(ark_client)
+            .await?
+            .into_iter()
+            .collect();
+        send.attempts += 1;
+        send.submission = SendSubmission::Unknown;
+        send.attempt_started_at = Some(self.now());
+        self.inner.storage.save_pending_send(&send).await?;
+        info!(
+            "[{}] Sending {} sats to {}, attempt {}",
+            send.correlation_id, send.amount_sat, send.destination, send.attempts
+        );
+
+        // Use the Ark client to send the VTXO
+        self.inner.event_emitter.emit(&SdkEvent::PaymentSending {
+            destination: send.destination.clone(),
+            amount_sat: send.amount_sat,
+            correlation_id: send.correlation_id.clone(),
+            send_id: send.id.clone(),
+        });
+        let result = ark_client.send_vtxo(ark_address, amount).await;
+        if result.is_err() {
+            // The call may have failed after the server accepted the send, which only shows in
+            // the VTXOs it spent
+            send.submission = match Self::spendable_outpoints(ark_client).await {
+                Ok(spendable) => sends::submission_after_failure(&send.attempt_inputs, &spendable),
+                Err(_) => SendSubmission::Unknown,
+            };
+        }
+        drop(send_guard);
+        if result.is_ok() {
+            self.emit_send_progress(&send.id, SendStep::AcceptedByServer);
+        }
+
+        let psbt = match result {
+            Ok(psbt) => psbt,
+            Err(e) => {
+                error!("[{}] Send failed: {e}", send.correlation_id);
+                let message = e.to_string();
+                send.last_error = Some(message.clone());
+                send.failure_reason = Some(PaymentFailureReason::from(&SdkError::from(e)));
+                self.inner.storage.save_pending_send(&send).await?;
+                if send.submission == SendSubmission::Unknown {
+                    return Err(SdkError::PaymentError(format!(
+                        "The payment may have been sent, it's reconciled on the next sync: \
+                         {message}"
+                    )));
+                }
+                return Err(SdkError::GenericError(format!(
+                    "Failed to send payment: {message}"
+                )));
             }
+        };
+        let txid = psbt.extract_tx()?.compute_txid();
+        info!(
+            "[{}] Send accepted in Ark transaction {txid}",
+            send.correlation_id
+        );
+        let payment = sends::sent_payment(&send, txid.to_string(), self.now());
+        self.inner.event_emitter.emit(&SdkEvent::PaymentPending {
+            payment: payment.clone(),
+            correlation_id: send.correlation_id.clone(),
+            send_id: Some(send.id.clone()),
+        });
+
+        // The server accepted the send, so failing to record it locally is only logged. A
+        // payment that wasn't saved is added by a later sync, without its destination.
+        let _sync_guard = self.inner.state.begin_sync().await;
+        if let Err(e) = self.inner.storage.save_payment(&payment).await {
+            error!(
+                "[{}] Failed to save payment {txid}: {e}",
+                send.correlation_id
+            );
+        }
+        if let Err(e) = self.inner.storage.delete_pending_send(&send.id).await {
+            error!(
+                "[{}] Failed to delete pending send {}: {e}",
+                send.correlation_id, send.id
+            );
+        }
+        if let Err(e) = self.sync_wallet_internal().await {
+            warn!(
+                "[{}] Failed to sync after the send, it's synced later: {e}",
+                send.correlation_id
+            );
+        }
+
+        Ok(payment)
+    }
+
+    /// Returns the latest entries logged by the SDK
+    ///
+    /// Entries are kept in memory, so apps can attach them to support requests without access
+    /// to `sdk.log`. They are only recorded when logging was set up with [BreezSdk::init_logging].
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Contains the maximum number of entries to return
+    ///
+    /// # Returns
+    ///
+    /// The entries, oldest first
+    pub fn get_recent_logs(&self, request: GetRecentLogsRequest) -> GetRecentLogsResponse {
+        GetRecentLogsResponse {
+            logs: logger::recent_logs(request.limit as usize),
         }
     }
 
@@ -573,6 +4057,9 @@ impl BreezSdk {
     /// It must be called only once in the application lifecycle. Alternatively, If the application
     /// already uses a globally-registered logger, this method shouldn't be called at all.
     ///
+    /// Log lines are filtered with the default [LoggingConfig], see
+    /// [BreezSdk::init_logging_with_config] to change it.
+    ///
     /// ### Arguments
     ///
     /// - `log_dir`: Location where the SDK log file will be created. The directory must already exist.
@@ -592,8 +4079,35 @@ impl BreezSdk {
     pub fn init_logging(
         log_dir: &str,
         app_logger: Option<Box<dyn log::Log>>,
+    ) -> anyhow::Result<()> {
+        Self::init_logging_with_config(log_dir, app_logger, LoggingConfig::default())
+    }
+
+    /// Configures the global SDK logger like [BreezSdk::init_logging], choosing which log lines
+    /// are recorded
+    ///
+    /// By default the SDK's own lines are logged at debug level and those of dependencies at
+    /// warn level, keeping HTTP internals out of `sdk.log`. Wire-level traces can be enabled
+    /// separately, they are written to `wire.log` in the same directory.
+    ///
+    /// ### Arguments
+    ///
+    /// - `log_dir`: Location where the SDK log files will be created.
+    ///
+    /// - `app_logger`: Optional application logger, receiving the lines that pass the filter.
+    ///
+    /// - `config`: The levels per log target and whether to write wire-level traces.
+    ///
+    /// ### Errors
+    ///
+    /// An error is thrown if the log files cannot be created, or if a global logger is already
+    /// configured.
+    pub fn init_logging_with_config(
+        log_dir: &str,
+        app_logger: Option<Box<dyn log::Log>>,
+        config: LoggingConfig,
     ) -> anyhow::Result<()> {
         // Initialize the logger using the logger module
-        crate::logger::SdkLogger::init(log_dir, app_logger)
+        crate::logger::SdkLogger::init(log_dir, app_logger, &config)
     }
 }
@@ -0,0 +1,123 @@
+use crate::error::SdkError;
+use crate::models::PayAmount;
+
+/// Smallest HTLC accepted by default by Lightning nodes, in satoshis
+pub(crate) const MIN_HTLC_SAT: u64 = 1;
+/// Largest HTLC allowed on channels without large channel support, in satoshis
+pub(crate) const MAX_HTLC_SAT: u64 = 4_294_967;
+
+/// Fails as long as BOLT11 invoices can't be paid
+///
+/// Checked before e.g. an LNURL service is asked for an invoice, so it isn't contacted for an
+/// invoice that is then rejected by [crate::BreezSdk::send_payment].
+pub(crate) fn ensure_sends_supported() -> Result<(), SdkError> {
+    Err(SdkError::GenericError(
+        "BOLT11 payments are not yet implemented".to_string(),
+    ))
+}
+
+/// Resolves the amount to pay for an invoice
+///
+/// Invoices without an amount require the caller to specify one, while invoices with an amount
+/// reject any other amount, so a payment can never send a different amount than was intended.
+pub(crate) fn resolve_invoice_amount(
+    invoice_amount_msat: Option<u64>,
+    amount: Option<&PayAmount>,
+) -> Result<u64, SdkError> {
+    let amount_sat = match (invoice_amount_msat, amount) {
+        (Some(amount_msat), None) => amount_msat.div_ceil(1_000),
+        (
+            Some(amount_msat),
+            Some(PayAmount::Specific {
+                receiver_amount_sat,
+            }),
+        ) if *receiver_amount_sat == amount_msat.div_ceil(1_000) => *receiver_amount_sat,
+        (Some(amount_msat), Some(_)) => {
+            return Err(SdkError::AmountMismatch(format!(
+                "The invoice requests {} sats, a different amount can't be paid",
+                amount_msat.div_ceil(1_000)
+            )))
+        }
+        (
+            None,
+            Some(PayAmount::Specific {
+                receiver_amount_sat,
+            }),
+        ) => *receiver_amount_sat,
+        (None, Some(PayAmount::Drain)) => {
+            return Err(SdkError::AmountRequired(
+                "Zero-amount invoices can't be drained to, specify the amount to pay".to_string(),
+            ))
+        }
+        (None, None) => {
+            return Err(SdkError::AmountRequired(
+                "The invoice has no amount, specify the amount to pay".to_string(),
+            ))
+        }
+    };
+
+    if !(MIN_HTLC_SAT..=MAX_HTLC_SAT).contains(&amount_sat) {
+        return Err(SdkError::AmountOutOfRange {
+            amount_sat,
+            min_sat: MIN_HTLC_SAT,
+            max_sat: MAX_HTLC_SAT,
+        });
+    }
+    Ok(amount_sat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn specific(receiver_amount_sat: u64) -> PayAmount {
+        PayAmount::Specific {
+            receiver_amount_sat,
+        }
+    }
+
+    #[test]
+    fn test_resolve_invoice_amount() {
+        // Invoices with an amount pay exactly that amount
+        assert_eq!(
+            5_000,
+            resolve_invoice_amount(Some(5_000_000), None).unwrap()
+        );
+        assert_eq!(
+            5_000,
+            resolve_invoice_amount(Some(5_000_000), Some(&specific(5_000))).unwrap()
+        );
+        assert!(matches!(
+            resolve_invoice_amount(Some(5_000_000), Some(&specific(6_000))),
+            Err(SdkError::AmountMismatch(_))
+        ));
+        assert!(matches!(
+            resolve_invoice_amount(Some(5_000_000), Some(&PayAmount::Drain)),
+            Err(SdkError::AmountMismatch(_))
+        ));
+
+        // Zero-amount invoices need a specific amount
+        assert_eq!(
+            2_000,
+            resolve_invoice_amount(None, Some(&specific(2_000))).unwrap()
+        );
+        assert!(matches!(
+            resolve_invoice_amount(None, None),
+            Err(SdkError::AmountRequired(_))
+        ));
+        assert!(matches!(
+            resolve_invoice_amount(None, Some(&PayAmount::Drain)),
+            Err(SdkError::AmountRequired(_))
+        ));
+
+        // Amounts must fit in an HTLC
+        assert!(matches!(
+            resolve_invoice_amount(None, Some(&specific(0))),
+            Err(SdkError::AmountOutOfRange { .. })
+        ));
+        assert!(matches!(
+            resolve_invoice_amount(None, Some(&specific(MAX_HTLC_SAT + 1))),
+            Err(SdkError::AmountOutOfRange { .. })
+        ));
+    }
+}
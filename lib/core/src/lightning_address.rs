@@ -0,0 +1,159 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use bitcoin::bip32::{DerivationPath, Xpriv};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{Keypair, Message, Secp256k1};
+use bitcoin::Network;
+use serde::{Deserialize, Serialize};
+
+use crate::error::SdkError;
+
+/// Longest username accepted in a lightning address
+const MAX_USERNAME_LEN: usize = 64;
+/// How long to wait for the LNURL server to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Path of the key identifying the wallet to the LNURL server, under the LNURL purpose of
+/// LUD-05 at an index it doesn't use
+const KEY_PATH: &str = "m/138'/1'";
+
+/// Registration of a lightning address with the LNURL server
+#[derive(Serialize)]
+struct RegisterRequest<'a> {
+    username: &'a str,
+    ark_address: &'a str,
+    time: u64,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct RegisterResponse {
+    lightning_address: String,
+}
+
+/// Checks a username only uses the characters LUD-16 allows in lightning addresses
+pub(crate) fn validate_username(username: &str) -> Result<(), SdkError> {
+    let valid = !username.is_empty()
+        && username.len() <= MAX_USERNAME_LEN
+        && username
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-_.".contains(c));
+    if !valid {
+        return Err(SdkError::InvalidInput(format!(
+            "Invalid username {username}, use up to {MAX_USERNAME_LEN} lowercase letters, digits, '-', '_' and '.'"
+        )));
+    }
+    Ok(())
+}
+
+/// Derives the key registrations are signed with from the wallet seed
+///
+/// The key is kept apart from the key of the wallet's VTXOs, so the LNURL server never sees a
+/// signature by a key that controls funds.
+pub(crate) fn derive_keypair(seed: &[u8], network: Network) -> Result<Keypair, SdkError> {
+    let secp = Secp256k1::new();
+    let path = DerivationPath::from_str(KEY_PATH).expect("valid derivation path");
+    let key = Xpriv::new_master(network, seed)
+        .and_then(|master| master.derive_priv(&secp, &path))
+        .map_err(|e| SdkError::GenericError(format!("Failed to derive LNURL key: {e}")))?;
+    Ok(key.to_keypair(&secp))
+}
+
+/// Signs a registration, proving the wallet key owns the registered username
+fn sign_registration(keypair: &Keypair, username: &str, ark_address: &str, time: u64) -> String {
+    let digest = sha256::Hash::hash(format!("{username}-{ark_address}-{time}").as_bytes());
+    let message = Message::from_digest(digest.to_byte_array());
+    Secp256k1::new()
+        .sign_schnorr_no_aux_rand(&message, keypair)
+        .to_string()
+}
+
+/// Registers a username with the LNURL server, which pays what the lightning address receives
+/// to the given Ark address
+///
+/// Registrations are keyed by the public key of [derive_keypair], so registering again replaces
+/// the username and Ark address of the wallet.
+pub(crate) async fn register(
+    http_client: &reqwest::Client,
+    server_url: &str,
+    keypair: &Keypair,
+    username: &str,
+    ark_address: &str,
+    time: u64,
+) -> Result<String, SdkError> {
+    validate_username(username)?;
+    let request = RegisterRequest {
+        username,
+        ark_address,
+        time,
+        signature: sign_registration(keypair, username, ark_address, time),
+    };
+    let url = format!(
+        "{}/lnurlpay/{}",
+        server_url.trim_end_matches('/'),
+        keypair.x_only_public_key().0
+    );
+
+    let response = http_client
+        .post(&url)
+        .timeout(REQUEST_TIMEOUT)
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| SdkError::NetworkError(format!("Failed to reach the LNURL server: {e}")))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(SdkError::GenericError(format!(
+            "Failed to register lightning address ({status}): {body}"
+        )));
+    }
+
+    let response: RegisterResponse = response
+        .json()
+        .await
+        .map_err(|e| SdkError::NetworkError(format!("Invalid LNURL server response: {e}")))?;
+    Ok(response.lightning_address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::{schnorr::Signature, SecretKey};
+
+    #[test]
+    fn test_validate_username() {
+        assert!(validate_username("satoshi").is_ok());
+        assert!(validate_username("sat.oshi_21-x").is_ok());
+        assert!(validate_username("").is_err());
+        assert!(validate_username("Satoshi").is_err());
+        assert!(validate_username("sat oshi").is_err());
+        assert!(validate_username(&"a".repeat(MAX_USERNAME_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_derive_keypair() {
+        let seed = [3u8; 64];
+        let keypair = derive_keypair(&seed, Network::Regtest).unwrap();
+        assert_eq!(keypair, derive_keypair(&seed, Network::Regtest).unwrap());
+
+        // The wallet's VTXO key is the start of the seed, the LNURL key must differ from it
+        let wallet_key = SecretKey::from_slice(&seed[..32]).unwrap();
+        assert_ne!(wallet_key, keypair.secret_key());
+    }
+
+    #[test]
+    fn test_registration_signature_verifies() {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, &SecretKey::from_slice(&[7u8; 32]).unwrap());
+
+        let signature = sign_registration(&keypair, "satoshi", "tark1address", 1_700_000_000);
+        let digest = sha256::Hash::hash(b"satoshi-tark1address-1700000000");
+        secp.verify_schnorr(
+            &Signature::from_str(&signature).unwrap(),
+            &Message::from_digest(digest.to_byte_array()),
+            &keypair.x_only_public_key().0,
+        )
+        .unwrap();
+    }
+}
@@ -1,14 +1,144 @@
 use anyhow::Result;
 use log::{LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
 use std::fs::{create_dir_all, File, OpenOptions};
 use std::io::Write;
 use std::path::Path;
 use std::sync::Mutex;
 
+use crate::models::{LogEntry, LoggingConfig};
+use crate::redact;
+
+/// Number of log entries kept in memory for [crate::BreezSdk::get_recent_logs]
+const LOG_BUFFER_CAPACITY: usize = 1_000;
+/// Target of the SDK's own log lines
+const SDK_TARGET: &str = "breez_sdk_ark";
+/// Targets of the HTTP and gRPC stacks, whose traces show the traffic with the servers
+const WIRE_TARGETS: [&str; 7] = [
+    "h2",
+    "hyper",
+    "hyper_util",
+    "reqwest",
+    "rustls",
+    "tonic",
+    "tower",
+];
+
+/// The latest entries logged by [SdkLogger], oldest first
+static RECENT_LOGS: Mutex<LogBuffer> = Mutex::new(LogBuffer::new(LOG_BUFFER_CAPACITY));
+
+/// Ring buffer of log entries, dropping the oldest entry once full
+struct LogBuffer {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    const fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    fn push(&mut self, entry: LogEntry) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// The last `limit` entries, oldest first
+    fn recent(&self, limit: usize) -> Vec<LogEntry> {
+        let skip = self.entries.len().saturating_sub(limit);
+        self.entries.iter().skip(skip).cloned().collect()
+    }
+}
+
+/// Returns the last `limit` entries logged by [SdkLogger], oldest first
+pub(crate) fn recent_logs(limit: usize) -> Vec<LogEntry> {
+    RECENT_LOGS
+        .lock()
+        .map(|buffer| buffer.recent(limit))
+        .unwrap_or_default()
+}
+
+/// Creates an ID tying together the log lines and events of one payment flow
+///
+/// Log lines of the flow are prefixed with it, so a payment can be traced through `sdk.log`.
+pub(crate) fn new_correlation_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..12].to_string()
+}
+
+/// Whether `target` is `prefix` or one of its submodules
+fn matches_target(target: &str, prefix: &str) -> bool {
+    target
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+fn is_wire_target(target: &str) -> bool {
+    WIRE_TARGETS
+        .iter()
+        .any(|prefix| matches_target(target, prefix))
+}
+
+/// Level filter per log target
+struct TargetFilter {
+    sdk_level: LevelFilter,
+    dependency_level: LevelFilter,
+    /// Overrides, longest target first so the most specific one applies
+    target_levels: Vec<(String, LevelFilter)>,
+}
+
+impl TargetFilter {
+    fn new(config: &LoggingConfig) -> Self {
+        let mut target_levels: Vec<(String, LevelFilter)> = config
+            .target_levels
+            .iter()
+            .map(|(target, level)| (target.clone(), *level))
+            .collect();
+        target_levels.sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+        Self {
+            sdk_level: config.sdk_level,
+            dependency_level: config.dependency_level,
+            target_levels,
+        }
+    }
+
+    fn level(&self, target: &str) -> LevelFilter {
+        if let Some((_, level)) = self
+            .target_levels
+            .iter()
+            .find(|(prefix, _)| matches_target(target, prefix))
+        {
+            return *level;
+        }
+        match matches_target(target, SDK_TARGET) {
+            true => self.sdk_level,
+            false => self.dependency_level,
+        }
+    }
+
+    /// The most verbose level any target is logged at
+    fn max_level(&self) -> LevelFilter {
+        self.target_levels
+            .iter()
+            .map(|(_, level)| *level)
+            .chain([self.sdk_level, self.dependency_level])
+            .max()
+            .unwrap_or(LevelFilter::Off)
+    }
+}
+
 /// Logger implementation for the Breez SDK
 pub struct SdkLogger {
     app_logger: Option<Box<dyn Log>>,
+    filter: TargetFilter,
     log_file: Mutex<Option<File>>,
+    /// Receives the wire-level traces, if enabled
+    wire_log_file: Option<Mutex<File>>,
+    full_addresses: bool,
 }
 
 impl SdkLogger {
@@ -18,25 +148,38 @@ impl SdkLogger {
     ///
     /// * `log_dir` - Directory where log files will be stored
     /// * `app_logger` - Optional application logger to forward logs to
+    /// * `config` - Which log lines to record
     ///
     /// # Returns
     ///
     /// A new `SdkLogger` instance
-    pub fn new(log_dir: &str, app_logger: Option<Box<dyn Log>>) -> Result<Self> {
+    pub fn new(
+        log_dir: &str,
+        app_logger: Option<Box<dyn Log>>,
+        config: &LoggingConfig,
+    ) -> Result<Self> {
         let log_path = Path::new(log_dir);
         if !log_path.exists() {
             create_dir_all(log_path)?;
         }
 
-        let log_file_path = log_path.join("sdk.log");
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(log_file_path)?;
+        let open = |file_name: &str| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_path.join(file_name))
+        };
+        let wire_log_file = match config.wire_traces {
+            true => Some(Mutex::new(open("wire.log")?)),
+            false => None,
+        };
 
         Ok(Self {
             app_logger,
-            log_file: Mutex::new(Some(file)),
+            filter: TargetFilter::new(config),
+            log_file: Mutex::new(Some(open("sdk.log")?)),
+            wire_log_file,
+            full_addresses: config.full_addresses,
         })
     }
 
@@ -46,21 +189,34 @@ impl SdkLogger {
     ///
     /// * `log_dir` - Directory where log files will be stored
     /// * `app_logger` - Optional application logger to forward logs to
+    /// * `config` - Which log lines to record
     ///
     /// # Returns
     ///
     /// Result indicating success or failure
-    pub fn init(log_dir: &str, app_logger: Option<Box<dyn Log>>) -> Result<()> {
-        let logger = Self::new(log_dir, app_logger)?;
+    pub fn init(
+        log_dir: &str,
+        app_logger: Option<Box<dyn Log>>,
+        config: &LoggingConfig,
+    ) -> Result<()> {
+        let logger = Self::new(log_dir, app_logger, config)?;
+        let max_level = match logger.wire_log_file {
+            Some(_) => LevelFilter::Trace,
+            None => logger.filter.max_level(),
+        };
         log::set_boxed_logger(Box::new(logger))?;
-        log::set_max_level(LevelFilter::Debug);
+        log::set_max_level(max_level);
         Ok(())
     }
+
+    fn is_wire_trace(&self, metadata: &Metadata) -> bool {
+        self.wire_log_file.is_some() && is_wire_target(metadata.target())
+    }
 }
 
 impl Log for SdkLogger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= log::max_level()
+        metadata.level() <= self.filter.level(metadata.target()) || self.is_wire_trace(metadata)
     }
 
     fn log(&self, record: &Record) {
@@ -68,15 +224,39 @@ impl Log for SdkLogger {
             return;
         }
 
-        // Format the log message
+        // Mask secrets before the line reaches any output
+        let now = chrono::Local::now();
+        let args = redact::redact(&record.args().to_string(), self.full_addresses);
         let message = format!(
             "{} [{}] {}: {}\n",
-            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            now.format("%Y-%m-%d %H:%M:%S"),
             record.level(),
             record.target(),
-            record.args()
+            args
         );
 
+        // Wire-level traces go to their own file, only lines passing the filter go further
+        if let Some(wire_log_file) = &self.wire_log_file {
+            if is_wire_target(record.target()) {
+                if let Ok(mut file) = wire_log_file.lock() {
+                    let _ = file.write_all(message.as_bytes());
+                }
+            }
+        }
+        if record.level() > self.filter.level(record.target()) {
+            return;
+        }
+
+        // Keep it in memory for apps without access to the log file
+        if let Ok(mut buffer) = RECENT_LOGS.lock() {
+            buffer.push(LogEntry {
+                timestamp_ms: now.timestamp_millis().max(0) as u64,
+                level: record.level().to_string(),
+                target: record.target().to_string(),
+                message: args.clone(),
+            });
+        }
+
         // Write to log file
         if let Ok(mut file_guard) = self.log_file.lock() {
             if let Some(file) = file_guard.as_mut() {
@@ -87,7 +267,15 @@ impl Log for SdkLogger {
 
         // Forward to app logger if provided
         if let Some(app_logger) = &self.app_logger {
-            app_logger.log(record);
+            app_logger.log(
+                &Record::builder()
+                    .args(format_args!("{args}"))
+                    .metadata(record.metadata().clone())
+                    .module_path(record.module_path())
+                    .file(record.file())
+                    .line(record.line())
+                    .build(),
+            );
         }
     }
 
@@ -97,9 +285,68 @@ impl Log for SdkLogger {
                 let _ = file.flush();
             }
         }
+        if let Some(Ok(mut file)) = self.wire_log_file.as_ref().map(Mutex::lock) {
+            let _ = file.flush();
+        }
 
         if let Some(app_logger) = &self.app_logger {
             app_logger.flush();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn entry(message: &str) -> LogEntry {
+        LogEntry {
+            timestamp_ms: 0,
+            level: "INFO".to_string(),
+            target: "breez_sdk_ark".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_log_buffer_keeps_latest_entries() {
+        let mut buffer = LogBuffer::new(2);
+        buffer.push(entry("first"));
+        buffer.push(entry("second"));
+        buffer.push(entry("third"));
+
+        let messages = |entries: Vec<LogEntry>| -> Vec<String> {
+            entries.into_iter().map(|e| e.message).collect()
+        };
+        assert_eq!(vec!["second", "third"], messages(buffer.recent(10)));
+        assert_eq!(vec!["third"], messages(buffer.recent(1)));
+    }
+
+    #[test]
+    fn test_target_filter() {
+        let filter = TargetFilter::new(&LoggingConfig {
+            target_levels: HashMap::from([
+                ("ark_client".to_string(), LevelFilter::Debug),
+                ("ark_client::round".to_string(), LevelFilter::Trace),
+            ]),
+            ..LoggingConfig::default()
+        });
+
+        assert_eq!(LevelFilter::Debug, filter.level("breez_sdk_ark"));
+        assert_eq!(
+            LevelFilter::Debug,
+            filter.level("breez_sdk_ark::chain::esplora")
+        );
+        assert_eq!(LevelFilter::Warn, filter.level("hyper::proto::h1"));
+        // Only whole module names match
+        assert_eq!(LevelFilter::Warn, filter.level("breez_sdk_ark_cli"));
+        // The most specific override applies
+        assert_eq!(LevelFilter::Debug, filter.level("ark_client::wallet"));
+        assert_eq!(LevelFilter::Trace, filter.level("ark_client::round::batch"));
+        assert_eq!(LevelFilter::Trace, filter.max_level());
+
+        assert!(is_wire_target("hyper_util::client"));
+        assert!(!is_wire_target("hyperion"));
+    }
+}
@@ -0,0 +1,256 @@
+use std::borrow::Cow;
+
+use bip39::{Language, Mnemonic};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+use crate::error::SdkError;
+use crate::models::{
+    GenerateMnemonicRequest, GenerateMnemonicResponse, MnemonicLanguage, MnemonicValidationError,
+    ValidateMnemonicRequest, ValidateMnemonicResponse,
+};
+
+const VALID_WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+const MAX_SUGGESTIONS: usize = 5;
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// Generates a fresh BIP39 mnemonic from system entropy
+pub(crate) fn generate_mnemonic(
+    request: GenerateMnemonicRequest,
+) -> Result<GenerateMnemonicResponse, SdkError> {
+    let language = request.language.unwrap_or(MnemonicLanguage::English);
+
+    let mut entropy = vec![0u8; request.word_count.entropy_len()];
+    StdRng::from_entropy().fill_bytes(&mut entropy);
+
+    let mnemonic = Mnemonic::from_entropy_in(language.into(), &entropy)
+        .map_err(|e| SdkError::GenericError(format!("Failed to generate mnemonic: {e}")))?;
+
+    Ok(GenerateMnemonicResponse {
+        mnemonic: mnemonic.to_string(),
+    })
+}
+
+/// Parses a mnemonic in any BIP39 wordlist, detecting its language
+pub(crate) fn parse_mnemonic(mnemonic: &str) -> Result<Mnemonic, bip39::Error> {
+    let words = normalized_words(mnemonic);
+    let language = detect_language(&words).unwrap_or(Language::English);
+    Mnemonic::parse_in_normalized(language, &words.join(" "))
+}
+
+/// Validates a mnemonic, reporting unknown words (with suggested corrections),
+/// an invalid word count or a checksum mismatch
+///
+/// Words are checked against the wordlist of the detected language.
+pub(crate) fn validate_mnemonic(request: ValidateMnemonicRequest) -> ValidateMnemonicResponse {
+    let words = normalized_words(&request.mnemonic);
+    let detected = detect_language(&words);
+    let language = detected.unwrap_or(Language::English);
+
+    let mut errors = Vec::new();
+    if !VALID_WORD_COUNTS.contains(&words.len()) {
+        errors.push(MnemonicValidationError::InvalidWordCount {
+            word_count: words.len() as u32,
+        });
+    }
+
+    for (index, word) in words.iter().enumerate() {
+        if language.find_word(word).is_none() {
+            errors.push(MnemonicValidationError::UnknownWord {
+                index: index as u32,
+                word: word.clone(),
+                suggestions: suggest_words(language, word),
+            });
+        }
+    }
+
+    // The checksum can only be verified once the words themselves are valid
+    if errors.is_empty() && Mnemonic::parse_in_normalized(language, &words.join(" ")).is_err() {
+        errors.push(MnemonicValidationError::InvalidChecksum);
+    }
+
+    ValidateMnemonicResponse {
+        is_valid: errors.is_empty(),
+        errors,
+        language: detected.map(Into::into),
+    }
+}
+
+/// Splits a mnemonic into lowercase words, normalized like the wordlists
+///
+/// Wordlists are stored decomposed (NFKD), so accented words typed in composed form still match.
+fn normalized_words(mnemonic: &str) -> Vec<String> {
+    let mut mnemonic = Cow::Borrowed(mnemonic);
+    Mnemonic::normalize_utf8_cow(&mut mnemonic);
+    mnemonic
+        .split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Picks the wordlist containing most of the words, preferring English on a tie
+///
+/// Some wordlists share words, e.g. English and French, so all words are considered rather
+/// than the first one. Returns `None` if no word is in any wordlist.
+fn detect_language(words: &[String]) -> Option<Language> {
+    Language::ALL
+        .iter()
+        .map(|language| {
+            let known = words
+                .iter()
+                .filter(|word| language.find_word(word).is_some())
+                .count();
+            (known, *language == Language::English, *language)
+        })
+        .filter(|(known, _, _)| *known > 0)
+        .max()
+        .map(|(_, _, language)| language)
+}
+
+/// Finds the closest wordlist entries to a misspelled word
+fn suggest_words(language: Language, word: &str) -> Vec<String> {
+    let mut candidates: Vec<(usize, &str)> = language
+        .word_list()
+        .iter()
+        .map(|candidate| (edit_distance(word, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .collect();
+
+    // Words sharing the typed prefix are likely what the user was typing
+    if candidates.is_empty() && !word.is_empty() {
+        candidates = language
+            .words_by_prefix(word)
+            .iter()
+            .map(|candidate| (candidate.len() - word.len(), *candidate))
+            .collect();
+    }
+
+    candidates.sort();
+    candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// Levenshtein distance between two words
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MnemonicWordCount;
+
+    const VALID_MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn validate(mnemonic: &str) -> ValidateMnemonicResponse {
+        validate_mnemonic(ValidateMnemonicRequest {
+            mnemonic: mnemonic.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_generate_mnemonic_word_count() {
+        for (word_count, expected) in [
+            (MnemonicWordCount::Words12, 12),
+            (MnemonicWordCount::Words24, 24),
+        ] {
+            let response = generate_mnemonic(GenerateMnemonicRequest {
+                word_count,
+                language: None,
+            })
+            .unwrap();
+            assert_eq!(expected, response.mnemonic.split_whitespace().count());
+            assert!(validate(&response.mnemonic).is_valid);
+        }
+    }
+
+    #[test]
+    fn test_validate_valid_mnemonic() {
+        let response = validate(VALID_MNEMONIC);
+        assert!(response.is_valid);
+        assert!(response.errors.is_empty());
+    }
+
+    #[test]
+    fn test_validate_unknown_word_suggestions() {
+        let response = validate(&VALID_MNEMONIC.replacen("about", "abuot", 1));
+        assert!(!response.is_valid);
+        match &response.errors[..] {
+            [MnemonicValidationError::UnknownWord {
+                index,
+                word,
+                suggestions,
+            }] => {
+                assert_eq!(11, *index);
+                assert_eq!("abuot", word);
+                assert!(suggestions.contains(&"about".to_string()));
+            }
+            errors => panic!("Unexpected errors: {errors:?}"),
+        }
+    }
+
+    #[test]
+    fn test_validate_invalid_checksum() {
+        let response = validate(&VALID_MNEMONIC.replace("about", "abandon"));
+        assert_eq!(
+            vec![MnemonicValidationError::InvalidChecksum],
+            response.errors
+        );
+    }
+
+    #[test]
+    fn test_non_english_mnemonics() {
+        let french = Mnemonic::from_entropy_in(Language::French, &[7; 16])
+            .unwrap()
+            .to_string();
+        let response = validate(&french);
+        assert!(response.is_valid);
+        assert_eq!(Some(MnemonicLanguage::French), response.language);
+        let parsed = parse_mnemonic(&french).unwrap();
+        assert_eq!(Language::French, parsed.language());
+        assert_eq!(vec![7; 16], parsed.to_entropy());
+
+        // Misspelled words are corrected from the detected wordlist
+        let mut words: Vec<String> = french.split_whitespace().map(str::to_string).collect();
+        let last_word = words[11].clone();
+        words[11].pop();
+        words[11].push('x');
+        let response = validate(&words.join(" "));
+        match &response.errors[..] {
+            [MnemonicValidationError::UnknownWord { suggestions, .. }] => {
+                assert!(suggestions.contains(&last_word));
+            }
+            errors => panic!("Unexpected errors: {errors:?}"),
+        }
+
+        let japanese = Mnemonic::from_entropy_in(Language::Japanese, &[7; 16])
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            Some(MnemonicLanguage::Japanese),
+            validate(&japanese).language
+        );
+    }
+
+    #[test]
+    fn test_validate_invalid_word_count() {
+        let response = validate("abandon abandon abandon");
+        assert_eq!(
+            vec![MnemonicValidationError::InvalidWordCount { word_count: 3 }],
+            response.errors
+        );
+    }
+}
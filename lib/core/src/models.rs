@@ -1,9 +1,16 @@
 use ark_core::ArkTransaction;
-use sdk_common::prelude::{LNInvoice, LNOffer};
+use chrono::{Offset, TimeZone};
+use sdk_common::prelude::{
+    BitcoinAddressData, LNInvoice, LNOffer, LnUrlAuthRequestData, LnUrlErrorData,
+    LnUrlPayRequestData, LnUrlWithdrawRequestData, SuccessAction,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 
 use crate::error::SdkError;
+use crate::redact::REDACTED;
+use crate::telemetry::FailureCategory;
 
 /// Network configuration for the SDK
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -25,6 +32,17 @@ impl fmt::Display for Network {
     }
 }
 
+impl From<Network> for sdk_common::prelude::Network {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Bitcoin => sdk_common::prelude::Network::Bitcoin,
+            Network::Testnet => sdk_common::prelude::Network::Testnet,
+            Network::Signet => sdk_common::prelude::Network::Signet,
+            Network::Regtest => sdk_common::prelude::Network::Regtest,
+        }
+    }
+}
+
 impl From<Network> for bitcoin::Network {
     fn from(network: Network) -> Self {
         match network {
@@ -39,10 +57,197 @@ impl From<Network> for bitcoin::Network {
 pub struct ConnectRequest {
     /// The SDK [Config]
     pub config: Config,
-    /// The mnemonic for the wallet
+    /// The mnemonic for the wallet, or a request to generate a new one
+    pub mnemonic: MnemonicSource,
+}
+
+/// Request for validating a mnemonic
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ValidateMnemonicRequest {
+    /// The mnemonic phrase to validate
+    pub mnemonic: String,
+}
+
+impl fmt::Debug for ValidateMnemonicRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidateMnemonicRequest")
+            .field("mnemonic", &REDACTED)
+            .finish()
+    }
+}
+
+/// Response for validating a mnemonic
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ValidateMnemonicResponse {
+    /// Whether the mnemonic is valid and can be used to connect
+    pub is_valid: bool,
+    /// The problems found in the mnemonic, empty if it is valid
+    pub errors: Vec<MnemonicValidationError>,
+    /// Wordlist language detected from the words, `None` if no word is in any wordlist
+    pub language: Option<MnemonicLanguage>,
+}
+
+/// A problem found while validating a mnemonic
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum MnemonicValidationError {
+    /// The mnemonic doesn't have 12, 15, 18, 21 or 24 words
+    InvalidWordCount { word_count: u32 },
+    /// A word is not part of the BIP39 wordlist
+    UnknownWord {
+        /// Zero-based position of the word in the mnemonic
+        index: u32,
+        word: String,
+        /// Closest words from the BIP39 wordlist, best match first
+        suggestions: Vec<String>,
+    },
+    /// All words are valid but the checksum doesn't match
+    InvalidChecksum,
+}
+
+impl fmt::Display for MnemonicValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MnemonicValidationError::InvalidWordCount { word_count } => {
+                write!(f, "Invalid word count: {word_count}")
+            }
+            MnemonicValidationError::UnknownWord {
+                index,
+                word,
+                suggestions,
+            } => {
+                write!(f, "Unknown word #{}: {word}", index + 1)?;
+                if !suggestions.is_empty() {
+                    write!(f, " (did you mean: {}?)", suggestions.join(", "))?;
+                }
+                Ok(())
+            }
+            MnemonicValidationError::InvalidChecksum => write!(f, "Invalid checksum"),
+        }
+    }
+}
+
+/// Where the wallet mnemonic comes from when connecting
+#[derive(Clone)]
+pub enum MnemonicSource {
+    /// Use an existing BIP39 mnemonic
+    Existing { mnemonic: String },
+    /// Let the SDK generate a new mnemonic. It can be retrieved once, for backup,
+    /// via [crate::BreezSdk::take_generated_mnemonic]
+    GenerateNew {
+        word_count: MnemonicWordCount,
+        language: Option<MnemonicLanguage>,
+    },
+}
+
+impl fmt::Debug for MnemonicSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MnemonicSource::Existing { .. } => f
+                .debug_struct("Existing")
+                .field("mnemonic", &REDACTED)
+                .finish(),
+            MnemonicSource::GenerateNew {
+                word_count,
+                language,
+            } => f
+                .debug_struct("GenerateNew")
+                .field("word_count", word_count)
+                .field("language", language)
+                .finish(),
+        }
+    }
+}
+
+/// Number of words in a generated mnemonic
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum MnemonicWordCount {
+    Words12,
+    Words24,
+}
+
+impl MnemonicWordCount {
+    /// Number of entropy bytes backing a mnemonic of this length
+    pub(crate) fn entropy_len(&self) -> usize {
+        match self {
+            MnemonicWordCount::Words12 => 16,
+            MnemonicWordCount::Words24 => 32,
+        }
+    }
+}
+
+/// BIP39 wordlist language
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum MnemonicLanguage {
+    English,
+    SimplifiedChinese,
+    TraditionalChinese,
+    Czech,
+    French,
+    Italian,
+    Japanese,
+    Korean,
+    Portuguese,
+    Spanish,
+}
+
+impl From<bip39::Language> for MnemonicLanguage {
+    fn from(language: bip39::Language) -> Self {
+        match language {
+            bip39::Language::English => MnemonicLanguage::English,
+            bip39::Language::SimplifiedChinese => MnemonicLanguage::SimplifiedChinese,
+            bip39::Language::TraditionalChinese => MnemonicLanguage::TraditionalChinese,
+            bip39::Language::Czech => MnemonicLanguage::Czech,
+            bip39::Language::French => MnemonicLanguage::French,
+            bip39::Language::Italian => MnemonicLanguage::Italian,
+            bip39::Language::Japanese => MnemonicLanguage::Japanese,
+            bip39::Language::Korean => MnemonicLanguage::Korean,
+            bip39::Language::Portuguese => MnemonicLanguage::Portuguese,
+            bip39::Language::Spanish => MnemonicLanguage::Spanish,
+        }
+    }
+}
+
+impl From<MnemonicLanguage> for bip39::Language {
+    fn from(language: MnemonicLanguage) -> Self {
+        match language {
+            MnemonicLanguage::English => bip39::Language::English,
+            MnemonicLanguage::SimplifiedChinese => bip39::Language::SimplifiedChinese,
+            MnemonicLanguage::TraditionalChinese => bip39::Language::TraditionalChinese,
+            MnemonicLanguage::Czech => bip39::Language::Czech,
+            MnemonicLanguage::French => bip39::Language::French,
+            MnemonicLanguage::Italian => bip39::Language::Italian,
+            MnemonicLanguage::Japanese => bip39::Language::Japanese,
+            MnemonicLanguage::Korean => bip39::Language::Korean,
+            MnemonicLanguage::Portuguese => bip39::Language::Portuguese,
+            MnemonicLanguage::Spanish => bip39::Language::Spanish,
+        }
+    }
+}
+
+/// Request for generating a new mnemonic
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GenerateMnemonicRequest {
+    /// Number of words in the mnemonic
+    pub word_count: MnemonicWordCount,
+    /// Wordlist language, defaults to English
+    pub language: Option<MnemonicLanguage>,
+}
+
+/// Response for generating a new mnemonic
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GenerateMnemonicResponse {
+    /// The generated mnemonic phrase
     pub mnemonic: String,
 }
 
+impl fmt::Debug for GenerateMnemonicResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GenerateMnemonicResponse")
+            .field("mnemonic", &REDACTED)
+            .finish()
+    }
+}
+
 /// Configuration for the SDK
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
@@ -52,11 +257,193 @@ pub struct Config {
     pub ark_server_url: String,
     /// The Esplora server URL
     pub esplora_url: String,
+    /// HTTP headers sent with every Esplora request, e.g. an `Authorization` header for hosted
+    /// instances requiring an API key
+    ///
+    /// Not supported with a custom HTTP client set with [crate::SdkBuilder::http_client], set
+    /// them as default headers of the client instead. They aren't sent to the Ark server.
+    #[serde(default)]
+    pub esplora_headers: HashMap<String, String>,
     /// Directory for storing data files (e.g., SQLite database)
     pub data_dir: String,
+    /// Other Ark servers the wallet holds funds with, next to the main one
+    #[serde(default)]
+    pub additional_ark_servers: Vec<ArkServerConfig>,
+    /// Keep all wallet data in memory, never touching disk. Useful for tests and throwaway
+    /// wallets, as everything is lost once the SDK is dropped
+    #[serde(default)]
+    pub in_memory: bool,
+    /// Hours during which non-critical events are held back and delivered afterward, e.g. so
+    /// apps driving notifications from events don't wake the user
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+    /// LNURL server hosting the wallet's lightning address, see
+    /// [crate::BreezSdk::register_lightning_address]
+    #[serde(default)]
+    pub lnurl_server_url: Option<String>,
+    /// Hex encoded public key the Ark server must sign with. Connecting fails if the server at
+    /// `ark_server_url` uses another key, e.g. because the URL was hijacked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_server_pubkey: Option<String>,
+    /// Merges small VTXOs in a round automatically, see [crate::BreezSdk::consolidate]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consolidation_policy: Option<ConsolidationPolicy>,
+    /// Base URL of a mempool.space-style explorer, used to link to onchain transactions. The
+    /// public mempool.space instance of the network is used if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explorer_url: Option<String>,
+    /// Seconds between periodic syncs of the wallet, 10 if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_interval_secs: Option<u64>,
+    /// Moves old payments out of the payment history, keeping it fast to list for long-lived
+    /// wallets. Payments are kept if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_retention: Option<PaymentRetention>,
+    /// Confirmations a deposit to the boarding address needs before it's boarded in a round,
+    /// 1 if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_boarding_confirmations: Option<u32>,
+}
+
+/// When small VTXOs are merged automatically after a sync
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConsolidationPolicy {
+    /// VTXOs below this amount are merged, in satoshis
+    pub dust_threshold_sat: u64,
+    /// Smallest number of VTXOs below the threshold worth joining a round for
+    pub min_vtxos: u32,
+}
+
+/// How long payments stay in the payment history, checked daily
+///
+/// Only payments in a final state are moved, pending ones stay until they settle. Use
+/// [crate::BreezSdk::export_payments] to keep a copy before pruning.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PaymentRetention {
+    /// Payments older than this many months, counted as 30 days, leave the payment history
+    pub after_months: u32,
+    /// What happens to them
+    pub action: RetentionAction,
+}
+
+/// What happens to payments leaving the payment history
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum RetentionAction {
+    /// Kept compressed in an archive, included in [crate::BreezSdk::export_payments]
+    Archive,
+    /// Deleted for good
+    Prune,
+}
+
+/// A daily window, in local time, during which non-critical events are held back
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuietHours {
+    /// Hour of the day the window starts, from 0 to 23
+    pub start_hour: u8,
+    /// Hour of the day the window ends, exclusive. May be lower than `start_hour` for windows
+    /// spanning midnight
+    pub end_hour: u8,
+    /// Offset of the user's local time from UTC in minutes, e.g. 120 for UTC+2. The offset of
+    /// the system's time zone is used if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utc_offset_minutes: Option<i32>,
+}
+
+impl QuietHours {
+    /// Whether the given hour of the day falls within the window
+    pub fn contains(&self, hour: u8) -> bool {
+        if self.start_hour <= self.end_hour {
+            (self.start_hour..self.end_hour).contains(&hour)
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+
+    /// Whether a Unix timestamp falls within the window, in the user's local time
+    pub fn contains_time(&self, timestamp: u64) -> bool {
+        let timestamp = timestamp as i64;
+        let offset_secs = match self.utc_offset_minutes {
+            Some(minutes) => minutes as i64 * 60,
+            None => chrono::Local
+                .timestamp_opt(timestamp, 0)
+                .single()
+                .map_or(0, |local| local.offset().fix().local_minus_utc() as i64),
+        };
+        // The hour is always below 24, so it fits a u8
+        let hour = (timestamp + offset_secs).rem_euclid(24 * 60 * 60) / (60 * 60);
+        self.contains(hour as u8)
+    }
+}
+
+/// Configuration of an additional Ark server
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArkServerConfig {
+    /// The Ark server URL
+    pub url: String,
+    /// Prefix of the Ark addresses of this server, i.e. the human readable part followed by the
+    /// leading characters of the server key. Sends to matching addresses go through this server
+    pub address_prefix: String,
+    /// Hex encoded public key the server must sign with, see [Config::expected_server_pubkey]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_server_pubkey: Option<String>,
+}
+
+/// Ark operators with curated connection settings, see [Config::for_operator]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum ArkOperator {
+    /// Arkade's public server on Mutinynet, a signet with 30 second blocks
+    Arkade,
+    /// A local regtest setup, such as the one started by nigiri
+    LocalRegtest,
 }
 
 impl Config {
+    /// Creates a configuration connecting to a known Ark operator
+    ///
+    /// The preset includes the operator's network, Ark server and a matching Esplora instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `operator` - The Ark operator to connect to
+    /// * `data_dir` - Directory for storing data files
+    ///
+    /// # Returns
+    ///
+    /// A new `Config` instance with the operator's settings
+    pub fn for_operator(operator: ArkOperator, data_dir: String) -> Self {
+        let (network, ark_server_url, esplora_url, explorer_url) = match operator {
+            ArkOperator::Arkade => (
+                Network::Signet,
+                "https://mutinynet.arkade.sh",
+                "https://mutinynet.com/api",
+                Some("https://mutinynet.com"),
+            ),
+            ArkOperator::LocalRegtest => (
+                Network::Regtest,
+                "http://localhost:7070",
+                "http://localhost:30000",
+                None,
+            ),
+        };
+        Self {
+            network,
+            ark_server_url: ark_server_url.to_string(),
+            esplora_url: esplora_url.to_string(),
+            esplora_headers: HashMap::new(),
+            data_dir,
+            additional_ark_servers: Vec::new(),
+            in_memory: false,
+            quiet_hours: None,
+            lnurl_server_url: None,
+            expected_server_pubkey: None,
+            consolidation_policy: None,
+            explorer_url: explorer_url.map(str::to_string),
+            sync_interval_secs: None,
+            payment_retention: None,
+            min_boarding_confirmations: None,
+        }
+    }
+
     /// Creates a default configuration for the specified network
     ///
     /// # Arguments
@@ -71,20 +458,47 @@ impl Config {
         match network {
             Network::Bitcoin => Err(SdkError::InvalidNetwork),
             Network::Testnet => Err(SdkError::InvalidNetwork),
-            Network::Signet => Ok(Self {
-                network,
-                ark_server_url: "https://mutinynet.arkade.sh".to_string(),
-                esplora_url: "https://mutinynet.com/api".to_string(),
-                data_dir,
-            }),
-            Network::Regtest => Ok(Self {
-                network,
-                ark_server_url: "http://localhost:7070".to_string(),
-                esplora_url: "http://localhost:30000".to_string(),
-                data_dir,
-            }),
+            Network::Signet => Ok(Self::for_operator(ArkOperator::Arkade, data_dir)),
+            Network::Regtest => Ok(Self::for_operator(ArkOperator::LocalRegtest, data_dir)),
         }
     }
+
+    /// Confirmations a boarding deposit needs before it's boarded
+    pub(crate) fn min_boarding_confirmations(&self) -> u32 {
+        self.min_boarding_confirmations.unwrap_or(1).max(1)
+    }
+}
+
+/// Request to change settings of a running SDK, see [crate::BreezSdk::update_config]
+///
+/// Fields left `None` keep their current value. Settings tied to the wallet itself, such as the
+/// network, the data directory or the Ark servers, can only change by connecting again.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UpdateConfigRequest {
+    /// The Esplora server URL
+    pub esplora_url: Option<String>,
+    /// HTTP headers sent with every Esplora request, replacing the current ones
+    ///
+    /// Rejected if the SDK was built with a custom HTTP client, see [Config::esplora_headers].
+    pub esplora_headers: Option<HashMap<String, String>>,
+    /// Seconds between periodic syncs of the wallet
+    pub sync_interval_secs: Option<u64>,
+    /// Hours during which non-critical events are held back, `Some(None)` turning them off
+    pub quiet_hours: Option<Option<QuietHours>>,
+    /// Policy merging small VTXOs automatically, `Some(None)` turning it off
+    pub consolidation_policy: Option<Option<ConsolidationPolicy>>,
+    /// Base URL of the explorer linked to, `Some(None)` going back to the public mempool.space
+    /// instance of the network
+    pub explorer_url: Option<Option<String>>,
+    /// How long payments stay in the payment history, `Some(None)` keeping them all
+    pub payment_retention: Option<Option<PaymentRetention>>,
+}
+
+/// Response from updating the configuration
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpdateConfigResponse {
+    /// The configuration now in use
+    pub config: Config,
 }
 
 /// Represents a payment in the system
@@ -106,6 +520,72 @@ pub struct Payment {
     pub description: Option<String>,
     /// Optional destination address
     pub destination: Option<String>,
+    /// Whether this is a send that failed midway and can be retried with
+    /// [crate::BreezSdk::retry_payment]
+    #[serde(default)]
+    pub is_retryable: bool,
+    /// Details specific to how the payment moved, if known
+    #[serde(default)]
+    pub details: Option<PaymentDetails>,
+    /// Onchain transactions the payment relates to, see [crate::BreezSdk::explorer_tx_url] to
+    /// link to them
+    #[serde(default)]
+    pub onchain_txs: Vec<OnchainTx>,
+    /// External order ID of the payment request the payment was matched to, see
+    /// [ReceivePaymentRequest::order_id]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<String>,
+    /// Wallet an external deposit was sent from, for payments that paid a
+    /// [PaymentMethod::ExternalDeposit] request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deposit_source: Option<String>,
+    /// Why the payment failed, expired or was cancelled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<PaymentFailureReason>,
+}
+
+/// Onchain transaction related to a payment
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct OnchainTx {
+    /// The transaction ID
+    pub txid: String,
+    /// What the transaction did for the payment
+    pub kind: OnchainTxKind,
+}
+
+/// Role of an onchain transaction in a payment
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum OnchainTxKind {
+    /// Funded the boarding address
+    Boarding,
+    /// Commitment transaction of the round that settled the payment
+    RoundCommitment,
+    /// Moved the funds out of Ark to an onchain address
+    Exit,
+}
+
+/// How a payment moved funds
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum PaymentDetails {
+    /// Funds moved between the chain and Ark, e.g. by boarding
+    Onchain {
+        /// The onchain transaction ID
+        txid: String,
+        /// Unix timestamp when the transaction confirmed, if it has
+        confirmed_at: Option<u64>,
+    },
+    /// Funds moved offchain within Ark
+    Ark {
+        /// The Ark transaction ID
+        txid: String,
+        /// The round transaction that settled the payment, if it was settled in a round
+        round_txid: Option<String>,
+    },
+    /// Funds moved over Lightning
+    Lightning {
+        /// The hex encoded payment hash
+        payment_hash: String,
+    },
 }
 
 /// Type of payment
@@ -131,6 +611,8 @@ pub enum PaymentStatus {
     Completed,
     Failed,
     Expired,
+    /// A send that was cancelled before it reached the Ark server
+    Cancelled,
 }
 
 impl fmt::Display for PaymentStatus {
@@ -140,10 +622,101 @@ impl fmt::Display for PaymentStatus {
             PaymentStatus::Completed => write!(f, "Completed"),
             PaymentStatus::Failed => write!(f, "Failed"),
             PaymentStatus::Expired => write!(f, "Expired"),
+            PaymentStatus::Cancelled => write!(f, "Cancelled"),
         }
     }
 }
 
+/// Why a payment didn't complete
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PaymentFailureReason {
+    /// What kind of failure it was
+    pub kind: PaymentFailureKind,
+    /// Details of the failure, e.g. the error reported by the Ark server
+    pub details: String,
+}
+
+impl PaymentFailureReason {
+    pub(crate) fn new(kind: PaymentFailureKind, details: impl ToString) -> Self {
+        Self {
+            kind,
+            details: details.to_string(),
+        }
+    }
+}
+
+impl From<&SdkError> for PaymentFailureReason {
+    fn from(err: &SdkError) -> Self {
+        let kind = match FailureCategory::from(err) {
+            FailureCategory::Network => PaymentFailureKind::Network,
+            FailureCategory::Server => PaymentFailureKind::ServerRejected,
+            FailureCategory::Wallet => PaymentFailureKind::Wallet,
+            FailureCategory::Storage | FailureCategory::InvalidInput | FailureCategory::Other => {
+                PaymentFailureKind::Other
+            }
+        };
+        Self::new(kind, err)
+    }
+}
+
+/// Kind of [PaymentFailureReason]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum PaymentFailureKind {
+    /// The Ark server couldn't be reached or didn't answer in time
+    Network,
+    /// The Ark server rejected the payment
+    ServerRejected,
+    /// The wallet couldn't build or fund the payment
+    Wallet,
+    /// A received payment wasn't settled before its VTXO expired
+    Expired,
+    /// The send was cancelled before it reached the Ark server
+    Cancelled,
+    /// Any other failure
+    Other,
+}
+
+/// Step of a send reported by [crate::SdkEvent::PaymentProgress]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+///
+/// Only steps the SDK observes are reported: the Ark client registers a payment, signs it and
+/// submits it in a single call.
+pub enum SendStep {
+    /// Funds covering the amount and the fees were set aside for the send
+    FundsReserved,
+    /// The Ark server cosigned the payment, or the round paying an onchain send completed
+    AcceptedByServer,
+    /// The round transaction paying an onchain send was found on the chain
+    Broadcast,
+}
+
+/// Text for apps to show in their own language, identified by a stable key with the values to
+/// fill in its placeholders
+///
+/// The SDK doesn't ship translations, apps map each key to a localized string, e.g.
+/// `payment.pending_settlement` to "Payment pending settlement in next round".
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LocalizedText {
+    /// Stable key of the text, e.g. `payment.pending_settlement`
+    pub key: String,
+    /// Values to fill in the placeholders of the text, keyed by placeholder name
+    pub params: HashMap<String, String>,
+}
+
+impl LocalizedText {
+    pub(crate) fn new(key: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            params: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn param(mut self, name: &str, value: impl ToString) -> Self {
+        self.params.insert(name.to_string(), value.to_string());
+        self
+    }
+}
+
 impl From<ArkTransaction> for Payment {
     fn from(tx: ArkTransaction) -> Self {
         match tx {
@@ -163,6 +736,18 @@ impl From<ArkTransaction> for Payment {
                 timestamp: tx.created_at() as u64,
                 description: None,
                 destination: None,
+                is_retryable: false,
+                details: Some(PaymentDetails::Onchain {
+                    txid: txid.to_string(),
+                    confirmed_at: confirmed_at.map(|t| t as u64),
+                }),
+                onchain_txs: vec![OnchainTx {
+                    txid: txid.to_string(),
+                    kind: OnchainTxKind::Boarding,
+                }],
+                order_id: None,
+                deposit_source: None,
+                failure_reason: None,
             },
             ArkTransaction::Round {
                 txid,
@@ -180,6 +765,18 @@ impl From<ArkTransaction> for Payment {
                 timestamp: created_at as u64,
                 description: None,
                 destination: None,
+                is_retryable: false,
+                details: Some(PaymentDetails::Ark {
+                    txid: txid.to_string(),
+                    round_txid: Some(txid.to_string()),
+                }),
+                onchain_txs: vec![OnchainTx {
+                    txid: txid.to_string(),
+                    kind: OnchainTxKind::RoundCommitment,
+                }],
+                order_id: None,
+                deposit_source: None,
+                failure_reason: None,
             },
             ArkTransaction::Redeem {
                 txid,
@@ -201,11 +798,70 @@ impl From<ArkTransaction> for Payment {
                 timestamp: created_at as u64,
                 description: None,
                 destination: None,
+                is_retryable: false,
+                details: Some(PaymentDetails::Ark {
+                    txid: txid.to_string(),
+                    round_txid: None,
+                }),
+                onchain_txs: Vec::new(),
+                order_id: None,
+                deposit_source: None,
+                failure_reason: None,
             },
         }
     }
 }
 
+impl Payment {
+    /// Describes the state of the payment for display, e.g. that it settles in the next round
+    ///
+    /// The text has the `amount_sat` and `fees_sat` parameters.
+    pub fn status_description(&self) -> LocalizedText {
+        let key = match (&self.status, &self.payment_type, &self.details) {
+            (PaymentStatus::Pending, _, Some(PaymentDetails::Onchain { .. })) => {
+                "payment.awaiting_confirmation"
+            }
+            (
+                PaymentStatus::Pending,
+                PaymentType::Received,
+                Some(PaymentDetails::Ark {
+                    round_txid: None, ..
+                }),
+            ) => "payment.pending_settlement",
+            (PaymentStatus::Pending, PaymentType::Received, _) => "payment.received.pending",
+            (PaymentStatus::Pending, PaymentType::Sent, _) => "payment.sent.pending",
+            (PaymentStatus::Completed, PaymentType::Received, _) => "payment.received.completed",
+            (PaymentStatus::Completed, PaymentType::Sent, _) => "payment.sent.completed",
+            (PaymentStatus::Failed, _, _) => "payment.failed",
+            (PaymentStatus::Expired, _, _) => "payment.expired",
+            (PaymentStatus::Cancelled, _, _) => "payment.cancelled",
+        };
+        LocalizedText::new(key)
+            .param("amount_sat", self.amount)
+            .param("fees_sat", self.fees)
+    }
+
+    /// Keeps the details only known locally when the payment is synced again from the Ark
+    /// server, e.g. who a send went to. The server's transaction history only reports amounts.
+    pub(crate) fn keep_local_details(&mut self, stored: &Payment) {
+        if self.description.is_none() {
+            self.description = stored.description.clone();
+        }
+        if self.destination.is_none() {
+            self.destination = stored.destination.clone();
+        }
+        if self.fees == 0 {
+            self.fees = stored.fees;
+        }
+        // Keep the transactions recorded when the payment was made, e.g. its exit
+        for onchain_tx in &stored.onchain_txs {
+            if !self.onchain_txs.contains(onchain_tx) {
+                self.onchain_txs.push(onchain_tx.clone());
+            }
+        }
+    }
+}
+
 // Request/Response structures for the SDK methods
 
 /// Request for getting the wallet balance
@@ -213,7 +869,7 @@ impl From<ArkTransaction> for Payment {
 pub struct GetBalanceRequest {}
 
 /// Represents the offchain balance with pending and confirmed amounts
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq)]
 pub struct OffchainBalance {
     /// Pending balance in satoshis
     pub pending_sats: u64,
@@ -239,13 +895,288 @@ impl OffchainBalance {
 /// Response for getting the wallet balance
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GetBalanceResponse {
+    /// The offchain balance details, across all Ark servers
+    pub balance: OffchainBalance,
+    /// Settled funds that can be sent right now, excluding those reserved by in-flight sends
+    pub spendable_sats: u64,
+    /// Funds reserved by sends in flight
+    pub reserved_sats: u64,
+    /// Funds received but not yet settled in an Ark round, which can't be sent yet
+    pub pending_settlement_sats: u64,
+    /// Onchain deposits to the boarding address, not boarded yet
+    pub boarding: BoardingBalance,
+    /// The offchain balance held with each Ark server
+    pub server_balances: Vec<ServerBalance>,
+}
+
+/// Onchain deposits to the boarding address, as of the last sync
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct BoardingBalance {
+    /// Deposits with enough confirmations to be boarded in the next round
+    pub eligible_sats: u64,
+    /// Deposits waiting for [Config::min_boarding_confirmations]
+    pub waiting_sats: u64,
+}
+
+/// Offchain balance held with a single Ark server
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServerBalance {
+    /// The Ark server URL
+    pub ark_server_url: String,
     /// The offchain balance details
     pub balance: OffchainBalance,
 }
 
+/// Request for getting information about the SDK
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetInfoRequest {}
+
+/// Snapshot of what the SDK is currently doing
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq)]
+pub struct SdkState {
+    /// Whether the background tasks are running
+    pub is_connected: bool,
+    /// Whether a wallet sync is in progress
+    pub is_syncing: bool,
+    /// Whether the wallet is currently participating in an Ark round
+    pub is_round_in_progress: bool,
+    /// Whether sends are paused because the Ark server reported inconsistent state
+    pub are_sends_paused: bool,
+}
+
+/// Response for getting information about the SDK
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetInfoResponse {
+    /// The network the SDK is connected to
+    pub network: Network,
+    /// The Ark server URL
+    pub ark_server_url: String,
+    /// The current runtime state
+    pub state: SdkState,
+    /// The fees charged by the Ark operator, `None` if the fee schedule couldn't be fetched
+    pub service_fees: Option<ServiceFees>,
+    /// The SDK's background tasks, empty until [crate::BreezSdk::start] is called
+    pub background_tasks: Vec<BackgroundTaskStatus>,
+    /// Health of the connection to the Ark server, measured by wallet syncs
+    pub ark_server_connection: ConnectionStatus,
+    /// Health of the connection to Esplora, measured by chain tip checks
+    pub esplora_connection: ConnectionStatus,
+    /// How far the local clock is off from the time of the chain tip in seconds, positive when
+    /// it's ahead. Only set while the skew is large enough to cause round or signing failures,
+    /// see [crate::SdkEvent::ClockSkewDetected]
+    pub clock_skew_secs: Option<i64>,
+}
+
+/// Request for getting the current chain tip
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetChainTipRequest {}
+
+/// Response containing the current chain tip
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetChainTipResponse {
+    /// Height of the tip
+    pub height: u32,
+    /// Hash of the block at the tip, if the chain service reports it
+    pub block_hash: Option<String>,
+}
+
+/// A remote service the SDK depends on
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum RemoteService {
+    /// The main Ark server
+    ArkServer,
+    /// The Esplora instance used to read the chain
+    Esplora,
+}
+
+/// Request for checking the services of a configuration, see [crate::BreezSdk::test_connection]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TestConnectionRequest {
+    /// The configuration to connect with
+    pub config: Config,
+}
+
+/// Response containing the outcome of checking each service of a configuration
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct TestConnectionResponse {
+    /// Outcome of connecting to [Config::ark_server_url]
+    pub ark_server: ConnectionCheck,
+    /// Outcome of connecting to [Config::esplora_url]
+    pub esplora: ConnectionCheck,
+}
+
+impl TestConnectionResponse {
+    /// Whether every service can be connected to
+    pub fn is_ok(&self) -> bool {
+        matches!(self.ark_server, ConnectionCheck::Ok { .. })
+            && matches!(self.esplora, ConnectionCheck::Ok { .. })
+    }
+}
+
+/// Outcome of checking a remote service before connecting to it
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub enum ConnectionCheck {
+    /// The service is reachable and on the configured network
+    Ok { latency_ms: u64 },
+    /// The URL of the service isn't a valid HTTP(S) URL
+    InvalidUrl { reason: String },
+    /// The service couldn't be reached or returned an error
+    Unreachable { reason: String },
+    /// The service is on another network than [Config::network]
+    NetworkMismatch { expected: String, actual: String },
+    /// The Ark server doesn't sign with [Config::expected_server_pubkey]
+    UntrustedServer { reason: String },
+}
+
+/// Health of the connection to a remote service
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq)]
+pub struct ConnectionStatus {
+    /// The current connection state
+    pub state: ConnectionState,
+    /// Round-trip time of the last successful call, in milliseconds
+    pub latency_ms: Option<u64>,
+    /// Unix timestamp of the last successful call
+    pub last_success_at: Option<u64>,
+    /// Number of calls in a row that failed
+    pub consecutive_failures: u32,
+}
+
+/// State of the connection to a remote service
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq)]
+pub enum ConnectionState {
+    /// The last call succeeded in time
+    Connected,
+    /// The last call was slow, or recent calls failed
+    Degraded,
+    /// Several calls in a row failed, or no call completed yet
+    #[default]
+    Disconnected,
+}
+
+/// Status of one of the SDK's background tasks
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct BackgroundTaskStatus {
+    /// Name of the task, e.g. `periodic_sync`
+    pub name: String,
+    /// What the task is currently doing
+    pub state: BackgroundTaskState,
+    /// Unix timestamp when the task last finished a run
+    pub last_run_at: Option<u64>,
+    /// Number of runs in a row that failed. The task waits longer between runs while failing
+    pub consecutive_failures: u32,
+    /// Error of the last run, if it failed
+    pub last_error: Option<String>,
+}
+
+/// State of a background task
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum BackgroundTaskState {
+    /// Waiting for its next run
+    Waiting,
+    /// Currently running
+    Running,
+    /// Stopped by [crate::BreezSdk::disconnect], or done for tasks that run once
+    Stopped,
+}
+
+/// Request for preparing a consolidation of small VTXOs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PrepareConsolidateRequest {
+    /// Consolidating is planned when at least two VTXOs are below this amount, in satoshis.
+    /// Defaults to 10,000 sats
+    pub dust_threshold_sat: Option<u64>,
+}
+
+/// Expected cost and benefit of consolidating small VTXOs
+///
+/// The round settles every spendable VTXO, so the estimate covers all of them, not only the
+/// small ones.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PrepareConsolidateResponse {
+    /// Number of VTXOs the round spends
+    pub vtxo_count: u32,
+    /// Number of those below the threshold
+    pub dust_vtxo_count: u32,
+    /// Total amount held in the VTXOs the round spends, in satoshis
+    pub amount_sat: u64,
+    /// Round fees paid to merge them, in satoshis
+    pub fee_sat: u64,
+    /// Onchain fees saved by not having to exit them separately, at the current fee rate
+    pub exit_savings_sat: u64,
+    /// Whether the savings outweigh the fees
+    pub is_worth_it: bool,
+}
+
+/// Request for consolidating small VTXOs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConsolidateRequest {}
+
+/// Response for consolidating small VTXOs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConsolidateResponse {}
+
+/// Request for getting the current payment limits
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetLimitsRequest {}
+
+/// Response for getting the current payment limits
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetLimitsResponse {
+    /// Limits of offchain sends to Ark addresses
+    pub offchain_send: Limits,
+    /// Limits of onchain deposits to the boarding address
+    pub onchain_receive: Limits,
+    /// Limits of Lightning sends, `None` until Lightning payments are supported
+    pub lightning_send: Option<Limits>,
+    /// Largest VTXO the Ark server accepts, if it limits them
+    pub max_vtxo_sat: Option<u64>,
+}
+
+/// Range of amounts a payment method accepts
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Limits {
+    /// Smallest amount in satoshis
+    pub min_sat: u64,
+    /// Largest amount in satoshis, `None` if there is no limit
+    pub max_sat: Option<u64>,
+}
+
+impl Limits {
+    /// Checks that an amount is within the limits
+    pub(crate) fn check(&self, amount_sat: u64) -> Result<(), SdkError> {
+        if amount_sat < self.min_sat {
+            return Err(SdkError::AmountTooSmall {
+                amount_sat,
+                min_sat: self.min_sat,
+            });
+        }
+        match self.max_sat {
+            Some(max_sat) if amount_sat > max_sat => Err(SdkError::AmountTooLarge {
+                amount_sat,
+                max_sat,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
 /// Request for syncing the wallet
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct SyncWalletRequest {}
+pub struct SyncWalletRequest {
+    /// How much of the wallet to sync
+    pub mode: SyncMode,
+}
+
+/// Controls how much work a wallet sync does
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum SyncMode {
+    /// Only refresh the offchain balance, skipping boarding and payment history.
+    /// Useful for a quick pull-to-refresh on large wallets
+    BalanceOnly,
+    /// Join a round to board pending deposits, then refresh the balance and payment history
+    #[default]
+    Full,
+}
 
 /// Response for syncing the wallet
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -262,29 +1193,50 @@ pub struct ReceiveOnchainResponse {
     pub deposit_address: String,
 }
 
+/// Fees charged by the Ark operator, on top of any onchain fees
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ServiceFees {
+    /// Fee for bringing onchain funds into Ark, in satoshis
+    pub boarding_fee_sat: u64,
+    /// Fee for sending funds from Ark to an onchain address, in satoshis
+    pub offboard_fee_sat: u64,
+    /// Fee for sending funds to another Ark address, in satoshis
+    pub offchain_fee_sat: u64,
+    /// Fee for each VTXO renewed in a round, in satoshis
+    pub round_fee_sat: u64,
+    /// Share of a Lightning payment reserved for routing fees, in parts per million
+    pub ln_routing_margin_ppm: u32,
+}
+
 /// Request for preparing an on-chain send
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PrepareSendOnchainRequest {
+    /// Bitcoin address to send to
+    pub onchain_address: String,
     /// Amount in satoshis
     pub receiver_amount_sats: u64,
 }
 
-/// Response for preparing an on-chain send
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Quote for an on-chain send, executed with [crate::BreezSdk::send_onchain]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct PrepareSendOnchainResponse {
+    /// Identifies the quote, which can only be executed once
+    pub quote_id: String,
+    /// Bitcoin address to send to
+    pub onchain_address: String,
     /// Amount in satoshis
     pub receiver_amount_sats: u64,
     /// Estimated fee in satoshis
     pub fee_sats: u64,
+    /// Unix timestamp after which the quote can no longer be executed
+    pub expires_at: u64,
 }
 
 /// Request for sending on-chain funds
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SendOnchainRequest {
-    /// The prepare response
+    /// The quote returned by [crate::BreezSdk::prepare_send_onchain], unmodified
     pub prepare_send_onchain_response: PrepareSendOnchainResponse,
-    /// Bitcoin address to send to
-    pub onchain_address: String,
 }
 
 /// Response for sending on-chain funds
@@ -294,23 +1246,226 @@ pub struct SendOnchainResponse {
     pub tx_id: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum PaymentMethod {
-    Bolt11Invoice { receiver_amount_sat: u64 },
+    Bolt11Invoice {
+        receiver_amount_sat: u64,
+    },
     Bolt12Offer,
-    BitcoinAddress { receiver_amount_sat: Option<u64> },
-    ArkAddress { receiver_amount_sat: Option<u64> },
+    BitcoinAddress {
+        receiver_amount_sat: Option<u64>,
+    },
+    ArkAddress {
+        receiver_amount_sat: Option<u64>,
+    },
+    /// The boarding address, funded from an external wallet such as a hardware wallet. The
+    /// response carries a [DepositVerification] to check the address with before sending, and
+    /// the received payment is tagged with `source`, see [Payment::deposit_source]
+    ExternalDeposit {
+        receiver_amount_sat: Option<u64>,
+        /// Name of the wallet the deposit is sent from, e.g. the hardware wallet model
+        source: String,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReceivePaymentRequest {
     pub payment_method: PaymentMethod,
+    /// How long the payment request is tracked as outstanding, in seconds. Defaults to a day
+    #[serde(default)]
+    pub expiry_secs: Option<u64>,
+    /// External order ID, e.g. of a point-of-sale system, the destination is generated for.
+    /// It's copied to the payment matched to the request, so orders can be reconciled from
+    /// [crate::SdkEvent::PaymentReceived] or the payment history.
+    ///
+    /// Ark and boarding addresses are shared, so payments are told apart by amount: an order
+    /// needs an amount, and no other request may be open for the same amount and address.
+    #[serde(default)]
+    pub order_id: Option<String>,
+    /// Note about what the payment is for, set as the description of the payment matched to
+    /// the request
+    #[serde(default)]
+    pub description: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReceivePaymentResponse {
     pub destination: String,
     pub fee_sat: u64,
+    /// Position of the issued offchain address among the addresses the wallet handed out,
+    /// starting at 0, for Ark addresses only
+    ///
+    /// The Ark client derives its address from the wallet key, so this isn't a key derivation
+    /// index. It stays the same when the same address is issued again.
+    pub address_index: Option<u32>,
+    /// Identifies this receive in the SDK logs, and is the ID of its [PaymentRequest]
+    pub correlation_id: String,
+    /// The payment method the destination was generated for, i.e. what kind of string
+    /// `destination` is
+    pub payment_method: PaymentMethod,
+    /// Unix timestamp after which the request is no longer expected to be paid
+    pub expires_at: u64,
+    /// Smallest amount in satoshis that can be received with the destination
+    pub min_amount_sat: u64,
+    /// BIP21 URI of the destination, including the requested amount, for onchain and Ark
+    /// addresses. Either address is paid by wallets that understand the URI
+    pub bip21: Option<String>,
+    /// What to check the address against before sending, for external deposits only
+    pub deposit_verification: Option<DepositVerification>,
+    /// Set if the Ark server couldn't confirm it accepts the destination at the last sync. It
+    /// shouldn't be shared until [crate::BreezSdk::receive_payment] succeeds without a warning
+    /// after a later sync
+    pub warning: Option<ReceiveWarning>,
+}
+
+/// Why a generated destination may not be payable
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ReceiveWarning {
+    /// The Ark server couldn't be reached to confirm it still accepts the destination
+    ServerUnreachable { reason: String },
+    /// The Ark server now signs with another key than the one the destination was derived
+    /// from, so it won't accept payments to it. Reconnecting derives destinations from the
+    /// new key
+    ServerKeyChanged {
+        derived_with: String,
+        current: String,
+    },
+}
+
+/// Lets a user verify a boarding address on a hardware wallet before sending to it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DepositVerification {
+    /// The address in groups of four characters, as hardware wallets display it, to compare
+    /// with the device screen group by group
+    pub address_groups: Vec<String>,
+    /// The `addr()` output descriptor of the address, with its checksum, for tracking the
+    /// deposit in a watch-only wallet
+    pub descriptor: String,
+    /// Public key of the Ark server that co-signs boarding the deposit
+    pub server_pubkey: String,
+}
+
+/// A payment destination generated by [crate::BreezSdk::receive_payment]
+///
+/// Requests are matched against received payments on each sync, so outstanding requests can be
+/// told apart from paid ones.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PaymentRequest {
+    /// Unique identifier of the request, the correlation ID of the receive that generated it
+    pub id: String,
+    /// The payment method the destination was generated for
+    pub payment_method: PaymentMethod,
+    /// The generated destination
+    pub destination: String,
+    /// Amount requested in satoshis, if any
+    pub amount_sat: Option<u64>,
+    /// Unix timestamp when the request was generated
+    pub created_at: u64,
+    /// Unix timestamp after which the request is no longer expected to be paid
+    pub expires_at: u64,
+    /// Whether the request was paid
+    pub status: PaymentRequestStatus,
+    /// ID of the received payment that paid the request
+    pub payment_id: Option<String>,
+    /// External order ID the request was generated for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<String>,
+    /// Note about what the payment is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Status of a payment request
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum PaymentRequestStatus {
+    /// No matching payment was received yet
+    Pending,
+    /// A matching payment was received
+    Paid,
+    /// The request expired before it was paid
+    Expired,
+}
+
+impl fmt::Display for PaymentRequestStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentRequestStatus::Pending => write!(f, "Pending"),
+            PaymentRequestStatus::Paid => write!(f, "Paid"),
+            PaymentRequestStatus::Expired => write!(f, "Expired"),
+        }
+    }
+}
+
+/// Request for exporting the proof that the round a payment settled in is confirmed onchain
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportRoundProofRequest {
+    /// ID of the payment whose round is proven
+    pub payment_id: String,
+}
+
+/// Response containing the round inclusion proof of a payment
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportRoundProofResponse {
+    pub proof: RoundInclusionProof,
+}
+
+/// Evidence that the round a payment settled in is confirmed onchain, which a third party can
+/// check against its own node instead of trusting the Ark server
+///
+/// Only the round transaction is proven. The branch of the VTXO tree leading from it to the
+/// payment's VTXO isn't included, so the proof doesn't show the round pays the payment.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RoundInclusionProof {
+    /// ID of the payment the round settled
+    pub payment_id: String,
+    /// Amount of the payment in satoshis, as recorded by the wallet
+    pub amount_sat: u64,
+    /// The round transaction that settled the payment
+    pub round_txid: String,
+    /// The consensus encoded round transaction, including the signatures of its inputs
+    pub round_tx_hex: String,
+    /// Hash of the block that confirmed the round
+    pub block_hash: String,
+    /// Height of the block that confirmed the round
+    pub block_height: u32,
+    /// Merkle path of the round transaction in its block, encoded as the merkle block that
+    /// `bitcoin-cli verifytxoutproof` checks
+    pub txout_proof_hex: String,
+    /// Public key of the Ark server that signed the round
+    pub server_pubkey: String,
+}
+
+/// Request for listing payment requests
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListPaymentRequestsRequest {
+    /// Only list requests with this status
+    pub status: Option<PaymentRequestStatus>,
+    /// Only list requests generated for this external order ID
+    #[serde(default)]
+    pub order_id: Option<String>,
+}
+
+/// Response for listing payment requests
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListPaymentRequestsResponse {
+    /// The payment requests, newest first
+    pub payment_requests: Vec<PaymentRequest>,
+}
+
+/// Request to wait until a destination from [crate::BreezSdk::receive_payment] is paid
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WaitForPaymentRequest {
+    /// The destination returned by `receive_payment`
+    pub destination: String,
+    /// How long to wait for the payment to settle, in seconds
+    pub timeout_secs: u64,
+}
+
+/// Response from waiting for a payment
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WaitForPaymentResponse {
+    /// The settled payment
+    pub payment: Payment,
 }
 
 /// Request for receiving Ark payments
@@ -322,13 +1477,167 @@ pub struct ReceiveArkRequest {}
 pub struct ReceiveArkResponse {
     /// Ark address
     pub address: String,
+    /// Hex encoded taproot output key of the VTXOs paid to the address
+    pub vtxo_pubkey: String,
+    /// Approximate time, in seconds, received VTXOs can be held before they expire and must be
+    /// renewed in a round
+    pub vtxo_expiry_secs: Option<u64>,
+    /// Approximate time, in seconds, it takes to exit a received VTXO onchain without the
+    /// Ark server's cooperation
+    pub exit_delay_secs: Option<u64>,
+}
+
+/// The result of parsing a user provided input with [crate::BreezSdk::parse]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum InputType {
+    /// An Ark address that can be paid offchain
+    ArkAddress {
+        address: String,
+    },
+    /// A bearer Ark note that can be claimed with [crate::BreezSdk::redeem_note]
+    ArkNote {
+        /// The encoded note
+        note: String,
+        /// The value embedded in the note
+        amount_sat: u64,
+        /// Unix timestamp after which the note can no longer be redeemed, if any
+        expires_at: Option<u64>,
+    },
+    /// An onchain address or BIP21 URI
+    BitcoinAddress {
+        address: BitcoinAddressData,
+    },
+    Bolt11 {
+        invoice: LNInvoice,
+    },
+    Bolt12Offer {
+        offer: LNOffer,
+        /// The BIP353 address the offer was resolved from, if any
+        bip353_address: Option<String>,
+    },
+    NodeId {
+        node_id: String,
+    },
+    Url {
+        url: String,
+    },
+    /// An LNURL-pay request or lightning address, see [crate::BreezSdk::prepare_lnurl_pay]
+    LnUrlPay {
+        data: LnUrlPayRequestData,
+        /// The BIP353 address the request was resolved from, if any
+        bip353_address: Option<String>,
+    },
+    LnUrlWithdraw {
+        data: LnUrlWithdrawRequestData,
+    },
+    LnUrlAuth {
+        data: LnUrlAuthRequestData,
+    },
+    /// An LNURL endpoint answered with an error
+    LnUrlError {
+        data: LnUrlErrorData,
+    },
+}
+
+/// Request for redeeming an Ark note
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RedeemNoteRequest {
+    /// The encoded Ark note, as returned in [InputType::ArkNote]
+    pub note: String,
+}
+
+/// Response for redeeming an Ark note
+#[derive(Clone, Debug, Serialize)]
+pub struct RedeemNoteResponse {
+    /// The received payment recording the redeemed value
+    pub payment: Payment,
+}
+
+/// A bearer note created with [crate::BreezSdk::create_note]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Note {
+    /// Unique identifier of the note
+    pub id: String,
+    /// The encoded note to hand over to the recipient
+    pub note: String,
+    /// Amount reserved for the note in satoshis
+    pub amount_sat: u64,
+    /// Unix timestamp when the note was created
+    pub created_at: u64,
+    /// Unix timestamp after which the recipient can no longer redeem the note
+    pub expires_at: u64,
+    /// Whether the reserved funds are still waiting to be claimed
+    pub status: NoteStatus,
+}
+
+/// Status of a note created by the wallet
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum NoteStatus {
+    /// The funds are reserved until the note is redeemed or reclaimed
+    Reserved,
+    /// The note was redeemed by its recipient
+    Redeemed,
+    /// The funds were reclaimed back into the wallet
+    Reclaimed,
+}
+
+impl fmt::Display for NoteStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoteStatus::Reserved => write!(f, "Reserved"),
+            NoteStatus::Redeemed => write!(f, "Redeemed"),
+            NoteStatus::Reclaimed => write!(f, "Reclaimed"),
+        }
+    }
+}
+
+/// Request for creating a bearer note
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateNoteRequest {
+    /// Amount to lock into the note in satoshis
+    pub amount_sat: u64,
+    /// How long the recipient has to redeem the note, in seconds. Defaults to 30 days
+    pub expiry_secs: Option<u64>,
+}
+
+/// Response for creating a bearer note
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateNoteResponse {
+    /// The created note
+    pub note: Note,
+}
+
+/// Request for reclaiming the funds of a note that wasn't redeemed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReclaimNoteRequest {
+    /// The note ID
+    pub id: String,
+}
+
+/// Response for reclaiming a note
+#[derive(Clone, Debug, Serialize)]
+pub struct ReclaimNoteResponse {
+    /// The received payment recording the reclaimed value
+    pub payment: Payment,
+}
+
+/// Request for listing the notes created by the wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListNotesRequest {}
+
+/// Response for listing notes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListNotesResponse {
+    /// Notes created by the wallet, newest first
+    pub notes: Vec<Note>,
 }
 
 /// An argument when calling [crate::sdk::LiquidSdk::prepare_send_payment].
 #[derive(Debug, Serialize, Clone)]
 pub struct PrepareSendPaymentRequest {
     /// The destination we intend to pay to.
-    /// Supports Ark addresses, BIP21 URIs, BOLT11 invoices, BOLT12 offers
+    /// Supports Ark addresses, BIP21 URIs, BOLT11 invoices, BOLT12 offers, LNURL-pay requests
+    /// and lightning addresses
     pub destination: String,
 
     /// Should only be set when paying directly onchain or to a BIP21 URI
@@ -354,6 +1663,8 @@ pub enum SendDestination {
     },
     Bolt11 {
         invoice: LNInvoice,
+        /// The amount to pay, which zero-amount invoices leave to the payer
+        receiver_amount_sat: u64,
         /// A BIP353 address, in case one was used to resolve this BOLT11
         bip353_address: Option<String>,
     },
@@ -363,12 +1674,67 @@ pub enum SendDestination {
         /// A BIP353 address, in case one was used to resolve this BOLT12
         bip353_address: Option<String>,
     },
+    /// A BIP21 URI, which may offer an Ark address besides its onchain address
+    Bip21 {
+        /// The BIP21 URI
+        uri: String,
+        /// The onchain address of the URI, if any
+        onchain_address: Option<String>,
+        /// The Ark address from the URI's `ark` parameter, if any
+        ark_address: Option<String>,
+        receiver_amount_sat: u64,
+        /// The onchain send quote, when the payment falls back to the onchain address
+        onchain_quote: Option<PrepareSendOnchainResponse>,
+    },
+}
+
+/// How a prepared payment will reach its destination
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub enum PaymentRoute {
+    /// Paid offchain to an Ark address
+    Ark,
+    /// Paid over Lightning
+    Lightning,
+    /// Paid onchain by leaving Ark in a round
+    Onchain,
+}
+
+/// Request for the routes a payment can take, see [crate::BreezSdk::route_payment]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoutePaymentRequest {
+    /// An Ark address, BOLT11 invoice, BIP21 URI or Bitcoin address
+    pub destination: String,
+    /// The amount to send in satoshis
+    pub amount_sat: u64,
+}
+
+/// A route a payment can take, with its estimated cost and timing
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct RouteOption {
+    pub route: PaymentRoute,
+    /// Estimated fees in satoshis, on top of the amount sent
+    pub fees_sat: u64,
+    /// Estimated time until the receiver has the funds, in seconds
+    pub estimated_settlement_secs: u64,
+    /// Whether this is the route the SDK picks when paying the destination
+    pub is_default: bool,
+}
+
+/// Response containing the routes a payment can take
+#[derive(Clone, Debug, Serialize)]
+pub struct RoutePaymentResponse {
+    /// The feasible routes, cheapest first
+    pub options: Vec<RouteOption>,
 }
 
 #[derive(Debug, Serialize, Clone)]
 pub struct PrepareSendPaymentResponse {
     pub destination: SendDestination,
     pub fees_sat: Option<u64>,
+    /// The route the payment will take
+    pub route: PaymentRoute,
+    /// Identifies this payment in the SDK logs and events, from preparing to sending it
+    pub correlation_id: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -376,6 +1742,29 @@ pub struct SendPaymentRequest {
     pub prepare_response: PrepareSendPaymentResponse,
 }
 
+/// Request for preparing to pay an LNURL-pay request, see [crate::BreezSdk::prepare_lnurl_pay]
+#[derive(Debug, Serialize, Clone)]
+pub struct PrepareLnUrlPayRequest {
+    /// The request data, as returned in [InputType::LnUrlPay]
+    pub data: LnUrlPayRequestData,
+    /// The amount to pay in satoshis
+    pub amount_sat: u64,
+    /// A comment for the receiver, if the request allows one
+    pub comment: Option<String>,
+    /// Whether a URL success action must point to the request's domain. Defaults to true
+    pub validate_success_action_url: Option<bool>,
+}
+
+/// Response for preparing to pay an LNURL-pay request
+#[derive(Debug, Serialize)]
+pub struct PrepareLnUrlPayResponse {
+    /// The prepared payment of the invoice the LNURL service returned, to pass to
+    /// [crate::BreezSdk::send_payment]
+    pub prepare_response: PrepareSendPaymentResponse,
+    /// What to show the user once the payment succeeded, if the service set anything
+    pub success_action: Option<SuccessAction>,
+}
+
 /// Returned when calling [crate::sdk::LiquidSdk::send_payment].
 #[derive(Debug, Serialize)]
 pub struct SendPaymentResponse {
@@ -389,6 +1778,23 @@ pub struct ListPaymentsRequest {
     pub offset: u32,
     /// Maximum number of payments to return
     pub limit: u32,
+    /// Continue after the previous page, see [ListPaymentsResponse::next_cursor]. Unlike an
+    /// offset, a cursor isn't thrown off by payments arriving while paging
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Order of the payments, newest first by default
+    #[serde(default)]
+    pub sort: SortDirection,
+}
+
+/// Order payments are listed in, by timestamp
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum SortDirection {
+    /// Oldest first
+    Ascending,
+    /// Newest first
+    #[default]
+    Descending,
 }
 
 /// Response for listing payments
@@ -396,4 +1802,402 @@ pub struct ListPaymentsRequest {
 pub struct ListPaymentsResponse {
     /// List of payments
     pub payments: Vec<Payment>,
+    /// Opaque cursor to pass in [ListPaymentsRequest::cursor] for the next page, if the page
+    /// was full
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// Request for exporting the payment history
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExportPaymentsRequest {}
+
+/// Response containing the whole payment history
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportPaymentsResponse {
+    /// Every payment, including archived ones, oldest first
+    pub payments: Vec<Payment>,
+}
+
+/// Request for exporting the wallet state
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportStateRequest {
+    /// Maximum number of characters of encrypted payload per chunk. Defaults to 300
+    pub max_chunk_size: Option<u32>,
+}
+
+/// Response for exporting the wallet state
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportStateResponse {
+    /// The encrypted state, split in chunks that can be shown one by one as an animated QR code
+    pub chunks: Vec<String>,
+}
+
+/// Request for registering a lightning address
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RegisterLightningAddressRequest {
+    /// The username, i.e. the part of the lightning address before the `@`
+    pub username: String,
+}
+
+/// Response for registering a lightning address
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RegisterLightningAddressResponse {
+    /// The registered lightning address, e.g. `satoshi@example.com`
+    pub lightning_address: String,
+}
+
+/// Request for getting the latest SDK log entries
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetRecentLogsRequest {
+    /// Maximum number of entries to return
+    pub limit: u32,
+}
+
+/// Response for getting the latest SDK log entries
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetRecentLogsResponse {
+    /// The entries, oldest first
+    pub logs: Vec<LogEntry>,
+}
+
+/// A line logged by the SDK
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LogEntry {
+    /// Unix timestamp in milliseconds
+    pub timestamp_ms: u64,
+    /// Log level, e.g. `INFO`
+    pub level: String,
+    /// Module that logged the entry
+    pub target: String,
+    /// The logged message
+    pub message: String,
+}
+
+/// Which log lines the SDK logger records, see [crate::BreezSdk::init_logging_with_config]
+#[derive(Clone, Debug)]
+pub struct LoggingConfig {
+    /// Level of the SDK's own log lines
+    pub sdk_level: log::LevelFilter,
+    /// Level of the log lines of dependencies, e.g. the Ark client or the HTTP stack
+    pub dependency_level: log::LevelFilter,
+    /// Levels overriding the above for specific targets and their submodules, e.g.
+    /// `ark_client` to debug a round
+    pub target_levels: HashMap<String, log::LevelFilter>,
+    /// Write every log line of the HTTP and gRPC stacks, down to trace level, to a separate
+    /// `wire.log` file. Meant for debugging connection issues, as it grows quickly
+    pub wire_traces: bool,
+    /// Log addresses in full. By default they are truncated, while mnemonics and seeds are
+    /// always masked
+    pub full_addresses: bool,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            sdk_level: log::LevelFilter::Debug,
+            dependency_level: log::LevelFilter::Warn,
+            target_levels: HashMap::new(),
+            wire_traces: false,
+            full_addresses: false,
+        }
+    }
+}
+
+/// Request for importing a wallet state exported by another device
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportStateRequest {
+    /// All chunks of the export, in any order
+    pub chunks: Vec<String>,
+}
+
+/// Response for importing a wallet state
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportStateResponse {
+    /// Number of payments that were added or labelled
+    pub payments_imported: u32,
+    /// Number of notes that were added
+    pub notes_imported: u32,
+}
+
+/// Request for listing the unspent onchain outputs of the wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListOnchainUtxosRequest {}
+
+/// Response for listing onchain outputs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListOnchainUtxosResponse {
+    /// The unspent outputs
+    pub utxos: Vec<OnchainUtxo>,
+}
+
+/// An unspent onchain output held by the wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OnchainUtxo {
+    /// Transaction ID of the output
+    pub txid: String,
+    /// Index of the output in its transaction
+    pub vout: u32,
+    /// Amount in satoshis
+    pub amount_sat: u64,
+    /// Address holding the output
+    pub address: String,
+    /// Where the output comes from
+    pub kind: OnchainUtxoKind,
+    /// Number of confirmations, 0 while unconfirmed
+    pub confirmations: u32,
+}
+
+/// Origin of an onchain output held by the wallet
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum OnchainUtxoKind {
+    /// Funds sent to the boarding address, waiting to be boarded
+    Boarding,
+    /// Funds recovered through a unilateral exit
+    Exit,
+}
+
+impl fmt::Display for OnchainUtxoKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnchainUtxoKind::Boarding => write!(f, "Boarding"),
+            OnchainUtxoKind::Exit => write!(f, "Exit"),
+        }
+    }
+}
+
+/// A send that was started but didn't complete, kept so it can be resumed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingSend {
+    /// Unique identifier of the send
+    pub id: String,
+    /// The Ark address to pay
+    pub destination: String,
+    /// Amount in satoshis
+    pub amount_sat: u64,
+    /// Estimated fees in satoshis
+    pub fees_sat: u64,
+    /// Unix timestamp when the send was first attempted
+    pub created_at: u64,
+    /// Number of attempts made so far
+    pub attempts: u32,
+    /// Error of the last failed attempt
+    pub last_error: Option<String>,
+    /// Kind and details of the last failed attempt
+    pub failure_reason: Option<PaymentFailureReason>,
+    /// Identifies the payment flow the send belongs to in the SDK logs and events
+    pub correlation_id: String,
+    /// Whether the last attempt may have reached the Ark server
+    pub submission: SendSubmission,
+    /// Outpoints of the VTXOs that were spendable when the last attempt started, the only ones
+    /// it could spend
+    pub attempt_inputs: Vec<String>,
+    /// Unix timestamp when the last attempt started
+    pub attempt_started_at: Option<u64>,
+}
+
+/// What's known about the last attempt of a [PendingSend] reaching the Ark server
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum SendSubmission {
+    /// No attempt reached the Ark server, so the send can be retried or cancelled
+    NotSubmitted,
+    /// The last attempt may have reached the Ark server, e.g. the app was closed while it ran.
+    /// The send is reconciled with the wallet's VTXOs and history before it can be retried or
+    /// cancelled
+    Unknown,
+}
+
+/// Request for retrying a send that failed midway
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RetryPaymentRequest {
+    /// The ID of the retryable payment
+    pub id: String,
+}
+
+/// Response for retrying a send
+#[derive(Clone, Debug, Serialize)]
+pub struct RetryPaymentResponse {
+    /// The sent payment
+    pub payment: Payment,
+}
+
+/// Request for cancelling a send that didn't reach the Ark server
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CancelPaymentRequest {
+    /// The ID of the pending or retryable payment
+    pub id: String,
+}
+
+/// Response for cancelling a send
+#[derive(Clone, Debug, Serialize)]
+pub struct CancelPaymentResponse {
+    /// The cancelled payment
+    pub payment: Payment,
+}
+
+/// Golden tests guarding the serialized form of models
+///
+/// Payments and events are persisted and passed through the bindings, so their JSON form is a
+/// compatibility guarantee:
+/// - fields and variants are never renamed or removed, use `#[serde(rename)]` to keep the old
+///   name when renaming them in Rust
+/// - new fields of persisted or configured models need `#[serde(default)]`, so data written by
+///   older versions still parses
+/// - fixtures are never edited, a new format gets a new fixture next to the old one
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::SdkEvent;
+    use bitcoin::hashes::Hash;
+    use serde::de::DeserializeOwned;
+    use serde_json::Value;
+
+    /// Asserts that a fixture parses and serializes back to exactly the same JSON
+    fn assert_round_trip<T: Serialize + DeserializeOwned>(fixture: &str) {
+        let parsed: T = serde_json::from_str(fixture).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(fixture).unwrap(),
+            serde_json::to_value(parsed).unwrap()
+        );
+    }
+
+    /// Asserts that a fixture of an older format parses and keeps all its values when
+    /// serialized again, only adding the fields introduced since
+    fn assert_backward_compatible<T: Serialize + DeserializeOwned>(fixture: &str) {
+        fn is_kept(old: &Value, new: &Value) -> bool {
+            match (old, new) {
+                (Value::Object(old), Value::Object(new)) => old
+                    .iter()
+                    .all(|(key, old)| new.get(key).is_some_and(|new| is_kept(old, new))),
+                (Value::Array(old), Value::Array(new)) => {
+                    old.len() == new.len()
+                        && old.iter().zip(new).all(|(old, new)| is_kept(old, new))
+                }
+                (old, new) => old == new,
+            }
+        }
+
+        let parsed: T = serde_json::from_str(fixture).unwrap();
+        let old = serde_json::from_str::<Value>(fixture).unwrap();
+        let new = serde_json::to_value(parsed).unwrap();
+        assert!(is_kept(&old, &new), "{old} isn't kept in {new}");
+    }
+
+    #[test]
+    fn test_payment_serialization() {
+        assert_round_trip::<Payment>(include_str!(
+            "../fixtures/serialization/payment_onchain_txs.json"
+        ));
+        assert_round_trip::<Payment>(include_str!(
+            "../fixtures/serialization/payment_failed.json"
+        ));
+
+        // Payments persisted before onchain transactions were recorded
+        assert_backward_compatible::<Payment>(include_str!(
+            "../fixtures/serialization/payment.json"
+        ));
+
+        // Payments persisted before retries and details existed
+        let payment: Payment =
+            serde_json::from_str(include_str!("../fixtures/serialization/payment_v0.json"))
+                .unwrap();
+        assert!(!payment.is_retryable);
+        assert!(payment.details.is_none());
+        assert!(payment.onchain_txs.is_empty());
+    }
+
+    #[test]
+    fn test_config_serialization() {
+        assert_round_trip::<Config>(include_str!("../fixtures/serialization/config.json"));
+
+        // Configs written before the optional settings existed
+        let config: Config =
+            serde_json::from_str(include_str!("../fixtures/serialization/config_v0.json")).unwrap();
+        assert!(config.additional_ark_servers.is_empty());
+        assert!(!config.in_memory);
+        assert!(config.lnurl_server_url.is_none());
+    }
+
+    #[test]
+    fn test_sdk_event_serialization() {
+        assert_round_trip::<Vec<SdkEvent>>(include_str!(
+            "../fixtures/serialization/sdk_events_onchain_txs.json"
+        ));
+        assert_backward_compatible::<Vec<SdkEvent>>(include_str!(
+            "../fixtures/serialization/sdk_events.json"
+        ));
+    }
+
+    #[test]
+    fn test_limits_check() {
+        let limits = Limits {
+            min_sat: 330,
+            max_sat: Some(100_000),
+        };
+        assert!(limits.check(330).is_ok());
+        assert!(limits.check(100_000).is_ok());
+        assert!(matches!(
+            limits.check(329),
+            Err(SdkError::AmountTooSmall { min_sat: 330, .. })
+        ));
+        assert!(matches!(
+            limits.check(100_001),
+            Err(SdkError::AmountTooLarge {
+                max_sat: 100_000,
+                ..
+            })
+        ));
+
+        let unbounded = Limits {
+            max_sat: None,
+            ..limits
+        };
+        assert!(unbounded.check(u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_keep_local_details() {
+        let stored: Payment = serde_json::from_str(include_str!(
+            "../fixtures/serialization/payment_onchain_txs.json"
+        ))
+        .unwrap();
+        let mut synced = Payment {
+            description: None,
+            destination: None,
+            fees: 0,
+            onchain_txs: Vec::new(),
+            ..stored.clone()
+        };
+
+        synced.keep_local_details(&stored);
+        assert_eq!(stored.description, synced.description);
+        assert_eq!(stored.destination, synced.destination);
+        assert_eq!(stored.fees, synced.fees);
+        assert_eq!(stored.onchain_txs, synced.onchain_txs);
+    }
+
+    #[test]
+    fn test_status_description() {
+        let payment = Payment::from(ArkTransaction::Redeem {
+            txid: bitcoin::Txid::all_zeros(),
+            amount: bitcoin::SignedAmount::from_sat(1_000),
+            is_settled: false,
+            created_at: 1_700_000_000,
+        });
+        let description = payment.status_description();
+        assert_eq!("payment.pending_settlement", description.key);
+        assert_eq!(
+            Some(&"1000".to_string()),
+            description.params.get("amount_sat")
+        );
+
+        let settled = Payment {
+            status: PaymentStatus::Completed,
+            ..payment
+        };
+        assert_eq!(
+            "payment.received.completed",
+            settled.status_description().key
+        );
+    }
 }
@@ -0,0 +1,94 @@
+use bitcoin::bech32::{self, Bech32m, Hrp};
+use bitcoin::secp256k1::SecretKey;
+
+use crate::error::SdkError;
+
+/// Human readable prefix of a gift note created by this SDK
+pub(crate) const GIFT_NOTE_HRP: &str = "arkgift";
+/// Description of the payment recording a redeemed note
+pub(crate) const REDEEMED_NOTE_DESCRIPTION: &str = "Ark note";
+/// Description of the payment recording a reclaimed note
+pub(crate) const RECLAIMED_NOTE_DESCRIPTION: &str = "Reclaimed note";
+
+/// A bearer note created by this SDK
+///
+/// Unlike operator-issued Ark notes, a gift note is backed by a regular VTXO locked to an
+/// ephemeral key. Whoever holds the encoded note holds the key and can sweep the VTXO.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GiftNote {
+    pub(crate) secret_key: SecretKey,
+    pub(crate) amount_sat: u64,
+    pub(crate) expires_at: u64,
+}
+
+impl GiftNote {
+    pub(crate) fn encode(&self) -> Result<String, SdkError> {
+        let mut data = Vec::with_capacity(48);
+        data.extend_from_slice(&self.secret_key.secret_bytes());
+        data.extend_from_slice(&self.amount_sat.to_be_bytes());
+        data.extend_from_slice(&self.expires_at.to_be_bytes());
+
+        let hrp = Hrp::parse(GIFT_NOTE_HRP)
+            .map_err(|e| SdkError::GenericError(format!("Invalid note prefix: {e}")))?;
+        bech32::encode::<Bech32m>(hrp, &data)
+            .map_err(|e| SdkError::GenericError(format!("Failed to encode note: {e}")))
+    }
+
+    pub(crate) fn decode(note: &str) -> Result<Self, SdkError> {
+        let (hrp, data) = bech32::decode(note.trim())
+            .map_err(|e| SdkError::InvalidInput(format!("Invalid gift note: {e}")))?;
+        if hrp.to_lowercase() != GIFT_NOTE_HRP || data.len() != 48 {
+            return Err(SdkError::InvalidInput("Invalid gift note".to_string()));
+        }
+
+        let secret_key = SecretKey::from_slice(&data[..32])
+            .map_err(|e| SdkError::InvalidInput(format!("Invalid gift note key: {e}")))?;
+        let amount_sat = u64::from_be_bytes(data[32..40].try_into().unwrap_or_default());
+        let expires_at = u64::from_be_bytes(data[40..48].try_into().unwrap_or_default());
+
+        Ok(Self {
+            secret_key,
+            amount_sat,
+            expires_at,
+        })
+    }
+
+    /// Identifier of the note, derived from its ephemeral key so it never reveals the secret
+    pub(crate) fn id(&self) -> String {
+        let secp = bitcoin::key::Secp256k1::new();
+        self.secret_key.x_only_public_key(&secp).0.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gift_note_roundtrip() {
+        let note = GiftNote {
+            secret_key: SecretKey::from_slice(&[7u8; 32]).unwrap(),
+            amount_sat: 21_000,
+            expires_at: 1_700_000_000,
+        };
+
+        let encoded = note.encode().unwrap();
+        assert!(encoded.starts_with(GIFT_NOTE_HRP));
+        assert_eq!(note, GiftNote::decode(&encoded).unwrap());
+        assert_eq!(note, GiftNote::decode(&encoded.to_uppercase()).unwrap());
+    }
+
+    #[test]
+    fn test_gift_note_rejects_corrupted_input() {
+        let note = GiftNote {
+            secret_key: SecretKey::from_slice(&[7u8; 32]).unwrap(),
+            amount_sat: 21_000,
+            expires_at: 1_700_000_000,
+        };
+        let mut encoded = note.encode().unwrap();
+        let last = encoded.pop().unwrap();
+        encoded.push(if last == 'q' { 'p' } else { 'q' });
+
+        assert!(GiftNote::decode(&encoded).is_err());
+    }
+}
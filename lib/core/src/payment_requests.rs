@@ -0,0 +1,320 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{
+    Payment, PaymentDetails, PaymentMethod, PaymentRequest, PaymentRequestStatus, PaymentStatus,
+    PaymentType,
+};
+use crate::notes::{RECLAIMED_NOTE_DESCRIPTION, REDEEMED_NOTE_DESCRIPTION};
+
+/// How long a payment request is tracked as outstanding when no expiry is given
+pub(crate) const DEFAULT_PAYMENT_REQUEST_EXPIRY_SECS: u64 = 24 * 60 * 60;
+
+/// Matches pending payment requests against the received payments
+///
+/// The wallet hands out the same Ark and boarding addresses to every payer, so payments can't be
+/// tied to a request by destination. A request is paid instead by the first received payment of
+/// its method that arrived while the request was open, for exactly the requested amount and not
+/// already claimed by another request. Older requests are matched first. Requests without an
+/// amount can't be told apart from any other payment, so they're never matched and expire.
+///
+/// Redeemed and reclaimed notes are received payments too, but never pay a request.
+///
+/// Returns the requests whose status changed.
+pub(crate) fn reconcile(
+    requests: &[PaymentRequest],
+    payments: &[Payment],
+    now: u64,
+) -> Vec<PaymentRequest> {
+    let mut claimed: HashSet<&str> = requests
+        .iter()
+        .filter_map(|request| request.payment_id.as_deref())
+        .collect();
+
+    let mut pending: Vec<&PaymentRequest> = requests
+        .iter()
+        .filter(|request| request.status == PaymentRequestStatus::Pending)
+        .collect();
+    pending.sort_by_key(|request| request.created_at);
+
+    let mut updated = Vec::new();
+    for request in pending {
+        let payment = payments.iter().find(|payment| {
+            !claimed.contains(payment.id.as_str()) && pays_request(payment, request)
+        });
+        match payment {
+            Some(payment) => {
+                claimed.insert(&payment.id);
+                updated.push(PaymentRequest {
+                    status: PaymentRequestStatus::Paid,
+                    payment_id: Some(payment.id.clone()),
+                    ..request.clone()
+                });
+            }
+            None if request.expires_at <= now => updated.push(PaymentRequest {
+                status: PaymentRequestStatus::Expired,
+                ..request.clone()
+            }),
+            None => {}
+        }
+    }
+    updated
+}
+
+/// Finds an open request that a new request for the given method and amount couldn't be told
+/// apart from, when either of them is for an order
+///
+/// Both would be paid to the same shared address with the same amount, so a payment could be
+/// attributed to the wrong order.
+pub(crate) fn conflicting_order<'a>(
+    requests: &'a [PaymentRequest],
+    payment_method: &PaymentMethod,
+    amount_sat: Option<u64>,
+    has_order: bool,
+    now: u64,
+) -> Option<&'a PaymentRequest> {
+    let kind = shared_address_kind(payment_method)?;
+    requests.iter().find(|request| {
+        request.status == PaymentRequestStatus::Pending
+            && request.expires_at > now
+            && (has_order || request.order_id.is_some())
+            && shared_address_kind(&request.payment_method) == Some(kind)
+            && request.amount_sat == amount_sat
+    })
+}
+
+/// The kind of shared address a method pays to, if it does
+fn shared_address_kind(payment_method: &PaymentMethod) -> Option<&'static str> {
+    match payment_method {
+        PaymentMethod::ArkAddress { .. } => Some("ark"),
+        PaymentMethod::BitcoinAddress { .. } | PaymentMethod::ExternalDeposit { .. } => {
+            Some("boarding")
+        }
+        PaymentMethod::Bolt11Invoice { .. } | PaymentMethod::Bolt12Offer => None,
+    }
+}
+
+/// Copies the order ID, description and deposit source of each paid request to the payment
+/// that paid it
+///
+/// A description the payment already has, e.g. set when it was received, is kept.
+pub(crate) fn tag_payments(requests: &[PaymentRequest], payments: &mut [Payment]) {
+    let paid_requests: HashMap<&str, &PaymentRequest> = requests
+        .iter()
+        .filter_map(|request| Some((request.payment_id.as_deref()?, request)))
+        .collect();
+    for payment in payments {
+        let Some(request) = paid_requests.get(payment.id.as_str()) else {
+            continue;
+        };
+        if let Some(order_id) = &request.order_id {
+            payment.order_id = Some(order_id.clone());
+        }
+        if payment.description.is_none() {
+            payment.description = request.description.clone();
+        }
+        if let PaymentMethod::ExternalDeposit { source, .. } = &request.payment_method {
+            payment.deposit_source = Some(source.clone());
+        }
+    }
+}
+
+fn pays_request(payment: &Payment, request: &PaymentRequest) -> bool {
+    let method_matches = matches!(
+        (&request.payment_method, &payment.details),
+        (
+            PaymentMethod::ArkAddress { .. },
+            Some(PaymentDetails::Ark { .. })
+        ) | (
+            PaymentMethod::BitcoinAddress { .. } | PaymentMethod::ExternalDeposit { .. },
+            Some(PaymentDetails::Onchain { .. })
+        )
+    );
+    let from_note = matches!(
+        payment.description.as_deref(),
+        Some(REDEEMED_NOTE_DESCRIPTION | RECLAIMED_NOTE_DESCRIPTION)
+    );
+    method_matches
+        && !from_note
+        && payment.payment_type == PaymentType::Received
+        && payment.status != PaymentStatus::Failed
+        && (request.created_at..=request.expires_at).contains(&payment.timestamp)
+        && request.amount_sat == Some(payment.amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(id: &str, amount_sat: Option<u64>, created_at: u64) -> PaymentRequest {
+        PaymentRequest {
+            id: id.to_string(),
+            payment_method: PaymentMethod::ArkAddress {
+                receiver_amount_sat: amount_sat,
+            },
+            destination: "tark1destination".to_string(),
+            amount_sat,
+            created_at,
+            expires_at: created_at + DEFAULT_PAYMENT_REQUEST_EXPIRY_SECS,
+            status: PaymentRequestStatus::Pending,
+            payment_id: None,
+            order_id: None,
+            description: None,
+        }
+    }
+
+    fn received(id: &str, amount: u64, timestamp: u64) -> Payment {
+        Payment {
+            id: id.to_string(),
+            payment_type: PaymentType::Received,
+            status: PaymentStatus::Completed,
+            amount,
+            fees: 0,
+            timestamp,
+            description: None,
+            destination: None,
+            is_retryable: false,
+            details: Some(PaymentDetails::Ark {
+                txid: id.to_string(),
+                round_txid: None,
+            }),
+            onchain_txs: Vec::new(),
+            order_id: None,
+            deposit_source: None,
+            failure_reason: None,
+        }
+    }
+
+    #[test]
+    fn test_reconcile_payment_requests() {
+        let requests = [
+            request("first", Some(5_000), 1_000),
+            request("second", Some(6_000), 1_100),
+            request("unpaid", Some(9_000), 1_200),
+            request("any_amount", None, 1_200),
+        ];
+        let payments = [
+            // Received before any request was generated
+            received("early", 5_000, 900),
+            // More than requested, e.g. another payer's payment to the shared address
+            received("larger", 9_500, 1_250),
+            Payment {
+                description: Some(REDEEMED_NOTE_DESCRIPTION.to_string()),
+                ..received("note", 9_000, 1_250)
+            },
+            received("payment_2", 6_000, 1_300),
+            received("payment_1", 5_000, 1_400),
+        ];
+
+        let updated = reconcile(&requests, &payments, 2_000);
+        assert_eq!(2, updated.len());
+        assert_eq!(Some("payment_1".to_string()), updated[0].payment_id);
+        assert_eq!(Some("payment_2".to_string()), updated[1].payment_id);
+        assert!(updated
+            .iter()
+            .all(|request| request.status == PaymentRequestStatus::Paid));
+
+        // Payments already claimed by a request aren't matched again
+        let requests = [
+            PaymentRequest {
+                status: PaymentRequestStatus::Paid,
+                payment_id: Some("payment_1".to_string()),
+                ..request("first", Some(5_000), 1_000)
+            },
+            request("second", Some(5_000), 1_100),
+        ];
+        let payments = [received("payment_1", 5_000, 1_300)];
+        assert!(reconcile(&requests, &payments, 2_000).is_empty());
+
+        // Unpaid requests expire
+        let updated = reconcile(
+            &requests,
+            &payments,
+            1_100 + DEFAULT_PAYMENT_REQUEST_EXPIRY_SECS,
+        );
+        assert_eq!(1, updated.len());
+        assert_eq!(PaymentRequestStatus::Expired, updated[0].status);
+    }
+
+    #[test]
+    fn test_conflicting_order() {
+        let ark = |amount_sat| PaymentMethod::ArkAddress {
+            receiver_amount_sat: amount_sat,
+        };
+        let requests = [
+            PaymentRequest {
+                order_id: Some("order_1".to_string()),
+                ..request("first", Some(5_000), 1_000)
+            },
+            request("second", Some(6_000), 1_000),
+        ];
+
+        // A second order for the same amount couldn't be told apart from the first
+        let conflict = conflicting_order(&requests, &ark(Some(5_000)), Some(5_000), true, 2_000);
+        assert_eq!(Some("first"), conflict.map(|request| request.id.as_str()));
+        // Nor could a plain request, as its payment could be attributed to the order
+        assert!(
+            conflicting_order(&requests, &ark(Some(5_000)), Some(5_000), false, 2_000).is_some()
+        );
+        // Requests without orders may share an amount
+        assert!(
+            conflicting_order(&requests, &ark(Some(6_000)), Some(6_000), false, 2_000).is_none()
+        );
+        assert!(
+            conflicting_order(&requests, &ark(Some(6_000)), Some(6_000), true, 2_000).is_some()
+        );
+
+        // Boarding addresses aren't shared with Ark addresses
+        let onchain = PaymentMethod::BitcoinAddress {
+            receiver_amount_sat: Some(5_000),
+        };
+        assert!(conflicting_order(&requests, &onchain, Some(5_000), true, 2_000).is_none());
+
+        // Expired and paid requests are no longer matched
+        let expired_at = 1_000 + DEFAULT_PAYMENT_REQUEST_EXPIRY_SECS;
+        assert!(
+            conflicting_order(&requests, &ark(Some(5_000)), Some(5_000), true, expired_at)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_tag_payments() {
+        let requests = [
+            PaymentRequest {
+                status: PaymentRequestStatus::Paid,
+                payment_id: Some("payment_1".to_string()),
+                order_id: Some("order_1".to_string()),
+                description: Some("Coffee".to_string()),
+                ..request("first", None, 1_000)
+            },
+            PaymentRequest {
+                order_id: Some("order_2".to_string()),
+                ..request("second", None, 1_100)
+            },
+        ];
+        let mut payments = [
+            received("payment_1", 5_000, 1_300),
+            received("payment_2", 5_000, 1_400),
+        ];
+
+        tag_payments(&requests, &mut payments);
+        assert_eq!(Some("order_1".to_string()), payments[0].order_id);
+        assert_eq!(Some("Coffee".to_string()), payments[0].description);
+        // The order of an unpaid request isn't guessed
+        assert_eq!(None, payments[1].order_id);
+        assert_eq!(None, payments[1].description);
+        assert!(payments.iter().all(|p| p.deposit_source.is_none()));
+
+        let requests = [PaymentRequest {
+            payment_method: PaymentMethod::ExternalDeposit {
+                receiver_amount_sat: None,
+                source: "Coldcard".to_string(),
+            },
+            status: PaymentRequestStatus::Paid,
+            payment_id: Some("payment_1".to_string()),
+            ..request("deposit", None, 1_000)
+        }];
+        tag_payments(&requests, &mut payments);
+        assert_eq!(Some("Coldcard".to_string()), payments[0].deposit_source);
+    }
+}
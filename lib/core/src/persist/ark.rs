@@ -1,46 +1,93 @@
-use std::{collections::HashMap, sync::RwLock};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
 
-use ark_client::{wallet::Persistence, Error};
+use ark_client::{error::IntoError, wallet::Persistence, Error};
 use ark_core::BoardingOutput;
 use bitcoin::{secp256k1::SecretKey, XOnlyPublicKey};
 
+/// Wallet persistence shared between the SDK and the Ark client
+///
+/// Wraps the implementation given to [crate::sdk_builder::SdkBuilder::wallet_persistence], so
+/// the Ark client can be used with any [Persistence] implementation.
+#[derive(Clone)]
+pub(crate) struct SharedPersistence(pub(crate) Arc<dyn Persistence + Send + Sync>);
+
+impl Persistence for SharedPersistence {
+    fn save_boarding_output(
+        &self,
+        sk: SecretKey,
+        boarding_output: BoardingOutput,
+    ) -> Result<(), Error> {
+        self.0.save_boarding_output(sk, boarding_output)
+    }
+
+    fn load_boarding_outputs(&self) -> Result<Vec<BoardingOutput>, Error> {
+        self.0.load_boarding_outputs()
+    }
+
+    fn sk_for_pk(&self, pk: &XOnlyPublicKey) -> Result<SecretKey, Error> {
+        self.0.sk_for_pk(pk)
+    }
+}
+
 #[derive(Default)]
 pub struct InMemoryDb {
     boarding_outputs: RwLock<HashMap<BoardingOutput, SecretKey>>,
 }
 
+impl InMemoryDb {
+    fn read(&self) -> Result<RwLockReadGuard<'_, HashMap<BoardingOutput, SecretKey>>, Error> {
+        self.boarding_outputs
+            .read()
+            .map_err(|_| "Failed to lock boarding outputs".to_string().into_error())
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, HashMap<BoardingOutput, SecretKey>>, Error> {
+        self.boarding_outputs
+            .write()
+            .map_err(|_| "Failed to lock boarding outputs".to_string().into_error())
+    }
+}
+
 impl Persistence for InMemoryDb {
     fn save_boarding_output(
         &self,
         sk: SecretKey,
         boarding_output: BoardingOutput,
     ) -> Result<(), Error> {
-        self.boarding_outputs
-            .write()
-            .unwrap()
-            .insert(boarding_output, sk);
+        self.write()?.insert(boarding_output, sk);
 
         Ok(())
     }
 
     fn load_boarding_outputs(&self) -> Result<Vec<BoardingOutput>, Error> {
-        Ok(self
-            .boarding_outputs
-            .read()
-            .unwrap()
-            .keys()
-            .cloned()
-            .collect())
+        Ok(self.read()?.keys().cloned().collect())
     }
 
     fn sk_for_pk(&self, pk: &XOnlyPublicKey) -> Result<SecretKey, Error> {
-        let maybe_sk = self
-            .boarding_outputs
-            .read()
-            .unwrap()
+        self.read()?
             .iter()
-            .find_map(|(b, sk)| if b.owner_pk() == *pk { Some(*sk) } else { None });
-        let secret_key = maybe_sk.unwrap();
-        Ok(secret_key)
+            .find_map(|(b, sk)| if b.owner_pk() == *pk { Some(*sk) } else { None })
+            .ok_or_else(|| format!("No boarding output owned by key {pk}").into_error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::SdkError;
+    use bitcoin::secp256k1::{Keypair, Secp256k1};
+
+    #[test]
+    fn test_sk_for_unknown_pk() {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, &SecretKey::from_slice(&[1; 32]).unwrap());
+        let (pk, _) = keypair.x_only_public_key();
+
+        // A key the wallet never boarded with is an error for the caller, not a panic
+        let err = InMemoryDb::default().sk_for_pk(&pk).unwrap_err();
+        assert!(matches!(SdkError::from(err), SdkError::ArkClientError(_)));
     }
 }
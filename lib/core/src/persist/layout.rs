@@ -0,0 +1,123 @@
+use std::fs;
+use std::path::Path;
+
+use log::info;
+
+use crate::error::SdkError;
+
+/// Version of the data directory layout written by this SDK version
+const LAYOUT_VERSION: u32 = 1;
+/// File recording the layout version of a data directory
+const LAYOUT_VERSION_FILE: &str = "layout_version";
+
+/// Moves a data directory from one layout version to the next
+type Migration = fn(&Path) -> Result<(), SdkError>;
+
+/// Migrations indexed by the layout version they migrate from
+///
+/// A storage reorganization bumps [LAYOUT_VERSION] and appends the migration moving existing
+/// data directories to the new layout.
+const MIGRATIONS: [Migration; LAYOUT_VERSION as usize] = [adopt_unversioned_layout];
+
+/// Directories created before layout versioning already use the first layout
+fn adopt_unversioned_layout(_dir: &Path) -> Result<(), SdkError> {
+    Ok(())
+}
+
+/// Prepares a data directory for use, creating it or migrating it to the current layout
+///
+/// New directories are initialized under a temporary name and moved into place, so a crash
+/// never leaves a half initialized directory behind. Migrations record their progress after
+/// each step, so an interrupted migration resumes where it stopped.
+pub(crate) fn prepare_data_dir(dir: &Path) -> Result<(), SdkError> {
+    if !dir.exists() {
+        return init_data_dir(dir);
+    }
+
+    let version = read_layout_version(dir)?;
+    if version > LAYOUT_VERSION {
+        return Err(SdkError::StorageError(format!(
+            "Data directory {} uses layout version {version}, written by a newer SDK version",
+            dir.display()
+        )));
+    }
+    for from_version in version..LAYOUT_VERSION {
+        info!(
+            "Migrating data directory {} from layout version {from_version}",
+            dir.display()
+        );
+        MIGRATIONS[from_version as usize](dir)?;
+        write_layout_version(dir, from_version + 1)?;
+    }
+    Ok(())
+}
+
+fn init_data_dir(dir: &Path) -> Result<(), SdkError> {
+    let parent = dir.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+    let dir_name = dir
+        .file_name()
+        .ok_or_else(|| SdkError::StorageError(format!("Invalid data directory {}", dir.display())))?
+        .to_string_lossy();
+    let tmp_dir = parent.join(format!(".{dir_name}.{}.tmp", uuid::Uuid::new_v4()));
+
+    fs::create_dir(&tmp_dir)?;
+    write_layout_version(&tmp_dir, LAYOUT_VERSION)?;
+    if let Err(e) = fs::rename(&tmp_dir, dir) {
+        fs::remove_dir_all(&tmp_dir)?;
+        // Another process initialized the directory first
+        if !dir.exists() {
+            return Err(e.into());
+        }
+    }
+    Ok(())
+}
+
+/// Reads the layout version, directories without a version file predate versioning
+fn read_layout_version(dir: &Path) -> Result<u32, SdkError> {
+    match fs::read_to_string(dir.join(LAYOUT_VERSION_FILE)) {
+        Ok(content) => content.trim().parse().map_err(|_| {
+            SdkError::StorageError(format!("Invalid layout version in {}", dir.display()))
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes the layout version atomically, by renaming a fully written file into place
+fn write_layout_version(dir: &Path, version: u32) -> Result<(), SdkError> {
+    let tmp_file = dir.join(format!("{LAYOUT_VERSION_FILE}.tmp"));
+    fs::write(&tmp_file, version.to_string())?;
+    fs::rename(&tmp_file, dir.join(LAYOUT_VERSION_FILE))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_data_dir() {
+        let root = std::env::temp_dir().join(format!("breez-sdk-ark-{}", uuid::Uuid::new_v4()));
+
+        // New directories start at the current layout
+        let new_dir = root.join("regtest");
+        prepare_data_dir(&new_dir).unwrap();
+        assert_eq!(LAYOUT_VERSION, read_layout_version(&new_dir).unwrap());
+        assert_eq!(1, fs::read_dir(&root).unwrap().count());
+
+        // Directories created before versioning are migrated in place
+        let old_dir = root.join("signet");
+        fs::create_dir_all(&old_dir).unwrap();
+        fs::write(old_dir.join("breez-sdk-ark.db"), "data").unwrap();
+        prepare_data_dir(&old_dir).unwrap();
+        assert_eq!(LAYOUT_VERSION, read_layout_version(&old_dir).unwrap());
+        assert!(old_dir.join("breez-sdk-ark.db").exists());
+
+        // Layouts written by a newer SDK are left alone
+        write_layout_version(&old_dir, LAYOUT_VERSION + 1).unwrap();
+        assert!(prepare_data_dir(&old_dir).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,86 @@
+use std::fs::{self, File, OpenOptions};
+use std::path::Path;
+
+use fs2::FileExt;
+
+use crate::error::SdkError;
+
+/// Extension of the file next to the data directory locked by the process using the wallet
+const LOCK_FILE_EXTENSION: &str = "lock";
+
+/// How the SDK holds on to its data directory
+///
+/// A wallet syncing from two processes would interleave their writes, so the process using a
+/// data directory holds an advisory lock on it until the SDK is dropped. The lock is released by
+/// the OS if the process dies, so a crash never leaves a stale lock behind.
+pub enum WalletLock {
+    /// The data directory is locked for this process, until the file is closed
+    Exclusive { _file: File },
+    /// The data directory is opened read-only, without taking the lock
+    ReadOnly,
+    /// The wallet doesn't keep data in the data directory, e.g. in-memory wallets
+    Unlocked,
+}
+
+impl WalletLock {
+    /// Locks a data directory, failing with [SdkError::AlreadyRunning] if another process
+    /// holds the lock
+    ///
+    /// The lock file sits next to the directory, so the lock is held before the directory is
+    /// created or migrated.
+    pub(crate) fn acquire(dir: &Path) -> Result<Self, SdkError> {
+        let parent = dir.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(parent)?;
+        let dir_name = dir.file_name().ok_or_else(|| {
+            SdkError::StorageError(format!("Invalid data directory {}", dir.display()))
+        })?;
+        let path = parent.join(format!(
+            "{}.{LOCK_FILE_EXTENSION}",
+            dir_name.to_string_lossy()
+        ));
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+        file.try_lock_exclusive().map_err(|e| {
+            if e.kind() == fs2::lock_contended_error().kind() {
+                SdkError::AlreadyRunning(format!(
+                    "Data directory {} is in use by another process",
+                    dir.display()
+                ))
+            } else {
+                e.into()
+            }
+        })?;
+        Ok(WalletLock::Exclusive { _file: file })
+    }
+
+    pub(crate) fn is_read_only(&self) -> bool {
+        matches!(self, WalletLock::ReadOnly)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wallet_lock() {
+        let root = std::env::temp_dir().join(format!("wallet-lock-{}", uuid::Uuid::new_v4()));
+        // The directory doesn't need to exist yet
+        let dir = root.join("regtest");
+
+        let lock = WalletLock::acquire(&dir).unwrap();
+        assert!(!dir.exists());
+        assert!(matches!(
+            WalletLock::acquire(&dir),
+            Err(SdkError::AlreadyRunning(_))
+        ));
+
+        // The directory can be used again once the SDK holding it is dropped
+        drop(lock);
+        assert!(WalletLock::acquire(&dir).is_ok());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
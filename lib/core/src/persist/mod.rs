@@ -1,26 +1,227 @@
 pub(crate) mod ark;
+pub(crate) mod layout;
+pub(crate) mod lock;
 pub(crate) mod sqlite;
 
 use crate::error::SdkError;
-use crate::models::{OffchainBalance, Payment, PaymentStatus, PaymentType};
+use crate::history::{HISTORY_REFRESH_SECS, PAYMENT_CONVERSION_VERSION};
+use crate::models::{
+    BoardingBalance, Note, OffchainBalance, Payment, PaymentRequest, PendingSend, RetentionAction,
+    ServerBalance, SortDirection,
+};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Position in the payment history a page of payments continues after
+///
+/// Payments are ordered by timestamp, with the ID breaking ties, so a cursor stays valid when
+/// payments are added before or after it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaymentCursor {
+    pub timestamp: u64,
+    pub id: String,
+}
+
+impl PaymentCursor {
+    /// Cursor continuing after the given payment
+    pub fn after(payment: &Payment) -> Self {
+        Self {
+            timestamp: payment.timestamp,
+            id: payment.id.clone(),
+        }
+    }
+
+    /// Cursor before every payment when listing newest first
+    pub(crate) fn newest() -> Self {
+        Self {
+            timestamp: i64::MAX as u64,
+            id: String::new(),
+        }
+    }
+
+    /// Encodes the cursor as an opaque string for API callers
+    pub(crate) fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}:{}", self.timestamp, self.id))
+    }
+
+    pub(crate) fn decode(cursor: &str) -> Result<Self, SdkError> {
+        let invalid = || SdkError::InvalidInput(format!("Invalid payments cursor {cursor}"));
+        let decoded = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+        let (timestamp, id) = decoded.split_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            timestamp: timestamp.parse().map_err(|_| invalid())?,
+            id: id.to_string(),
+        })
+    }
+}
+
+/// The Ark transaction history whose payments were last saved
+///
+/// The Ark server doesn't version its transaction history, so the wallet fingerprints it
+/// instead. A sync finding the same history skips converting and saving the payments again,
+/// and a sync finding the same balances skips fetching the history.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryCheckpoint {
+    /// Fingerprint of the transaction history
+    pub fingerprint: String,
+    /// When the next pending payment expires, after which the payments need saving again even
+    /// if the history is the same
+    pub revisit_at: Option<u64>,
+    /// Version of the conversion of transactions into payments the payments were saved with.
+    /// Checkpoints saved before it was recorded read as version 0, so they're never current
+    #[serde(default)]
+    pub conversion_version: u32,
+    /// Fingerprint of the balances when the history was last fetched
+    #[serde(default)]
+    pub balances_fingerprint: String,
+    /// Unix timestamp when the history was last fetched
+    #[serde(default)]
+    pub fetched_at: u64,
+}
+
+impl HistoryCheckpoint {
+    /// Whether the payments saved at this checkpoint are up to date for a history
+    pub(crate) fn is_current(&self, fingerprint: &str, now: u64) -> bool {
+        self.fingerprint == fingerprint && self.is_fresh(now)
+    }
+
+    /// Whether the payments saved at this checkpoint are up to date for the balances, without
+    /// fetching the history again
+    pub(crate) fn covers_balances(&self, balances_fingerprint: &str, now: u64) -> bool {
+        self.balances_fingerprint == balances_fingerprint
+            && now < self.fetched_at.saturating_add(HISTORY_REFRESH_SECS)
+            && self.is_fresh(now)
+    }
+
+    fn is_fresh(&self, now: u64) -> bool {
+        self.conversion_version == PAYMENT_CONVERSION_VERSION
+            && self.revisit_at.is_none_or(|revisit_at| now < revisit_at)
+    }
+}
+
+/// Which page of payments to list
+#[derive(Clone, Debug)]
+pub struct PaymentsQuery {
+    /// Number of payments to skip, after the cursor if any
+    pub offset: u32,
+    /// Maximum number of payments to return
+    pub limit: u32,
+    /// List the payments after this one
+    pub cursor: Option<PaymentCursor>,
+    pub sort: SortDirection,
+}
 
 /// Trait for persistent storage implementations
-pub trait Storage {
+///
+/// Methods are async so implementations can perform I/O without blocking the runtime.
+#[async_trait]
+pub trait Storage: Send + Sync {
     /// Save a payment to the storage
-    fn save_payment(&self, payment: &Payment) -> Result<(), SdkError>;
+    async fn save_payment(&self, payment: &Payment) -> Result<(), SdkError>;
 
     /// Save a list of payments and delete any payments that don't exist in the list
-    fn save_payments(&self, payments: &[Payment]) -> Result<(), SdkError>;
+    async fn save_payments(&self, payments: &[Payment]) -> Result<(), SdkError>;
 
     /// Get a payment by ID
-    fn get_payment(&self, id: &str) -> Result<Option<Payment>, SdkError>;
+    async fn get_payment(&self, id: &str) -> Result<Option<Payment>, SdkError>;
 
     /// List payments with pagination
-    fn list_payments(&self, offset: u32, limit: u32) -> Result<Vec<Payment>, SdkError>;
+    async fn list_payments(&self, offset: u32, limit: u32) -> Result<Vec<Payment>, SdkError>;
+
+    /// List a page of payments in the requested order
+    async fn list_payments_page(&self, query: &PaymentsQuery) -> Result<Vec<Payment>, SdkError>;
+
+    /// Move the payments in a final state older than `before` out of the payment history,
+    /// returning how many were moved
+    ///
+    /// Their IDs are remembered either way, so a sync doesn't bring them back.
+    async fn archive_payments(&self, before: u64, action: RetentionAction)
+        -> Result<u32, SdkError>;
+
+    /// Get the IDs of all payments moved out of the payment history
+    async fn archived_payment_ids(&self) -> Result<HashSet<String>, SdkError>;
+
+    /// List the archived payments, oldest first, leaving out pruned ones
+    async fn list_archived_payments(&self) -> Result<Vec<Payment>, SdkError>;
 
     /// Save the offchain balance
-    fn save_offchain_balance(&self, balance: &OffchainBalance) -> Result<(), SdkError>;
+    async fn save_offchain_balance(&self, balance: &OffchainBalance) -> Result<(), SdkError>;
 
     /// Get the offchain balance
-    fn get_offchain_balance(&self) -> Result<OffchainBalance, SdkError>;
+    async fn get_offchain_balance(&self) -> Result<OffchainBalance, SdkError>;
+
+    /// Save the offchain balance held with each Ark server
+    async fn save_server_balances(&self, balances: &[ServerBalance]) -> Result<(), SdkError>;
+
+    /// Get the offchain balance held with each Ark server
+    async fn get_server_balances(&self) -> Result<Vec<ServerBalance>, SdkError>;
+
+    /// Save the deposits waiting on the boarding address
+    async fn save_boarding_balance(&self, balance: &BoardingBalance) -> Result<(), SdkError>;
+
+    /// Get the deposits waiting on the boarding address, none before the first sync
+    async fn get_boarding_balance(&self) -> Result<BoardingBalance, SdkError>;
+
+    /// Save the checkpoint of the transaction history the payments were last saved for
+    async fn save_history_checkpoint(&self, checkpoint: &HistoryCheckpoint)
+        -> Result<(), SdkError>;
+
+    /// Get the checkpoint of the transaction history, `None` before the first sync
+    async fn get_history_checkpoint(&self) -> Result<Option<HistoryCheckpoint>, SdkError>;
+
+    /// Save a note created by the wallet, replacing any note with the same ID
+    async fn save_note(&self, note: &Note) -> Result<(), SdkError>;
+
+    /// Get a note by ID
+    async fn get_note(&self, id: &str) -> Result<Option<Note>, SdkError>;
+
+    /// List all notes created by the wallet, newest first
+    async fn list_notes(&self) -> Result<Vec<Note>, SdkError>;
+
+    /// Get the index of an issued receive address, recording the address with the next free
+    /// index the first time it's issued
+    async fn get_or_insert_receive_address(
+        &self,
+        address: &str,
+        created_at: u64,
+    ) -> Result<u32, SdkError>;
+
+    /// Save a payment request, replacing any request with the same ID
+    async fn save_payment_request(&self, request: &PaymentRequest) -> Result<(), SdkError>;
+
+    /// List all payment requests, newest first
+    async fn list_payment_requests(&self) -> Result<Vec<PaymentRequest>, SdkError>;
+
+    /// Save a send that hasn't completed yet, replacing any send with the same ID
+    async fn save_pending_send(&self, send: &PendingSend) -> Result<(), SdkError>;
+
+    /// Delete a pending send once it completed or was resolved
+    async fn delete_pending_send(&self, id: &str) -> Result<(), SdkError>;
+
+    /// List all sends that haven't completed yet, oldest first
+    async fn list_pending_sends(&self) -> Result<Vec<PendingSend>, SdkError>;
+
+    /// Reserve funds for an in-flight send, if the funds not reserved yet cover it
+    ///
+    /// The check and the reservation are atomic, so concurrent sends can't reserve the same
+    /// funds. Returns `false`, without reserving anything, if `available_sat` minus the existing
+    /// reservations is below `amount_sat`.
+    async fn reserve_funds(
+        &self,
+        id: &str,
+        amount_sat: u64,
+        available_sat: u64,
+    ) -> Result<bool, SdkError>;
+
+    /// Release the funds reserved for a send once it completed or failed
+    async fn release_funds(&self, id: &str) -> Result<(), SdkError>;
+
+    /// Get the total amount currently reserved by in-flight sends
+    async fn get_reserved_sats(&self) -> Result<u64, SdkError>;
+
+    /// Release all reservations, e.g. on startup when no send can be in flight
+    async fn clear_reservations(&self) -> Result<(), SdkError>;
 }
@@ -1,13 +1,27 @@
 use crate::error::SdkError;
-use crate::models::{OffchainBalance, Payment, PaymentStatus, PaymentType};
-use crate::persist::Storage;
+use crate::models::{
+    BoardingBalance, Note, NoteStatus, OffchainBalance, Payment, PaymentFailureReason,
+    PaymentRequest, PaymentRequestStatus, PaymentStatus, PaymentType, PendingSend, RetentionAction,
+    SendSubmission, ServerBalance, SortDirection,
+};
+use crate::persist::{HistoryCheckpoint, PaymentsQuery, Storage};
+use async_trait::async_trait;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
 use rusqlite::types::Type;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OpenFlags, Row};
+use serde::de::DeserializeOwned;
 use serde_json;
+use std::collections::HashSet;
+use std::io::{Read, Write};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 /// SQLite implementation of the Storage trait
+///
+/// All queries run on tokio's blocking thread pool so that large writes don't stall the
+/// async runtime.
 pub struct SqliteStorage {
     connection: Arc<Mutex<Connection>>,
 }
@@ -31,7 +45,29 @@ impl SqliteStorage {
         Ok(storage)
     }
 
-    /// Creates a new in-memory SQLite storage instance for testing
+    /// Opens an existing SQLite database without writing to it
+    ///
+    /// The schema isn't created or migrated, and every write fails with a storage error. Used
+    /// to inspect a wallet while another process has it open.
+    ///
+    /// # Arguments
+    ///
+    /// * `db_path` - Path to the SQLite database file
+    ///
+    /// # Returns
+    ///
+    /// A new read-only `SqliteStorage` instance
+    pub fn open_read_only(db_path: &Path) -> Result<Self, SdkError> {
+        let connection = Connection::open_with_flags(
+            db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Creates a new in-memory SQLite storage instance, for tests and ephemeral wallets
     ///
     /// # Returns
     ///
@@ -61,7 +97,112 @@ impl SqliteStorage {
           fees INTEGER NOT NULL,
           timestamp INTEGER NOT NULL,
           description TEXT,          
-          destination TEXT
+          destination TEXT,
+          details TEXT,
+          onchain_txs TEXT,
+          order_id TEXT,
+          deposit_source TEXT,
+          failure_reason TEXT
+      )",
+            [],
+        )?;
+        Self::add_column_if_missing(&connection, "payments", "details", "TEXT")?;
+        Self::add_column_if_missing(&connection, "payments", "onchain_txs", "TEXT")?;
+        Self::add_column_if_missing(&connection, "payments", "order_id", "TEXT")?;
+        Self::add_column_if_missing(&connection, "payments", "deposit_source", "TEXT")?;
+        Self::add_column_if_missing(&connection, "payments", "failure_reason", "TEXT")?;
+        connection.execute(
+            "CREATE INDEX IF NOT EXISTS payments_timestamp_id ON payments (timestamp, id)",
+            [],
+        )?;
+
+        // Create archive table for payments moved out of the payment history. The data is the
+        // compressed payment, NULL once pruned
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS payments_archive (
+          id TEXT PRIMARY KEY,
+          timestamp INTEGER NOT NULL,
+          data BLOB
+      )",
+            [],
+        )?;
+
+        // Create notes table for tracking funds reserved by created notes
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS notes (
+          id TEXT PRIMARY KEY,
+          note TEXT NOT NULL,
+          amount INTEGER NOT NULL,
+          created_at INTEGER NOT NULL,
+          expires_at INTEGER NOT NULL,
+          status TEXT NOT NULL
+      )",
+            [],
+        )?;
+
+        // Create receive addresses table for tracking the addresses handed out
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS receive_addresses (
+          address TEXT PRIMARY KEY,
+          derivation_index INTEGER NOT NULL UNIQUE,
+          created_at INTEGER NOT NULL
+      )",
+            [],
+        )?;
+
+        // Create payment requests table for tracking the destinations handed out to payers
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS payment_requests (
+          id TEXT PRIMARY KEY,
+          payment_method TEXT NOT NULL,
+          destination TEXT NOT NULL,
+          amount INTEGER,
+          created_at INTEGER NOT NULL,
+          expires_at INTEGER NOT NULL,
+          status TEXT NOT NULL,
+          payment_id TEXT,
+          order_id TEXT,
+          description TEXT
+      )",
+            [],
+        )?;
+        Self::add_column_if_missing(&connection, "payment_requests", "order_id", "TEXT")?;
+        Self::add_column_if_missing(&connection, "payment_requests", "description", "TEXT")?;
+
+        // Create pending sends table for resuming sends that failed midway
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS pending_sends (
+          id TEXT PRIMARY KEY,
+          destination TEXT NOT NULL,
+          amount INTEGER NOT NULL,
+          fees INTEGER NOT NULL,
+          created_at INTEGER NOT NULL,
+          attempts INTEGER NOT NULL,
+          last_error TEXT,
+          correlation_id TEXT,
+          failure_reason TEXT,
+          submission TEXT,
+          attempt_inputs TEXT,
+          attempt_started_at INTEGER
+      )",
+            [],
+        )?;
+        Self::add_column_if_missing(&connection, "pending_sends", "correlation_id", "TEXT")?;
+        Self::add_column_if_missing(&connection, "pending_sends", "failure_reason", "TEXT")?;
+        Self::add_column_if_missing(&connection, "pending_sends", "submission", "TEXT")?;
+        Self::add_column_if_missing(&connection, "pending_sends", "attempt_inputs", "TEXT")?;
+        Self::add_column_if_missing(
+            &connection,
+            "pending_sends",
+            "attempt_started_at",
+            "INTEGER",
+        )?;
+
+        // Create reservations table for earmarking funds of in-flight sends
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS reservations (
+          id TEXT PRIMARY KEY,
+          amount INTEGER NOT NULL
       )",
             [],
         )?;
@@ -78,12 +219,45 @@ impl SqliteStorage {
         Ok(())
     }
 
-    fn get_setting(&self, key: &str) -> Result<Option<String>, SdkError> {
-        let connection = self
-            .connection
-            .lock()
-            .map_err(|_| SdkError::StorageError("Failed to lock connection".to_string()))?;
+    /// Runs a closure against the connection on the blocking thread pool
+    async fn run_blocking<F, T>(&self, f: F) -> Result<T, SdkError>
+    where
+        F: FnOnce(&mut Connection) -> Result<T, SdkError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let connection = self.connection.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut connection = connection
+                .lock()
+                .map_err(|_| SdkError::StorageError("Failed to lock connection".to_string()))?;
+            f(&mut connection)
+        })
+        .await
+        .map_err(|e| SdkError::StorageError(format!("Storage task failed: {e}")))?
+    }
+
+    /// Adds a column to a table created by an older version of the schema
+    fn add_column_if_missing(
+        connection: &Connection,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<(), SdkError> {
+        let exists: bool = connection.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info(?) WHERE name = ?",
+            params![table, column],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            connection.execute(
+                &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+                [],
+            )?;
+        }
+        Ok(())
+    }
 
+    fn get_setting(connection: &Connection, key: &str) -> Result<Option<String>, SdkError> {
         let value = connection.query_row(
             "SELECT value FROM settings WHERE key = ?",
             params![key],
@@ -97,12 +271,7 @@ impl SqliteStorage {
         }
     }
 
-    fn set_setting(&self, key: &str, value: &str) -> Result<(), SdkError> {
-        let connection = self
-            .connection
-            .lock()
-            .map_err(|_| SdkError::StorageError("Failed to lock connection".to_string()))?;
-
+    fn set_setting(connection: &Connection, key: &str, value: &str) -> Result<(), SdkError> {
         connection.execute(
             "INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)",
             params![key, value],
@@ -110,235 +279,439 @@ impl SqliteStorage {
 
         Ok(())
     }
-}
-
-impl Storage for SqliteStorage {
-    fn save_payment(&self, payment: &Payment) -> Result<(), SdkError> {
-        let connection = self
-            .connection
-            .lock()
-            .map_err(|_| SdkError::StorageError("Failed to lock connection".to_string()))?;
 
-        connection.execute(
+    fn insert_payment(connection: &Connection, payment: &Payment) -> Result<(), SdkError> {
+        let details = payment
+            .details
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| {
+                SdkError::StorageError(format!("Failed to serialize payment details: {}", e))
+            })?;
+        let onchain_txs = serde_json::to_string(&payment.onchain_txs).map_err(|e| {
+            SdkError::StorageError(format!("Failed to serialize onchain transactions: {}", e))
+        })?;
+        let failure_reason = Self::failure_reason_to_json(&payment.failure_reason)?;
+        // Cached, so saving a whole history prepares the statement once
+        let mut statement = connection.prepare_cached(
             "INSERT OR REPLACE INTO payments (
-                id, payment_type, status, amount, fees, timestamp, description, destination
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-            params![
-                payment.id,
-                payment.payment_type.to_string(),
-                payment.status.to_string(),
-                payment.amount,
-                payment.fees,
-                payment.timestamp,
-                payment.description,
-                payment.destination,
-            ],
+                id, payment_type, status, amount, fees, timestamp, description, destination,
+                details, onchain_txs, order_id, deposit_source, failure_reason
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )?;
+        statement.execute(params![
+            payment.id,
+            payment.payment_type.to_string(),
+            payment.status.to_string(),
+            payment.amount,
+            payment.fees,
+            payment.timestamp,
+            payment.description,
+            payment.destination,
+            details,
+            onchain_txs,
+            payment.order_id,
+            payment.deposit_source,
+            failure_reason,
+        ])?;
 
         Ok(())
     }
 
-    fn save_payments(&self, payments: &[Payment]) -> Result<(), SdkError> {
-        // Acquire the lock on the connection
-        let mut connection = self
-            .connection
-            .lock()
-            .map_err(|_| SdkError::StorageError("Failed to lock connection".to_string()))?;
+    fn compress_payment(payment: &Payment) -> Result<Vec<u8>, SdkError> {
+        let json = serde_json::to_vec(payment)
+            .map_err(|e| SdkError::StorageError(format!("Failed to serialize payment: {e}")))?;
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&json)?;
+        Ok(encoder.finish()?)
+    }
 
-        // Start a transaction to ensure atomicity
-        let tx = connection.transaction()?;
+    fn decompress_payment(data: &[u8]) -> Result<Payment, SdkError> {
+        let mut json = Vec::new();
+        ZlibDecoder::new(data).read_to_end(&mut json)?;
+        serde_json::from_slice(&json).map_err(|e| {
+            SdkError::StorageError(format!("Failed to deserialize archived payment: {e}"))
+        })
+    }
 
-        // First, collect all payment IDs to keep
-        let mut payment_ids = Vec::with_capacity(payments.len());
+    fn payment_from_row(row: &Row) -> rusqlite::Result<Payment> {
+        let payment_type_str: String = row.get(1)?;
+        let status_str: String = row.get(2)?;
+
+        let payment_type = match payment_type_str.as_str() {
+            "Sent" => PaymentType::Sent,
+            "Received" => PaymentType::Received,
+            _ => {
+                return Err(rusqlite::Error::InvalidColumnType(
+                    1,
+                    "Invalid payment type".to_string(),
+                    Type::Text,
+                ))
+            }
+        };
 
-        // Insert or update all payments in the list
-        for payment in payments {
-            tx.execute(
-                "INSERT OR REPLACE INTO payments (
-                    id, payment_type, status, amount, fees, timestamp, description, destination
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-                params![
-                    payment.id,
-                    payment.payment_type.to_string(),
-                    payment.status.to_string(),
-                    payment.amount,
-                    payment.fees,
-                    payment.timestamp,
-                    payment.description,
-                    payment.destination,
-                ],
-            )?;
+        let details = row
+            .get::<_, Option<String>>(8)?
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(8, Type::Text, Box::new(e)))?;
+
+        // Payments stored before onchain transactions were recorded have none
+        let onchain_txs = row
+            .get::<_, Option<String>>(9)?
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(9, Type::Text, Box::new(e)))?
+            .unwrap_or_default();
+
+        let status = match status_str.as_str() {
+            "Pending" => PaymentStatus::Pending,
+            "Completed" => PaymentStatus::Completed,
+            "Failed" => PaymentStatus::Failed,
+            "Expired" => PaymentStatus::Expired,
+            "Cancelled" => PaymentStatus::Cancelled,
+            _ => {
+                return Err(rusqlite::Error::InvalidColumnType(
+                    2,
+                    "Invalid payment status".to_string(),
+                    Type::Text,
+                ))
+            }
+        };
 
-            payment_ids.push(&payment.id);
-        }
+        Ok(Payment {
+            id: row.get(0)?,
+            payment_type,
+            status,
+            amount: row.get(3)?,
+            fees: row.get(4)?,
+            timestamp: row.get(5)?,
+            description: row.get(6)?,
+            destination: row.get(7)?,
+            is_retryable: false,
+            details,
+            onchain_txs,
+            order_id: row.get(10)?,
+            deposit_source: row.get(11)?,
+            failure_reason: Self::failure_reason_from_row(row, 12)?,
+        })
+    }
 
-        // Delete any payments not in the list
-        if !payment_ids.is_empty() {
-            // Create placeholders for the IN clause
-            let placeholders = payment_ids
-                .iter()
-                .map(|_| "?")
-                .collect::<Vec<_>>()
-                .join(",");
-            let query = format!("DELETE FROM payments WHERE id NOT IN ({})", placeholders);
-
-            // Convert payment_ids to a Vec of rusqlite::types::ToSql trait objects
-            let params: Vec<&dyn rusqlite::types::ToSql> = payment_ids
-                .iter()
-                .map(|id| id as &dyn rusqlite::types::ToSql)
-                .collect();
-
-            tx.execute(&query, &params[..])?;
-        } else {
-            // If the payments list is empty, delete all payments
-            tx.execute("DELETE FROM payments", [])?;
-        }
+    fn failure_reason_to_json(
+        reason: &Option<PaymentFailureReason>,
+    ) -> Result<Option<String>, SdkError> {
+        reason
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| SdkError::StorageError(format!("Failed to serialize failure reason: {e}")))
+    }
 
-        // Commit the transaction
-        tx.commit()?;
+    fn failure_reason_from_row(
+        row: &Row,
+        index: usize,
+    ) -> rusqlite::Result<Option<PaymentFailureReason>> {
+        Self::json_from_row(row, index)
+    }
 
-        Ok(())
+    fn json_from_row<T: DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<Option<T>> {
+        row.get::<_, Option<String>>(index)?
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
     }
 
-    fn get_payment(&self, id: &str) -> Result<Option<Payment>, SdkError> {
-        let connection = self
-            .connection
-            .lock()
-            .map_err(|_| SdkError::StorageError("Failed to lock connection".to_string()))?;
+    fn note_from_row(row: &Row) -> rusqlite::Result<Note> {
+        let status_str: String = row.get(5)?;
+        let status = match status_str.as_str() {
+            "Reserved" => NoteStatus::Reserved,
+            "Redeemed" => NoteStatus::Redeemed,
+            "Reclaimed" => NoteStatus::Reclaimed,
+            _ => {
+                return Err(rusqlite::Error::InvalidColumnType(
+                    5,
+                    "Invalid note status".to_string(),
+                    Type::Text,
+                ))
+            }
+        };
 
-        let mut stmt = connection.prepare(
-            "SELECT id, payment_type, status, amount, fees, timestamp, description, destination
-             FROM payments
-             WHERE id = ?",
-        )?;
+        Ok(Note {
+            id: row.get(0)?,
+            note: row.get(1)?,
+            amount_sat: row.get(2)?,
+            created_at: row.get(3)?,
+            expires_at: row.get(4)?,
+            status,
+        })
+    }
 
-        let payment = stmt.query_row(params![id], |row| {
-            let payment_type_str: String = row.get(1)?;
-            let status_str: String = row.get(2)?;
-
-            let payment_type = match payment_type_str.as_str() {
-                "Sent" => PaymentType::Sent,
-                "Received" => PaymentType::Received,
-                _ => {
-                    return Err(rusqlite::Error::InvalidColumnType(
-                        1,
-                        "Invalid payment type".to_string(),
-                        Type::Text,
-                    ))
-                }
-            };
+    fn payment_request_from_row(row: &Row) -> rusqlite::Result<PaymentRequest> {
+        let payment_method_str: String = row.get(1)?;
+        let payment_method = serde_json::from_str(&payment_method_str).map_err(|_| {
+            rusqlite::Error::InvalidColumnType(1, "Invalid payment method".to_string(), Type::Text)
+        })?;
+        let status_str: String = row.get(6)?;
+        let status = match status_str.as_str() {
+            "Pending" => PaymentRequestStatus::Pending,
+            "Paid" => PaymentRequestStatus::Paid,
+            "Expired" => PaymentRequestStatus::Expired,
+            _ => {
+                return Err(rusqlite::Error::InvalidColumnType(
+                    6,
+                    "Invalid payment request status".to_string(),
+                    Type::Text,
+                ))
+            }
+        };
 
-            let status = match status_str.as_str() {
-                "Pending" => PaymentStatus::Pending,
-                "Completed" => PaymentStatus::Completed,
-                "Failed" => PaymentStatus::Failed,
-                "Expired" => PaymentStatus::Expired,
-                _ => {
-                    return Err(rusqlite::Error::InvalidColumnType(
-                        2,
-                        "Invalid payment status".to_string(),
-                        Type::Text,
-                    ))
-                }
-            };
+        Ok(PaymentRequest {
+            id: row.get(0)?,
+            payment_method,
+            destination: row.get(2)?,
+            amount_sat: row.get(3)?,
+            created_at: row.get(4)?,
+            expires_at: row.get(5)?,
+            status,
+            payment_id: row.get(7)?,
+            order_id: row.get(8)?,
+            description: row.get(9)?,
+        })
+    }
+}
 
-            Ok(Payment {
-                id: row.get(0)?,
-                payment_type,
-                status,
-                amount: row.get(3)?,
-                fees: row.get(4)?,
-                timestamp: row.get(5)?,
-                description: row.get(6)?,
-                destination: row.get(7)?,
-            })
-        });
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn save_payment(&self, payment: &Payment) -> Result<(), SdkError> {
+        let payment = payment.clone();
+        self.run_blocking(move |connection| Self::insert_payment(connection, &payment))
+            .await
+    }
 
-        match payment {
-            Ok(payment) => Ok(Some(payment)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(SdkError::StorageError(e.to_string())),
-        }
+    async fn save_payments(&self, payments: &[Payment]) -> Result<(), SdkError> {
+        let payments = payments.to_vec();
+        self.run_blocking(move |connection| {
+            // Start a transaction to ensure atomicity
+            let tx = connection.transaction()?;
+
+            // First, collect all payment IDs to keep
+            let mut payment_ids = Vec::with_capacity(payments.len());
+
+            // Insert or update all payments in the list
+            for payment in &payments {
+                Self::insert_payment(&tx, payment)?;
+                payment_ids.push(&payment.id);
+            }
+
+            // Delete any payments not in the list
+            if !payment_ids.is_empty() {
+                // Create placeholders for the IN clause
+                let placeholders = payment_ids
+                    .iter()
+                    .map(|_| "?")
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let query = format!("DELETE FROM payments WHERE id NOT IN ({})", placeholders);
+
+                // Convert payment_ids to a Vec of rusqlite::types::ToSql trait objects
+                let params: Vec<&dyn rusqlite::types::ToSql> = payment_ids
+                    .iter()
+                    .map(|id| id as &dyn rusqlite::types::ToSql)
+                    .collect();
+
+                tx.execute(&query, &params[..])?;
+            } else {
+                // If the payments list is empty, delete all payments
+                tx.execute("DELETE FROM payments", [])?;
+            }
+
+            // Commit the transaction
+            tx.commit()?;
+
+            Ok(())
+        })
+        .await
     }
 
-    fn list_payments(&self, offset: u32, limit: u32) -> Result<Vec<Payment>, SdkError> {
-        let connection = self
-            .connection
-            .lock()
-            .map_err(|_| SdkError::StorageError("Failed to lock connection".to_string()))?;
+    async fn get_payment(&self, id: &str) -> Result<Option<Payment>, SdkError> {
+        let id = id.to_string();
+        self.run_blocking(move |connection| {
+            let mut stmt = connection.prepare(
+                "SELECT id, payment_type, status, amount, fees, timestamp, description, destination,
+                        details, onchain_txs, order_id, deposit_source, failure_reason
+                 FROM payments
+                 WHERE id = ?",
+            )?;
 
-        let mut stmt = connection.prepare(
-            "SELECT id, payment_type, status, amount, fees, timestamp, description, destination
-             FROM payments
-             ORDER BY timestamp DESC
-             LIMIT ? OFFSET ?",
-        )?;
+            let payment = stmt.query_row(params![id], Self::payment_from_row);
 
-        let payment_iter = stmt.query_map(params![limit, offset], |row| {
-            let payment_type_str: String = row.get(1)?;
-            let status_str: String = row.get(2)?;
-
-            let payment_type = match payment_type_str.as_str() {
-                "Sent" => PaymentType::Sent,
-                "Received" => PaymentType::Received,
-                _ => {
-                    return Err(rusqlite::Error::InvalidColumnType(
-                        1,
-                        "Invalid payment type".to_string(),
-                        Type::Text,
-                    ))
-                }
+            match payment {
+                Ok(payment) => Ok(Some(payment)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(SdkError::StorageError(e.to_string())),
+            }
+        })
+        .await
+    }
+
+    async fn list_payments(&self, offset: u32, limit: u32) -> Result<Vec<Payment>, SdkError> {
+        self.run_blocking(move |connection| {
+            let mut stmt = connection.prepare(
+                "SELECT id, payment_type, status, amount, fees, timestamp, description, destination,
+                        details, onchain_txs, order_id, deposit_source, failure_reason
+                 FROM payments
+                 ORDER BY timestamp DESC
+                 LIMIT ? OFFSET ?",
+            )?;
+
+            let payment_iter = stmt.query_map(params![limit, offset], Self::payment_from_row)?;
+
+            let mut payments = Vec::new();
+            for payment in payment_iter {
+                payments.push(payment?);
+            }
+
+            Ok(payments)
+        })
+        .await
+    }
+
+    async fn list_payments_page(&self, query: &PaymentsQuery) -> Result<Vec<Payment>, SdkError> {
+        let query = query.clone();
+        self.run_blocking(move |connection| {
+            let (comparison, order) = match query.sort {
+                SortDirection::Ascending => (">", "ASC"),
+                SortDirection::Descending => ("<", "DESC"),
+            };
+            // Without a cursor the condition holds for every payment
+            let (cursor_timestamp, cursor_id, has_cursor) = match &query.cursor {
+                Some(cursor) => (cursor.timestamp, cursor.id.clone(), true),
+                None => (0, String::new(), false),
             };
+            let mut stmt = connection.prepare(&format!(
+                "SELECT id, payment_type, status, amount, fees, timestamp, description, destination,
+                        details, onchain_txs, order_id, deposit_source, failure_reason
+                 FROM payments
+                 WHERE NOT ?1 OR timestamp {comparison} ?2 OR (timestamp = ?2 AND id {comparison} ?3)
+                 ORDER BY timestamp {order}, id {order}
+                 LIMIT ?4 OFFSET ?5"
+            ))?;
+
+            let payment_iter = stmt.query_map(
+                params![
+                    has_cursor,
+                    cursor_timestamp,
+                    cursor_id,
+                    query.limit,
+                    query.offset
+                ],
+                Self::payment_from_row,
+            )?;
 
-            let status = match status_str.as_str() {
-                "Pending" => PaymentStatus::Pending,
-                "Completed" => PaymentStatus::Completed,
-                "Failed" => PaymentStatus::Failed,
-                "Expired" => PaymentStatus::Expired,
-                _ => {
-                    return Err(rusqlite::Error::InvalidColumnType(
-                        2,
-                        "Invalid payment status".to_string(),
-                        Type::Text,
-                    ))
-                }
+            let mut payments = Vec::new();
+            for payment in payment_iter {
+                payments.push(payment?);
+            }
+
+            Ok(payments)
+        })
+        .await
+    }
+
+    async fn archive_payments(
+        &self,
+        before: u64,
+        action: RetentionAction,
+    ) -> Result<u32, SdkError> {
+        self.run_blocking(move |connection| {
+            let tx = connection.transaction()?;
+            let payments = {
+                let mut stmt = tx.prepare(
+                    "SELECT id, payment_type, status, amount, fees, timestamp, description, destination,
+                            details, onchain_txs, order_id, deposit_source, failure_reason
+                     FROM payments
+                     WHERE timestamp < ? AND status != ?",
+                )?;
+                let payment_iter = stmt.query_map(
+                    params![before, PaymentStatus::Pending.to_string()],
+                    Self::payment_from_row,
+                )?;
+                payment_iter.collect::<rusqlite::Result<Vec<_>>>()?
             };
 
-            Ok(Payment {
-                id: row.get(0)?,
-                payment_type,
-                status,
-                amount: row.get(3)?,
-                fees: row.get(4)?,
-                timestamp: row.get(5)?,
-                description: row.get(6)?,
-                destination: row.get(7)?,
-            })
-        })?;
+            for payment in &payments {
+                let data = match action {
+                    RetentionAction::Archive => Some(Self::compress_payment(payment)?),
+                    RetentionAction::Prune => None,
+                };
+                tx.execute(
+                    "INSERT OR REPLACE INTO payments_archive (id, timestamp, data) VALUES (?, ?, ?)",
+                    params![payment.id, payment.timestamp, data],
+                )?;
+                tx.execute("DELETE FROM payments WHERE id = ?", params![payment.id])?;
+            }
+            // Earlier archived payments are pruned along with the history
+            if action == RetentionAction::Prune {
+                tx.execute(
+                    "UPDATE payments_archive SET data = NULL WHERE timestamp < ?",
+                    params![before],
+                )?;
+            }
 
-        let mut payments = Vec::new();
-        for payment in payment_iter {
-            payments.push(payment?);
-        }
+            tx.commit()?;
+            Ok(payments.len() as u32)
+        })
+        .await
+    }
 
-        Ok(payments)
+    async fn archived_payment_ids(&self) -> Result<HashSet<String>, SdkError> {
+        self.run_blocking(|connection| {
+            let mut stmt = connection.prepare("SELECT id FROM payments_archive")?;
+            let ids = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<HashSet<String>>>()?;
+            Ok(ids)
+        })
+        .await
     }
 
-    fn save_offchain_balance(
-        &self,
-        balance: &crate::models::OffchainBalance,
-    ) -> Result<(), SdkError> {
+    async fn list_archived_payments(&self) -> Result<Vec<Payment>, SdkError> {
+        self.run_blocking(|connection| {
+            let mut stmt = connection.prepare(
+                "SELECT data FROM payments_archive
+                 WHERE data IS NOT NULL
+                 ORDER BY timestamp ASC, id ASC",
+            )?;
+            let data = stmt
+                .query_map([], |row| row.get::<_, Vec<u8>>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            data.iter()
+                .map(|data| Self::decompress_payment(data))
+                .collect()
+        })
+        .await
+    }
+
+    async fn save_offchain_balance(&self, balance: &OffchainBalance) -> Result<(), SdkError> {
         // Serialize the OffchainBalance struct to JSON
         let json_value = serde_json::to_string(balance)
             .map_err(|e| SdkError::StorageError(format!("Failed to serialize balance: {}", e)))?;
 
         // Store the serialized JSON under a single key
-        self.set_setting("offchain_balance", &json_value)
+        self.run_blocking(move |connection| {
+            Self::set_setting(connection, "offchain_balance", &json_value)
+        })
+        .await
     }
 
-    fn get_offchain_balance(&self) -> Result<crate::models::OffchainBalance, SdkError> {
+    async fn get_offchain_balance(&self) -> Result<OffchainBalance, SdkError> {
         // Retrieve the serialized JSON from settings
-        match self.get_setting("offchain_balance")? {
+        let value = self
+            .run_blocking(|connection| Self::get_setting(connection, "offchain_balance"))
+            .await?;
+
+        match value {
             Some(json_value) => {
                 // Deserialize the JSON back to an OffchainBalance struct
                 serde_json::from_str(&json_value).map_err(|e| {
@@ -347,16 +720,381 @@ impl Storage for SqliteStorage {
             }
             None => {
                 // Return default balance if no value is found
-                Ok(crate::models::OffchainBalance::default())
+                Ok(OffchainBalance::default())
             }
         }
     }
+
+    async fn save_server_balances(&self, balances: &[ServerBalance]) -> Result<(), SdkError> {
+        let json_value = serde_json::to_string(balances).map_err(|e| {
+            SdkError::StorageError(format!("Failed to serialize server balances: {}", e))
+        })?;
+
+        self.run_blocking(move |connection| {
+            Self::set_setting(connection, "server_balances", &json_value)
+        })
+        .await
+    }
+
+    async fn get_server_balances(&self) -> Result<Vec<ServerBalance>, SdkError> {
+        let value = self
+            .run_blocking(|connection| Self::get_setting(connection, "server_balances"))
+            .await?;
+
+        match value {
+            Some(json_value) => serde_json::from_str(&json_value).map_err(|e| {
+                SdkError::StorageError(format!("Failed to deserialize server balances: {}", e))
+            }),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn save_boarding_balance(&self, balance: &BoardingBalance) -> Result<(), SdkError> {
+        let json_value = serde_json::to_string(balance).map_err(|e| {
+            SdkError::StorageError(format!("Failed to serialize boarding balance: {}", e))
+        })?;
+
+        self.run_blocking(move |connection| {
+            Self::set_setting(connection, "boarding_balance", &json_value)
+        })
+        .await
+    }
+
+    async fn get_boarding_balance(&self) -> Result<BoardingBalance, SdkError> {
+        let value = self
+            .run_blocking(|connection| Self::get_setting(connection, "boarding_balance"))
+            .await?;
+
+        match value {
+            Some(json_value) => serde_json::from_str(&json_value).map_err(|e| {
+                SdkError::StorageError(format!("Failed to deserialize boarding balance: {}", e))
+            }),
+            None => Ok(BoardingBalance::default()),
+        }
+    }
+
+    async fn save_history_checkpoint(
+        &self,
+        checkpoint: &HistoryCheckpoint,
+    ) -> Result<(), SdkError> {
+        let json_value = serde_json::to_string(checkpoint).map_err(|e| {
+            SdkError::StorageError(format!("Failed to serialize history checkpoint: {}", e))
+        })?;
+
+        self.run_blocking(move |connection| {
+            Self::set_setting(connection, "history_checkpoint", &json_value)
+        })
+        .await
+    }
+
+    async fn get_history_checkpoint(&self) -> Result<Option<HistoryCheckpoint>, SdkError> {
+        let value = self
+            .run_blocking(|connection| Self::get_setting(connection, "history_checkpoint"))
+            .await?;
+
+        value
+            .map(|json_value| {
+                serde_json::from_str(&json_value).map_err(|e| {
+                    SdkError::StorageError(format!(
+                        "Failed to deserialize history checkpoint: {}",
+                        e
+                    ))
+                })
+            })
+            .transpose()
+    }
+
+    async fn save_note(&self, note: &Note) -> Result<(), SdkError> {
+        let note = note.clone();
+        self.run_blocking(move |connection| {
+            connection.execute(
+                "INSERT OR REPLACE INTO notes (
+                    id, note, amount, created_at, expires_at, status
+                ) VALUES (?, ?, ?, ?, ?, ?)",
+                params![
+                    note.id,
+                    note.note,
+                    note.amount_sat,
+                    note.created_at,
+                    note.expires_at,
+                    note.status.to_string(),
+                ],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_note(&self, id: &str) -> Result<Option<Note>, SdkError> {
+        let id = id.to_string();
+        self.run_blocking(move |connection| {
+            let note = connection.query_row(
+                "SELECT id, note, amount, created_at, expires_at, status
+                 FROM notes
+                 WHERE id = ?",
+                params![id],
+                Self::note_from_row,
+            );
+
+            match note {
+                Ok(note) => Ok(Some(note)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(SdkError::StorageError(e.to_string())),
+            }
+        })
+        .await
+    }
+
+    async fn list_notes(&self) -> Result<Vec<Note>, SdkError> {
+        self.run_blocking(|connection| {
+            let mut stmt = connection.prepare(
+                "SELECT id, note, amount, created_at, expires_at, status
+                 FROM notes
+                 ORDER BY created_at DESC",
+            )?;
+
+            let note_iter = stmt.query_map([], Self::note_from_row)?;
+
+            let mut notes = Vec::new();
+            for note in note_iter {
+                notes.push(note?);
+            }
+
+            Ok(notes)
+        })
+        .await
+    }
+
+    async fn get_or_insert_receive_address(
+        &self,
+        address: &str,
+        created_at: u64,
+    ) -> Result<u32, SdkError> {
+        let address = address.to_string();
+        self.run_blocking(move |connection| {
+            connection.execute(
+                "INSERT OR IGNORE INTO receive_addresses (address, derivation_index, created_at)
+                 VALUES (?, (SELECT COALESCE(MAX(derivation_index) + 1, 0) FROM receive_addresses), ?)",
+                params![address, created_at],
+            )?;
+            let index = connection.query_row(
+                "SELECT derivation_index FROM receive_addresses WHERE address = ?",
+                params![address],
+                |row| row.get(0),
+            )?;
+            Ok(index)
+        })
+        .await
+    }
+
+    async fn save_payment_request(&self, request: &PaymentRequest) -> Result<(), SdkError> {
+        let request = request.clone();
+        let payment_method = serde_json::to_string(&request.payment_method)
+            .map_err(|e| SdkError::StorageError(e.to_string()))?;
+        self.run_blocking(move |connection| {
+            connection.execute(
+                "INSERT OR REPLACE INTO payment_requests (
+                    id, payment_method, destination, amount, created_at, expires_at, status,
+                    payment_id, order_id, description
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    request.id,
+                    payment_method,
+                    request.destination,
+                    request.amount_sat,
+                    request.created_at,
+                    request.expires_at,
+                    request.status.to_string(),
+                    request.payment_id,
+                    request.order_id,
+                    request.description,
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_payment_requests(&self) -> Result<Vec<PaymentRequest>, SdkError> {
+        self.run_blocking(|connection| {
+            let mut stmt = connection.prepare(
+                "SELECT id, payment_method, destination, amount, created_at, expires_at, status,
+                    payment_id, order_id, description
+                 FROM payment_requests
+                 ORDER BY created_at DESC",
+            )?;
+
+            let request_iter = stmt.query_map([], Self::payment_request_from_row)?;
+
+            let mut requests = Vec::new();
+            for request in request_iter {
+                requests.push(request?);
+            }
+
+            Ok(requests)
+        })
+        .await
+    }
+
+    async fn save_pending_send(&self, send: &PendingSend) -> Result<(), SdkError> {
+        let send = send.clone();
+        let failure_reason = Self::failure_reason_to_json(&send.failure_reason)?;
+        let submission = serde_json::to_string(&send.submission).map_err(|e| {
+            SdkError::StorageError(format!("Failed to serialize send submission: {e}"))
+        })?;
+        let attempt_inputs = serde_json::to_string(&send.attempt_inputs).map_err(|e| {
+            SdkError::StorageError(format!("Failed to serialize attempt inputs: {e}"))
+        })?;
+        self.run_blocking(move |connection| {
+            connection.execute(
+                "INSERT OR REPLACE INTO pending_sends (
+                    id, destination, amount, fees, created_at, attempts, last_error,
+                    correlation_id, failure_reason, submission, attempt_inputs, attempt_started_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    send.id,
+                    send.destination,
+                    send.amount_sat,
+                    send.fees_sat,
+                    send.created_at,
+                    send.attempts,
+                    send.last_error,
+                    send.correlation_id,
+                    failure_reason,
+                    submission,
+                    attempt_inputs,
+                    send.attempt_started_at,
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete_pending_send(&self, id: &str) -> Result<(), SdkError> {
+        let id = id.to_string();
+        self.run_blocking(move |connection| {
+            connection.execute("DELETE FROM pending_sends WHERE id = ?", params![id])?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_pending_sends(&self) -> Result<Vec<PendingSend>, SdkError> {
+        self.run_blocking(|connection| {
+            let mut stmt = connection.prepare(
+                "SELECT id, destination, amount, fees, created_at, attempts, last_error,
+                    correlation_id, failure_reason, submission, attempt_inputs, attempt_started_at
+                 FROM pending_sends
+                 ORDER BY created_at ASC",
+            )?;
+
+            let send_iter = stmt.query_map([], |row| {
+                let id: String = row.get(0)?;
+                // Sends persisted before correlation IDs existed are traced by their own ID
+                let correlation_id: Option<String> = row.get(7)?;
+                let attempts: u32 = row.get(5)?;
+                Ok(PendingSend {
+                    correlation_id: correlation_id.unwrap_or_else(|| id.clone()),
+                    id,
+                    destination: row.get(1)?,
+                    amount_sat: row.get(2)?,
+                    fees_sat: row.get(3)?,
+                    created_at: row.get(4)?,
+                    last_error: row.get(6)?,
+                    failure_reason: Self::failure_reason_from_row(row, 8)?,
+                    // Sends persisted before submissions were tracked were attempted while
+                    // the app ran, their outcome is unknown
+                    submission: Self::json_from_row(row, 9)?.unwrap_or(match attempts {
+                        0 => SendSubmission::NotSubmitted,
+                        _ => SendSubmission::Unknown,
+                    }),
+                    attempt_inputs: Self::json_from_row(row, 10)?.unwrap_or_default(),
+                    attempt_started_at: row.get(11)?,
+                    attempts,
+                })
+            })?;
+
+            let mut sends = Vec::new();
+            for send in send_iter {
+                sends.push(send?);
+            }
+
+            Ok(sends)
+        })
+        .await
+    }
+
+    async fn reserve_funds(
+        &self,
+        id: &str,
+        amount_sat: u64,
+        available_sat: u64,
+    ) -> Result<bool, SdkError> {
+        let id = id.to_string();
+        self.run_blocking(move |connection| {
+            let tx = connection.transaction()?;
+            let reserved_sat: u64 = tx.query_row(
+                "SELECT COALESCE(SUM(amount), 0) FROM reservations WHERE id != ?",
+                params![id],
+                |row| row.get(0),
+            )?;
+            let total_sat = reserved_sat.checked_add(amount_sat).ok_or_else(|| {
+                SdkError::InvalidInput(format!("Can't reserve {amount_sat} sats, it overflows"))
+            })?;
+            if total_sat > available_sat {
+                return Ok(false);
+            }
+
+            tx.execute(
+                "INSERT OR REPLACE INTO reservations (id, amount) VALUES (?, ?)",
+                params![id, amount_sat],
+            )?;
+            tx.commit()?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn release_funds(&self, id: &str) -> Result<(), SdkError> {
+        let id = id.to_string();
+        self.run_blocking(move |connection| {
+            connection.execute("DELETE FROM reservations WHERE id = ?", params![id])?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_reserved_sats(&self) -> Result<u64, SdkError> {
+        self.run_blocking(|connection| {
+            let reserved_sat = connection.query_row(
+                "SELECT COALESCE(SUM(amount), 0) FROM reservations",
+                [],
+                |row| row.get(0),
+            )?;
+            Ok(reserved_sat)
+        })
+        .await
+    }
+
+    async fn clear_reservations(&self) -> Result<(), SdkError> {
+        self.run_blocking(|connection| {
+            connection.execute("DELETE FROM reservations", [])?;
+            Ok(())
+        })
+        .await
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{OffchainBalance, Payment, PaymentStatus, PaymentType};
+    use crate::models::{
+        Note, NoteStatus, OffchainBalance, OnchainTx, OnchainTxKind, Payment, PaymentDetails,
+        PaymentFailureKind, PaymentMethod, PaymentStatus, PaymentType,
+    };
+    use crate::persist::PaymentCursor;
 
     fn create_test_payment(id: &str, payment_type: PaymentType, status: PaymentStatus) -> Payment {
         Payment {
@@ -368,28 +1106,62 @@ mod tests {
             timestamp: 1620000000,
             description: Some("Test payment".to_string()),
             destination: Some("test_destination".to_string()),
+            is_retryable: false,
+            details: Some(PaymentDetails::Ark {
+                txid: id.to_string(),
+                round_txid: None,
+            }),
+            onchain_txs: Vec::new(),
+            order_id: None,
+            deposit_source: None,
+            failure_reason: None,
         }
     }
 
-    #[test]
-    fn test_init() {
+    #[tokio::test]
+    async fn test_init() {
         let storage = SqliteStorage::new_in_memory().unwrap();
         // If we got here without error, initialization succeeded
         assert!(true);
     }
 
-    #[test]
-    fn test_save_and_get_payment() {
+    #[tokio::test]
+    async fn test_open_read_only() {
+        let db_path = std::env::temp_dir().join(format!("read-only-{}.db", uuid::Uuid::new_v4()));
+        let payment = create_test_payment("a", PaymentType::Sent, PaymentStatus::Completed);
+        SqliteStorage::new(&db_path)
+            .unwrap()
+            .save_payment(&payment)
+            .await
+            .unwrap();
+
+        let storage = SqliteStorage::open_read_only(&db_path).unwrap();
+        assert!(storage.get_payment("a").await.unwrap().is_some());
+        assert!(matches!(
+            storage.save_payment(&payment).await,
+            Err(SdkError::StorageError(_))
+        ));
+        std::fs::remove_file(&db_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_save_and_get_payment() {
         let storage = SqliteStorage::new_in_memory().unwrap();
 
         // Create a test payment
-        let payment = create_test_payment("test_id_1", PaymentType::Sent, PaymentStatus::Completed);
+        let payment = Payment {
+            onchain_txs: vec![OnchainTx {
+                txid: "test_round_txid".to_string(),
+                kind: OnchainTxKind::RoundCommitment,
+            }],
+            ..create_test_payment("test_id_1", PaymentType::Sent, PaymentStatus::Completed)
+        };
 
         // Save the payment
-        storage.save_payment(&payment).unwrap();
+        storage.save_payment(&payment).await.unwrap();
 
         // Retrieve the payment
-        let retrieved_payment = storage.get_payment("test_id_1").unwrap().unwrap();
+        let retrieved_payment = storage.get_payment("test_id_1").await.unwrap().unwrap();
 
         // Verify the retrieved payment matches the original
         assert_eq!(payment.id, retrieved_payment.id);
@@ -402,21 +1174,23 @@ mod tests {
             payment.status.to_string(),
             retrieved_payment.status.to_string()
         );
+        assert_eq!(payment.details, retrieved_payment.details);
+        assert_eq!(payment.onchain_txs, retrieved_payment.onchain_txs);
     }
 
-    #[test]
-    fn test_get_nonexistent_payment() {
+    #[tokio::test]
+    async fn test_get_nonexistent_payment() {
         let storage = SqliteStorage::new_in_memory().unwrap();
 
         // Try to retrieve a payment that doesn't exist
-        let result = storage.get_payment("nonexistent_id").unwrap();
+        let result = storage.get_payment("nonexistent_id").await.unwrap();
 
         // Verify that None is returned
         assert!(result.is_none());
     }
 
-    #[test]
-    fn test_list_payments() {
+    #[tokio::test]
+    async fn test_list_payments() {
         let storage = SqliteStorage::new_in_memory().unwrap();
 
         // Create and save multiple test payments
@@ -427,34 +1201,155 @@ mod tests {
         let payment3 =
             create_test_payment("test_id_5", PaymentType::Received, PaymentStatus::Completed);
 
-        storage.save_payment(&payment1).unwrap();
-        storage.save_payment(&payment2).unwrap();
-        storage.save_payment(&payment3).unwrap();
+        storage.save_payment(&payment1).await.unwrap();
+        storage.save_payment(&payment2).await.unwrap();
+        storage.save_payment(&payment3).await.unwrap();
 
         // List all payments
-        let all_payments = storage.list_payments(0, 10).unwrap();
+        let all_payments = storage.list_payments(0, 10).await.unwrap();
         assert_eq!(3, all_payments.len());
 
         // Test pagination
-        let first_page = storage.list_payments(0, 2).unwrap();
+        let first_page = storage.list_payments(0, 2).await.unwrap();
         assert_eq!(2, first_page.len());
 
-        let second_page = storage.list_payments(2, 2).unwrap();
+        let second_page = storage.list_payments(2, 2).await.unwrap();
         assert_eq!(1, second_page.len());
     }
 
-    #[test]
-    fn test_save_and_get_offchain_balance() {
+    async fn page_ids(
+        storage: &SqliteStorage,
+        cursor: Option<PaymentCursor>,
+        sort: SortDirection,
+    ) -> Vec<String> {
+        let query = PaymentsQuery {
+            offset: 0,
+            limit: 2,
+            cursor,
+            sort,
+        };
+        let payments = storage.list_payments_page(&query).await.unwrap();
+        payments.into_iter().map(|payment| payment.id).collect()
+    }
+
+    #[tokio::test]
+    async fn test_list_payments_page() {
+        let storage = SqliteStorage::new_in_memory().unwrap();
+        for (id, timestamp) in [("a", 100), ("b", 200), ("c", 200), ("d", 300), ("e", 400)] {
+            let payment = Payment {
+                timestamp,
+                ..create_test_payment(id, PaymentType::Received, PaymentStatus::Completed)
+            };
+            storage.save_payment(&payment).await.unwrap();
+        }
+        let cursor = |timestamp, id: &str| {
+            Some(PaymentCursor {
+                timestamp,
+                id: id.to_string(),
+            })
+        };
+
+        assert_eq!(
+            vec!["e", "d"],
+            page_ids(&storage, None, SortDirection::Descending).await
+        );
+        // Payments with the same timestamp are ordered by ID, so none is skipped
+        assert_eq!(
+            vec!["c", "b"],
+            page_ids(&storage, cursor(300, "d"), SortDirection::Descending).await
+        );
+        assert_eq!(
+            vec!["a"],
+            page_ids(&storage, cursor(200, "b"), SortDirection::Descending).await
+        );
+        assert_eq!(
+            vec!["e", "d"],
+            page_ids(
+                &storage,
+                Some(PaymentCursor::newest()),
+                SortDirection::Descending
+            )
+            .await
+        );
+
+        assert_eq!(
+            vec!["a", "b"],
+            page_ids(&storage, None, SortDirection::Ascending).await
+        );
+        assert_eq!(
+            vec!["c", "d"],
+            page_ids(&storage, cursor(200, "b"), SortDirection::Ascending).await
+        );
+
+        let encoded = cursor(200, "b:1").unwrap().encode();
+        assert_eq!(
+            cursor(200, "b:1").unwrap(),
+            PaymentCursor::decode(&encoded).unwrap()
+        );
+        assert!(PaymentCursor::decode("not a cursor").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_archive_payments() {
+        let storage = SqliteStorage::new_in_memory().unwrap();
+        for (id, timestamp, status) in [
+            ("a", 100, PaymentStatus::Completed),
+            ("b", 200, PaymentStatus::Pending),
+            ("c", 300, PaymentStatus::Failed),
+            ("d", 400, PaymentStatus::Completed),
+        ] {
+            let payment = Payment {
+                timestamp,
+                ..create_test_payment(id, PaymentType::Sent, status)
+            };
+            storage.save_payment(&payment).await.unwrap();
+        }
+
+        // Pending payments stay in the history until they settle
+        assert_eq!(
+            2,
+            storage
+                .archive_payments(350, RetentionAction::Archive)
+                .await
+                .unwrap()
+        );
+        let ids = |payments: Vec<Payment>| payments.into_iter().map(|p| p.id).collect::<Vec<_>>();
+        assert_eq!(
+            vec!["d", "b"],
+            ids(storage.list_payments(0, 10).await.unwrap())
+        );
+        let archived = storage.list_archived_payments().await.unwrap();
+        assert_eq!(vec!["a", "c"], ids(archived.clone()));
+        assert_eq!(PaymentStatus::Failed, archived[1].status);
+        assert_eq!(Some("Test payment".to_string()), archived[1].description);
+
+        // Pruning drops the archived data too, but remembers every ID
+        storage
+            .archive_payments(250, RetentionAction::Prune)
+            .await
+            .unwrap();
+        assert_eq!(
+            vec!["c"],
+            ids(storage.list_archived_payments().await.unwrap())
+        );
+        assert_eq!(
+            HashSet::from(["a".to_string(), "c".to_string()]),
+            storage.archived_payment_ids().await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_and_get_offchain_balance() {
         let storage = SqliteStorage::new_in_memory().unwrap();
 
         // Create a test balance
         let balance = OffchainBalance::new(5000, 10000);
 
         // Save the balance
-        storage.save_offchain_balance(&balance).unwrap();
+        storage.save_offchain_balance(&balance).await.unwrap();
 
         // Retrieve the balance
-        let retrieved_balance = storage.get_offchain_balance().unwrap();
+        let retrieved_balance = storage.get_offchain_balance().await.unwrap();
 
         // Verify the retrieved balance matches the original
         assert_eq!(balance.pending_sats, retrieved_balance.pending_sats);
@@ -462,20 +1357,26 @@ mod tests {
         assert_eq!(balance.total_sats(), retrieved_balance.total_sats());
     }
 
-    #[test]
-    fn test_update_offchain_balance() {
+    #[tokio::test]
+    async fn test_update_offchain_balance() {
         let storage = SqliteStorage::new_in_memory().unwrap();
 
         // Create and save an initial balance
         let initial_balance = OffchainBalance::new(1000, 2000);
-        storage.save_offchain_balance(&initial_balance).unwrap();
+        storage
+            .save_offchain_balance(&initial_balance)
+            .await
+            .unwrap();
 
         // Create and save an updated balance
         let updated_balance = OffchainBalance::new(3000, 4000);
-        storage.save_offchain_balance(&updated_balance).unwrap();
+        storage
+            .save_offchain_balance(&updated_balance)
+            .await
+            .unwrap();
 
         // Retrieve the balance and verify it was updated
-        let retrieved_balance = storage.get_offchain_balance().unwrap();
+        let retrieved_balance = storage.get_offchain_balance().await.unwrap();
         assert_eq!(updated_balance.pending_sats, retrieved_balance.pending_sats);
         assert_eq!(
             updated_balance.confirmed_sats,
@@ -484,16 +1385,230 @@ mod tests {
         assert_eq!(updated_balance.total_sats(), retrieved_balance.total_sats());
     }
 
-    #[test]
-    fn test_default_offchain_balance() {
+    #[tokio::test]
+    async fn test_save_and_get_server_balances() {
+        let storage = SqliteStorage::new_in_memory().unwrap();
+
+        // No balances are stored initially
+        assert!(storage.get_server_balances().await.unwrap().is_empty());
+
+        let balances = vec![
+            ServerBalance {
+                ark_server_url: "http://localhost:7070".to_string(),
+                balance: OffchainBalance::new(1000, 2000),
+            },
+            ServerBalance {
+                ark_server_url: "http://localhost:7071".to_string(),
+                balance: OffchainBalance::new(0, 500),
+            },
+        ];
+        storage.save_server_balances(&balances).await.unwrap();
+
+        let retrieved = storage.get_server_balances().await.unwrap();
+        assert_eq!(2, retrieved.len());
+        assert_eq!("http://localhost:7071", retrieved[1].ark_server_url);
+        assert_eq!(500, retrieved[1].balance.total_sats());
+    }
+
+    #[tokio::test]
+    async fn test_save_and_get_history_checkpoint() {
+        let storage = SqliteStorage::new_in_memory().unwrap();
+        assert!(storage.get_history_checkpoint().await.unwrap().is_none());
+
+        let checkpoint = HistoryCheckpoint {
+            fingerprint: "fingerprint".to_string(),
+            revisit_at: Some(1_700_000_000),
+            conversion_version: 1,
+            balances_fingerprint: "balances".to_string(),
+            fetched_at: 1_699_000_000,
+        };
+        storage.save_history_checkpoint(&checkpoint).await.unwrap();
+        assert_eq!(
+            Some(checkpoint),
+            storage.get_history_checkpoint().await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_and_get_boarding_balance() {
+        let storage = SqliteStorage::new_in_memory().unwrap();
+        assert_eq!(
+            BoardingBalance::default(),
+            storage.get_boarding_balance().await.unwrap()
+        );
+
+        let balance = BoardingBalance {
+            eligible_sats: 10_000,
+            waiting_sats: 5_000,
+        };
+        storage.save_boarding_balance(&balance).await.unwrap();
+        assert_eq!(balance, storage.get_boarding_balance().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_default_offchain_balance() {
         let storage = SqliteStorage::new_in_memory().unwrap();
 
         // Retrieve the balance without saving one first
-        let default_balance = storage.get_offchain_balance().unwrap();
+        let default_balance = storage.get_offchain_balance().await.unwrap();
 
         // Verify the default values are used
         assert_eq!(0, default_balance.pending_sats);
         assert_eq!(0, default_balance.confirmed_sats);
         assert_eq!(0, default_balance.total_sats());
     }
+
+    #[tokio::test]
+    async fn test_save_and_update_note() {
+        let storage = SqliteStorage::new_in_memory().unwrap();
+
+        let mut note = Note {
+            id: "note_id".to_string(),
+            note: "arkgift1test".to_string(),
+            amount_sat: 5000,
+            created_at: 1620000000,
+            expires_at: 1620086400,
+            status: NoteStatus::Reserved,
+        };
+        storage.save_note(&note).await.unwrap();
+
+        // Mark the note as reclaimed
+        note.status = NoteStatus::Reclaimed;
+        storage.save_note(&note).await.unwrap();
+
+        let retrieved_note = storage.get_note("note_id").await.unwrap().unwrap();
+        assert_eq!(NoteStatus::Reclaimed, retrieved_note.status);
+        assert_eq!(note.amount_sat, retrieved_note.amount_sat);
+        assert_eq!(1, storage.list_notes().await.unwrap().len());
+        assert!(storage.get_note("missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_receive_address_indexes() {
+        let storage = SqliteStorage::new_in_memory().unwrap();
+
+        let first = storage
+            .get_or_insert_receive_address("tark1first", 1_700_000_000)
+            .await
+            .unwrap();
+        let second = storage
+            .get_or_insert_receive_address("tark1second", 1_700_000_100)
+            .await
+            .unwrap();
+        assert_eq!(0, first);
+        assert_eq!(1, second);
+
+        // Issuing an address again keeps its index
+        let again = storage
+            .get_or_insert_receive_address("tark1first", 1_700_000_200)
+            .await
+            .unwrap();
+        assert_eq!(0, again);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_update_payment_request() {
+        let storage = SqliteStorage::new_in_memory().unwrap();
+
+        let mut request = PaymentRequest {
+            id: "request_1".to_string(),
+            payment_method: PaymentMethod::ArkAddress {
+                receiver_amount_sat: Some(5_000),
+            },
+            destination: "tark1destination".to_string(),
+            amount_sat: Some(5_000),
+            created_at: 1_700_000_000,
+            expires_at: 1_700_086_400,
+            status: PaymentRequestStatus::Pending,
+            payment_id: None,
+            order_id: Some("order_1".to_string()),
+            description: Some("Coffee".to_string()),
+        };
+        storage.save_payment_request(&request).await.unwrap();
+
+        // Saving again updates the existing request
+        request.status = PaymentRequestStatus::Paid;
+        request.payment_id = Some("payment_1".to_string());
+        storage.save_payment_request(&request).await.unwrap();
+
+        let requests = storage.list_payment_requests().await.unwrap();
+        assert_eq!(vec![request], requests);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_delete_pending_send() {
+        let storage = SqliteStorage::new_in_memory().unwrap();
+
+        let mut send = PendingSend {
+            id: "send_1".to_string(),
+            destination: "tark1destination".to_string(),
+            amount_sat: 5_000,
+            fees_sat: 0,
+            created_at: 1_700_000_000,
+            attempts: 1,
+            last_error: None,
+            failure_reason: None,
+            correlation_id: "flow_1".to_string(),
+            submission: SendSubmission::NotSubmitted,
+            attempt_inputs: Vec::new(),
+            attempt_started_at: None,
+        };
+        storage.save_pending_send(&send).await.unwrap();
+
+        // Saving again updates the existing send
+        send.attempts = 2;
+        send.submission = SendSubmission::Unknown;
+        send.attempt_inputs = vec!["input:0".to_string()];
+        send.attempt_started_at = Some(1_700_000_100);
+        send.last_error = Some("Round failed".to_string());
+        send.failure_reason = Some(PaymentFailureReason::new(
+            PaymentFailureKind::ServerRejected,
+            "Round failed",
+        ));
+        storage.save_pending_send(&send).await.unwrap();
+
+        let sends = storage.list_pending_sends().await.unwrap();
+        assert_eq!(1, sends.len());
+        assert_eq!(2, sends[0].attempts);
+        assert_eq!(Some("Round failed".to_string()), sends[0].last_error);
+        assert_eq!(send.failure_reason, sends[0].failure_reason);
+        assert_eq!(SendSubmission::Unknown, sends[0].submission);
+        assert_eq!(send.attempt_inputs, sends[0].attempt_inputs);
+        assert_eq!(Some(1_700_000_100), sends[0].attempt_started_at);
+
+        storage.delete_pending_send("send_1").await.unwrap();
+        assert!(storage.list_pending_sends().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reserve_funds() {
+        let storage = SqliteStorage::new_in_memory().unwrap();
+
+        assert!(storage
+            .reserve_funds("send_1", 6_000, 10_000)
+            .await
+            .unwrap());
+        // A concurrent send can't reserve the same funds
+        assert!(!storage
+            .reserve_funds("send_2", 6_000, 10_000)
+            .await
+            .unwrap());
+        assert!(storage
+            .reserve_funds("send_2", 4_000, 10_000)
+            .await
+            .unwrap());
+        assert_eq!(10_000, storage.get_reserved_sats().await.unwrap());
+
+        storage.release_funds("send_1").await.unwrap();
+        assert_eq!(4_000, storage.get_reserved_sats().await.unwrap());
+
+        // An amount too large to add to the reservations is rejected
+        assert!(matches!(
+            storage.reserve_funds("send_3", u64::MAX, 10_000).await,
+            Err(SdkError::InvalidInput(_))
+        ));
+
+        storage.clear_reservations().await.unwrap();
+        assert_eq!(0, storage.get_reserved_sats().await.unwrap());
+    }
 }
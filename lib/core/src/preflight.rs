@@ -0,0 +1,137 @@
+use std::time::{Duration, Instant};
+
+use bitcoin::constants::ChainHash;
+use bitcoin::BlockHash;
+use reqwest::Url;
+
+use crate::chain::esplora::EsploraBlockchain;
+use crate::fees;
+use crate::models::{Config, ConnectionCheck};
+use crate::verification;
+
+/// How long the Ark server may take to report its info before it's considered unreachable
+const ARK_SERVER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Checks a service URL can be connected to, i.e. is an absolute HTTP(S) URL
+fn check_url(url: &str) -> Result<(), ConnectionCheck> {
+    let invalid = |reason: String| ConnectionCheck::InvalidUrl { reason };
+    let parsed = Url::parse(url).map_err(|e| invalid(e.to_string()))?;
+    match parsed.scheme() {
+        "http" | "https" if parsed.host().is_some() => Ok(()),
+        "http" | "https" => Err(invalid("The URL has no host".to_string())),
+        scheme => Err(invalid(format!("Unsupported scheme {scheme}"))),
+    }
+}
+
+/// Checks the network a service reports is the configured one
+fn check_network(
+    expected: bitcoin::Network,
+    actual: bitcoin::Network,
+) -> Result<(), ConnectionCheck> {
+    if expected != actual {
+        return Err(ConnectionCheck::NetworkMismatch {
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Identifies a network by the hash of its genesis block
+fn network_of_genesis(genesis_hash: BlockHash) -> Option<bitcoin::Network> {
+    bitcoin::Network::from_chain_hash(ChainHash::from_genesis_block_hash(genesis_hash))
+}
+
+/// Connects to the Ark server, checking it's on the configured network and, if set, signs
+/// with the expected key
+pub(crate) async fn check_ark_server(config: &Config) -> ConnectionCheck {
+    let check = async {
+        check_url(&config.ark_server_url)?;
+        let started = Instant::now();
+        let unreachable = |reason: String| ConnectionCheck::Unreachable { reason };
+        let info = tokio::time::timeout(
+            ARK_SERVER_TIMEOUT,
+            fees::fetch_server_info(&config.ark_server_url),
+        )
+        .await
+        .map_err(|_| unreachable("Timed out waiting for the server info".to_string()))?
+        .map_err(|e| unreachable(e.to_string()))?;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        check_network(config.network.clone().into(), info.network)?;
+        if let Some(expected) = &config.expected_server_pubkey {
+            verification::verify_server_pubkey(&config.ark_server_url, expected, info.pk).map_err(
+                |e| ConnectionCheck::UntrustedServer {
+                    reason: e.to_string(),
+                },
+            )?;
+        }
+        Ok(ConnectionCheck::Ok { latency_ms })
+    };
+    check.await.unwrap_or_else(|failed| failed)
+}
+
+/// Reads the genesis block from Esplora, checking it follows the configured network
+pub(crate) async fn check_esplora(config: &Config) -> ConnectionCheck {
+    let check = async {
+        check_url(&config.esplora_url)?;
+        let started = Instant::now();
+        let genesis_hash =
+            EsploraBlockchain::with_headers(config.esplora_url.clone(), &config.esplora_headers)
+                .map_err(|e| ConnectionCheck::Unreachable {
+                    reason: e.to_string(),
+                })?
+                .genesis_hash()
+                .await
+                .map_err(|e| ConnectionCheck::Unreachable {
+                    reason: e.to_string(),
+                })?;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let Some(network) = network_of_genesis(genesis_hash) else {
+            return Err(ConnectionCheck::NetworkMismatch {
+                expected: bitcoin::Network::from(config.network.clone()).to_string(),
+                actual: format!("unknown chain with genesis block {genesis_hash}"),
+            });
+        };
+        check_network(config.network.clone().into(), network)?;
+        Ok(ConnectionCheck::Ok { latency_ms })
+    };
+    check.await.unwrap_or_else(|failed| failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::constants::genesis_block;
+    use bitcoin::hashes::Hash;
+
+    #[test]
+    fn test_check_url() {
+        assert!(check_url("https://ark.example").is_ok());
+        assert!(check_url("http://localhost:7070").is_ok());
+        assert!(matches!(
+            check_url("ark.example"),
+            Err(ConnectionCheck::InvalidUrl { .. })
+        ));
+        assert!(matches!(
+            check_url("ftp://ark.example"),
+            Err(ConnectionCheck::InvalidUrl { .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_network() {
+        let signet_genesis = genesis_block(bitcoin::Network::Signet).block_hash();
+        let network = network_of_genesis(signet_genesis).unwrap();
+        assert!(check_network(bitcoin::Network::Signet, network).is_ok());
+        assert_eq!(
+            Err(ConnectionCheck::NetworkMismatch {
+                expected: "bitcoin".to_string(),
+                actual: "signet".to_string(),
+            }),
+            check_network(bitcoin::Network::Bitcoin, network)
+        );
+        assert_eq!(None, network_of_genesis(BlockHash::all_zeros()));
+    }
+}
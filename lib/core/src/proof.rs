@@ -0,0 +1,127 @@
+use bitcoin::consensus::encode::serialize_hex;
+use bitcoin::{MerkleBlock, Transaction, Txid};
+
+use crate::error::SdkError;
+use crate::models::RoundInclusionProof;
+
+/// What a payment proof is assembled from
+pub(crate) struct ProofParts {
+    pub(crate) payment_id: String,
+    pub(crate) amount_sat: u64,
+    pub(crate) round_txid: Txid,
+    pub(crate) round_tx: Transaction,
+    pub(crate) block_height: u32,
+    pub(crate) inclusion_proof: MerkleBlock,
+    pub(crate) server_pubkey: String,
+}
+
+/// Assembles the proof that a payment's round is confirmed onchain
+///
+/// The parts come from the chain backend, so they are checked to fit together before they are
+/// handed to a third party: the round transaction must hash to the round txid, and the merkle
+/// block must commit to it under its header's merkle root.
+pub(crate) fn build_proof(parts: ProofParts) -> Result<RoundInclusionProof, SdkError> {
+    let round_txid = parts.round_txid;
+    if parts.round_tx.compute_txid() != round_txid {
+        return Err(SdkError::RoundVerificationFailed(format!(
+            "Transaction returned for round {round_txid} has a different txid"
+        )));
+    }
+    verify_inclusion(&parts.inclusion_proof, round_txid)?;
+
+    Ok(RoundInclusionProof {
+        payment_id: parts.payment_id,
+        amount_sat: parts.amount_sat,
+        round_txid: round_txid.to_string(),
+        round_tx_hex: serialize_hex(&parts.round_tx),
+        block_hash: parts.inclusion_proof.header.block_hash().to_string(),
+        block_height: parts.block_height,
+        txout_proof_hex: serialize_hex(&parts.inclusion_proof),
+        server_pubkey: parts.server_pubkey,
+    })
+}
+
+/// Checks that a merkle block proves a transaction is included in the block
+fn verify_inclusion(proof: &MerkleBlock, txid: Txid) -> Result<(), SdkError> {
+    let mut matches = Vec::new();
+    let mut indexes = Vec::new();
+    proof
+        .extract_matches(&mut matches, &mut indexes)
+        .map_err(|e| {
+            SdkError::RoundVerificationFailed(format!("Invalid merkle proof for round {txid}: {e}"))
+        })?;
+    if !matches.contains(&txid) {
+        return Err(SdkError::RoundVerificationFailed(format!(
+            "Merkle proof doesn't include round {txid}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::block::{Header, Version as BlockVersion};
+    use bitcoin::hashes::Hash;
+    use bitcoin::{absolute::LockTime, transaction::Version, BlockHash, CompactTarget};
+
+    fn tx(lock_time: u32) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::from_consensus(lock_time),
+            input: vec![],
+            output: vec![],
+        }
+    }
+
+    fn block_proof(txids: &[Txid], proven: Txid) -> MerkleBlock {
+        let header = Header {
+            version: BlockVersion::ONE,
+            prev_blockhash: BlockHash::all_zeros(),
+            merkle_root: bitcoin::merkle_tree::calculate_root(txids.iter().copied())
+                .unwrap()
+                .into(),
+            time: 0,
+            bits: CompactTarget::from_consensus(0),
+            nonce: 0,
+        };
+        MerkleBlock::from_header_txids_with_predicate(&header, txids, |txid| *txid == proven)
+    }
+
+    #[test]
+    fn test_build_proof() {
+        let round_tx = tx(1);
+        let round_txid = round_tx.compute_txid();
+        let txids = [tx(2).compute_txid(), round_txid, tx(3).compute_txid()];
+        let parts = |round_tx: Transaction, inclusion_proof: MerkleBlock| ProofParts {
+            payment_id: "payment".to_string(),
+            amount_sat: 5_000,
+            round_txid,
+            round_tx,
+            block_height: 100,
+            inclusion_proof,
+            server_pubkey: "02server".to_string(),
+        };
+
+        let proof = build_proof(parts(round_tx.clone(), block_proof(&txids, round_txid))).unwrap();
+        assert_eq!(round_txid.to_string(), proof.round_txid);
+        assert_eq!(serialize_hex(&round_tx), proof.round_tx_hex);
+
+        // A proof for another transaction of the block doesn't cover the round
+        assert!(matches!(
+            build_proof(parts(round_tx.clone(), block_proof(&txids, txids[0]))),
+            Err(SdkError::RoundVerificationFailed(_))
+        ));
+        // Nor does a merkle path that doesn't lead to the header's merkle root
+        let mut forged = block_proof(&[txids[0], round_txid], round_txid);
+        forged.header = block_proof(&txids, round_txid).header;
+        assert!(matches!(
+            build_proof(parts(round_tx, forged)),
+            Err(SdkError::RoundVerificationFailed(_))
+        ));
+        assert!(matches!(
+            build_proof(parts(tx(4), block_proof(&txids, round_txid))),
+            Err(SdkError::RoundVerificationFailed(_))
+        ));
+    }
+}
@@ -0,0 +1,132 @@
+use bitcoin::bech32::primitives::decode::CheckedHrpstring;
+use bitcoin::bech32::{Bech32, Bech32m};
+
+use crate::error::SdkError;
+use crate::models::Network;
+
+/// Human-readable part of Ark addresses on a network
+fn ark_hrp(network: Network) -> &'static str {
+    match network {
+        Network::Bitcoin => "ark",
+        Network::Testnet | Network::Signet | Network::Regtest => "tark",
+    }
+}
+
+/// Whether a string is bech32 or bech32m encoded, e.g. an Ark address, a segwit address or a
+/// BOLT11 invoice. These can be upper-cased without changing their meaning
+fn is_bech32(input: &str) -> bool {
+    CheckedHrpstring::new::<Bech32m>(input).is_ok()
+        || CheckedHrpstring::new::<Bech32>(input).is_ok()
+}
+
+/// Normalizes an Ark address to lowercase, checking its casing, checksum and network
+///
+/// Addresses scanned from a QR code in alphanumeric mode are upper case, which is as valid as
+/// lower case. Mixing both isn't.
+pub(crate) fn normalize_ark_address(address: &str, network: Network) -> Result<String, SdkError> {
+    let address = address.trim();
+    let checked = CheckedHrpstring::new::<Bech32m>(address)
+        .map_err(|e| SdkError::InvalidInput(format!("Invalid Ark address: {e}")))?;
+    let hrp = checked.hrp().to_lowercase();
+    if hrp != ark_hrp(network) {
+        return match hrp.as_str() {
+            "ark" | "tark" => Err(SdkError::InvalidNetwork),
+            _ => Err(SdkError::InvalidInput(format!(
+                "Invalid Ark address: unknown prefix {hrp}"
+            ))),
+        };
+    }
+    let address = address.to_lowercase();
+    ark_core::ArkAddress::decode(&address)
+        .map_err(|e| SdkError::InvalidInput(format!("Invalid Ark address: {e}")))?;
+    Ok(address)
+}
+
+/// Formats a destination to be shown as a QR code, upper-casing it where that keeps it valid
+///
+/// Upper case strings fit the alphanumeric mode of QR codes, which makes the code of long Ark
+/// addresses much less dense. Only bech32 strings are upper-cased, and of BIP21 URIs only the
+/// scheme, the address and the `ark` and `lightning` parameters, as other parameters such as
+/// the label are case-sensitive. Anything else is returned unchanged.
+pub(crate) fn format_for_qr(destination: &str) -> String {
+    let destination = destination.trim();
+    if is_bech32(destination) {
+        return destination.to_uppercase();
+    }
+    let Some((scheme, rest)) = destination.split_once(':') else {
+        return destination.to_string();
+    };
+    if !scheme.eq_ignore_ascii_case("bitcoin") {
+        return destination.to_string();
+    }
+
+    let (address, query) = match rest.split_once('?') {
+        Some((address, query)) => (address, Some(query)),
+        None => (rest, None),
+    };
+    let mut uri = format!("BITCOIN:{}", format_for_qr(address));
+    if let Some(query) = query {
+        let params: Vec<String> = query
+            .split('&')
+            .map(|param| match param.split_once('=') {
+                Some((key, value))
+                    if key.eq_ignore_ascii_case("ark") || key.eq_ignore_ascii_case("lightning") =>
+                {
+                    format!("{}={}", key.to_uppercase(), format_for_qr(value))
+                }
+                _ => param.to_string(),
+            })
+            .collect();
+        uri.push('?');
+        uri.push_str(&params.join("&"));
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARK_ADDRESS: &str = "tark1qr9f0qgjegdmmjh6cgcm8x3rm3x60ph0lq28cnnjhxq80pd0aeytk03raqtqqw2efgecjnm9vnsmzdyth4aqpzx5939vkulw4m2ecqyat5gxn8";
+    const TAPROOT_ADDRESS: &str = "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0";
+
+    #[test]
+    fn test_format_for_qr() {
+        assert_eq!(ARK_ADDRESS.to_uppercase(), format_for_qr(ARK_ADDRESS));
+        assert_eq!(
+            TAPROOT_ADDRESS.to_uppercase(),
+            format_for_qr(TAPROOT_ADDRESS)
+        );
+        // Base58 addresses are case-sensitive
+        let legacy = "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2";
+        assert_eq!(legacy, format_for_qr(legacy));
+
+        assert_eq!(
+            format!(
+                "BITCOIN:{}?amount=0.001&label=Coffee%20Shop&ARK={}",
+                TAPROOT_ADDRESS.to_uppercase(),
+                ARK_ADDRESS.to_uppercase()
+            ),
+            format_for_qr(&format!(
+                "bitcoin:{TAPROOT_ADDRESS}?amount=0.001&label=Coffee%20Shop&ark={ARK_ADDRESS}"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_normalize_ark_address_rejected() {
+        let mixed_case = format!("TARK{}", &ARK_ADDRESS[4..]);
+        assert!(matches!(
+            normalize_ark_address(&mixed_case, Network::Signet),
+            Err(SdkError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            normalize_ark_address(ARK_ADDRESS, Network::Bitcoin),
+            Err(SdkError::InvalidNetwork)
+        ));
+        assert!(matches!(
+            normalize_ark_address(TAPROOT_ADDRESS, Network::Bitcoin),
+            Err(SdkError::InvalidInput(_))
+        ));
+    }
+}
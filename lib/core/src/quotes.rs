@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::error::SdkError;
+use crate::models::PrepareSendOnchainResponse;
+
+/// How long an onchain send quote can be executed, before its fee estimate is considered stale
+pub(crate) const ONCHAIN_QUOTE_TTL_SECS: u64 = 10 * 60;
+
+/// Onchain send quotes handed out by `prepare_send_onchain` that weren't executed yet
+#[derive(Default)]
+pub(crate) struct OnchainQuotes {
+    quotes: Mutex<HashMap<String, PrepareSendOnchainResponse>>,
+}
+
+impl OnchainQuotes {
+    /// Remembers a quote so it can be executed, dropping the quotes that expired meanwhile
+    pub(crate) fn insert(&self, quote: PrepareSendOnchainResponse, now: u64) {
+        let mut quotes = self.quotes.lock().unwrap();
+        quotes.retain(|_, quote| quote.expires_at > now);
+        quotes.insert(quote.quote_id.clone(), quote);
+    }
+
+    /// Takes a quote out for execution, so it can only be executed once
+    ///
+    /// Fails if the quote wasn't handed out by this SDK, was altered or has expired. A rejected
+    /// quote is kept, so an altered copy can't void the prepared one.
+    pub(crate) fn take(
+        &self,
+        quote: &PrepareSendOnchainResponse,
+        now: u64,
+    ) -> Result<PrepareSendOnchainResponse, SdkError> {
+        let mut quotes = self.quotes.lock().unwrap();
+        let prepared = quotes.get(&quote.quote_id).ok_or_else(|| {
+            SdkError::InvalidInput(format!(
+                "Unknown quote {}, it was already used or never prepared",
+                quote.quote_id
+            ))
+        })?;
+
+        if prepared != quote {
+            return Err(SdkError::InvalidInput(format!(
+                "Quote {} doesn't match the prepared send",
+                quote.quote_id
+            )));
+        }
+        if prepared.expires_at <= now {
+            return Err(SdkError::QuoteExpired(quote.quote_id.clone()));
+        }
+        quotes.remove(&quote.quote_id);
+        Ok(quote.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(quote_id: &str) -> PrepareSendOnchainResponse {
+        PrepareSendOnchainResponse {
+            quote_id: quote_id.to_string(),
+            onchain_address: "bcrt1qdestination".to_string(),
+            receiver_amount_sats: 10_000,
+            fee_sats: 200,
+            expires_at: 1_000 + ONCHAIN_QUOTE_TTL_SECS,
+        }
+    }
+
+    #[test]
+    fn test_quote_executed_once() {
+        let quotes = OnchainQuotes::default();
+        quotes.insert(quote("quote_1"), 1_000);
+
+        assert!(quotes.take(&quote("quote_1"), 1_001).is_ok());
+        assert!(matches!(
+            quotes.take(&quote("quote_1"), 1_002),
+            Err(SdkError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_quote_rejected_when_altered_or_expired() {
+        let quotes = OnchainQuotes::default();
+        quotes.insert(quote("quote_1"), 1_000);
+        quotes.insert(quote("quote_2"), 1_000);
+
+        let altered = PrepareSendOnchainResponse {
+            onchain_address: "bcrt1qother".to_string(),
+            ..quote("quote_1")
+        };
+        assert!(matches!(
+            quotes.take(&altered, 1_001),
+            Err(SdkError::InvalidInput(_))
+        ));
+        // The prepared quote can still be executed
+        assert!(quotes.take(&quote("quote_1"), 1_001).is_ok());
+
+        let expiry = quote("quote_2").expires_at;
+        assert!(matches!(
+            quotes.take(&quote("quote_2"), expiry),
+            Err(SdkError::QuoteExpired(_))
+        ));
+    }
+}
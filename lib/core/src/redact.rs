@@ -0,0 +1,185 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use bip39::{Language, Mnemonic};
+
+/// Placeholder replacing secrets in logs and `Debug` output
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Fewest consecutive wordlist words taken for a mnemonic
+const MIN_MNEMONIC_WORDS: usize = 12;
+/// Shortest hex string taken for a seed or private key material, i.e. 64 bytes
+const MIN_SECRET_HEX_LEN: usize = 128;
+/// Prefixes and length of base58 encoded extended private keys
+const EXTENDED_KEY_PREFIXES: [&str; 2] = ["xprv", "tprv"];
+const EXTENDED_KEY_LEN: usize = 111;
+/// Human readable parts of the bech32 addresses the wallet deals with, including the separator
+const ADDRESS_PREFIXES: [&str; 5] = ["bc1", "tb1", "bcrt1", "ark1", "tark1"];
+/// Shortest bech32 string taken for an address rather than e.g. a word starting with "bc1"
+const MIN_ADDRESS_LEN: usize = 26;
+/// Characters kept at the start and end of truncated addresses
+const ADDRESS_PREFIX_CHARS: usize = 12;
+const ADDRESS_SUFFIX_CHARS: usize = 6;
+
+/// Masks secrets in a log line, and truncates addresses unless `full_addresses` is set
+///
+/// Mnemonics are recognized as runs of words from a single wordlist, seeds as long hex strings.
+/// Truncated addresses keep enough characters to tell them apart when debugging.
+pub(crate) fn redact(message: &str, full_addresses: bool) -> String {
+    let tokens = tokenize(message);
+    let mut redacted = String::with_capacity(message.len());
+    let mut pos = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let (start, end) = tokens[i];
+        redacted.push_str(&message[pos..start]);
+
+        let mnemonic_words = mnemonic_len(message, &tokens[i..]);
+        if mnemonic_words >= MIN_MNEMONIC_WORDS {
+            redacted.push_str(REDACTED);
+            pos = tokens[i + mnemonic_words - 1].1;
+            i += mnemonic_words;
+            continue;
+        }
+
+        redact_token(&message[start..end], full_addresses, &mut redacted);
+        pos = end;
+        i += 1;
+    }
+    redacted.push_str(&message[pos..]);
+    redacted
+}
+
+/// Byte ranges of the alphanumeric runs of a message
+fn tokenize(message: &str) -> Vec<(usize, usize)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (index, c) in message.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(index),
+            (false, Some(token_start)) => {
+                tokens.push((token_start, index));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(token_start) = start {
+        tokens.push((token_start, message.len()));
+    }
+    tokens
+}
+
+/// Number of leading tokens that are words of the same wordlist, separated by whitespace only
+fn mnemonic_len(message: &str, tokens: &[(usize, usize)]) -> usize {
+    // Languages whose wordlist contains every word so far
+    let mut languages = u16::MAX;
+    let mut len = 0;
+    for (k, (start, end)) in tokens.iter().enumerate() {
+        let separated_by_whitespace = k == 0
+            || message[tokens[k - 1].1..*start]
+                .chars()
+                .all(char::is_whitespace);
+        if !separated_by_whitespace {
+            break;
+        }
+        let mut word = Cow::Borrowed(&message[*start..*end]);
+        Mnemonic::normalize_utf8_cow(&mut word);
+        languages &= wordlist_languages()
+            .get(word.to_lowercase().as_str())
+            .copied()
+            .unwrap_or_default();
+        if languages == 0 {
+            break;
+        }
+        len += 1;
+    }
+    len
+}
+
+/// Maps every wordlist word to the bitmask of the languages it belongs to
+///
+/// Built once, as most wordlists can only be searched linearly.
+fn wordlist_languages() -> &'static HashMap<&'static str, u16> {
+    static WORDS: OnceLock<HashMap<&'static str, u16>> = OnceLock::new();
+    WORDS.get_or_init(|| {
+        let mut words = HashMap::new();
+        for (index, language) in Language::ALL.iter().enumerate() {
+            for word in language.word_list() {
+                *words.entry(*word).or_default() |= 1 << index;
+            }
+        }
+        words
+    })
+}
+
+fn redact_token(token: &str, full_addresses: bool, redacted: &mut String) {
+    let is_secret_hex =
+        token.len() >= MIN_SECRET_HEX_LEN && token.chars().all(|c| c.is_ascii_hexdigit());
+    let is_extended_key = token.len() == EXTENDED_KEY_LEN
+        && EXTENDED_KEY_PREFIXES
+            .iter()
+            .any(|prefix| token.starts_with(prefix));
+    if is_secret_hex || is_extended_key {
+        redacted.push_str(REDACTED);
+        return;
+    }
+
+    let lowercase = token.to_ascii_lowercase();
+    let is_address = token.is_ascii()
+        && token.len() >= MIN_ADDRESS_LEN
+        && ADDRESS_PREFIXES
+            .iter()
+            .any(|prefix| lowercase.starts_with(prefix));
+    if is_address && !full_addresses {
+        redacted.push_str(&token[..ADDRESS_PREFIX_CHARS]);
+        redacted.push_str("...");
+        redacted.push_str(&token[token.len() - ADDRESS_SUFFIX_CHARS..]);
+        return;
+    }
+    redacted.push_str(token);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        assert_eq!(
+            "Using mnemonic: <redacted>.",
+            redact(&format!("Using mnemonic: {mnemonic}."), false)
+        );
+        // Fewer words are regular text
+        assert_eq!(
+            "Sync found an empty wallet",
+            redact("Sync found an empty wallet", false)
+        );
+        let spanish = Mnemonic::from_entropy_in(Language::Spanish, &[7; 16]).unwrap();
+        assert_eq!(
+            "Restoring <redacted>",
+            redact(&format!("Restoring {spanish}"), false)
+        );
+
+        let seed = "ab".repeat(64);
+        assert_eq!("seed: <redacted>", redact(&format!("seed: {seed}"), false));
+        // Txids are left alone
+        let txid = "5c1e4b3ad07c6fbb0c1fc6a6a3f8d5ec4c2bd2a3e8e8d3c3f4e6a2a1c0b9d8e7";
+        assert_eq!(
+            format!("txid {txid}"),
+            redact(&format!("txid {txid}"), false)
+        );
+
+        let address = "bcrt1q9ylz8ld5yqz7quevzp6ruzhsmpyvxhkwdfx2mq";
+        assert_eq!(
+            "Sent to Some(\"bcrt1q9ylz8l...dfx2mq\")",
+            redact(&format!("Sent to Some({address:?})"), false)
+        );
+        assert_eq!(
+            format!("Sent to {address}"),
+            redact(&format!("Sent to {address}"), true)
+        );
+    }
+}
@@ -1,19 +1,37 @@
 use crate::chain::esplora::EsploraBlockchain;
+use crate::chain::ChainService;
+use crate::chain::ReplaceableChainService;
+use crate::clock::{Clock, SystemClock};
+#[cfg(feature = "demo")]
+use crate::demo::{DemoChainService, DemoFeeProvider};
 use crate::error::SdkError;
+use crate::fees::{DefaultFeeProvider, FeeProvider};
 use crate::models::Config;
+use crate::persist::ark::InMemoryDb;
+use crate::persist::layout;
+use crate::persist::lock::WalletLock;
 use crate::persist::sqlite::SqliteStorage;
 use crate::persist::Storage;
+use crate::tasks;
 use crate::BreezSdk;
-use std::fs;
+use ark_client::wallet::Persistence;
 use std::path::PathBuf;
 use std::sync::Arc;
+use tokio::runtime::Handle;
 use tokio::sync::watch;
 
 /// Builder for creating and configuring a BreezSdk instance
 pub struct SdkBuilder {
     config: Config,
-    storage: Option<Arc<dyn Storage + Send + Sync>>,
-    chain_service: Option<Arc<EsploraBlockchain>>,
+    storage: Option<Arc<dyn Storage>>,
+    chain_service: Option<Arc<dyn ChainService>>,
+    wallet_persistence: Option<Arc<dyn Persistence + Send + Sync>>,
+    fee_provider: Option<Arc<dyn FeeProvider>>,
+    clock: Option<Arc<dyn Clock>>,
+    runtime: Option<Handle>,
+    http_client: Option<reqwest::Client>,
+    read_only: bool,
+    demo: bool,
     mnemonic: String,
 }
 
@@ -33,10 +51,67 @@ impl SdkBuilder {
             config,
             storage: None,
             chain_service: None,
+            wallet_persistence: None,
+            fee_provider: None,
+            clock: None,
+            runtime: None,
+            http_client: None,
+            read_only: false,
+            demo: false,
             mnemonic,
         }
     }
 
+    /// Keeps all wallet data in memory, so the wallet never touches disk
+    ///
+    /// This is the same as setting `in_memory` in the [Config].
+    ///
+    /// # Returns
+    ///
+    /// The updated SdkBuilder instance
+    pub fn in_memory(mut self) -> Self {
+        self.config.in_memory = true;
+        self
+    }
+
+    /// Opens the wallet in the data directory read-only, even if another process is using it
+    ///
+    /// Without this, [SdkBuilder::build] fails with [SdkError::AlreadyRunning] while another
+    /// process has the data directory open. A read-only wallet can be queried, but anything
+    /// writing to storage fails and [BreezSdk::start] can't run the background tasks.
+    ///
+    /// # Returns
+    ///
+    /// The updated SdkBuilder instance
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Builds a demo wallet, fabricating balances, payments and events without any server
+    ///
+    /// Apps can build their UI against the real API surface offline and record demos with it.
+    /// The wallet is kept in memory and starts with a confirmed deposit, a completed send and a
+    /// received payment pending settlement. Everything is derived from the [Clock], so with a
+    /// fixed one every run produces the same payments, IDs and events:
+    /// - [BreezSdk::receive_payment] returns made-up destinations, which can't be paid
+    /// - [BreezSdk::send_payment] pays any destination offchain for a fixed fee, right away
+    /// - [BreezSdk::sync_wallet] settles pending payments, as the next round would
+    /// - [BreezSdk::simulate_incoming_payment] fabricates a payment from someone else
+    ///
+    /// Balances and payments are queried as usual. The background tasks don't run, and calls
+    /// needing an Ark server fail. The storage, chain service and fee provider set on the
+    /// builder are replaced by demo ones.
+    ///
+    /// # Returns
+    ///
+    /// The updated SdkBuilder instance
+    #[cfg(feature = "demo")]
+    pub fn demo(mut self) -> Self {
+        self.demo = true;
+        self
+    }
+
     /// Sets a custom storage implementation
     ///
     /// # Arguments
@@ -46,13 +121,16 @@ impl SdkBuilder {
     /// # Returns
     ///
     /// The updated SdkBuilder instance
-    pub fn storage(mut self, storage: Arc<dyn Storage + Send + Sync>) -> Self {
+    pub fn storage(mut self, storage: Arc<dyn Storage>) -> Self {
         self.storage = Some(storage);
         self
     }
 
     /// Sets a custom chain service implementation
     ///
+    /// It replaces the Esplora instance from the [Config], both for the SDK and the Ark client,
+    /// until an Esplora URL is set with [BreezSdk::update_config].
+    ///
     /// # Arguments
     ///
     /// * `chain_service` - The chain service implementation
@@ -60,40 +138,262 @@ impl SdkBuilder {
     /// # Returns
     ///
     /// The updated SdkBuilder instance
-    pub fn chain_service(mut self, chain_service: Arc<EsploraBlockchain>) -> Self {
+    pub fn chain_service(mut self, chain_service: Arc<dyn ChainService>) -> Self {
         self.chain_service = Some(chain_service);
         self
     }
 
+    /// Sets a custom wallet persistence implementation
+    ///
+    /// It stores the boarding outputs of the wallet and their secret keys, e.g. in the platform
+    /// keychain. By default they are kept in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `wallet_persistence` - The wallet persistence implementation
+    ///
+    /// # Returns
+    ///
+    /// The updated SdkBuilder instance
+    pub fn wallet_persistence(
+        mut self,
+        wallet_persistence: Arc<dyn Persistence + Send + Sync>,
+    ) -> Self {
+        self.wallet_persistence = Some(wallet_persistence);
+        self
+    }
+
+    /// Sets a custom fee provider implementation
+    ///
+    /// # Arguments
+    ///
+    /// * `fee_provider` - The fee provider implementation
+    ///
+    /// # Returns
+    ///
+    /// The updated SdkBuilder instance
+    pub fn fee_provider(mut self, fee_provider: Arc<dyn FeeProvider>) -> Self {
+        self.fee_provider = Some(fee_provider);
+        self
+    }
+
+    /// Sets a custom clock implementation
+    ///
+    /// The SDK reads the current time from it, e.g. to timestamp payments and check their
+    /// expiry. By default the system clock is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `clock` - The clock implementation
+    ///
+    /// # Returns
+    ///
+    /// The updated SdkBuilder instance
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Sets the tokio runtime the SDK spawns its background tasks on and runs its calls in
+    ///
+    /// By default it's the runtime [SdkBuilder::build] is called from. Host apps without a
+    /// tokio runtime of their own, e.g. calling in through bindings, can pass the handle of a
+    /// multi-threaded runtime kept alive for as long as the SDK. The SDK's calls then enter it
+    /// whenever they're polled, so storage and network calls work from any executor.
+    ///
+    /// # Arguments
+    ///
+    /// * `runtime` - Handle of the runtime
+    ///
+    /// # Returns
+    ///
+    /// The updated SdkBuilder instance
+    pub fn runtime(mut self, runtime: Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Sets the HTTP client the SDK makes its HTTP requests with
+    ///
+    /// This allows a client set up with custom TLS roots, certificate pinning, a proxy or a
+    /// user agent, e.g. to meet the egress policies of an enterprise network. It is used for
+    /// Esplora, also after switching Esplora with [BreezSdk::update_config], and for the LNURL
+    /// server. It can't be combined with [Config::esplora_headers], building fails if any are
+    /// set, so set them as default headers of the client instead. The Ark server is reached over
+    /// its own gRPC transport, so neither this client nor its headers are used for it.
+    ///
+    /// The client has to come from reqwest 0.11, the release the Esplora client is built on.
+    ///
+    /// # Arguments
+    ///
+    /// * `http_client` - The preconfigured HTTP client
+    ///
+    /// # Returns
+    ///
+    /// The updated SdkBuilder instance
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
     /// Builds the BreezSdk instance
     ///
     /// # Returns
     ///
     /// A Result containing either the initialized BreezSdk or an SdkError
     pub async fn build(self) -> Result<BreezSdk, SdkError> {
-        // Create default storage if not provided
-        let storage = match self.storage {
-            Some(storage) => storage,
-            None => {
+        // A custom HTTP client sends its own headers, the configured ones would be silently dropped
+        if self.chain_service.is_none()
+            && !self.demo
+            && self.http_client.is_some()
+            && !self.config.esplora_headers.is_empty()
+        {
+            return Err(SdkError::InvalidInput(
+                "Esplora headers can't be set with a custom HTTP client, set them as default \
+                 headers of the client instead"
+                    .to_string(),
+            ));
+        }
+
+        // Create default storage if not provided, locking the data directory it lives in
+        let (storage, wallet_lock): (Arc<dyn Storage>, _) = match self.storage {
+            Some(storage) if !self.demo => (storage, WalletLock::Unlocked),
+            // The wallet persistence is always kept in memory, so only storage needs switching
+            _ if self.demo || self.config.in_memory => (
+                Arc::new(SqliteStorage::new_in_memory()?),
+                WalletLock::Unlocked,
+            ),
+            _ => {
                 let path =
                     PathBuf::from(&self.config.data_dir).join(self.config.network.to_string());
                 let db_path = path.join("breez-sdk-ark.db");
-                fs::create_dir_all(&path)?;
-                Arc::new(SqliteStorage::new(&db_path)?)
+                if self.read_only {
+                    (
+                        Arc::new(SqliteStorage::open_read_only(&db_path)?),
+                        WalletLock::ReadOnly,
+                    )
+                } else {
+                    // Another process may be creating or migrating the directory
+                    let wallet_lock = WalletLock::acquire(&path)?;
+                    layout::prepare_data_dir(&path)?;
+                    (Arc::new(SqliteStorage::new(&db_path)?), wallet_lock)
+                }
             }
         };
 
+        let clock = self.clock.unwrap_or_else(|| Arc::new(SystemClock));
+
+        // Create default chain service if not provided
+        let chain_service: Arc<dyn ChainService> = match (self.chain_service, &self.http_client) {
+            #[cfg(feature = "demo")]
+            _ if self.demo => Arc::new(DemoChainService::new(clock.clone())),
+            (Some(chain_service), _) => chain_service,
+            (None, Some(http_client)) => Arc::new(EsploraBlockchain::with_http_client(
+                self.config.esplora_url.to_string(),
+                http_client.clone(),
+            )),
+            (None, None) => Arc::new(EsploraBlockchain::with_headers(
+                self.config.esplora_url.to_string(),
+                &self.config.esplora_headers,
+            )?),
+        };
+        // Everything reads the chain through the same handle, so the Esplora instance can be
+        // switched at runtime
+        let chain_service = Arc::new(ReplaceableChainService::new(chain_service));
+
+        // Keep the wallet's boarding outputs in memory if no persistence was provided
+        let wallet_persistence = self
+            .wallet_persistence
+            .unwrap_or_else(|| Arc::new(InMemoryDb::default()));
+
+        // Create default fee provider if not provided
+        let fee_provider = match self.fee_provider {
+            #[cfg(feature = "demo")]
+            _ if self.demo => Arc::new(DemoFeeProvider),
+            Some(fee_provider) => fee_provider,
+            None => Arc::new(DefaultFeeProvider::new(
+                self.config.ark_server_url.clone(),
+                chain_service.clone(),
+            )),
+        };
+
+        let runtime = match self.runtime {
+            Some(runtime) => runtime,
+            None => Handle::try_current().map_err(|_| {
+                SdkError::GenericError(
+                    "No tokio runtime to run the SDK on, set one with SdkBuilder::runtime"
+                        .to_string(),
+                )
+            })?,
+        };
+
         // Create shutdown channel
         let (shutdown_sender, shutdown_receiver) = watch::channel(());
 
-        // Create the SDK instance
-        BreezSdk::new(
-            self.config,
-            self.mnemonic,
-            storage,
-            shutdown_sender,
-            shutdown_receiver,
+        // Create the SDK instance, connecting to the Ark server on the SDK's runtime
+        tasks::on_runtime(
+            &runtime.clone(),
+            BreezSdk::new(
+                self.config,
+                self.mnemonic,
+                storage,
+                chain_service,
+                wallet_persistence,
+                fee_provider,
+                clock,
+                wallet_lock,
+                self.http_client,
+                self.demo,
+                runtime,
+                shutdown_sender,
+                shutdown_receiver,
+            ),
         )
         .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Network;
+
+    const MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[tokio::test]
+    async fn test_http_client_with_esplora_headers_rejected() {
+        let mut config = Config::default_config(Network::Regtest, String::new()).unwrap();
+        config.esplora_headers = [("Authorization".to_string(), "Bearer key".to_string())].into();
+
+        let result = SdkBuilder::new(config, String::new())
+            .in_memory()
+            .http_client(reqwest::Client::new())
+            .build()
+            .await;
+        assert!(matches!(result, Err(SdkError::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_build_keeps_reservations() {
+        let data_dir = std::env::temp_dir().join(format!("read-only-{}", uuid::Uuid::new_v4()));
+        let mut config =
+            Config::default_config(Network::Regtest, data_dir.display().to_string()).unwrap();
+        config.ark_server_url = "http://127.0.0.1:1".to_string();
+
+        // The process holding the lock has a send in flight
+        let path = data_dir.join(config.network.to_string());
+        std::fs::create_dir_all(&path).unwrap();
+        let storage = SqliteStorage::new(&path.join("breez-sdk-ark.db")).unwrap();
+        assert!(storage.reserve_funds("send", 1_000, 10_000).await.unwrap());
+
+        let result = SdkBuilder::new(config, MNEMONIC.to_string())
+            .read_only()
+            .build()
+            .await;
+        // Building gets past the storage and only fails to reach the Ark server
+        assert!(matches!(result, Err(ref e) if !matches!(e, SdkError::StorageError(_))));
+        assert_eq!(1_000, storage.get_reserved_sats().await.unwrap());
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
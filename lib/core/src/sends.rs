@@ -0,0 +1,276 @@
+use std::collections::HashSet;
+
+use ark_core::ArkTransaction;
+
+use crate::error::SdkError;
+use crate::models::{
+    Payment, PaymentDetails, PaymentFailureKind, PaymentFailureReason, PaymentStatus, PaymentType,
+    PendingSend, SendSubmission,
+};
+
+/// A pending send after reconciling it with the wallet's VTXOs and history
+pub(crate) enum ReconciledSend {
+    /// The send is still pending, see its [PendingSend::submission]
+    Pending(PendingSend),
+    /// The send went through as this payment
+    Sent(Payment),
+}
+
+/// Outcome of reconciling a pending send whose last attempt may have reached the Ark server
+#[derive(Debug, PartialEq)]
+pub(crate) enum Reconciliation {
+    /// The VTXOs the attempt could spend are all still spendable, it never went through
+    NotSubmitted,
+    /// The attempt went through in the Ark transaction with this ID
+    Sent(String),
+    /// Some of the VTXOs were spent but no transaction of the wallet matches the send yet
+    Unknown,
+}
+
+/// The amount a send spends, its fees included, failing if caller-supplied values overflow
+pub(crate) fn total_sat(amount_sat: u64, fees_sat: u64) -> Result<u64, SdkError> {
+    amount_sat.checked_add(fees_sat).ok_or_else(|| {
+        SdkError::InvalidInput("The amount and fees of the send overflow".to_string())
+    })
+}
+
+/// Whether an attempt that returned an error may still have reached the Ark server
+///
+/// # Arguments
+///
+/// * `attempt_inputs` - Outpoints of the VTXOs spendable when the attempt started
+/// * `spendable` - Outpoints of the VTXOs spendable now
+pub(crate) fn submission_after_failure(
+    attempt_inputs: &[String],
+    spendable: &HashSet<String>,
+) -> SendSubmission {
+    match attempt_inputs
+        .iter()
+        .all(|outpoint| spendable.contains(outpoint))
+    {
+        true => SendSubmission::NotSubmitted,
+        false => SendSubmission::Unknown,
+    }
+}
+
+/// Reconciles a send whose last attempt may have reached the Ark server
+///
+/// An attempt that didn't spend any of the VTXOs it could spend never went through. Otherwise
+/// it's matched to an offchain transaction of the wallet sending the same amount that was made
+/// since the attempt started and isn't already recorded as a payment.
+///
+/// # Arguments
+///
+/// * `send` - The send, with [SendSubmission::Unknown]
+/// * `spendable` - Outpoints of the VTXOs spendable now
+/// * `history` - The wallet's transactions reported by the Ark server
+/// * `recorded_txids` - IDs of the sent payments already recorded, e.g. by other sends
+pub(crate) fn reconcile(
+    send: &PendingSend,
+    spendable: &HashSet<String>,
+    history: &[ArkTransaction],
+    recorded_txids: &HashSet<String>,
+) -> Reconciliation {
+    if submission_after_failure(&send.attempt_inputs, spendable) == SendSubmission::NotSubmitted {
+        return Reconciliation::NotSubmitted;
+    }
+
+    let started_at = send.attempt_started_at.unwrap_or(send.created_at) as i64;
+    history
+        .iter()
+        .filter_map(|tx| match tx {
+            ArkTransaction::Redeem {
+                txid,
+                amount,
+                created_at,
+                ..
+            } if amount.is_negative() && *created_at >= started_at => Some((txid, amount)),
+            _ => None,
+        })
+        .find(|(txid, amount)| {
+            let sent_sat = amount.unsigned_abs().to_sat();
+            (sent_sat == send.amount_sat
+                || send.amount_sat.checked_add(send.fees_sat) == Some(sent_sat))
+                && !recorded_txids.contains(&txid.to_string())
+        })
+        .map_or(Reconciliation::Unknown, |(txid, _)| {
+            Reconciliation::Sent(txid.to_string())
+        })
+}
+
+/// The payment of a send the Ark server accepted in the Ark transaction with the given ID
+pub(crate) fn sent_payment(send: &PendingSend, txid: String, timestamp: u64) -> Payment {
+    Payment {
+        id: txid.clone(),
+        payment_type: PaymentType::Sent,
+        status: PaymentStatus::Pending,
+        amount: send.amount_sat,
+        fees: send.fees_sat,
+        timestamp,
+        description: None,
+        destination: Some(send.destination.clone()),
+        is_retryable: false,
+        details: Some(PaymentDetails::Ark {
+            txid,
+            round_txid: None,
+        }),
+        onchain_txs: Vec::new(),
+        order_id: None,
+        deposit_source: None,
+        failure_reason: None,
+    }
+}
+
+/// How a pending send is listed among the payments
+///
+/// A send is only listed as failed and retryable once its last attempt is known not to have
+/// reached the Ark server, otherwise it's pending.
+pub(crate) fn pending_payment(send: PendingSend) -> Payment {
+    let failed = send.submission == SendSubmission::NotSubmitted && send.last_error.is_some();
+    // Sends that failed before reasons were recorded only have the error message
+    let failure_reason = match failed {
+        true => send.failure_reason.or_else(|| {
+            send.last_error
+                .map(|error| PaymentFailureReason::new(PaymentFailureKind::Other, error))
+        }),
+        false => None,
+    };
+    Payment {
+        id: send.id,
+        payment_type: PaymentType::Sent,
+        status: match failed {
+            true => PaymentStatus::Failed,
+            false => PaymentStatus::Pending,
+        },
+        amount: send.amount_sat,
+        fees: send.fees_sat,
+        timestamp: send.created_at,
+        description: None,
+        destination: Some(send.destination),
+        is_retryable: failed,
+        details: None,
+        onchain_txs: Vec::new(),
+        order_id: None,
+        deposit_source: None,
+        failure_reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::Hash;
+    use bitcoin::{SignedAmount, Txid};
+
+    fn send() -> PendingSend {
+        PendingSend {
+            id: "send_1".to_string(),
+            destination: "tark1destination".to_string(),
+            amount_sat: 5_000,
+            fees_sat: 0,
+            created_at: 1_700_000_000,
+            attempts: 1,
+            last_error: None,
+            failure_reason: None,
+            correlation_id: "flow_1".to_string(),
+            submission: SendSubmission::Unknown,
+            attempt_inputs: vec!["input:0".to_string(), "input:1".to_string()],
+            attempt_started_at: Some(1_700_000_100),
+        }
+    }
+
+    fn redeem(id: u8, amount_sat: i64, created_at: i64) -> ArkTransaction {
+        ArkTransaction::Redeem {
+            txid: Txid::from_byte_array([id; 32]),
+            amount: SignedAmount::from_sat(amount_sat),
+            is_settled: false,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_total_sat() {
+        assert_eq!(1_100, total_sat(1_000, 100).unwrap());
+        assert!(matches!(
+            total_sat(u64::MAX, 1),
+            Err(SdkError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_pending_payment() {
+        let mut send = send();
+        send.last_error = Some("connection reset".to_string());
+        send.failure_reason = Some(PaymentFailureReason::new(
+            PaymentFailureKind::Network,
+            "connection reset",
+        ));
+
+        // The attempt may have gone through, so it's not failed yet
+        let payment = pending_payment(send.clone());
+        assert_eq!(PaymentStatus::Pending, payment.status);
+        assert!(!payment.is_retryable);
+        assert_eq!(None, payment.failure_reason);
+
+        send.submission = SendSubmission::NotSubmitted;
+        let payment = pending_payment(send.clone());
+        assert_eq!(PaymentStatus::Failed, payment.status);
+        assert!(payment.is_retryable);
+        assert_eq!(send.failure_reason, payment.failure_reason);
+
+        // Sends failed before reasons were recorded
+        send.failure_reason = None;
+        let payment = pending_payment(send);
+        assert_eq!(
+            Some(PaymentFailureReason::new(
+                PaymentFailureKind::Other,
+                "connection reset"
+            )),
+            payment.failure_reason
+        );
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let send = send();
+        let unspent: HashSet<String> = send.attempt_inputs.iter().cloned().collect();
+        let spent = HashSet::from(["input:1".to_string()]);
+        let txid = |id: u8| Txid::from_byte_array([id; 32]).to_string();
+
+        // The inputs are unspent, whatever the history says
+        let history = vec![redeem(1, -5_000, 1_700_000_200)];
+        assert_eq!(
+            Reconciliation::NotSubmitted,
+            reconcile(&send, &unspent, &history, &HashSet::new())
+        );
+
+        // An earlier send of the same amount, one recorded for another send, and a receive
+        // aren't the attempt
+        let history = vec![
+            redeem(1, -5_000, 1_700_000_000),
+            redeem(2, -5_000, 1_700_000_200),
+            redeem(3, 5_000, 1_700_000_300),
+        ];
+        assert_eq!(
+            Reconciliation::Unknown,
+            reconcile(&send, &spent, &history, &HashSet::from([txid(2)]))
+        );
+
+        let history = vec![
+            redeem(1, -5_000, 1_700_000_000),
+            redeem(4, -5_000, 1_700_000_400),
+        ];
+        assert_eq!(
+            Reconciliation::Sent(txid(4)),
+            reconcile(&send, &spent, &history, &HashSet::new())
+        );
+
+        // Amounts whose total with the fees overflows are compared without it
+        let mut send = send;
+        send.amount_sat = u64::MAX;
+        assert_eq!(
+            Reconciliation::Unknown,
+            reconcile(&send, &spent, &history, &HashSet::new())
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tokio::sync::{watch, Mutex, MutexGuard};
+
+use crate::error::SdkError;
+use crate::models::{ReceiveWarning, SdkState};
+
+/// Runtime state shared by all clones of a `BreezSdk`
+///
+/// The flags describe what the SDK is currently doing, while the locks serialize operations
+/// that would otherwise conflict, such as two syncs writing to storage at the same time or a
+/// send selecting VTXOs that are being forfeited in a round.
+pub(crate) struct RuntimeState {
+    connected: AtomicBool,
+    stopped: AtomicBool,
+    syncing: AtomicBool,
+    round_in_progress: AtomicBool,
+    sends_paused: AtomicBool,
+    sync_lock: Mutex<()>,
+    vtxo_lock: Mutex<()>,
+    claimed_sends: std::sync::Mutex<HashSet<String>>,
+    clock_skew: std::sync::Mutex<Option<i64>>,
+    receive_warning: std::sync::Mutex<Option<ReceiveWarning>>,
+    ready: watch::Sender<bool>,
+}
+
+impl Default for RuntimeState {
+    fn default() -> Self {
+        Self {
+            connected: AtomicBool::default(),
+            stopped: AtomicBool::default(),
+            syncing: AtomicBool::default(),
+            round_in_progress: AtomicBool::default(),
+            sends_paused: AtomicBool::default(),
+            sync_lock: Mutex::default(),
+            vtxo_lock: Mutex::default(),
+            claimed_sends: std::sync::Mutex::default(),
+            clock_skew: std::sync::Mutex::default(),
+            receive_warning: std::sync::Mutex::default(),
+            ready: watch::channel(false).0,
+        }
+    }
+}
+
+impl RuntimeState {
+    pub(crate) fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::SeqCst);
+    }
+
+    /// Marks the SDK as stopped for good, returning `false` if it already was
+    pub(crate) fn mark_stopped(&self) -> bool {
+        self.connected.store(false, Ordering::SeqCst);
+        !self.stopped.swap(true, Ordering::SeqCst)
+    }
+
+    /// Records the skew of the local clock from the last check, returning `true` if it wasn't
+    /// skewed before
+    pub(crate) fn set_clock_skew(&self, skew_secs: Option<i64>) -> bool {
+        let previous = std::mem::replace(&mut *self.clock_skew.lock().unwrap(), skew_secs);
+        previous.is_none() && skew_secs.is_some()
+    }
+
+    pub(crate) fn clock_skew(&self) -> Option<i64> {
+        *self.clock_skew.lock().unwrap()
+    }
+
+    /// Records whether the Ark server accepted new destinations at the last check
+    pub(crate) fn set_receive_warning(&self, warning: Option<ReceiveWarning>) {
+        *self.receive_warning.lock().unwrap() = warning;
+    }
+
+    pub(crate) fn receive_warning(&self) -> Option<ReceiveWarning> {
+        self.receive_warning.lock().unwrap().clone()
+    }
+
+    /// Fails with [SdkError::AlreadyStopped] once the SDK was stopped
+    pub(crate) fn ensure_not_stopped(&self) -> Result<(), SdkError> {
+        if self.stopped.load(Ordering::SeqCst) {
+            return Err(SdkError::AlreadyStopped);
+        }
+        Ok(())
+    }
+
+    /// Waits for any running sync to finish and marks the SDK as syncing
+    pub(crate) async fn begin_sync(&self) -> OperationGuard<'_> {
+        let lock = self.sync_lock.lock().await;
+        OperationGuard::new(lock, &self.syncing)
+    }
+
+    /// Marks the SDK as syncing, unless a sync is already running
+    pub(crate) fn try_begin_sync(&self) -> Option<OperationGuard<'_>> {
+        let lock = self.sync_lock.try_lock().ok()?;
+        Some(OperationGuard::new(lock, &self.syncing))
+    }
+
+    /// Waits for any operation spending VTXOs to finish and marks a round as in progress
+    pub(crate) async fn begin_round(&self) -> OperationGuard<'_> {
+        let lock = self.vtxo_lock.lock().await;
+        OperationGuard::new(lock, &self.round_in_progress)
+    }
+
+    /// Waits for any operation spending VTXOs to finish before an out-of-round send
+    pub(crate) async fn begin_send(&self) -> MutexGuard<'_, ()> {
+        self.vtxo_lock.lock().await
+    }
+
+    /// Claims a pending send for sending or cancelling it, unless it's already claimed
+    pub(crate) fn claim_send(&self, id: &str) -> Option<SendClaim<'_>> {
+        let mut claimed_sends = self.claimed_sends.lock().unwrap();
+        if !claimed_sends.insert(id.to_string()) {
+            return None;
+        }
+        Some(SendClaim {
+            claimed_sends: &self.claimed_sends,
+            id: id.to_string(),
+        })
+    }
+
+    /// Pauses or resumes sends, returning whether they were paused before
+    pub(crate) fn set_sends_paused(&self, paused: bool) -> bool {
+        self.sends_paused.swap(paused, Ordering::SeqCst)
+    }
+
+    /// Fails if sends are paused because the Ark server can't be trusted
+    pub(crate) fn ensure_sends_allowed(&self) -> Result<(), SdkError> {
+        if self.sends_paused.load(Ordering::SeqCst) {
+            return Err(SdkError::ServerInconsistency(
+                "Sends are paused until the Ark server state matches the chain".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Marks the first sync as completed, returning `true` only the first time
+    pub(crate) fn mark_ready(&self) -> bool {
+        self.ready
+            .send_if_modified(|ready| !std::mem::replace(ready, true))
+    }
+
+    /// Resolves once the first sync has completed
+    pub(crate) async fn wait_ready(&self) {
+        let mut ready = self.ready.subscribe();
+        // The sender lives as long as self, so this can't fail
+        let _ = ready.wait_for(|ready| *ready).await;
+    }
+
+    pub(crate) fn snapshot(&self) -> SdkState {
+        SdkState {
+            is_connected: self.connected.load(Ordering::SeqCst),
+            is_syncing: self.syncing.load(Ordering::SeqCst),
+            is_round_in_progress: self.round_in_progress.load(Ordering::SeqCst),
+            are_sends_paused: self.sends_paused.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// Holds an operation lock and keeps its state flag set until dropped
+pub(crate) struct OperationGuard<'a> {
+    _lock: MutexGuard<'a, ()>,
+    flag: &'a AtomicBool,
+}
+
+impl<'a> OperationGuard<'a> {
+    fn new(lock: MutexGuard<'a, ()>, flag: &'a AtomicBool) -> Self {
+        flag.store(true, Ordering::SeqCst);
+        Self { _lock: lock, flag }
+    }
+}
+
+impl Drop for OperationGuard<'_> {
+    fn drop(&mut self) {
+        self.flag.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Keeps a pending send claimed until dropped
+pub(crate) struct SendClaim<'a> {
+    claimed_sends: &'a std::sync::Mutex<HashSet<String>>,
+    id: String,
+}
+
+impl Drop for SendClaim<'_> {
+    fn drop(&mut self) {
+        self.claimed_sends.lock().unwrap().remove(&self.id);
+    }
+}
@@ -0,0 +1,338 @@
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::{error, info};
+use tokio::runtime::Handle;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::clock::Clock;
+use crate::error::SdkError;
+use crate::models::{BackgroundTaskState, BackgroundTaskStatus};
+
+/// Longest delay between the runs of a failing task
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Polls a future in the context of the given runtime
+///
+/// Storage, HTTP and gRPC calls look up the current tokio runtime while they're polled, so a
+/// host app polling without a runtime of its own, e.g. calling in through bindings, has the
+/// runtime entered on every poll. The runtime needs to be multi-threaded, so its IO and timer
+/// drivers keep running on its own threads.
+pub(crate) async fn on_runtime<F: Future>(runtime: &Handle, future: F) -> F::Output {
+    let mut future = pin!(future);
+    poll_fn(|cx| {
+        let _guard = runtime.enter();
+        future.as_mut().poll(cx)
+    })
+    .await
+}
+
+/// Runs the SDK's named background tasks and stops them on the shutdown signal
+///
+/// A task run is never interrupted: the shutdown signal is only observed between runs, so a
+/// task doesn't stop halfway through e.g. writing a sync to storage. Failing or panicking runs
+/// are logged and retried with exponential backoff, without affecting other tasks.
+pub(crate) struct TaskSupervisor {
+    shutdown_receiver: watch::Receiver<()>,
+    tasks: Mutex<Vec<SupervisedTask>>,
+    /// Timestamps the runs of the tasks
+    clock: Arc<dyn Clock>,
+    /// Runtime the tasks are spawned on, which needn't be the caller's
+    runtime: Handle,
+}
+
+struct SupervisedTask {
+    status: Arc<Mutex<BackgroundTaskStatus>>,
+    /// Time between runs of periodic tasks, read before each wait
+    interval: Option<Arc<Mutex<Duration>>>,
+    /// Taken once the task is waited for on shutdown
+    handle: Option<JoinHandle<()>>,
+}
+
+impl TaskSupervisor {
+    pub(crate) fn new(
+        shutdown_receiver: watch::Receiver<()>,
+        clock: Arc<dyn Clock>,
+        runtime: Handle,
+    ) -> Self {
+        Self {
+            shutdown_receiver,
+            tasks: Mutex::default(),
+            clock,
+            runtime,
+        }
+    }
+
+    /// The runtime the tasks are spawned on
+    pub(crate) fn runtime(&self) -> &Handle {
+        &self.runtime
+    }
+
+    /// Runs `job` right away and then every `interval` until shutdown
+    pub(crate) fn spawn_periodic<F, Fut>(&self, name: &str, interval: Duration, job: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), SdkError>> + Send + 'static,
+    {
+        let status = new_status(name);
+        let task_status = status.clone();
+        let interval = Arc::new(Mutex::new(interval));
+        let task_interval = interval.clone();
+        let mut shutdown_receiver = self.shutdown_receiver.clone();
+        let clock = self.clock.clone();
+        let handle = self.runtime.spawn(async move {
+            let mut delay = Duration::ZERO;
+            loop {
+                tokio::select! {
+                    _ = shutdown_receiver.changed() => break,
+                    _ = tokio::time::sleep(delay) => {}
+                }
+                let consecutive_failures = run(&task_status, clock.as_ref(), job()).await;
+                let interval = *task_interval.lock().unwrap();
+                delay = backoff(interval, consecutive_failures);
+            }
+            stop(&task_status);
+        });
+        self.tasks.lock().unwrap().push(SupervisedTask {
+            status,
+            interval: Some(interval),
+            handle: Some(handle),
+        });
+    }
+
+    /// Runs `job` once `trigger` completes, unless the shutdown signal comes first
+    pub(crate) fn spawn_after<T, F, Fut>(&self, name: &str, trigger: T, job: F)
+    where
+        T: Future<Output = ()> + Send + 'static,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), SdkError>> + Send + 'static,
+    {
+        let status = new_status(name);
+        let task_status = status.clone();
+        let mut shutdown_receiver = self.shutdown_receiver.clone();
+        let clock = self.clock.clone();
+        let handle = self.runtime.spawn(async move {
+            tokio::select! {
+                _ = shutdown_receiver.changed() => {}
+                _ = trigger => {
+                    run(&task_status, clock.as_ref(), job()).await;
+                }
+            }
+            stop(&task_status);
+        });
+        self.tasks.lock().unwrap().push(SupervisedTask {
+            status,
+            interval: None,
+            handle: Some(handle),
+        });
+    }
+
+    /// Changes the time between runs of a periodic task, starting after its next run
+    ///
+    /// Returns `false` if there is no periodic task with that name.
+    pub(crate) fn set_interval(&self, name: &str, interval: Duration) -> bool {
+        let tasks = self.tasks.lock().unwrap();
+        let Some(task_interval) = tasks
+            .iter()
+            .filter(|task| task.status.lock().unwrap().name == name)
+            .find_map(|task| task.interval.as_ref())
+        else {
+            return false;
+        };
+        *task_interval.lock().unwrap() = interval;
+        true
+    }
+
+    /// Returns the status of every task, in the order they were started
+    pub(crate) fn statuses(&self) -> Vec<BackgroundTaskStatus> {
+        self.tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|task| task.status.lock().unwrap().clone())
+            .collect()
+    }
+
+    /// Waits for all tasks to stop after the shutdown signal was sent
+    pub(crate) async fn join(&self) {
+        let handles: Vec<(String, JoinHandle<()>)> = self
+            .tasks
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter_map(|task| {
+                let name = task.status.lock().unwrap().name.clone();
+                task.handle.take().map(|handle| (name, handle))
+            })
+            .collect();
+        for (name, handle) in handles {
+            if let Err(e) = handle.await {
+                error!("Background task {name} didn't stop cleanly: {e}");
+            }
+        }
+    }
+}
+
+fn new_status(name: &str) -> Arc<Mutex<BackgroundTaskStatus>> {
+    Arc::new(Mutex::new(BackgroundTaskStatus {
+        name: name.to_string(),
+        state: BackgroundTaskState::Waiting,
+        last_run_at: None,
+        consecutive_failures: 0,
+        last_error: None,
+    }))
+}
+
+/// Runs a single job in its own task, so a panic only fails the run, and records the outcome
+///
+/// Returns the number of consecutive failed runs.
+async fn run<Fut>(status: &Mutex<BackgroundTaskStatus>, clock: &dyn Clock, job: Fut) -> u32
+where
+    Fut: Future<Output = Result<(), SdkError>> + Send + 'static,
+{
+    status.lock().unwrap().state = BackgroundTaskState::Running;
+    let result = match tokio::spawn(job).await {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(e) => Err(format!("Task panicked: {e}")),
+    };
+
+    let mut status = status.lock().unwrap();
+    status.state = BackgroundTaskState::Waiting;
+    status.last_run_at = Some(clock.now_secs());
+    match result {
+        Ok(()) => {
+            status.consecutive_failures = 0;
+            status.last_error = None;
+        }
+        Err(e) => {
+            status.consecutive_failures += 1;
+            error!(
+                "Background task {} failed {} times in a row: {e}",
+                status.name, status.consecutive_failures
+            );
+            status.last_error = Some(e);
+        }
+    }
+    status.consecutive_failures
+}
+
+fn stop(status: &Mutex<BackgroundTaskStatus>) {
+    let mut status = status.lock().unwrap();
+    status.state = BackgroundTaskState::Stopped;
+    info!("Background task {} stopped", status.name);
+}
+
+/// Delay before the next run, doubling with each consecutive failure up to [MAX_BACKOFF]
+fn backoff(interval: Duration, consecutive_failures: u32) -> Duration {
+    if consecutive_failures == 0 {
+        return interval;
+    }
+    let factor = 2u32.saturating_pow(consecutive_failures);
+    interval
+        .saturating_mul(factor)
+        .min(MAX_BACKOFF.max(interval))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedClock(u64);
+
+    impl Clock for FixedClock {
+        fn now_secs(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let interval = Duration::from_secs(10);
+        assert_eq!(interval, backoff(interval, 0));
+        assert_eq!(Duration::from_secs(20), backoff(interval, 1));
+        assert_eq!(Duration::from_secs(80), backoff(interval, 3));
+        assert_eq!(MAX_BACKOFF, backoff(interval, 40));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_supervisor_shutdown() {
+        let (shutdown_sender, shutdown_receiver) = watch::channel(());
+        let supervisor = TaskSupervisor::new(
+            shutdown_receiver,
+            Arc::new(FixedClock(1_700_000_000)),
+            Handle::current(),
+        );
+        supervisor.spawn_periodic("failing", Duration::from_millis(1), || async {
+            Err(SdkError::GenericError("unreachable".to_string()))
+        });
+        supervisor.spawn_periodic("panicking", Duration::from_millis(1), || async {
+            panic!("task bug")
+        });
+        supervisor.spawn_after("never_triggered", std::future::pending(), || async {
+            Ok(())
+        });
+        assert!(supervisor.set_interval("failing", Duration::from_millis(2)));
+        assert!(!supervisor.set_interval("never_triggered", Duration::from_millis(2)));
+        // Each step lets the tasks run the ticks that became due
+        for _ in 0..20 {
+            tokio::time::advance(Duration::from_millis(1)).await;
+        }
+
+        let statuses = supervisor.statuses();
+        assert_eq!(3, statuses.len());
+        assert!(statuses[0].consecutive_failures > 0);
+        assert_eq!(Some(1_700_000_000), statuses[0].last_run_at);
+        assert_eq!(
+            Some("Generic error: unreachable".to_string()),
+            statuses[0].last_error
+        );
+        // A panicking run doesn't take the task down
+        assert!(statuses[1].consecutive_failures > 0);
+        assert!(statuses[2].last_run_at.is_none());
+
+        shutdown_sender.send(()).unwrap();
+        supervisor.join().await;
+        assert!(supervisor
+            .statuses()
+            .iter()
+            .all(|status| status.state == BackgroundTaskState::Stopped));
+    }
+
+    #[test]
+    fn test_supervisor_outside_runtime() {
+        // Like a host app calling in over FFI, the caller has no runtime of its own
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (shutdown_sender, shutdown_receiver) = watch::channel(());
+        let supervisor = TaskSupervisor::new(
+            shutdown_receiver,
+            Arc::new(FixedClock(1_700_000_000)),
+            runtime.handle().clone(),
+        );
+        let (ran_sender, ran_receiver) = std::sync::mpsc::channel();
+        supervisor.spawn_periodic("outside", Duration::from_secs(60), move || {
+            let ran_sender = ran_sender.clone();
+            async move {
+                ran_sender.send(()).unwrap();
+                Ok(())
+            }
+        });
+
+        ran_receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        shutdown_sender.send(()).unwrap();
+        runtime.block_on(supervisor.join());
+    }
+
+    #[test]
+    fn test_on_runtime_outside_runtime() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        // Blocking work and timers need a runtime, which the host's executor doesn't provide
+        let result = futures::executor::block_on(on_runtime(runtime.handle(), async {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            tokio::task::spawn_blocking(|| 42).await.unwrap()
+        }));
+        assert_eq!(42, result);
+    }
+}
@@ -0,0 +1,195 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::error;
+
+use crate::error::SdkError;
+use crate::models::SyncMode;
+
+/// Operational metric reported to a [TelemetryListener]
+///
+/// Metrics are anonymized: they never carry addresses, amounts, identifiers or error messages.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TelemetryMetric {
+    /// A wallet sync completed
+    SyncCompleted {
+        /// How much work the sync did
+        mode: SyncMode,
+        /// How long the sync took, in milliseconds
+        duration_ms: u64,
+    },
+
+    /// A wallet sync failed
+    SyncFailed {
+        /// How much work the sync attempted
+        mode: SyncMode,
+        /// How long the sync ran before failing, in milliseconds
+        duration_ms: u64,
+        /// What kind of failure stopped the sync
+        category: FailureCategory,
+    },
+
+    /// A send failed
+    SendFailed {
+        /// What kind of failure stopped the send
+        category: FailureCategory,
+    },
+}
+
+/// Coarse category of an [SdkError], safe to report without the error details
+#[derive(Clone, Debug, PartialEq)]
+pub enum FailureCategory {
+    /// A service couldn't be reached or didn't answer in time
+    Network,
+    /// The Ark server rejected the operation or reported inconsistent state
+    Server,
+    /// Reading or writing the local storage failed
+    Storage,
+    /// The wallet couldn't build or fund a transaction
+    Wallet,
+    /// The caller provided invalid input
+    InvalidInput,
+    /// Any other failure
+    Other,
+}
+
+impl From<&SdkError> for FailureCategory {
+    fn from(err: &SdkError) -> Self {
+        match err {
+            SdkError::ConnectError(_) | SdkError::NetworkError(_) | SdkError::Timeout(_) => {
+                FailureCategory::Network
+            }
+            SdkError::ArkClientError(_)
+            | SdkError::ServerInconsistency(_)
+            | SdkError::UntrustedServer(_)
+            | SdkError::RoundVerificationFailed(_) => FailureCategory::Server,
+            SdkError::StorageError(_) | SdkError::AlreadyRunning(_) => FailureCategory::Storage,
+            SdkError::PaymentError(_)
+            | SdkError::WalletError(_)
+            | SdkError::TransactionError(_) => FailureCategory::Wallet,
+            SdkError::InvalidInput(_)
+            | SdkError::AddressParsingError(_)
+            | SdkError::InvalidNetwork
+            | SdkError::AmountRequired(_)
+            | SdkError::AmountMismatch(_)
+            | SdkError::AmountOutOfRange { .. }
+            | SdkError::AmountTooSmall { .. }
+            | SdkError::AmountTooLarge { .. }
+            | SdkError::QuoteExpired(_)
+            | SdkError::InvalidMnemonic(_) => FailureCategory::InvalidInput,
+            SdkError::GenericError(_) | SdkError::NotInitialized | SdkError::AlreadyStopped => {
+                FailureCategory::Other
+            }
+        }
+    }
+}
+
+/// Trait for receiving operational metrics, e.g. to feed the host app's own dashboards
+///
+/// The SDK never reports metrics over the network itself, they only reach the registered
+/// listener.
+pub trait TelemetryListener: Send + Sync {
+    /// Called when a metric is recorded
+    fn on_metric(&self, metric: &TelemetryMetric);
+}
+
+/// Forwards metrics to the registered [TelemetryListener], if any
+#[derive(Default)]
+pub(crate) struct Telemetry {
+    listener: Mutex<Option<Arc<dyn TelemetryListener>>>,
+}
+
+impl Telemetry {
+    /// Replaces the listener, or stops reporting metrics if `None`
+    pub(crate) fn set_listener(&self, listener: Option<Box<dyn TelemetryListener>>) {
+        *self.listener.lock().unwrap() = listener.map(Arc::from);
+    }
+
+    /// Reports a metric, a panicking listener doesn't affect the SDK
+    pub(crate) fn record(&self, metric: TelemetryMetric) {
+        let Some(listener) = self.listener.lock().unwrap().clone() else {
+            return;
+        };
+        if panic::catch_unwind(AssertUnwindSafe(|| listener.on_metric(&metric))).is_err() {
+            error!("Telemetry listener panicked handling {metric:?}");
+        }
+    }
+
+    /// Reports the outcome of a wallet sync
+    pub(crate) fn record_sync(
+        &self,
+        mode: SyncMode,
+        elapsed: Duration,
+        result: &Result<(), SdkError>,
+    ) {
+        let duration_ms = elapsed.as_millis() as u64;
+        self.record(match result {
+            Ok(()) => TelemetryMetric::SyncCompleted { mode, duration_ms },
+            Err(e) => TelemetryMetric::SyncFailed {
+                mode,
+                duration_ms,
+                category: e.into(),
+            },
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct RecordingListener {
+        metrics: Arc<Mutex<Vec<TelemetryMetric>>>,
+    }
+
+    impl TelemetryListener for RecordingListener {
+        fn on_metric(&self, metric: &TelemetryMetric) {
+            self.metrics.lock().unwrap().push(metric.clone());
+        }
+    }
+
+    struct PanickingListener;
+
+    impl TelemetryListener for PanickingListener {
+        fn on_metric(&self, _metric: &TelemetryMetric) {
+            panic!("listener bug");
+        }
+    }
+
+    #[test]
+    fn test_record_sync() {
+        let telemetry = Telemetry::default();
+        // Nothing is recorded until a listener is registered
+        telemetry.record_sync(SyncMode::Full, Duration::from_millis(5), &Ok(()));
+
+        let metrics = Arc::new(Mutex::new(Vec::new()));
+        telemetry.set_listener(Some(Box::new(RecordingListener {
+            metrics: metrics.clone(),
+        })));
+        telemetry.record_sync(SyncMode::Full, Duration::from_millis(1_500), &Ok(()));
+        telemetry.record_sync(
+            SyncMode::BalanceOnly,
+            Duration::from_millis(20),
+            &Err(SdkError::Timeout("Esplora didn't answer".to_string())),
+        );
+        assert_eq!(
+            vec![
+                TelemetryMetric::SyncCompleted {
+                    mode: SyncMode::Full,
+                    duration_ms: 1_500,
+                },
+                TelemetryMetric::SyncFailed {
+                    mode: SyncMode::BalanceOnly,
+                    duration_ms: 20,
+                    category: FailureCategory::Network,
+                },
+            ],
+            *metrics.lock().unwrap()
+        );
+
+        // A panicking listener doesn't take the SDK down
+        telemetry.set_listener(Some(Box::new(PanickingListener)));
+        telemetry.record_sync(SyncMode::Full, Duration::ZERO, &Ok(()));
+    }
+}
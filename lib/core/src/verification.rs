@@ -0,0 +1,268 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use ark_core::ArkTransaction;
+use bitcoin::secp256k1::{PublicKey, XOnlyPublicKey};
+use bitcoin::{Transaction, TxOut, Txid};
+use log::warn;
+
+use crate::chain::ChainService;
+use crate::error::SdkError;
+use crate::models::ReceiveWarning;
+
+/// How long a round transaction may take to reach the chain after the server reports it
+const ROUND_BROADCAST_GRACE_SECS: i64 = 60 * 60;
+
+/// Outcome of checking the settled rounds of a transaction history against the chain
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct HistoryCheck {
+    /// Rounds reported as settled that were never published onchain
+    pub(crate) inconsistencies: Vec<String>,
+    /// Rounds that couldn't be looked up onchain, checked again next time
+    pub(crate) unchecked_rounds: usize,
+}
+
+/// Cross-checks what the Ark server reports against the chain
+///
+/// A round the server reports as settled must be published onchain, otherwise the VTXOs it
+/// created can't be exited and the server may be equivocating.
+#[derive(Default)]
+pub(crate) struct SettlementVerifier {
+    verified_rounds: Mutex<HashSet<Txid>>,
+}
+
+impl SettlementVerifier {
+    /// Checks the settled rounds of the transaction history against the chain
+    ///
+    /// Rounds found onchain are remembered, so each one is only fetched until it is verified.
+    /// Rounds that couldn't be looked up, e.g. because the chain backend is unreachable, are
+    /// counted as unchecked rather than failing the whole check.
+    pub(crate) async fn verify_history(
+        &self,
+        chain_service: &dyn ChainService,
+        history: &[ArkTransaction],
+        now: i64,
+    ) -> HistoryCheck {
+        let mut check = HistoryCheck::default();
+        for transaction in history {
+            let ArkTransaction::Round {
+                txid, created_at, ..
+            } = transaction
+            else {
+                continue;
+            };
+            if self.verified_rounds.lock().unwrap().contains(txid)
+                || now - created_at < ROUND_BROADCAST_GRACE_SECS
+            {
+                continue;
+            }
+
+            match chain_service.find_tx(txid).await {
+                Ok(Some(_)) => {
+                    self.verified_rounds.lock().unwrap().insert(*txid);
+                }
+                Ok(None) => {
+                    warn!("Round {txid} reported as settled is not onchain");
+                    check.inconsistencies.push(format!(
+                        "Round {txid} reported as settled was never published onchain"
+                    ));
+                }
+                Err(e) => {
+                    warn!("Failed to look up round {txid} onchain: {e:?}");
+                    check.unchecked_rounds += 1;
+                }
+            }
+        }
+
+        check
+    }
+}
+
+/// Checks that a round transaction pays every expected output in full
+pub(crate) fn verify_round_outputs(tx: &Transaction, expected: &[TxOut]) -> Result<(), SdkError> {
+    for output in expected {
+        let paid = tx
+            .output
+            .iter()
+            .any(|o| o.script_pubkey == output.script_pubkey && o.value >= output.value);
+        if !paid {
+            return Err(SdkError::RoundVerificationFailed(format!(
+                "Round {} doesn't pay {} to {}",
+                tx.compute_txid(),
+                output.value,
+                output.script_pubkey
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the Ark server signs with the key the wallet was configured to expect
+///
+/// The expected key is hex encoded, either compressed or x-only. Only the x-only part is
+/// compared, as that's what the server signs with.
+pub(crate) fn verify_server_pubkey(
+    server_url: &str,
+    expected: &str,
+    actual: PublicKey,
+) -> Result<(), SdkError> {
+    let expected_x_only = match PublicKey::from_str(expected) {
+        Ok(pk) => pk.x_only_public_key().0,
+        Err(_) => XOnlyPublicKey::from_str(expected).map_err(|_| {
+            SdkError::InvalidInput(format!("Invalid expected server public key {expected}"))
+        })?,
+    };
+    if expected_x_only != actual.x_only_public_key().0 {
+        return Err(SdkError::UntrustedServer(format!(
+            "{server_url} signs with {actual}, expected {expected}"
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the Ark server still accepts destinations derived from the key it had when the SDK
+/// connected, given the key it reports now
+pub(crate) fn receive_warning(
+    derived_with: PublicKey,
+    current: Result<PublicKey, SdkError>,
+) -> Option<ReceiveWarning> {
+    match current {
+        Err(e) => Some(ReceiveWarning::ServerUnreachable {
+            reason: e.to_string(),
+        }),
+        Ok(current) if current.x_only_public_key() != derived_with.x_only_public_key() => {
+            Some(ReceiveWarning::ServerKeyChanged {
+                derived_with: derived_with.to_string(),
+                current: current.to_string(),
+            })
+        }
+        Ok(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::simulated::{MemoryChain, NetworkCondition, SimulatedChainService};
+    use bitcoin::{absolute::LockTime, transaction::Version, Amount, ScriptBuf};
+    use std::sync::Arc;
+
+    fn output(script: &[u8], sats: u64) -> TxOut {
+        TxOut {
+            value: Amount::from_sat(sats),
+            script_pubkey: ScriptBuf::from_bytes(script.to_vec()),
+        }
+    }
+
+    fn round_tx(outputs: Vec<TxOut>) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: outputs,
+        }
+    }
+
+    fn round(tx: &Transaction) -> ArkTransaction {
+        ArkTransaction::Round {
+            txid: tx.compute_txid(),
+            amount: bitcoin::SignedAmount::from_sat(1_000),
+            created_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_history_survives_chain_errors() {
+        let published = round_tx(vec![output(&[1], 1_000)]);
+        let unpublished = round_tx(vec![output(&[2], 1_000)]);
+        let chain = Arc::new(MemoryChain::default());
+        chain
+            .txs
+            .lock()
+            .unwrap()
+            .insert(published.compute_txid(), published.clone());
+        let chain = SimulatedChainService::new(chain);
+        let history = [round(&published), round(&unpublished)];
+        let now = ROUND_BROADCAST_GRACE_SECS;
+        let verifier = SettlementVerifier::default();
+
+        // The published round can't be looked up, the unpublished one is still caught
+        chain.script([NetworkCondition::Drop]);
+        let check = verifier.verify_history(&chain, &history, now).await;
+        assert_eq!(1, check.unchecked_rounds);
+        assert_eq!(1, check.inconsistencies.len());
+
+        let check = verifier.verify_history(&chain, &history[..1], now).await;
+        assert_eq!(HistoryCheck::default(), check);
+    }
+
+    #[test]
+    fn test_verify_round_outputs() {
+        let tx = round_tx(vec![output(&[1], 1_000), output(&[2], 5_000)]);
+
+        assert!(verify_round_outputs(&tx, &[output(&[2], 5_000)]).is_ok());
+        assert!(verify_round_outputs(&tx, &[output(&[2], 5_001)]).is_err());
+        assert!(verify_round_outputs(&tx, &[output(&[3], 1_000)]).is_err());
+    }
+
+    #[test]
+    fn test_verify_server_pubkey() {
+        let server_pk = PublicKey::from_str(
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        )
+        .unwrap();
+
+        // Compressed and x-only encodings of the server key are accepted
+        assert!(verify_server_pubkey(
+            "https://ark.example",
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            server_pk
+        )
+        .is_ok());
+        assert!(verify_server_pubkey(
+            "https://ark.example",
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            server_pk
+        )
+        .is_ok());
+
+        assert!(matches!(
+            verify_server_pubkey(
+                "https://ark.example",
+                "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+                server_pk
+            ),
+            Err(SdkError::UntrustedServer(_))
+        ));
+        assert!(matches!(
+            verify_server_pubkey("https://ark.example", "not a key", server_pk),
+            Err(SdkError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_receive_warning() {
+        let server_pk = PublicKey::from_str(
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        )
+        .unwrap();
+        let rotated_pk = PublicKey::from_str(
+            "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+        )
+        .unwrap();
+
+        assert_eq!(None, receive_warning(server_pk, Ok(server_pk)));
+        assert!(matches!(
+            receive_warning(server_pk, Ok(rotated_pk)),
+            Some(ReceiveWarning::ServerKeyChanged { .. })
+        ));
+        assert!(matches!(
+            receive_warning(
+                server_pk,
+                Err(SdkError::NetworkError("Connection dropped".to_string()))
+            ),
+            Some(ReceiveWarning::ServerUnreachable { .. })
+        ));
+    }
+}